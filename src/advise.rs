@@ -2,7 +2,9 @@
 
 use crate::errors::{MmapIoError, Result};
 use crate::mmap::MemoryMappedFile;
-use crate::utils::slice_range;
+use crate::utils::{ensure_in_bounds, page_size, slice_range};
+use std::ops::Range;
+use std::thread::JoinHandle;
 
 /// Memory access pattern advice for the OS.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,11 +49,12 @@ impl MemoryMappedFile {
         // Get the base pointer for the mapping
         let ptr = match &self.inner.map {
             crate::mmap::MapVariant::Ro(m) => m.as_ptr(),
-            crate::mmap::MapVariant::Rw(lock) => {
+            crate::mmap::MapVariant::Rw(lock) | crate::mmap::MapVariant::Cow(lock) => {
+                // Only the address is taken; no bytes are touched, so split parts writing
+                // their ranges concurrently do not race with the advice
                 let guard = lock.read();
                 guard.as_ptr()
             }
-            crate::mmap::MapVariant::Cow(m) => m.as_ptr(),
         };
 
         // SAFETY: We've validated the range is within bounds
@@ -71,8 +74,10 @@ impl MemoryMappedFile {
                 MmapAdvice::DontNeed => MADV_DONTNEED,
             };
 
+            // madvise needs a page-aligned start, which a file window need not have
+            let (addr, length) = crate::utils::page_span(addr, length);
             // SAFETY: madvise is safe to call with validated parameters
-            let result = unsafe { madvise(addr as *mut libc::c_void, length, advice_flag) };
+            let result = unsafe { madvise(addr.cast(), length, advice_flag) };
 
             if result != 0 {
                 let err = std::io::Error::last_os_error();
@@ -132,6 +137,76 @@ impl MemoryMappedFile {
 
         Ok(())
     }
+
+    /// Ask the OS to read in every listed range ahead of use, with a `WillNeed` hint each.
+    ///
+    /// Ranges are rounded out to page boundaries, sorted, and merged where they overlap or
+    /// touch, so a query engine can pass exactly the records an upcoming operation will read.
+    /// Empty ranges are ignored. All ranges are checked before any hint is issued.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if a range exceeds file bounds.
+    /// Returns `MmapIoError::AdviceFailed` if a hint fails.
+    #[cfg(feature = "advise")]
+    pub fn prefetch(&self, ranges: &[Range<u64>]) -> Result<()> {
+        let total = self.current_len()?;
+        let mut spans = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let len = range.end.saturating_sub(range.start);
+            ensure_in_bounds(range.start, len, total)?;
+            spans.push((range.start, len));
+        }
+        let spans = crate::flush::coalesce_ranges(&spans, page_size() as u64, total, usize::MAX);
+        for (offset, len) in spans {
+            self.advise(offset, len, MmapAdvice::WillNeed)?;
+        }
+        Ok(())
+    }
+
+    /// Like [`prefetch`](Self::prefetch), but on a background thread.
+    ///
+    /// The ranges are copied and the call returns at once; use the handle to wait for the
+    /// hints to be issued and collect any error. Dropping the handle detaches the thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if the thread cannot be spawned.
+    #[cfg(feature = "advise")]
+    pub fn prefetch_background(&self, ranges: &[Range<u64>]) -> Result<PrefetchHandle> {
+        let this = self.clone();
+        let ranges = ranges.to_vec();
+        let thread = std::thread::Builder::new()
+            .name("mmap-io-prefetch".into())
+            .spawn(move || this.prefetch(&ranges))?;
+        Ok(PrefetchHandle { thread })
+    }
+}
+
+/// Completion handle for [`MemoryMappedFile::prefetch_background`].
+#[derive(Debug)]
+pub struct PrefetchHandle {
+    thread: JoinHandle<Result<()>>,
+}
+
+impl PrefetchHandle {
+    /// Whether all hints have been issued.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Block until all hints have been issued.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from [`MemoryMappedFile::prefetch`], or
+    /// `MmapIoError::AdviceFailed` if the prefetch thread panicked.
+    pub fn wait(self) -> Result<()> {
+        self.thread
+            .join()
+            .map_err(|_| MmapIoError::AdviceFailed("prefetch thread panicked".into()))?
+    }
 }
 
 #[cfg(test)]
@@ -181,6 +256,36 @@ mod tests {
         std::fs::remove_file(file_path).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "advise")]
+    fn test_prefetch_ranges() {
+        let path = tmp_path("prefetch");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 64 * 1024).expect("create");
+        drop(mmap);
+        let mmap = MemoryMappedFile::open_ro(&path).expect("open ro");
+        // Unaligned, unsorted, overlapping, and empty ranges are all accepted
+        let ranges = [40_000..41_000, 100..200, 150..5000, 9000..9000];
+        mmap.prefetch(&ranges).expect("prefetch");
+        assert!(matches!(
+            mmap.prefetch(&[0..10, 60_000..70_000]),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+
+        let handle = mmap.prefetch_background(&ranges).expect("spawn");
+        handle.wait().expect("background prefetch");
+        let handle = mmap
+            .prefetch_background(&[0..10, 100..u64::MAX])
+            .expect("spawn");
+        assert!(matches!(
+            handle.wait(),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
     #[test]
     #[cfg(feature = "advise")]
     fn test_advise_with_different_modes() {
@@ -0,0 +1,269 @@
+//! Bump allocator handing out aligned regions of a mapped segment.
+//!
+//! Allocation advances a high-water mark persisted in the arena header, so records allocated
+//! before a restart stay allocated after [`MmapArena::open`]. Individual allocations are never
+//! freed; [`MmapArena::reset`] releases everything at once.
+//!
+//! Segment layout (all integers little-endian):
+//!
+//! | bytes  | field                                       |
+//! |--------|---------------------------------------------|
+//! | 0..8   | magic `MMIOAREN`                            |
+//! | 8..16  | high-water mark, relative to segment start  |
+//! | 16..24 | reserved                                    |
+//! | 24..   | allocations                                 |
+
+use std::mem::{align_of, size_of};
+
+use parking_lot::Mutex;
+
+use crate::errors::{MmapIoError, Result};
+use crate::segment::SegmentMut;
+use crate::typed::{Pod, SegmentTyped};
+use crate::utils::align_up;
+
+/// Size of the arena header at the start of the segment.
+pub const ARENA_HEADER_SIZE: u64 = 24;
+
+const MAGIC: &[u8; 8] = b"MMIOAREN";
+
+/// Persistent bump allocator over a segment of a mapped file.
+///
+/// Alignment is applied to absolute file offsets, so a `T` allocation is correctly aligned
+/// for typed access. Allocations through one handle are serialized; allocating from several
+/// processes at once must be coordinated externally (e.g. with a file lock).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, arena::MmapArena, segment::SegmentMut};
+///
+/// let mmap = Arc::new(MemoryMappedFile::create_rw("heap.bin", 1 << 20)?);
+/// let arena = MmapArena::create(SegmentMut::new(mmap, 0, 1 << 20)?)?;
+/// let counters = arena.alloc::<u64>(16)?;
+/// counters.set(0, 1)?;
+/// let blob = arena.alloc_bytes(100, 1)?;
+/// blob.write_at(0, b"payload")?;
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct MmapArena {
+    segment: SegmentMut,
+    // Serializes read-modify-write of the persisted high-water mark
+    alloc: Mutex<()>,
+}
+
+impl MmapArena {
+    /// Initialize an empty arena in `segment`, overwriting its header.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the segment cannot hold the header.
+    /// Returns `MmapIoError::InvalidMode` if the parent mapping is not ReadWrite.
+    pub fn create(segment: SegmentMut) -> Result<Self> {
+        let mut header = [0u8; ARENA_HEADER_SIZE as usize];
+        header[8..16].copy_from_slice(&ARENA_HEADER_SIZE.to_le_bytes());
+        segment.write_at(0, &header)?;
+        // Publish the magic last so a torn initialization is not mistaken for an arena
+        segment.write_at(0, MAGIC)?;
+        Ok(Self {
+            segment,
+            alloc: Mutex::new(()),
+        })
+    }
+
+    /// Open an arena previously created in `segment`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the magic is missing or the high-water mark lies
+    /// outside the segment.
+    pub fn open(segment: SegmentMut) -> Result<Self> {
+        let arena = Self {
+            segment,
+            alloc: Mutex::new(()),
+        };
+        let mut magic = [0u8; 8];
+        arena.read(0, &mut magic)?;
+        if &magic != MAGIC {
+            return Err(MmapIoError::Corrupt("missing arena magic".into()));
+        }
+        arena.mark()?;
+        Ok(arena)
+    }
+
+    /// Allocate `len` bytes aligned to `align` (a power of two).
+    ///
+    /// The returned segment is not zeroed after a [`reset`](Self::reset).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if `align` is not a power of two and
+    /// `MmapIoError::OutOfBounds` if the arena has no room left.
+    pub fn alloc_bytes(&self, len: u64, align: u64) -> Result<SegmentMut> {
+        if !align.is_power_of_two() {
+            return Err(MmapIoError::InvalidMode(
+                "arena alignment must be a power of two",
+            ));
+        }
+        let _guard = self.alloc.lock();
+        let mark = self.mark()?;
+        let base = self.segment.offset();
+        // Overflow saturates (align_up does too), so a huge `align` fails the check below
+        let start = align_up(base.saturating_add(mark), align) - base;
+        let end = start
+            .checked_add(len)
+            .filter(|&end| end <= self.segment.len())
+            .ok_or(MmapIoError::OutOfBounds {
+                offset: start,
+                len,
+                total: self.segment.len(),
+            })?;
+        let region = self.segment.sub(start, len)?;
+        self.segment.write_at(8, &end.to_le_bytes())?;
+        Ok(region)
+    }
+
+    /// Allocate an array of `count` `T`s.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the arena has no room left.
+    pub fn alloc<T: Pod>(&self, count: usize) -> Result<SegmentTyped<T>> {
+        let len =
+            (size_of::<T>() as u64)
+                .checked_mul(count as u64)
+                .ok_or(MmapIoError::OutOfBounds {
+                    offset: 0,
+                    len: u64::MAX,
+                    total: self.segment.len(),
+                })?;
+        let region = self.alloc_bytes(len, align_of::<T>() as u64)?;
+        SegmentTyped::from_segment(region)
+    }
+
+    /// Release every allocation. Existing views keep working but alias future allocations.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the parent mapping is not ReadWrite.
+    pub fn reset(&self) -> Result<()> {
+        let _guard = self.alloc.lock();
+        self.segment.write_at(8, &ARENA_HEADER_SIZE.to_le_bytes())
+    }
+
+    /// Bytes allocated so far, including alignment padding.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the persisted mark is invalid.
+    pub fn used(&self) -> Result<u64> {
+        Ok(self.mark()? - ARENA_HEADER_SIZE)
+    }
+
+    /// Bytes available for allocations in total.
+    #[must_use]
+    pub fn capacity(&self) -> u64 {
+        self.segment.len().saturating_sub(ARENA_HEADER_SIZE)
+    }
+
+    /// Bytes still available, ignoring alignment padding.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the persisted mark is invalid.
+    pub fn remaining(&self) -> Result<u64> {
+        Ok(self.segment.len() - self.mark()?)
+    }
+
+    /// The segment the arena lives in.
+    #[must_use]
+    pub fn segment(&self) -> &SegmentMut {
+        &self.segment
+    }
+
+    // Read the persisted high-water mark, re-read on every call so allocations made through
+    // other handles are respected.
+    fn mark(&self) -> Result<u64> {
+        let mut word = [0u8; 8];
+        self.read(8, &mut word)?;
+        let mark = u64::from_le_bytes(word);
+        if mark < ARENA_HEADER_SIZE || mark > self.segment.len() {
+            return Err(MmapIoError::Corrupt(format!(
+                "arena high-water mark {mark} lies outside the segment"
+            )));
+        }
+        Ok(mark)
+    }
+
+    fn read(&self, rel: u64, buf: &mut [u8]) -> Result<()> {
+        self.segment
+            .parent()
+            .read_into(self.segment.offset() + rel, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_arena_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_arena_alloc_reopen_reset() {
+        let path = tmp_path("arena");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 4096).expect("create"));
+        // Start at an odd offset so alignment has to be applied to absolute offsets
+        let segment = SegmentMut::new(mmap.clone(), 100, 1000).expect("segment");
+        let arena = MmapArena::create(segment.clone()).expect("arena");
+        assert_eq!(arena.capacity(), 1000 - ARENA_HEADER_SIZE);
+
+        let bytes = arena.alloc_bytes(3, 1).expect("bytes");
+        assert_eq!(bytes.offset(), 100 + ARENA_HEADER_SIZE);
+        bytes.write_at(0, b"abc").expect("write");
+        let words = arena.alloc::<u64>(4).expect("words");
+        assert_eq!(words.segment().offset() % 8, 0);
+        words.set(3, 0xDEAD_BEEF).expect("set");
+        let used = arena.used().expect("used");
+        drop(arena);
+
+        let arena = MmapArena::open(segment.clone()).expect("reopen");
+        assert_eq!(arena.used().expect("used"), used);
+        let next = arena.alloc_bytes(8, 8).expect("next");
+        assert!(next.offset() >= words.segment().offset() + 32);
+        assert_eq!(words.get(3).expect("get"), 0xDEAD_BEEF);
+
+        assert!(arena.alloc_bytes(2000, 1).is_err());
+        assert!(arena.alloc_bytes(1, 3).is_err());
+        // Neither a huge alignment nor a huge length wraps around into the segment
+        let oob = |r: Result<SegmentMut>| matches!(r, Err(MmapIoError::OutOfBounds { .. }));
+        assert!(oob(arena.alloc_bytes(1, 1 << 63)));
+        assert!(oob(arena.alloc_bytes(u64::MAX, 1)));
+
+        arena.reset().expect("reset");
+        assert_eq!(arena.used().expect("used"), 0);
+        assert_eq!(
+            arena.alloc_bytes(1, 1).expect("after reset").offset(),
+            100 + ARENA_HEADER_SIZE
+        );
+        assert!(MmapArena::open(SegmentMut::new(mmap.clone(), 0, 64).expect("seg")).is_err());
+
+        drop((arena, bytes, words, next, segment, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,84 @@
+//! Zero-copy Apache Arrow buffers backed by mapped files.
+
+use std::panic::AssertUnwindSafe;
+use std::ptr::NonNull;
+use std::sync::Arc;
+
+use arrow_buffer::Buffer;
+
+use crate::errors::Result;
+use crate::mmap::MemoryMappedFile;
+
+impl MemoryMappedFile {
+    /// Wrap `[offset, offset + len)` as an [`arrow_buffer::Buffer`] without copying.
+    ///
+    /// The buffer holds a clone of this mapping, so the mapped memory stays valid until the
+    /// last buffer (and every slice of it) is dropped, even if this handle goes away first.
+    /// Only immutable mappings (ReadOnly, ReadExecute) are supported, since a ReadWrite or
+    /// CopyOnWrite mapping could be rewritten (or resized) underneath the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the range exceeds the mapping.
+    /// Returns `MmapIoError::InvalidMode` for ReadWrite and CopyOnWrite mappings.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mmap_io::MemoryMappedFile;
+    ///
+    /// let mmap = MemoryMappedFile::open_ro("values.i32")?;
+    /// let buffer = mmap.as_arrow_buffer(0, mmap.len())?;
+    /// let values: &[i32] = buffer.typed_data();
+    /// # let _ = values;
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    pub fn as_arrow_buffer(&self, offset: u64, len: u64) -> Result<Buffer> {
+        let slice = self.as_slice(offset, len)?;
+        let ptr = NonNull::from(slice).cast::<u8>();
+        let owner = Arc::new(AssertUnwindSafe(self.clone()));
+        // SAFETY: `ptr` is valid for `slice.len()` bytes for as long as the mapping lives, and
+        // the buffer keeps the mapping alive through `owner`. Immutable mappings are never
+        // remapped or written through this crate, so the bytes cannot change underneath.
+        Ok(unsafe { Buffer::from_custom_allocation(ptr, slice.len(), owner) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_arrow_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_arrow_buffer_outlives_handle() {
+        let path = tmp_path("buffer");
+        let _ = fs::remove_file(&path);
+        let values: Vec<u8> = (0..16i32).flat_map(|v| v.to_ne_bytes()).collect();
+        fs::write(&path, &values).expect("seed");
+
+        let mmap = MemoryMappedFile::open_ro(&path).expect("open");
+        let buffer = mmap.as_arrow_buffer(16, 32).expect("buffer");
+        drop(mmap);
+
+        let ints: &[i32] = buffer.typed_data();
+        assert_eq!(ints, &[4, 5, 6, 7, 8, 9, 10, 11]);
+        assert!(buffer.slice(8).as_slice().starts_with(&6i32.to_ne_bytes()));
+
+        let rw = MemoryMappedFile::open_rw(&path).expect("open rw");
+        assert!(rw.as_arrow_buffer(0, 4).is_err());
+        drop((buffer, rw));
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -4,6 +4,12 @@ use crate::errors::{MmapIoError, Result};
 use crate::mmap::MemoryMappedFile;
 use std::sync::atomic::{AtomicU32, AtomicU64};
 
+#[cfg(all(
+    feature = "atomic128",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+pub use portable_atomic::AtomicU128;
+
 impl MemoryMappedFile {
     /// Get an atomic view of a u64 value at the specified offset.
     ///
@@ -18,43 +24,34 @@ impl MemoryMappedFile {
     ///
     /// # Errors
     ///
-    /// Returns `MmapIoError::Misaligned` if the offset is not 8-byte aligned.
+    /// Returns `MmapIoError::Misaligned` if the mapped address of `offset` is not 8-byte
+    /// aligned.
+    /// Returns `MmapIoError::InvalidMode` if the mapping is
+    /// [`guarded`](crate::mmap::MemoryMappedFileBuilder::guarded).
     /// Returns `MmapIoError::OutOfBounds` if the offset + 8 exceeds file bounds.
     #[cfg(feature = "atomic")]
     pub fn atomic_u64(&self, offset: u64) -> Result<&AtomicU64> {
         const ALIGN: u64 = std::mem::align_of::<AtomicU64>() as u64;
         const SIZE: u64 = std::mem::size_of::<AtomicU64>() as u64;
 
-        // Check alignment
-        if offset % ALIGN != 0 {
-            return Err(MmapIoError::Misaligned {
-                required: ALIGN,
-                offset,
-            });
-        }
+        self.check_unguarded()?;
 
         // Check bounds
         let total = self.current_len()?;
-        if offset + SIZE > total {
-            return Err(MmapIoError::OutOfBounds {
-                offset,
-                len: SIZE,
-                total,
-            });
-        }
+        crate::utils::ensure_in_bounds(offset, SIZE, total)?;
 
         // Get the base pointer for the mapping
         let ptr = match &self.inner.map {
             crate::mmap::MapVariant::Ro(m) => m.as_ptr(),
-            crate::mmap::MapVariant::Rw(lock) => {
+            crate::mmap::MapVariant::Rw(lock) | crate::mmap::MapVariant::Cow(lock) => {
                 let guard = lock.read();
+                self.check_unsplit()?;
                 guard.as_ptr()
             }
-            crate::mmap::MapVariant::Cow(m) => m.as_ptr(),
         };
 
         // SAFETY: Multiple invariants are guaranteed:
-        // 1. Alignment: We've verified offset % 8 == 0 (required for AtomicU64)
+        // 1. Alignment: We've verified the address is 8-byte aligned (required for AtomicU64)
         // 2. Bounds: We've verified offset + 8 <= total file size
         // 3. Overflow: try_into() ensures offset fits in usize, preventing ptr arithmetic overflow
         // 4. Lifetime: The returned reference is bound to 'self', ensuring the mapping outlives it
@@ -65,6 +62,13 @@ impl MemoryMappedFile {
             len: SIZE,
             total,
         })?;
+        // Check alignment of the address: a file window may start mid-page
+        if (ptr as usize).wrapping_add(offset_usize) % ALIGN as usize != 0 {
+            return Err(MmapIoError::Misaligned {
+                required: ALIGN,
+                offset,
+            });
+        }
         unsafe {
             // ptr.add() is safe because:
             // - offset_usize is guaranteed to be within bounds (checked above)
@@ -88,43 +92,34 @@ impl MemoryMappedFile {
     ///
     /// # Errors
     ///
-    /// Returns `MmapIoError::Misaligned` if the offset is not 4-byte aligned.
+    /// Returns `MmapIoError::Misaligned` if the mapped address of `offset` is not 4-byte
+    /// aligned.
+    /// Returns `MmapIoError::InvalidMode` if the mapping is
+    /// [`guarded`](crate::mmap::MemoryMappedFileBuilder::guarded).
     /// Returns `MmapIoError::OutOfBounds` if the offset + 4 exceeds file bounds.
     #[cfg(feature = "atomic")]
     pub fn atomic_u32(&self, offset: u64) -> Result<&AtomicU32> {
         const ALIGN: u64 = std::mem::align_of::<AtomicU32>() as u64;
         const SIZE: u64 = std::mem::size_of::<AtomicU32>() as u64;
 
-        // Check alignment
-        if offset % ALIGN != 0 {
-            return Err(MmapIoError::Misaligned {
-                required: ALIGN,
-                offset,
-            });
-        }
+        self.check_unguarded()?;
 
         // Check bounds
         let total = self.current_len()?;
-        if offset + SIZE > total {
-            return Err(MmapIoError::OutOfBounds {
-                offset,
-                len: SIZE,
-                total,
-            });
-        }
+        crate::utils::ensure_in_bounds(offset, SIZE, total)?;
 
         // Get the base pointer for the mapping
         let ptr = match &self.inner.map {
             crate::mmap::MapVariant::Ro(m) => m.as_ptr(),
-            crate::mmap::MapVariant::Rw(lock) => {
+            crate::mmap::MapVariant::Rw(lock) | crate::mmap::MapVariant::Cow(lock) => {
                 let guard = lock.read();
+                self.check_unsplit()?;
                 guard.as_ptr()
             }
-            crate::mmap::MapVariant::Cow(m) => m.as_ptr(),
         };
 
         // SAFETY: Multiple invariants are guaranteed:
-        // 1. Alignment: We've verified offset % 4 == 0 (required for AtomicU32)
+        // 1. Alignment: We've verified the address is 4-byte aligned (required for AtomicU32)
         // 2. Bounds: We've verified offset + 4 <= total file size
         // 3. Overflow: try_into() ensures offset fits in usize, preventing ptr arithmetic overflow
         // 4. Lifetime: The returned reference is bound to 'self', ensuring the mapping outlives it
@@ -135,6 +130,13 @@ impl MemoryMappedFile {
             len: SIZE,
             total,
         })?;
+        // Check alignment of the address: a file window may start mid-page
+        if (ptr as usize).wrapping_add(offset_usize) % ALIGN as usize != 0 {
+            return Err(MmapIoError::Misaligned {
+                required: ALIGN,
+                offset,
+            });
+        }
         unsafe {
             // ptr.add() is safe because:
             // - offset_usize is guaranteed to be within bounds (checked above)
@@ -152,44 +154,42 @@ impl MemoryMappedFile {
     ///
     /// # Errors
     ///
-    /// Returns `MmapIoError::Misaligned` if the offset is not 8-byte aligned.
+    /// Returns `MmapIoError::Misaligned` if the mapped address of `offset` is not 8-byte
+    /// aligned.
+    /// Returns `MmapIoError::InvalidMode` if the mapping is
+    /// [`guarded`](crate::mmap::MemoryMappedFileBuilder::guarded).
     /// Returns `MmapIoError::OutOfBounds` if the range exceeds file bounds.
     #[cfg(feature = "atomic")]
     pub fn atomic_u64_slice(&self, offset: u64, count: usize) -> Result<&[AtomicU64]> {
         const ALIGN: u64 = std::mem::align_of::<AtomicU64>() as u64;
         const SIZE: u64 = std::mem::size_of::<AtomicU64>() as u64;
 
-        // Check alignment
-        if offset % ALIGN != 0 {
-            return Err(MmapIoError::Misaligned {
-                required: ALIGN,
-                offset,
-            });
-        }
+        self.check_unguarded()?;
 
         // Check bounds
-        let total_size = SIZE * count as u64;
         let total = self.current_len()?;
-        if offset + total_size > total {
-            return Err(MmapIoError::OutOfBounds {
+        let total_size = u64::try_from(count)
+            .ok()
+            .and_then(|n| n.checked_mul(SIZE))
+            .ok_or(MmapIoError::OutOfBounds {
                 offset,
-                len: total_size,
+                len: u64::MAX,
                 total,
-            });
-        }
+            })?;
+        crate::utils::ensure_in_bounds(offset, total_size, total)?;
 
         // Get the base pointer for the mapping
         let ptr = match &self.inner.map {
             crate::mmap::MapVariant::Ro(m) => m.as_ptr(),
-            crate::mmap::MapVariant::Rw(lock) => {
+            crate::mmap::MapVariant::Rw(lock) | crate::mmap::MapVariant::Cow(lock) => {
                 let guard = lock.read();
+                self.check_unsplit()?;
                 guard.as_ptr()
             }
-            crate::mmap::MapVariant::Cow(m) => m.as_ptr(),
         };
 
         // SAFETY: Multiple invariants are guaranteed:
-        // 1. Alignment: We've verified offset % 8 == 0 (required for AtomicU64 array)
+        // 1. Alignment: We've verified the address is 8-byte aligned (required for AtomicU64 array)
         // 2. Bounds: We've verified offset + (count * 8) <= total file size
         // 3. Overflow: try_into() ensures offset fits in usize, preventing ptr arithmetic overflow
         // 4. Lifetime: The returned slice is bound to 'self', ensuring the mapping outlives it
@@ -200,6 +200,13 @@ impl MemoryMappedFile {
             len: total_size,
             total,
         })?;
+        // Check alignment of the address: a file window may start mid-page
+        if (ptr as usize).wrapping_add(offset_usize) % ALIGN as usize != 0 {
+            return Err(MmapIoError::Misaligned {
+                required: ALIGN,
+                offset,
+            });
+        }
         unsafe {
             // ptr.add() is safe because:
             // - offset_usize is guaranteed to be within bounds (checked above)
@@ -220,44 +227,42 @@ impl MemoryMappedFile {
     ///
     /// # Errors
     ///
-    /// Returns `MmapIoError::Misaligned` if the offset is not 4-byte aligned.
+    /// Returns `MmapIoError::Misaligned` if the mapped address of `offset` is not 4-byte
+    /// aligned.
+    /// Returns `MmapIoError::InvalidMode` if the mapping is
+    /// [`guarded`](crate::mmap::MemoryMappedFileBuilder::guarded).
     /// Returns `MmapIoError::OutOfBounds` if the range exceeds file bounds.
     #[cfg(feature = "atomic")]
     pub fn atomic_u32_slice(&self, offset: u64, count: usize) -> Result<&[AtomicU32]> {
         const ALIGN: u64 = std::mem::align_of::<AtomicU32>() as u64;
         const SIZE: u64 = std::mem::size_of::<AtomicU32>() as u64;
 
-        // Check alignment
-        if offset % ALIGN != 0 {
-            return Err(MmapIoError::Misaligned {
-                required: ALIGN,
-                offset,
-            });
-        }
+        self.check_unguarded()?;
 
         // Check bounds
-        let total_size = SIZE * count as u64;
         let total = self.current_len()?;
-        if offset + total_size > total {
-            return Err(MmapIoError::OutOfBounds {
+        let total_size = u64::try_from(count)
+            .ok()
+            .and_then(|n| n.checked_mul(SIZE))
+            .ok_or(MmapIoError::OutOfBounds {
                 offset,
-                len: total_size,
+                len: u64::MAX,
                 total,
-            });
-        }
+            })?;
+        crate::utils::ensure_in_bounds(offset, total_size, total)?;
 
         // Get the base pointer for the mapping
         let ptr = match &self.inner.map {
             crate::mmap::MapVariant::Ro(m) => m.as_ptr(),
-            crate::mmap::MapVariant::Rw(lock) => {
+            crate::mmap::MapVariant::Rw(lock) | crate::mmap::MapVariant::Cow(lock) => {
                 let guard = lock.read();
+                self.check_unsplit()?;
                 guard.as_ptr()
             }
-            crate::mmap::MapVariant::Cow(m) => m.as_ptr(),
         };
 
         // SAFETY: Multiple invariants are guaranteed:
-        // 1. Alignment: We've verified offset % 4 == 0 (required for AtomicU32 array)
+        // 1. Alignment: We've verified the address is 4-byte aligned (required for AtomicU32 array)
         // 2. Bounds: We've verified offset + (count * 4) <= total file size
         // 3. Overflow: try_into() ensures offset fits in usize, preventing ptr arithmetic overflow
         // 4. Lifetime: The returned slice is bound to 'self', ensuring the mapping outlives it
@@ -268,6 +273,13 @@ impl MemoryMappedFile {
             len: total_size,
             total,
         })?;
+        // Check alignment of the address: a file window may start mid-page
+        if (ptr as usize).wrapping_add(offset_usize) % ALIGN as usize != 0 {
+            return Err(MmapIoError::Misaligned {
+                required: ALIGN,
+                offset,
+            });
+        }
         unsafe {
             // ptr.add() is safe because:
             // - offset_usize is guaranteed to be within bounds (checked above)
@@ -280,6 +292,87 @@ impl MemoryMappedFile {
             Ok(std::slice::from_raw_parts(atomic_ptr, count))
         }
     }
+
+    /// Get a 16-byte atomic view at the specified offset.
+    ///
+    /// Suited to pairs that must change together, such as a sequence number next to a
+    /// pointer or offset. The address must be 16-byte aligned. Only available where the CPU
+    /// has native 128-bit atomics (`cmpxchg16b` on x86_64, checked at run time; always on
+    /// aarch64), since a lock-based fallback would not be atomic across processes.
+    ///
+    /// # Safety
+    ///
+    /// The returned reference is valid for the lifetime of the memory mapping.
+    /// The caller must ensure that the memory at this offset is not concurrently
+    /// modified through non-atomic operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` if the mapped address is not 16-byte aligned.
+    /// Returns `MmapIoError::InvalidMode` if the mapping is
+    /// [`guarded`](crate::mmap::MemoryMappedFileBuilder::guarded).
+    /// Returns `MmapIoError::OutOfBounds` if the offset + 16 exceeds file bounds.
+    /// Returns `MmapIoError::InvalidMode` for ReadOnly mappings, because x86_64 implements
+    /// 128-bit loads with a compare-exchange that writes to the page.
+    /// Returns `MmapIoError::Unsupported` if the CPU lacks native 128-bit atomics.
+    #[cfg(all(
+        feature = "atomic128",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    pub fn atomic_u128(&self, offset: u64) -> Result<&AtomicU128> {
+        const ALIGN: u64 = std::mem::align_of::<AtomicU128>() as u64;
+        const SIZE: u64 = std::mem::size_of::<AtomicU128>() as u64;
+
+        self.check_unguarded()?;
+
+        if !AtomicU128::is_lock_free() {
+            return Err(MmapIoError::Unsupported(
+                "128-bit atomics need cmpxchg16b on this CPU",
+            ));
+        }
+
+        // Check bounds
+        let total = self.current_len()?;
+        crate::utils::ensure_in_bounds(offset, SIZE, total)?;
+
+        // Get the base pointer for the mapping
+        let ptr = match &self.inner.map {
+            crate::mmap::MapVariant::Ro(_) => {
+                return Err(MmapIoError::InvalidMode(
+                    "atomic_u128 requires a writable mapping",
+                ))
+            }
+            crate::mmap::MapVariant::Rw(lock) | crate::mmap::MapVariant::Cow(lock) => {
+                let guard = lock.read();
+                self.check_unsplit()?;
+                guard.as_ptr()
+            }
+        };
+
+        let offset_usize: usize = offset.try_into().map_err(|_| MmapIoError::OutOfBounds {
+            offset,
+            len: SIZE,
+            total,
+        })?;
+        // Check alignment of the address: a file window may start mid-page
+        if (ptr as usize).wrapping_add(offset_usize) % ALIGN as usize != 0 {
+            return Err(MmapIoError::Misaligned {
+                required: ALIGN,
+                offset,
+            });
+        }
+
+        // SAFETY: Multiple invariants are guaranteed:
+        // 1. Alignment: We've verified the address is 16-byte aligned (required for AtomicU128)
+        // 2. Bounds: We've verified offset + 16 <= total file size
+        // 3. Lifetime: The returned reference is bound to 'self', ensuring the mapping outlives it
+        // 4. Validity: The memory is mapped writable and valid for the entire file size
+        // 5. Atomicity: is_lock_free() confirmed native 128-bit atomic instructions
+        unsafe {
+            let addr = ptr.add(offset_usize);
+            Ok(&*(addr as *const AtomicU128))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -421,6 +514,63 @@ mod tests {
         fs::remove_file(&path).expect("cleanup");
     }
 
+    #[test]
+    #[cfg(feature = "atomic")]
+    fn test_atomic_bounds_near_u64_max() {
+        let path = tmp_path("atomic_overflow");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 64).expect("create");
+        // offset + SIZE and SIZE * count would wrap without checked arithmetic
+        for result in [
+            mmap.atomic_u64(u64::MAX - 7).map(|_| ()),
+            mmap.atomic_u32(u64::MAX - 3).map(|_| ()),
+            mmap.atomic_u64_slice(u64::MAX - 7, 2).map(|_| ()),
+            mmap.atomic_u64_slice(8, usize::MAX).map(|_| ()),
+            mmap.atomic_u32_slice(4, usize::MAX / 2).map(|_| ()),
+        ] {
+            assert!(matches!(result, Err(MmapIoError::OutOfBounds { .. })));
+        }
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    #[cfg(feature = "atomic")]
+    fn test_atomic_in_unaligned_window() {
+        let path = tmp_path("atomic_window");
+        let ps = crate::utils::page_size() as u64;
+        fs::write(&path, vec![0u8; 3 * ps as usize]).expect("write file");
+
+        // The window starts 100 bytes into a page, so offset 0 is only 4-byte aligned
+        let mmap = MemoryMappedFile::builder(&path)
+            .mode(crate::MmapMode::ReadWrite)
+            .offset(ps + 100)
+            .len(1000)
+            .open()
+            .expect("open window");
+        for result in [
+            mmap.atomic_u64(0).map(|_| ()),
+            mmap.atomic_u64(8).map(|_| ()),
+            mmap.atomic_u64_slice(0, 2).map(|_| ()),
+        ] {
+            assert!(matches!(
+                result,
+                Err(MmapIoError::Misaligned { required: 8, .. })
+            ));
+        }
+        mmap.atomic_u64(4)
+            .expect("aligned address")
+            .store(7, Ordering::SeqCst);
+        mmap.atomic_u32(0).expect("u32").store(9, Ordering::SeqCst);
+        assert_eq!(mmap.atomic_u32_slice(0, 2).expect("u32 slice").len(), 2);
+        assert_eq!(mmap.read_u64_le(4).expect("read"), 7);
+        assert_eq!(mmap.read_u32_le(0).expect("read"), 9);
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
     #[test]
     #[cfg(feature = "atomic")]
     fn test_atomic_with_different_modes() {
@@ -487,4 +637,59 @@ mod tests {
 
         fs::remove_file(&path).expect("cleanup");
     }
+
+    #[test]
+    #[cfg(all(
+        feature = "atomic128",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn test_atomic_u128_pair_update() {
+        let path = tmp_path("atomic_u128");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 64).expect("create");
+        if !AtomicU128::is_lock_free() {
+            assert!(matches!(
+                mmap.atomic_u128(0),
+                Err(MmapIoError::Unsupported(_))
+            ));
+            drop(mmap);
+            fs::remove_file(&path).expect("cleanup");
+            return;
+        }
+
+        // Sequence number in the high half, offset in the low half
+        let pair = mmap.atomic_u128(16).expect("atomic at 16");
+        pair.store((1u128 << 64) | 0x1000, Ordering::SeqCst);
+        let seen = pair.load(Ordering::SeqCst);
+        pair.compare_exchange(
+            seen,
+            (2u128 << 64) | 0x2000,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        )
+        .expect("cas");
+        assert!(pair
+            .compare_exchange(seen, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err());
+        let mut raw = [0u8; 16];
+        mmap.read_into(16, &mut raw).expect("read");
+        assert_eq!(u128::from_ne_bytes(raw), (2u128 << 64) | 0x2000);
+
+        assert!(matches!(
+            mmap.atomic_u128(8),
+            Err(MmapIoError::Misaligned { required: 16, .. })
+        ));
+        assert!(mmap.atomic_u128(64).is_err());
+        drop(mmap);
+
+        let ro = crate::MemoryMappedFile::open_ro(&path).expect("open ro");
+        assert!(matches!(
+            ro.atomic_u128(0),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
 }
@@ -0,0 +1,281 @@
+//! Persistent Bloom filter stored in a mapped region.
+//!
+//! Bits live in `u64` words updated with atomic `fetch_or`, so any number of threads (or
+//! processes sharing the file) can insert and query concurrently without locks. Hashing is a
+//! fixed, seedless function, so a filter written by one build can be read by another.
+//!
+//! Region layout (integers little-endian):
+//!
+//! | bytes  | field                                  |
+//! |--------|----------------------------------------|
+//! | 0..8   | magic `MMIOBLOM`                       |
+//! | 8..16  | number of bits                         |
+//! | 16..20 | number of hash functions               |
+//! | 20..24 | reserved                               |
+//! | 24..   | bit words, `ceil(bits / 64)` × `u64`   |
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+/// Size of the filter header preceding the bit words.
+pub const BLOOM_HEADER_SIZE: u64 = 24;
+
+const MAGIC: &[u8; 8] = b"MMIOBLOM";
+
+/// Bloom filter over byte keys, persisted in a mapping.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, bloom::BloomFilter};
+///
+/// let (bits, hashes) = BloomFilter::optimal_params(100_000, 0.01);
+/// let size = BloomFilter::region_size(bits);
+/// let mmap = Arc::new(MemoryMappedFile::create_rw("seen.bloom", size)?);
+/// let filter = BloomFilter::create_in(mmap, 0, bits, hashes)?;
+/// filter.insert(b"https://example.com/a")?;
+/// assert!(filter.contains(b"https://example.com/a")?);
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    mmap: Arc<MemoryMappedFile>,
+    offset: u64,
+    bits: u64,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// Initialize an empty filter of `bits` bits and `hashes` hash functions at `offset`.
+    ///
+    /// `offset` must be 8-byte aligned and the mapping must hold
+    /// [`region_size(bits)`](Self::region_size) bytes from there.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if `bits` or `hashes` is zero or the mapping is not
+    /// ReadWrite, `MmapIoError::Misaligned` for an unaligned offset, and
+    /// `MmapIoError::OutOfBounds` if the region does not fit.
+    pub fn create_in(
+        mmap: Arc<MemoryMappedFile>,
+        offset: u64,
+        bits: u64,
+        hashes: u32,
+    ) -> Result<Self> {
+        if bits == 0 || hashes == 0 {
+            return Err(MmapIoError::InvalidMode(
+                "Bloom filter needs at least one bit and one hash",
+            ));
+        }
+        check_alignment(offset)?;
+        crate::utils::ensure_in_bounds(offset, Self::region_size(bits), mmap.len())?;
+
+        let mut header = [0u8; BLOOM_HEADER_SIZE as usize];
+        header[8..16].copy_from_slice(&bits.to_le_bytes());
+        header[16..20].copy_from_slice(&hashes.to_le_bytes());
+        mmap.update_region(offset, &header)?;
+        mmap.fill_region(offset + BLOOM_HEADER_SIZE, word_count(bits) * 8, 0)?;
+        // Publish the magic last so a torn initialization is not mistaken for a filter
+        mmap.update_region(offset, MAGIC)?;
+        Ok(Self {
+            mmap,
+            offset,
+            bits,
+            hashes,
+        })
+    }
+
+    /// Open a filter previously created at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the header is missing or invalid.
+    pub fn open_in(mmap: Arc<MemoryMappedFile>, offset: u64) -> Result<Self> {
+        check_alignment(offset)?;
+        let mut header = [0u8; BLOOM_HEADER_SIZE as usize];
+        mmap.read_into(offset, &mut header)?;
+        if &header[0..8] != MAGIC {
+            return Err(MmapIoError::Corrupt("missing Bloom filter magic".into()));
+        }
+        let mut bits = [0u8; 8];
+        bits.copy_from_slice(&header[8..16]);
+        let bits = u64::from_le_bytes(bits);
+        let hashes = u32::from_le_bytes([header[16], header[17], header[18], header[19]]);
+        if bits == 0 || hashes == 0 {
+            return Err(MmapIoError::Corrupt(
+                "Bloom filter header has zero bits or hashes".into(),
+            ));
+        }
+        crate::utils::ensure_in_bounds(offset, Self::region_size(bits), mmap.len())?;
+        Ok(Self {
+            mmap,
+            offset,
+            bits,
+            hashes,
+        })
+    }
+
+    /// Bytes needed for a filter of `bits` bits, including the header.
+    #[must_use]
+    pub fn region_size(bits: u64) -> u64 {
+        BLOOM_HEADER_SIZE + word_count(bits) * 8
+    }
+
+    /// Bit count and hash count for `expected_items` at false-positive rate `fp_rate`.
+    #[must_use]
+    pub fn optimal_params(expected_items: u64, fp_rate: f64) -> (u64, u32) {
+        let n = expected_items.max(1) as f64;
+        let p = fp_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-(n * p.ln()) / (ln2 * ln2)).ceil().max(64.0);
+        let hashes = ((bits / n) * ln2).round().clamp(1.0, 32.0);
+        (bits as u64, hashes as u32)
+    }
+
+    /// Add `key` to the filter. Returns `true` if any bit was newly set (the key was
+    /// definitely not present before).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the mapping is read-only.
+    pub fn insert(&self, key: &[u8]) -> Result<bool> {
+        if self.mmap.mode() != crate::MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(
+                "Bloom filter insert requires ReadWrite mode",
+            ));
+        }
+        let mut added = false;
+        for bit in self.bit_positions(key) {
+            let mask = 1u64 << (bit % 64);
+            let prev = self.word(bit)?.fetch_or(mask, Ordering::AcqRel);
+            added |= prev & mask == 0;
+        }
+        Ok(added)
+    }
+
+    /// Whether `key` may be in the filter (false positives possible, no false negatives).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the mapping shrank below the filter.
+    pub fn contains(&self, key: &[u8]) -> Result<bool> {
+        for bit in self.bit_positions(key) {
+            if self.word(bit)?.load(Ordering::Acquire) & (1u64 << (bit % 64)) == 0 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Number of bits in the filter.
+    #[must_use]
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Number of hash functions.
+    #[must_use]
+    pub fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    fn word(&self, bit: u64) -> Result<&std::sync::atomic::AtomicU64> {
+        self.mmap
+            .atomic_u64(self.offset + BLOOM_HEADER_SIZE + (bit / 64) * 8)
+    }
+
+    // Kirsch-Mitzenmacher double hashing: h1 + i * h2.
+    fn bit_positions(&self, key: &[u8]) -> impl Iterator<Item = u64> {
+        let h1 = hash64(key, 0x9E37_79B9_7F4A_7C15);
+        let h2 = hash64(key, 0xC2B2_AE3D_27D4_EB4F) | 1;
+        let bits = self.bits;
+        (0..u64::from(self.hashes)).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bits)
+    }
+}
+
+fn word_count(bits: u64) -> u64 {
+    bits.div_ceil(64)
+}
+
+fn check_alignment(offset: u64) -> Result<()> {
+    if offset % 8 != 0 {
+        return Err(MmapIoError::Misaligned {
+            required: 8,
+            offset,
+        });
+    }
+    Ok(())
+}
+
+// FNV-1a with a seeded basis, finished with the murmur3 fmix64 avalanche.
+fn hash64(data: &[u8], seed: u64) -> u64 {
+    let mut h = 0xCBF2_9CE4_8422_2325 ^ seed;
+    for &b in data {
+        h ^= u64::from(b);
+        h = h.wrapping_mul(0x0100_0000_01B3);
+    }
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    h ^ (h >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_bloom_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_bloom_insert_contains_and_reopen() {
+        let path = tmp_path("filter");
+        let _ = fs::remove_file(&path);
+
+        let (bits, hashes) = BloomFilter::optimal_params(1000, 0.01);
+        assert_eq!(hashes, 7);
+        let mmap =
+            Arc::new(create_mmap(&path, 64 + BloomFilter::region_size(bits)).expect("create"));
+        let filter = BloomFilter::create_in(mmap.clone(), 64, bits, hashes).expect("create filter");
+
+        // A fresh key usually sets new bits; a few may already be covered (false positives)
+        let newly_added = (0..1000u32)
+            .filter(|i| filter.insert(&i.to_le_bytes()).expect("insert"))
+            .count();
+        assert!(newly_added > 950, "too few new keys: {newly_added}");
+        assert!(!filter.insert(&7u32.to_le_bytes()).expect("insert again"));
+        for i in 0..1000u32 {
+            assert!(filter.contains(&i.to_le_bytes()).expect("contains"));
+        }
+        let false_positives = (1000..11_000u32)
+            .filter(|i| filter.contains(&i.to_le_bytes()).expect("contains"))
+            .count();
+        assert!(false_positives < 300, "fp rate too high: {false_positives}");
+
+        let reopened = BloomFilter::open_in(mmap.clone(), 64).expect("open");
+        assert_eq!((reopened.bits(), reopened.hashes()), (bits, hashes));
+        assert!(reopened.contains(&999u32.to_le_bytes()).expect("contains"));
+
+        assert!(BloomFilter::open_in(mmap.clone(), 0).is_err());
+        assert!(BloomFilter::create_in(mmap.clone(), 4, 64, 1).is_err());
+        assert!(BloomFilter::create_in(mmap.clone(), 64, bits * 2, 3).is_err());
+
+        drop((filter, reopened, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,646 @@
+//! Read-optimized, bulk-loaded B-tree index persisted in a mapped file.
+//!
+//! A [`BTreeIndex`] maps fixed-size byte keys to `u64` values. It is built once from sorted
+//! input with [`BTreeIndex::bulk_load`], which packs leaves and internal nodes into full pages,
+//! and is then queried with point lookups and ordered range scans. Keys compare as raw bytes,
+//! so encode integers big-endian to get numeric order.
+//!
+//! The header page is written and flushed only after every node page has been flushed, so a
+//! build interrupted by a crash leaves no openable (half-built) index behind.
+//!
+//! Layout, starting at the base offset (integers little-endian):
+//!
+//! | page  | contents                                                                  |
+//! |-------|---------------------------------------------------------------------------|
+//! | 0     | header: magic `MMIOBTRE`, version, key_len, page_size, height, count, root page, first leaf page, leaf page count, total pages |
+//! | 1..   | leaves (contiguous, in key order), then internal levels bottom-up         |
+//!
+//! Every node page starts with an 8-byte header (kind, reserved, entry count `u16`, reserved)
+//! followed by `key_len + 8`-byte entries: `(key, value)` in leaves and
+//! `(first key of child, child page)` in internal nodes.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+/// Size in bytes of every page (header and nodes) of the index.
+pub const BTREE_PAGE_SIZE: u64 = 4096;
+
+const MAGIC: &[u8; 8] = b"MMIOBTRE";
+const FORMAT_VERSION: u32 = 1;
+const NODE_HEADER: usize = 8;
+const KIND_LEAF: u8 = 1;
+const KIND_INTERNAL: u8 = 2;
+
+/// Immutable B-tree index over fixed-size keys stored in a mapping.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, btree::BTreeIndex};
+///
+/// let mmap = Arc::new(MemoryMappedFile::create_rw("index.bin", 4096)?);
+/// let entries = (0u64..1000).map(|k| (k.to_be_bytes(), k * 10));
+/// let index = BTreeIndex::bulk_load(mmap, 0, 8, entries)?;
+/// assert_eq!(index.get(&42u64.to_be_bytes())?, Some(420));
+/// for entry in index.range(&10u64.to_be_bytes(), &20u64.to_be_bytes())? {
+///     let (key, value) = entry?;
+///     # let _ = (key, value);
+/// }
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BTreeIndex {
+    mmap: Arc<MemoryMappedFile>,
+    base: u64,
+    key_len: usize,
+    height: u32,
+    count: u64,
+    root: u64,
+    first_leaf: u64,
+    leaf_pages: u64,
+    total_pages: u64,
+}
+
+impl BTreeIndex {
+    /// Build an index at `base` from `entries`, which must be sorted by key with no duplicates.
+    ///
+    /// The file is grown as needed; pages are flushed before the header is published.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if a key has the wrong length or keys are not strictly
+    /// increasing, and `MmapIoError::InvalidMode` if `key_len` is zero or too large for a page.
+    /// Returns errors from resizing, writing, or flushing the mapping.
+    pub fn bulk_load<I, K>(
+        mmap: Arc<MemoryMappedFile>,
+        base: u64,
+        key_len: usize,
+        entries: I,
+    ) -> Result<Self>
+    where
+        I: IntoIterator<Item = (K, u64)>,
+        K: AsRef<[u8]>,
+    {
+        if key_len == 0 || capacity(key_len) < 2 {
+            return Err(MmapIoError::InvalidMode(
+                "B-tree key length must fit at least two entries per page",
+            ));
+        }
+        // Invalidate any previous index at `base` before its pages are overwritten
+        if mmap.len() >= base + MAGIC.len() as u64 {
+            mmap.update_region(base, &[0u8; 8])?;
+            mmap.flush_range(base, MAGIC.len() as u64)?;
+        }
+        let mut builder = PageWriter {
+            mmap: &mmap,
+            base,
+            key_len,
+            next_page: 1,
+            original_len: mmap.len(),
+        };
+
+        // Leaves, collecting (first key, page) for the level above
+        let mut level: Vec<(Vec<u8>, u64)> = Vec::new();
+        let mut page = NodePage::new(KIND_LEAF, key_len);
+        let mut prev: Option<Vec<u8>> = None;
+        let mut count = 0u64;
+        for (key, value) in entries {
+            let key = key.as_ref();
+            if key.len() != key_len {
+                return Err(MmapIoError::Corrupt(format!(
+                    "B-tree key of {} bytes, expected {key_len}",
+                    key.len()
+                )));
+            }
+            if prev.as_deref().is_some_and(|p| p >= key) {
+                return Err(MmapIoError::Corrupt(
+                    "B-tree bulk load input is not strictly increasing".into(),
+                ));
+            }
+            if page.is_full() {
+                level.push(builder.write(&page)?);
+                page = NodePage::new(KIND_LEAF, key_len);
+            }
+            page.push(key, value);
+            prev = Some(key.to_vec());
+            count += 1;
+        }
+        if !page.is_empty() {
+            level.push(builder.write(&page)?);
+        }
+        let first_leaf = 1;
+        let leaf_pages = level.len() as u64;
+
+        // Internal levels until a single root remains
+        let mut height = u32::from(!level.is_empty());
+        while level.len() > 1 {
+            let mut next = Vec::new();
+            let mut page = NodePage::new(KIND_INTERNAL, key_len);
+            for (key, child) in &level {
+                if page.is_full() {
+                    next.push(builder.write(&page)?);
+                    page = NodePage::new(KIND_INTERNAL, key_len);
+                }
+                page.push(key, *child);
+            }
+            next.push(builder.write(&page)?);
+            level = next;
+            height += 1;
+        }
+        let root = level.first().map_or(0, |(_, p)| *p);
+        let total_pages = builder.next_page;
+        builder.finish()?;
+
+        let index = Self {
+            mmap,
+            base,
+            key_len,
+            height,
+            count,
+            root,
+            first_leaf,
+            leaf_pages,
+            total_pages,
+        };
+        index.write_header()?;
+        Ok(index)
+    }
+
+    /// Open an index previously built at `base`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the header is missing or has an unsupported version.
+    pub fn open(mmap: Arc<MemoryMappedFile>, base: u64) -> Result<Self> {
+        let mut header = [0u8; 64];
+        mmap.read_into(base, &mut header)?;
+        if &header[0..8] != MAGIC {
+            return Err(MmapIoError::Corrupt("missing B-tree magic".into()));
+        }
+        let version = read_u32(&header, 8);
+        if version != FORMAT_VERSION {
+            return Err(MmapIoError::Corrupt(format!(
+                "unsupported B-tree format version {version}"
+            )));
+        }
+        let page_size = u64::from(read_u32(&header, 16));
+        if page_size != BTREE_PAGE_SIZE {
+            return Err(MmapIoError::Corrupt(format!(
+                "unsupported B-tree page size {page_size}"
+            )));
+        }
+        let key_len = read_u32(&header, 12) as usize;
+        if key_len == 0 || capacity(key_len) < 2 {
+            return Err(MmapIoError::Corrupt(format!(
+                "invalid B-tree key length {key_len}"
+            )));
+        }
+        let index = Self {
+            mmap,
+            base,
+            key_len,
+            height: read_u32(&header, 20),
+            count: read_u64(&header, 24),
+            root: read_u64(&header, 32),
+            first_leaf: read_u64(&header, 40),
+            leaf_pages: read_u64(&header, 48),
+            total_pages: read_u64(&header, 56),
+        };
+        let size = index
+            .total_pages
+            .checked_mul(BTREE_PAGE_SIZE)
+            .ok_or_else(|| MmapIoError::Corrupt("B-tree page count overflows".into()))?;
+        crate::utils::ensure_in_bounds(base, size, index.mmap.len())?;
+        Ok(index)
+    }
+
+    /// Look up the value stored for `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if `key` has the wrong length or a node is malformed.
+    pub fn get(&self, key: &[u8]) -> Result<Option<u64>> {
+        self.check_key(key)?;
+        let Some((page, slot)) = self.lower_bound(key)? else {
+            return Ok(None);
+        };
+        let node = self.read_node(page)?;
+        Ok((slot < node.len() && node.key(slot) == key).then(|| node.value(slot)))
+    }
+
+    /// Iterate over entries with `start <= key < end`, in key order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if a bound has the wrong length. Errors while reading
+    /// later pages are yielded by the iterator.
+    pub fn range(&self, start: &[u8], end: &[u8]) -> Result<BTreeRange<'_>> {
+        self.check_key(start)?;
+        self.check_key(end)?;
+        let position = self.lower_bound(start)?;
+        Ok(BTreeRange {
+            index: self,
+            position,
+            end: end.to_vec(),
+            node: None,
+        })
+    }
+
+    /// Iterate over every entry in key order.
+    pub fn iter(&self) -> BTreeRange<'_> {
+        BTreeRange {
+            index: self,
+            position: (self.leaf_pages > 0).then_some((self.first_leaf, 0)),
+            end: vec![0xFF; self.key_len],
+            node: None,
+        }
+        .inclusive_end()
+    }
+
+    /// Number of entries.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    /// Whether the index holds no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Key length in bytes.
+    #[must_use]
+    pub fn key_len(&self) -> usize {
+        self.key_len
+    }
+
+    /// Number of levels (0 for an empty index, 1 when the root is a leaf).
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Bytes occupied by the index, starting at its base offset.
+    #[must_use]
+    pub fn size_bytes(&self) -> u64 {
+        self.total_pages * BTREE_PAGE_SIZE
+    }
+
+    // Leaf page and slot of the first entry with key >= `key`, or None if past the end.
+    fn lower_bound(&self, key: &[u8]) -> Result<Option<(u64, usize)>> {
+        if self.height == 0 {
+            return Ok(None);
+        }
+        let mut page = self.root;
+        for _ in 1..self.height {
+            let node = self.read_node(page)?;
+            if node.kind() != KIND_INTERNAL || node.is_empty() {
+                return Err(MmapIoError::Corrupt(format!(
+                    "B-tree page {page} is not an internal node"
+                )));
+            }
+            // Last child whose first key is <= key; keys below the first go to child 0
+            let idx = node.partition_point(key, |c| c != Ordering::Greater);
+            page = node.value(idx.saturating_sub(1));
+        }
+        let node = self.read_node(page)?;
+        if node.kind() != KIND_LEAF {
+            return Err(MmapIoError::Corrupt(format!(
+                "B-tree page {page} is not a leaf"
+            )));
+        }
+        let slot = node.partition_point(key, |c| c == Ordering::Less);
+        if slot == node.len() {
+            let next = page + 1;
+            return Ok((next < self.first_leaf + self.leaf_pages).then_some((next, 0)));
+        }
+        Ok(Some((page, slot)))
+    }
+
+    fn read_node(&self, page: u64) -> Result<Node> {
+        if page == 0 || page >= self.total_pages {
+            return Err(MmapIoError::Corrupt(format!(
+                "B-tree page {page} out of range"
+            )));
+        }
+        let mut buf = vec![0u8; BTREE_PAGE_SIZE as usize];
+        self.mmap
+            .read_into(self.base + page * BTREE_PAGE_SIZE, &mut buf)?;
+        let node = Node {
+            buf,
+            key_len: self.key_len,
+        };
+        if node.len() > capacity(self.key_len) {
+            return Err(MmapIoError::Corrupt(format!(
+                "B-tree page {page} has too many entries"
+            )));
+        }
+        Ok(node)
+    }
+
+    fn check_key(&self, key: &[u8]) -> Result<()> {
+        if key.len() != self.key_len {
+            return Err(MmapIoError::Corrupt(format!(
+                "B-tree key of {} bytes, expected {}",
+                key.len(),
+                self.key_len
+            )));
+        }
+        Ok(())
+    }
+
+    fn write_header(&self) -> Result<()> {
+        let mut header = [0u8; 64];
+        header[0..8].copy_from_slice(MAGIC);
+        header[8..12].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        header[12..16].copy_from_slice(&(self.key_len as u32).to_le_bytes());
+        header[16..20].copy_from_slice(&(BTREE_PAGE_SIZE as u32).to_le_bytes());
+        header[20..24].copy_from_slice(&self.height.to_le_bytes());
+        header[24..32].copy_from_slice(&self.count.to_le_bytes());
+        header[32..40].copy_from_slice(&self.root.to_le_bytes());
+        header[40..48].copy_from_slice(&self.first_leaf.to_le_bytes());
+        header[48..56].copy_from_slice(&self.leaf_pages.to_le_bytes());
+        header[56..64].copy_from_slice(&self.total_pages.to_le_bytes());
+        self.mmap.update_region(self.base, &header)?;
+        self.mmap.flush_range(self.base, BTREE_PAGE_SIZE)
+    }
+}
+
+/// Ordered iterator over a key range of a [`BTreeIndex`].
+#[derive(Debug)]
+pub struct BTreeRange<'a> {
+    index: &'a BTreeIndex,
+    position: Option<(u64, usize)>,
+    end: Vec<u8>,
+    // Currently loaded leaf (page number and contents)
+    node: Option<(u64, Node)>,
+}
+
+impl BTreeRange<'_> {
+    // Used by `iter`: an all-0xFF end bound must still include an all-0xFF key.
+    fn inclusive_end(mut self) -> Self {
+        self.end.push(0);
+        self
+    }
+}
+
+impl Iterator for BTreeRange<'_> {
+    type Item = Result<(Vec<u8>, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (page, slot) = self.position?;
+        if self.node.as_ref().map(|(p, _)| *p) != Some(page) {
+            match self.index.read_node(page) {
+                Ok(node) => self.node = Some((page, node)),
+                Err(e) => {
+                    self.position = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+        let (_, node) = self.node.as_ref()?;
+        let key = node.key(slot);
+        if key >= self.end.as_slice() {
+            self.position = None;
+            return None;
+        }
+        let item = (key.to_vec(), node.value(slot));
+        self.position = if slot + 1 < node.len() {
+            Some((page, slot + 1))
+        } else if page + 1 < self.index.first_leaf + self.index.leaf_pages {
+            Some((page + 1, 0))
+        } else {
+            None
+        };
+        Some(Ok(item))
+    }
+}
+
+// Sequential page allocator used during bulk load.
+struct PageWriter<'a> {
+    mmap: &'a MemoryMappedFile,
+    base: u64,
+    key_len: usize,
+    next_page: u64,
+    original_len: u64,
+}
+
+impl PageWriter<'_> {
+    fn write(&mut self, page: &NodePage) -> Result<(Vec<u8>, u64)> {
+        let idx = self.next_page;
+        let end = self.base + (idx + 1) * BTREE_PAGE_SIZE;
+        let len = self.mmap.len();
+        if end > len {
+            // Grow geometrically to avoid a remap per page
+            self.mmap.resize(end.max(len.saturating_mul(2)))?;
+        }
+        self.mmap
+            .update_region(self.base + idx * BTREE_PAGE_SIZE, &page.buf)?;
+        self.next_page += 1;
+        Ok((
+            page.buf[NODE_HEADER..NODE_HEADER + self.key_len].to_vec(),
+            idx,
+        ))
+    }
+
+    // Size the file to the index (trimming geometric growth, reserving the header page)
+    // and make node pages durable before the header is written.
+    fn finish(&self) -> Result<()> {
+        let end = self.base + self.next_page * BTREE_PAGE_SIZE;
+        let target = end.max(self.original_len);
+        if self.mmap.len() != target {
+            self.mmap.resize(target)?;
+        }
+        self.mmap.flush()
+    }
+}
+
+// Node page being filled during bulk load.
+struct NodePage {
+    buf: Vec<u8>,
+    key_len: usize,
+    len: usize,
+}
+
+impl NodePage {
+    fn new(kind: u8, key_len: usize) -> Self {
+        let mut buf = vec![0u8; BTREE_PAGE_SIZE as usize];
+        buf[0] = kind;
+        Self {
+            buf,
+            key_len,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, key: &[u8], value: u64) {
+        let at = NODE_HEADER + self.len * (self.key_len + 8);
+        self.buf[at..at + self.key_len].copy_from_slice(key);
+        self.buf[at + self.key_len..at + self.key_len + 8].copy_from_slice(&value.to_le_bytes());
+        self.len += 1;
+        self.buf[2..4].copy_from_slice(&(self.len as u16).to_le_bytes());
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == capacity(self.key_len)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+// Node page read back from the mapping.
+#[derive(Debug)]
+struct Node {
+    buf: Vec<u8>,
+    key_len: usize,
+}
+
+impl Node {
+    fn kind(&self) -> u8 {
+        self.buf[0]
+    }
+
+    fn len(&self) -> usize {
+        u16::from_le_bytes([self.buf[2], self.buf[3]]) as usize
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn key(&self, slot: usize) -> &[u8] {
+        let at = NODE_HEADER + slot * (self.key_len + 8);
+        &self.buf[at..at + self.key_len]
+    }
+
+    fn value(&self, slot: usize) -> u64 {
+        read_u64(
+            &self.buf,
+            NODE_HEADER + slot * (self.key_len + 8) + self.key_len,
+        )
+    }
+
+    // First slot for which `pred(entry_key.cmp(key))` is false.
+    fn partition_point(&self, key: &[u8], pred: impl Fn(Ordering) -> bool) -> usize {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if pred(self.key(mid).cmp(key)) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+}
+
+fn capacity(key_len: usize) -> usize {
+    (BTREE_PAGE_SIZE as usize - NODE_HEADER) / (key_len + 8)
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    let mut b = [0u8; 4];
+    b.copy_from_slice(&buf[at..at + 4]);
+    u32::from_le_bytes(b)
+}
+
+fn read_u64(buf: &[u8], at: usize) -> u64 {
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&buf[at..at + 8]);
+    u64::from_le_bytes(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_btree_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_bulk_load_lookup_and_range() {
+        let path = tmp_path("bulk");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 4096).expect("create"));
+        // Even keys only, enough for three levels (255 entries per page)
+        let n = 70_000u64;
+        let entries = (0..n).map(|i| ((i * 2).to_be_bytes(), i));
+        let index = BTreeIndex::bulk_load(mmap.clone(), 4096, 8, entries).expect("bulk load");
+        assert_eq!(index.len(), n);
+        assert_eq!(index.height(), 3);
+        assert_eq!(mmap.len(), 4096 + index.size_bytes());
+
+        assert_eq!(index.get(&0u64.to_be_bytes()).expect("get"), Some(0));
+        assert_eq!(
+            index.get(&77_778u64.to_be_bytes()).expect("get"),
+            Some(38_889)
+        );
+        assert_eq!(index.get(&77_777u64.to_be_bytes()).expect("get"), None);
+        assert_eq!(index.get(&(n * 2).to_be_bytes()).expect("get"), None);
+        assert!(index.get(&[0u8; 4]).is_err());
+
+        // Range crosses a leaf boundary and starts between keys
+        let got: Vec<u64> = index
+            .range(&501u64.to_be_bytes(), &521u64.to_be_bytes())
+            .expect("range")
+            .map(|e| e.expect("entry").1)
+            .collect();
+        assert_eq!(got, (251..=260).collect::<Vec<_>>());
+        assert_eq!(index.iter().count() as u64, n);
+
+        let reopened = BTreeIndex::open(mmap.clone(), 4096).expect("open");
+        assert_eq!(
+            reopened.get(&1000u64.to_be_bytes()).expect("get"),
+            Some(500)
+        );
+        assert!(BTreeIndex::open(mmap.clone(), 0).is_err());
+
+        let unsorted = [(2u64.to_be_bytes(), 0), (1u64.to_be_bytes(), 1)];
+        assert!(BTreeIndex::bulk_load(mmap.clone(), 0, 8, unsorted).is_err());
+
+        drop((index, reopened, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_empty_and_single_leaf() {
+        let path = tmp_path("small");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 8192).expect("create"));
+        let empty = BTreeIndex::bulk_load(mmap.clone(), 0, 4, std::iter::empty::<([u8; 4], u64)>())
+            .expect("empty");
+        assert!(empty.is_empty());
+        assert_eq!(empty.get(b"abcd").expect("get"), None);
+        assert_eq!(empty.iter().count(), 0);
+
+        let keys = [*b"aaaa", *b"bbbb", [0xFF; 4]];
+        let index = BTreeIndex::bulk_load(mmap.clone(), 0, 4, keys.iter().zip(1..)).expect("small");
+        assert_eq!(index.height(), 1);
+        assert_eq!(index.get(&[0xFF; 4]).expect("get"), Some(3));
+        assert_eq!(index.iter().count(), 3);
+        assert_eq!(mmap.len(), 8192);
+
+        drop((empty, index, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
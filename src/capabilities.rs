@@ -0,0 +1,296 @@
+//! Runtime detection of optional OS facilities.
+//!
+//! Several features of this crate depend on what the running kernel, filesystem, and
+//! resource limits allow, not just on the target platform: huge pages may be disabled,
+//! `RLIMIT_MEMLOCK` may be tiny, `io_uring` or `userfaultfd` may be blocked by a sysctl or
+//! seccomp profile. [`capabilities`] probes these once so callers can pick a strategy up
+//! front instead of decoding platform-specific errors. APIs that find a facility missing
+//! at run time report it as [`MmapIoError::Unsupported`](crate::MmapIoError::Unsupported).
+//!
+//! Filesystem properties ([`Capabilities::sparse_files`], [`Capabilities::reflink`]) are
+//! probed in [`std::env::temp_dir`]; other filesystems may differ.
+
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+/// What the running system supports, as reported by [`capabilities`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Huge pages can back mappings (transparent huge pages not set to `never` on Linux,
+    /// a non-zero large-page minimum on Windows).
+    pub huge_pages: bool,
+    /// Size of a huge page in bytes, if known.
+    pub huge_page_size: Option<u64>,
+    /// Pages can be locked in memory with `mlock` / `VirtualLock`.
+    pub mlock: bool,
+    /// Current soft `RLIMIT_MEMLOCK` in bytes; `None` if unlimited or not applicable.
+    pub memlock_limit: Option<u64>,
+    /// `MAP_POPULATE` pre-faulting is available (Linux).
+    pub populate: bool,
+    /// Growing a file leaves unallocated holes instead of writing zeros.
+    pub sparse_files: bool,
+    /// Files can be cloned by sharing extents (`FICLONE` on Linux).
+    pub reflink: bool,
+    /// The `io_uring` interface can be set up (Linux).
+    pub io_uring: bool,
+    /// A `userfaultfd` can be opened by this process (Linux).
+    pub userfaultfd: bool,
+    /// Anonymous files can be created with `memfd_create` (Linux).
+    pub memfd: bool,
+}
+
+/// Probe the running system for optional facilities.
+///
+/// Each call re-probes (creating and removing small temporary files), so call it once at
+/// startup and keep the result.
+#[must_use]
+pub fn capabilities() -> Capabilities {
+    let (huge_pages, huge_page_size) = sys::huge_pages();
+    let (mlock, memlock_limit) = sys::memlock();
+    Capabilities {
+        huge_pages,
+        huge_page_size,
+        mlock,
+        memlock_limit,
+        populate: cfg!(target_os = "linux"),
+        sparse_files: with_probe_files(sys::sparse_files),
+        reflink: with_probe_files(sys::reflink),
+        io_uring: sys::io_uring(),
+        userfaultfd: sys::userfaultfd(),
+        memfd: sys::memfd(),
+    }
+}
+
+// Run `probe` on a fresh source and destination file in the temp dir, removing both after.
+fn with_probe_files(probe: fn(&File, &File) -> bool) -> bool {
+    let dir = std::env::temp_dir();
+    let name = |tag: &str| -> PathBuf {
+        dir.join(format!(
+            "mmap_io_caps_{tag}_{}_{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ))
+    };
+    let (src_path, dst_path) = (name("src"), name("dst"));
+    let result = match (File::create(&src_path), File::create(&dst_path)) {
+        (Ok(src), Ok(dst)) => probe(&src, &dst),
+        _ => false,
+    };
+    let _ = fs::remove_file(src_path);
+    let _ = fs::remove_file(dst_path);
+    result
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::fs::File;
+
+    pub(super) fn memlock() -> (bool, Option<u64>) {
+        let mut lim = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit writes into the struct we pass.
+        if unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut lim) } != 0 {
+            return (false, None);
+        }
+        if lim.rlim_cur == libc::RLIM_INFINITY {
+            return (true, None);
+        }
+        // SAFETY: geteuid has no preconditions.
+        let privileged = unsafe { libc::geteuid() } == 0;
+        // rlim_t is narrower than u64 on some targets
+        #[allow(clippy::unnecessary_cast)]
+        let limit = lim.rlim_cur as u64;
+        (limit > 0 || privileged, Some(limit))
+    }
+
+    pub(super) fn sparse_files(src: &File, _dst: &File) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        const PROBE_LEN: u64 = 16 * 1024 * 1024;
+        src.set_len(PROBE_LEN)
+            .and_then(|()| src.metadata())
+            .is_ok_and(|meta| meta.blocks() * 512 < PROBE_LEN)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) use linux::*;
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) use other::*;
+
+    #[cfg(target_os = "linux")]
+    mod linux {
+        use std::fs::{self, File};
+        use std::io::Write;
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        // _IOW(0x94, 9, int)
+        const FICLONE: libc::c_ulong = 0x4004_9409;
+        // sizeof(struct io_uring_params)
+        const IO_URING_PARAMS_LEN: usize = 120;
+
+        pub(crate) fn huge_pages() -> (bool, Option<u64>) {
+            let enabled = fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")
+                .is_ok_and(|mode| !mode.contains("[never]"));
+            let size = fs::read_to_string("/sys/kernel/mm/transparent_hugepage/hpage_pmd_size")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .or_else(meminfo_huge_page_size);
+            (enabled, size)
+        }
+
+        fn meminfo_huge_page_size() -> Option<u64> {
+            let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+            let line = meminfo.lines().find(|l| l.starts_with("Hugepagesize:"))?;
+            let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+            Some(kb * 1024)
+        }
+
+        pub(crate) fn reflink(src: &File, dst: &File) -> bool {
+            if (&*src).write_all(b"reflink probe").is_err() {
+                return false;
+            }
+            // SAFETY: both descriptors are open; FICLONE takes the source fd by value.
+            unsafe { libc::ioctl(dst.as_raw_fd(), FICLONE, src.as_raw_fd()) == 0 }
+        }
+
+        pub(crate) fn io_uring() -> bool {
+            let mut params = [0u8; IO_URING_PARAMS_LEN];
+            // SAFETY: io_uring_setup reads and writes a zeroed io_uring_params of the
+            // right size; the ring fd is closed right away.
+            let fd = unsafe { libc::syscall(libc::SYS_io_uring_setup, 1u32, params.as_mut_ptr()) };
+            close_if_valid(fd)
+        }
+
+        pub(crate) fn userfaultfd() -> bool {
+            let flags = libc::O_CLOEXEC | libc::O_NONBLOCK;
+            // User-mode-only faults need no privileges on 5.11+
+            const UFFD_USER_MODE_ONLY: libc::c_int = 1;
+            // SAFETY: plain syscalls; any returned fd is closed right away.
+            let mut fd =
+                unsafe { libc::syscall(libc::SYS_userfaultfd, flags | UFFD_USER_MODE_ONLY) };
+            if fd < 0 {
+                // SAFETY: as above, for kernels without UFFD_USER_MODE_ONLY.
+                fd = unsafe { libc::syscall(libc::SYS_userfaultfd, flags) };
+            }
+            close_if_valid(fd)
+        }
+
+        pub(crate) fn memfd() -> bool {
+            // SAFETY: the name is NUL-terminated; the fd is closed right away.
+            let fd = unsafe {
+                libc::memfd_create(b"mmap-io-probe\0".as_ptr().cast(), libc::MFD_CLOEXEC)
+            };
+            close_if_valid(libc::c_long::from(fd))
+        }
+
+        fn close_if_valid(fd: libc::c_long) -> bool {
+            if fd < 0 {
+                return false;
+            }
+            // SAFETY: the kernel just handed us this descriptor and nothing else owns it.
+            drop(unsafe { OwnedFd::from_raw_fd(fd as i32) });
+            true
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    mod other {
+        use std::fs::File;
+
+        pub(crate) fn huge_pages() -> (bool, Option<u64>) {
+            (false, None)
+        }
+
+        pub(crate) fn reflink(_src: &File, _dst: &File) -> bool {
+            false
+        }
+
+        pub(crate) fn io_uring() -> bool {
+            false
+        }
+
+        pub(crate) fn userfaultfd() -> bool {
+            false
+        }
+
+        pub(crate) fn memfd() -> bool {
+            false
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::fs::File;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetLargePageMinimum() -> usize;
+    }
+
+    pub(super) fn huge_pages() -> (bool, Option<u64>) {
+        // SAFETY: no arguments, no preconditions.
+        let size = unsafe { GetLargePageMinimum() } as u64;
+        // Using them also needs SeLockMemoryPrivilege, which is not checked here
+        (size > 0, (size > 0).then_some(size))
+    }
+
+    pub(super) fn memlock() -> (bool, Option<u64>) {
+        // VirtualLock is bounded by the working-set size, not a fixed limit
+        (true, None)
+    }
+
+    pub(super) fn sparse_files(_src: &File, _dst: &File) -> bool {
+        // Files are only sparse after FSCTL_SET_SPARSE, which this crate does not issue
+        false
+    }
+
+    pub(super) fn reflink(_src: &File, _dst: &File) -> bool {
+        false
+    }
+
+    pub(super) fn io_uring() -> bool {
+        false
+    }
+
+    pub(super) fn userfaultfd() -> bool {
+        false
+    }
+
+    pub(super) fn memfd() -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_are_consistent() {
+        let caps = capabilities();
+        assert_eq!(caps, capabilities());
+        assert_eq!(caps.populate, cfg!(target_os = "linux"));
+        if caps.huge_pages {
+            assert!(caps.huge_page_size.is_some_and(|size| size > 0));
+        }
+        #[cfg(unix)]
+        if caps.memlock_limit == Some(0) {
+            // Only privileged processes can lock with a zero limit
+            // SAFETY: geteuid has no preconditions.
+            assert_eq!(caps.mlock, unsafe { libc::geteuid() } == 0);
+        }
+        if !cfg!(target_os = "linux") {
+            assert!(!caps.io_uring && !caps.userfaultfd && !caps.memfd);
+        }
+        #[cfg(target_os = "linux")]
+        assert!(
+            caps.memfd,
+            "memfd_create is available on every supported kernel"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Page-granular record of the bytes written through a mapping since they were flushed.
+//!
+//! Backs [`MemoryMappedFile::dirty_ranges`](crate::MemoryMappedFile::dirty_ranges) and
+//! [`flush_dirty`](crate::MemoryMappedFile::flush_dirty). The bitmap grows on demand up to
+//! the highest page written, so untouched mappings cost nothing.
+
+use crate::utils::page_size;
+
+/// One bit per page, set by writes and cleared by flushes covering the page.
+#[derive(Debug, Default)]
+pub(crate) struct DirtyPages {
+    bits: Vec<u64>,
+}
+
+impl DirtyPages {
+    /// Mark the pages overlapping `[offset, offset + len)`.
+    pub(crate) fn mark(&mut self, offset: u64, len: u64) {
+        let Some((first, end)) = page_span(offset, len) else {
+            return;
+        };
+        let words = end.div_ceil(64) as usize;
+        if self.bits.len() < words {
+            self.bits.resize(words, 0);
+        }
+        self.update(first, end, true);
+    }
+
+    /// Clear the pages overlapping `[offset, offset + len)`; flushes cover whole pages.
+    pub(crate) fn clear(&mut self, offset: u64, len: u64) {
+        if let Some((first, end)) = page_span(offset, len) {
+            let end = end.min(self.bits.len() as u64 * 64);
+            self.update(first, end, false);
+        }
+    }
+
+    pub(crate) fn clear_all(&mut self) {
+        self.bits.clear();
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&word| word == 0)
+    }
+
+    /// Dirty pages as `(offset, len)` runs of adjacent pages, clamped to `total` bytes.
+    pub(crate) fn ranges(&self, total: u64) -> Vec<(u64, u64)> {
+        let page = page_size() as u64;
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        for (w, &word) in self.bits.iter().enumerate() {
+            let mut rest = word;
+            while rest != 0 {
+                let bit = u64::from(rest.trailing_zeros());
+                rest &= rest - 1;
+                let start = (w as u64 * 64 + bit) * page;
+                if start >= total {
+                    return ranges;
+                }
+                let len = page.min(total - start);
+                match ranges.last_mut() {
+                    Some((run, run_len)) if *run + *run_len == start => *run_len += len,
+                    _ => ranges.push((start, len)),
+                }
+            }
+        }
+        ranges
+    }
+
+    // Set or clear the bits of pages `[first, end)`, a word at a time.
+    fn update(&mut self, first: u64, end: u64, value: bool) {
+        let mut page = first;
+        while page < end {
+            let bit = page % 64;
+            let n = (64 - bit).min(end - page);
+            let mask = if n == 64 {
+                u64::MAX
+            } else {
+                ((1u64 << n) - 1) << bit
+            };
+            let word = &mut self.bits[(page / 64) as usize];
+            if value {
+                *word |= mask;
+            } else {
+                *word &= !mask;
+            }
+            page += n;
+        }
+    }
+}
+
+// Pages `[first, end)` overlapping `[offset, offset + len)`; None for an empty range.
+fn page_span(offset: u64, len: u64) -> Option<(u64, u64)> {
+    if len == 0 {
+        return None;
+    }
+    let page = page_size() as u64;
+    let end = offset.saturating_add(len);
+    Some((offset / page, end.div_ceil(page)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dirty_pages_mark_clear_ranges() {
+        let page = page_size() as u64;
+        let mut dirty = DirtyPages::default();
+        assert!(dirty.ranges(u64::MAX).is_empty());
+
+        dirty.mark(page + 10, 1);
+        dirty.mark(3 * page - 1, 2);
+        dirty.mark(70 * page, 130 * page);
+        dirty.mark(5, 0);
+        assert_eq!(
+            dirty.ranges(u64::MAX),
+            vec![(page, 3 * page), (70 * page, 130 * page)]
+        );
+        // Runs are clamped to the mapping length
+        assert_eq!(
+            dirty.ranges(72 * page + 7),
+            vec![(page, 3 * page), (70 * page, 2 * page + 7)]
+        );
+
+        // Any overlap clears a page; clearing past the bitmap is harmless
+        dirty.clear(2 * page + 5, 1);
+        dirty.clear(100 * page, 1000 * page);
+        assert_eq!(
+            dirty.ranges(u64::MAX),
+            vec![(page, page), (3 * page, page), (70 * page, 30 * page)]
+        );
+        assert!(!dirty.is_empty());
+        dirty.clear_all();
+        assert!(dirty.is_empty());
+        assert!(dirty.ranges(u64::MAX).is_empty());
+    }
+}
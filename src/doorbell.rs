@@ -0,0 +1,370 @@
+//! Cross-process wakeups for shared mappings.
+//!
+//! A [`Doorbell`] pairs a `u64` counter stored in the mapping with a kernel wait object:
+//! an `eventfd` on Linux or an auto-reset Event on Windows. Producers bump the counter and
+//! signal; consumers sleep on the wait object instead of polling the mapping.
+//!
+//! The wait object must be shared with the other process: on Linux pass the eventfd
+//! (inheritance across `fork`/`exec` or `SCM_RIGHTS`) and rebuild it with
+//! [`Doorbell::from_raw_fd`]; on Windows both sides open the same named event.
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+/// A mapped counter plus a wait object used to wake consumers in other processes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use mmap_io::{MemoryMappedFile, doorbell::Doorbell};
+///
+/// let mmap = Arc::new(MemoryMappedFile::create_rw("shared.bin", 4096)?);
+/// let bell = Doorbell::new(mmap, 0)?;
+/// bell.ring()?;
+/// assert_eq!(bell.wait(0, Some(Duration::from_millis(10)))?, 1);
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct Doorbell {
+    mmap: Arc<MemoryMappedFile>,
+    offset: u64,
+    event: sys::Event,
+}
+
+impl Doorbell {
+    /// Create a doorbell whose counter lives at `offset` (8-byte aligned) in `mmap`,
+    /// backed by a fresh eventfd (Linux) or unnamed event (Windows).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` / `OutOfBounds` for a bad counter offset.
+    /// Returns `MmapIoError::Io` if the wait object cannot be created.
+    pub fn new(mmap: Arc<MemoryMappedFile>, offset: u64) -> Result<Self> {
+        mmap.atomic_u64(offset)?;
+        Ok(Self {
+            mmap,
+            offset,
+            event: sys::Event::new()?,
+        })
+    }
+
+    /// Rebuild a doorbell around an eventfd received from another process.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open eventfd; ownership transfers to the doorbell, which closes it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` / `OutOfBounds` for a bad counter offset.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn from_raw_fd(
+        mmap: Arc<MemoryMappedFile>,
+        offset: u64,
+        fd: std::os::fd::RawFd,
+    ) -> Result<Self> {
+        mmap.atomic_u64(offset)?;
+        Ok(Self {
+            mmap,
+            offset,
+            event: sys::Event::from_raw_fd(fd),
+        })
+    }
+
+    /// The eventfd to hand to the other process.
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.event.fd
+    }
+
+    /// Create or open the named event `name` so unrelated processes can share it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` / `OutOfBounds` for a bad counter offset.
+    /// Returns `MmapIoError::Io` if the event cannot be created or opened.
+    #[cfg(windows)]
+    pub fn named(mmap: Arc<MemoryMappedFile>, offset: u64, name: &str) -> Result<Self> {
+        mmap.atomic_u64(offset)?;
+        Ok(Self {
+            mmap,
+            offset,
+            event: sys::Event::named(name)?,
+        })
+    }
+
+    /// Bump the mapped counter and wake a waiter. Returns the new count.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if signalling the wait object fails.
+    pub fn ring(&self) -> Result<u64> {
+        let count = self
+            .mmap
+            .atomic_u64(self.offset)?
+            .fetch_add(1, Ordering::SeqCst)
+            .wrapping_add(1);
+        self.event.signal()?;
+        Ok(count)
+    }
+
+    /// Current value of the mapped counter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the mapping shrank below the counter.
+    pub fn count(&self) -> Result<u64> {
+        Ok(self.mmap.atomic_u64(self.offset)?.load(Ordering::SeqCst))
+    }
+
+    /// Sleep until the counter differs from `last_seen`, or until `timeout` elapses.
+    ///
+    /// Returns the counter value observed on wakeup; it equals `last_seen` on timeout.
+    /// Rings that happen between reading the counter and sleeping are not lost, because
+    /// the wait object stays signalled until a waiter consumes it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if waiting on the wait object fails.
+    pub fn wait(&self, last_seen: u64, timeout: Option<Duration>) -> Result<u64> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        loop {
+            let current = self.count()?;
+            if current != last_seen {
+                return Ok(current);
+            }
+            let remaining = match deadline {
+                Some(d) => match d.checked_duration_since(Instant::now()) {
+                    Some(r) if !r.is_zero() => Some(r),
+                    _ => return Ok(current),
+                },
+                None => None,
+            };
+            self.event.wait(remaining)?;
+        }
+    }
+
+    /// The mapping holding the counter.
+    #[must_use]
+    pub fn mapping(&self) -> &Arc<MemoryMappedFile> {
+        &self.mmap
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use super::{Duration, MmapIoError, Result};
+    use std::io;
+
+    #[derive(Debug)]
+    pub(super) struct Event {
+        pub(super) fd: libc::c_int,
+    }
+
+    impl Event {
+        pub(super) fn new() -> Result<Self> {
+            // SAFETY: eventfd has no memory-safety preconditions.
+            let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+            if fd < 0 {
+                return Err(MmapIoError::Io(io::Error::last_os_error()));
+            }
+            Ok(Self { fd })
+        }
+
+        pub(super) fn from_raw_fd(fd: libc::c_int) -> Self {
+            // Reads after poll must not block if another waiter drained the counter first.
+            // SAFETY: fcntl on a caller-provided open fd.
+            unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                if flags >= 0 {
+                    libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                }
+            }
+            Self { fd }
+        }
+
+        pub(super) fn signal(&self) -> Result<()> {
+            let one: u64 = 1;
+            // SAFETY: writes 8 bytes from a live u64 to an owned fd.
+            let rc = unsafe { libc::write(self.fd, (&one as *const u64).cast(), 8) };
+            if rc < 0 {
+                let err = io::Error::last_os_error();
+                // The eventfd counter is saturated, so a waiter is already due to wake
+                if err.kind() != io::ErrorKind::WouldBlock {
+                    return Err(MmapIoError::Io(err));
+                }
+            }
+            Ok(())
+        }
+
+        pub(super) fn wait(&self, timeout: Option<Duration>) -> Result<()> {
+            let mut pfd = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ms = timeout.map_or(-1, |t| {
+                libc::c_int::try_from(t.as_millis().max(1)).unwrap_or(libc::c_int::MAX)
+            });
+            // SAFETY: pfd is a valid pollfd for the duration of the call.
+            let rc = unsafe { libc::poll(&mut pfd, 1, ms) };
+            if rc < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    return Ok(());
+                }
+                return Err(MmapIoError::Io(err));
+            }
+            if rc > 0 {
+                let mut buf = 0u64;
+                // SAFETY: reads 8 bytes into a live u64; EAGAIN just means another waiter won.
+                unsafe { libc::read(self.fd, (&mut buf as *mut u64).cast(), 8) };
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for Event {
+        fn drop(&mut self) {
+            // SAFETY: the fd is owned by this Event.
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use super::{Duration, MmapIoError, Result};
+    use std::ffi::c_void;
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+
+    const WAIT_FAILED: u32 = 0xFFFF_FFFF;
+    const INFINITE: u32 = 0xFFFF_FFFF;
+
+    extern "system" {
+        fn CreateEventW(
+            attributes: *mut c_void,
+            manual_reset: i32,
+            initial_state: i32,
+            name: *const u16,
+        ) -> *mut c_void;
+        fn SetEvent(event: *mut c_void) -> i32;
+        fn WaitForSingleObject(handle: *mut c_void, millis: u32) -> u32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    #[derive(Debug)]
+    pub(super) struct Event {
+        handle: *mut c_void,
+    }
+
+    // SAFETY: event handles may be used from any thread.
+    unsafe impl Send for Event {}
+    unsafe impl Sync for Event {}
+
+    impl Event {
+        pub(super) fn new() -> Result<Self> {
+            Self::create(std::ptr::null())
+        }
+
+        pub(super) fn named(name: &str) -> Result<Self> {
+            let wide: Vec<u16> = std::ffi::OsStr::new(name)
+                .encode_wide()
+                .chain(std::iter::once(0))
+                .collect();
+            Self::create(wide.as_ptr())
+        }
+
+        fn create(name: *const u16) -> Result<Self> {
+            // SAFETY: name is null or a NUL-terminated UTF-16 string; auto-reset, unsignalled.
+            let handle = unsafe { CreateEventW(std::ptr::null_mut(), 0, 0, name) };
+            if handle.is_null() {
+                return Err(MmapIoError::Io(io::Error::last_os_error()));
+            }
+            Ok(Self { handle })
+        }
+
+        pub(super) fn signal(&self) -> Result<()> {
+            // SAFETY: handle is a live event handle owned by this Event.
+            if unsafe { SetEvent(self.handle) } == 0 {
+                return Err(MmapIoError::Io(io::Error::last_os_error()));
+            }
+            Ok(())
+        }
+
+        pub(super) fn wait(&self, timeout: Option<Duration>) -> Result<()> {
+            let ms = timeout.map_or(INFINITE, |t| {
+                u32::try_from(t.as_millis().max(1)).unwrap_or(INFINITE - 1)
+            });
+            // SAFETY: handle is a live event handle owned by this Event.
+            if unsafe { WaitForSingleObject(self.handle, ms) } == WAIT_FAILED {
+                return Err(MmapIoError::Io(io::Error::last_os_error()));
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for Event {
+        fn drop(&mut self) {
+            // SAFETY: the handle is owned by this Event.
+            unsafe { CloseHandle(self.handle) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_doorbell_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_ring_wakes_waiter() {
+        let path = tmp_path("ring");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 4096).expect("create"));
+        let bell = Arc::new(Doorbell::new(mmap.clone(), 8).expect("doorbell"));
+        assert!(Doorbell::new(mmap.clone(), 3).is_err());
+
+        // Nothing rung yet: times out with the same count
+        let seen = bell.wait(0, Some(Duration::from_millis(20))).expect("wait");
+        assert_eq!(seen, 0);
+
+        let waiter = {
+            let bell = bell.clone();
+            std::thread::spawn(move || bell.wait(0, None).expect("wait"))
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(bell.ring().expect("ring"), 1);
+        assert_eq!(waiter.join().expect("join"), 1);
+
+        // The counter lives in the mapping itself
+        let mut buf = [0u8; 8];
+        mmap.read_into(8, &mut buf).expect("read");
+        assert_eq!(u64::from_ne_bytes(buf), 1);
+
+        drop(bell);
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,148 @@
+//! Endian-aware scalar reads and writes for mappings and segments.
+//!
+//! Each accessor copies the value, so offsets need not be aligned. Reads are available on
+//! [`MemoryMappedFile`] and [`Segment`]; writes on [`MemoryMappedFile`] and [`SegmentMut`],
+//! where they go through the usual write path (flush policy, dirty tracking, observers).
+//! Segment offsets are relative to the start of the segment.
+
+use std::mem::size_of;
+
+use crate::errors::Result;
+use crate::mmap::MemoryMappedFile;
+use crate::segment::{Segment, SegmentMut};
+
+// Expands to `read_<name>` methods built on `self.read_into(offset, buf)`.
+macro_rules! endian_reads {
+    ($($name:ident: $t:ty, $from:ident, $order:literal;)*) => {
+        $(
+            #[doc = concat!("Read a ", $order, " `", stringify!($t), "` at `offset`.")]
+            ///
+            /// # Errors
+            ///
+            /// Returns `MmapIoError::OutOfBounds` if the value extends past the end.
+            pub fn $name(&self, offset: u64) -> Result<$t> {
+                let mut buf = [0u8; size_of::<$t>()];
+                self.read_into(offset, &mut buf)?;
+                Ok(<$t>::$from(buf))
+            }
+        )*
+    };
+}
+
+// Expands to `write_<name>` methods built on `self.$write(offset, bytes)`.
+macro_rules! endian_writes {
+    ($write:ident; $($name:ident: $t:ty, $to:ident, $order:literal;)*) => {
+        $(
+            #[doc = concat!("Write `value` as a ", $order, " `", stringify!($t), "` at `offset`.")]
+            ///
+            /// # Errors
+            ///
+            /// Returns `MmapIoError::InvalidMode` if the mapping is read-only and
+            /// `MmapIoError::OutOfBounds` if the value extends past the end.
+            pub fn $name(&self, offset: u64, value: $t) -> Result<()> {
+                self.$write(offset, &value.$to())
+            }
+        )*
+    };
+}
+
+macro_rules! all_reads {
+    () => {
+        endian_reads! {
+            read_u16_le: u16, from_le_bytes, "little-endian";
+            read_u16_be: u16, from_be_bytes, "big-endian";
+            read_u32_le: u32, from_le_bytes, "little-endian";
+            read_u32_be: u32, from_be_bytes, "big-endian";
+            read_u64_le: u64, from_le_bytes, "little-endian";
+            read_u64_be: u64, from_be_bytes, "big-endian";
+            read_f32_le: f32, from_le_bytes, "little-endian";
+            read_f32_be: f32, from_be_bytes, "big-endian";
+            read_f64_le: f64, from_le_bytes, "little-endian";
+            read_f64_be: f64, from_be_bytes, "big-endian";
+        }
+    };
+}
+
+macro_rules! all_writes {
+    ($write:ident) => {
+        endian_writes! {
+            $write;
+            write_u16_le: u16, to_le_bytes, "little-endian";
+            write_u16_be: u16, to_be_bytes, "big-endian";
+            write_u32_le: u32, to_le_bytes, "little-endian";
+            write_u32_be: u32, to_be_bytes, "big-endian";
+            write_u64_le: u64, to_le_bytes, "little-endian";
+            write_u64_be: u64, to_be_bytes, "big-endian";
+            write_f32_le: f32, to_le_bytes, "little-endian";
+            write_f32_be: f32, to_be_bytes, "big-endian";
+            write_f64_le: f64, to_le_bytes, "little-endian";
+            write_f64_be: f64, to_be_bytes, "big-endian";
+        }
+    };
+}
+
+impl MemoryMappedFile {
+    all_reads!();
+    all_writes!(update_region);
+}
+
+impl Segment {
+    all_reads!();
+}
+
+impl SegmentMut {
+    all_writes!(write_at);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_endian_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_endian_accessors() {
+        let path = tmp_path("scalars");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 64).expect("create");
+        mmap.write_u16_be(0, 0x0102).expect("u16");
+        mmap.write_u32_le(3, 0x0a0b_0c0d).expect("u32");
+        mmap.write_u64_be(7, 42).expect("u64");
+        mmap.write_f64_le(15, -1.5).expect("f64");
+        let mut raw = [0u8; 7];
+        mmap.read_into(0, &mut raw).expect("raw");
+        assert_eq!(raw, [1, 2, 0, 0x0d, 0x0c, 0x0b, 0x0a]);
+        assert_eq!(mmap.read_u16_le(0).expect("u16"), 0x0201);
+        assert_eq!(mmap.read_u32_le(3).expect("u32"), 0x0a0b_0c0d);
+        assert_eq!(mmap.read_u64_be(7).expect("u64"), 42);
+        assert_eq!(mmap.read_f64_le(15).expect("f64"), -1.5);
+        assert!(mmap.read_u64_le(57).is_err());
+        assert!(mmap.write_u32_be(61, 0).is_err());
+
+        // Segment offsets are relative to the segment
+        let mmap = Arc::new(mmap);
+        let seg = SegmentMut::new(Arc::clone(&mmap), 32, 8).expect("segment mut");
+        seg.write_f32_be(4, 2.25).expect("f32");
+        assert!(seg.write_u64_le(4, 0).is_err());
+        let seg = Segment::new(Arc::clone(&mmap), 32, 8).expect("segment");
+        assert_eq!(seg.read_f32_be(4).expect("f32"), 2.25);
+        assert_eq!(mmap.read_f32_be(36).expect("f32"), 2.25);
+        assert!(seg.read_u64_be(1).is_err());
+
+        drop((seg, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
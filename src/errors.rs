@@ -1,8 +1,11 @@
 //! Crate-specific error types for mmap-io.
 
 use std::io;
+use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::integrity::IntegrityError;
+
 /// Result alias for mmap-io operations.
 pub type Result<T> = std::result::Result<T, MmapIoError>;
 
@@ -60,4 +63,75 @@ pub enum MmapIoError {
     /// Error when starting or running a watcher fails.
     #[error("watch failed: {0}")]
     WatchFailed(String),
+
+    /// Error when a named segment registry header is invalid or an entry is missing.
+    #[error("segment registry error: {0}")]
+    Registry(String),
+
+    /// Error when an on-disk structure has a bad magic or malformed contents.
+    #[error("corrupt data: {0}")]
+    Corrupt(String),
+
+    /// Error when encoding or decoding a serialized record fails.
+    #[error("serialization failed: {0}")]
+    Serialization(String),
+
+    /// Error when exclusive creation is requested but the file already exists.
+    #[error("file already exists: {}", .0.display())]
+    AlreadyExists(PathBuf),
+
+    /// Error when a versioned header cannot be brought to the current format version.
+    #[error("migration failed: {0}")]
+    Migration(String),
+
+    /// Error when a file fails the length or digest checks requested on open.
+    #[error("integrity check failed: {0}")]
+    Integrity(#[from] IntegrityError),
+
+    /// Error when the running system lacks a facility the operation needs.
+    ///
+    /// See [`capabilities`](crate::capabilities::capabilities) to check up front.
+    #[error("unsupported on this system: {0}")]
+    Unsupported(&'static str),
+
+    /// Error when the filesystem lacks the free space to back a new or grown file.
+    #[error("insufficient disk space: {required} bytes needed, {available} available")]
+    InsufficientSpace {
+        /// Bytes the file would still need to allocate.
+        required: u64,
+        /// Bytes available to this process on the filesystem.
+        available: u64,
+    },
+
+    /// Error when a guarded access touched pages the backing file can no longer provide,
+    /// e.g. because it was truncated or the disk failed.
+    #[error("mapping fault at offset {offset}: backing file truncated or unreadable")]
+    MappingFault {
+        /// Offset in the mapping of the first byte that could not be accessed.
+        offset: u64,
+    },
+
+    /// Error when flushing a set of mappings fails for one or more of them.
+    #[error("flush failed for {} mapping(s)", .0.len())]
+    FlushAll(Vec<(PathBuf, MmapIoError)>),
+}
+
+impl From<MmapIoError> for io::Error {
+    /// Lets mmap-io errors flow through `std::io` trait implementations; I/O errors are
+    /// unwrapped, everything else keeps its message under the closest `ErrorKind`.
+    fn from(err: MmapIoError) -> Self {
+        match err {
+            MmapIoError::Io(e) => e,
+            MmapIoError::OutOfBounds { .. } | MmapIoError::Misaligned { .. } => {
+                io::Error::new(io::ErrorKind::InvalidInput, err)
+            }
+            MmapIoError::InvalidMode(_) => io::Error::new(io::ErrorKind::PermissionDenied, err),
+            MmapIoError::AlreadyExists(_) => io::Error::new(io::ErrorKind::AlreadyExists, err),
+            MmapIoError::Unsupported(_) => io::Error::new(io::ErrorKind::Unsupported, err),
+            MmapIoError::Corrupt(_) | MmapIoError::Integrity(_) => {
+                io::Error::new(io::ErrorKind::InvalidData, err)
+            }
+            other => io::Error::other(other),
+        }
+    }
 }
@@ -0,0 +1,371 @@
+//! Sharing mappings between unrelated processes by passing file descriptors (Unix).
+//!
+//! A mapping's backing descriptor can be exported with
+//! [`MemoryMappedFile::as_sendable_fd`], sent over a Unix domain socket with [`send_fd`]
+//! (`SCM_RIGHTS`), received with [`recv_fd`], and mapped again on the other side with
+//! [`MemoryMappedFile::from_fd`]. Both processes then share the same pages. Combined with
+//! [`MemoryMappedFile::create_memfd`] (Linux) this shares anonymous memory that has no
+//! path in the filesystem at all.
+
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+
+use memmap2::{MmapMut, MmapOptions};
+use parking_lot::{Mutex, RwLock};
+
+use crate::errors::{MmapIoError, Result};
+use crate::flush::FlushPolicy;
+use crate::mmap::{
+    ensure_within_limit, ForkBehavior, Inner, MapVariant, MemoryMappedFile, MmapMode, RwMap,
+    ERR_ZERO_SIZE,
+};
+
+impl MemoryMappedFile {
+    /// Create a `size`-byte shared mapping backed by an anonymous `memfd` (Linux).
+    ///
+    /// `name` only appears in `/proc/<pid>/fd` listings; the memory lives until every
+    /// mapping and descriptor referring to it is gone.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::ResizeFailed` if `size` is zero or exceeds the size limit.
+    /// Returns `MmapIoError::Io` if the memfd cannot be created, sized, or mapped.
+    #[cfg(target_os = "linux")]
+    pub fn create_memfd(name: &str, size: u64) -> Result<Self> {
+        if size == 0 {
+            return Err(MmapIoError::ResizeFailed(ERR_ZERO_SIZE.into()));
+        }
+        ensure_within_limit(size, None)?;
+        let cname = std::ffi::CString::new(name)
+            .map_err(|_| MmapIoError::InvalidMode("memfd name must not contain NUL"))?;
+        // SAFETY: cname is a valid NUL-terminated string.
+        let fd = unsafe { libc::memfd_create(cname.as_ptr(), libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        // SAFETY: fd was just returned by the kernel and is owned by nobody else.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        File::from(fd.try_clone()?).set_len(size)?;
+        Self::from_fd(fd, MmapMode::ReadWrite, None)
+    }
+
+    /// Map a descriptor received from another process (or any open file descriptor).
+    ///
+    /// `len` defaults to the current file size. A `ReadWrite` mapping grows the file to
+    /// `len` if it is shorter; other modes require the file to be at least `len` bytes.
+    /// The descriptor must have been opened with access matching `mode`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::ResizeFailed` if the length is zero, exceeds the size limit,
+    /// or exceeds a read-only file.
+    /// Returns `MmapIoError::InvalidMode` for `CopyOnWrite` without the `cow` feature and
+    /// for `ReadExecute`.
+    /// Returns `MmapIoError::Io` if the descriptor cannot be inspected or mapped.
+    pub fn from_fd(fd: OwnedFd, mode: MmapMode, len: Option<u64>) -> Result<Self> {
+        let path = fd_path(fd.as_raw_fd());
+        let file = File::from(fd);
+        let file_len = file.metadata()?.len();
+        let len = len.unwrap_or(file_len);
+        if len == 0 {
+            return Err(MmapIoError::ResizeFailed(ERR_ZERO_SIZE.into()));
+        }
+        ensure_within_limit(len, None)?;
+        if len > file_len {
+            if mode != MmapMode::ReadWrite {
+                return Err(MmapIoError::ResizeFailed(format!(
+                    "length {len} exceeds the {file_len}-byte file behind the descriptor"
+                )));
+            }
+            file.set_len(len)?;
+        }
+        let map_len = usize::try_from(len)
+            .map_err(|_| MmapIoError::ResizeFailed("length exceeds usize".into()))?;
+        let (map, flush_policy) = match mode {
+            MmapMode::ReadWrite => {
+                // SAFETY: the descriptor refers to a file of at least `len` bytes.
+                let mmap: MmapMut = unsafe { MmapOptions::new().len(map_len).map_mut(&file)? };
+                (
+                    MapVariant::Rw(RwLock::new(RwMap::Std(mmap))),
+                    FlushPolicy::default(),
+                )
+            }
+            MmapMode::ReadOnly => {
+                // SAFETY: as above.
+                let mmap = unsafe { MmapOptions::new().len(map_len).map(&file)? };
+                (MapVariant::Ro(mmap), FlushPolicy::Never)
+            }
+            MmapMode::CopyOnWrite => {
+                #[cfg(feature = "cow")]
+                {
+                    let mmap = crate::mmap::map_private(&file, 0, map_len, false)?;
+                    (MapVariant::Cow(RwLock::new(mmap)), FlushPolicy::Never)
+                }
+                #[cfg(not(feature = "cow"))]
+                {
+                    return Err(MmapIoError::InvalidMode(
+                        "CopyOnWrite mode requires 'cow' feature",
+                    ));
+                }
+            }
+            MmapMode::ReadExecute => {
+                return Err(MmapIoError::InvalidMode(
+                    "ReadExecute mappings cannot be built from a descriptor",
+                ));
+            }
+        };
+        let inner = Inner {
+            path,
+            file,
+            mode,
+            cached_len: RwLock::new(len),
+            map,
+            flush_policy,
+            written_since_last_flush: RwLock::new(0),
+            #[cfg(feature = "hugepages")]
+            huge_pages: false,
+            observer: RwLock::new(None),
+            fork_behavior: ForkBehavior::Inherit,
+            size_limit: None,
+            tracking: None,
+            strict_flush: false,
+            recorder: RwLock::new(None),
+            split_parts: AtomicUsize::new(0),
+            scrub_on_shrink: false,
+            check_free_space: false,
+            guarded: None,
+            window_offset: None,
+            auto_grow: None,
+            interval_flusher: None,
+            dirty: Mutex::default(),
+        };
+        Self::from_inner(inner)
+    }
+
+    /// Duplicate of the descriptor backing this mapping, ready for [`send_fd`].
+    ///
+    /// The duplicate is close-on-exec and independent of this mapping's lifetime.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if the descriptor cannot be duplicated.
+    pub fn as_sendable_fd(&self) -> Result<OwnedFd> {
+        Ok(self.inner.file.as_fd().try_clone_to_owned()?)
+    }
+}
+
+// Best-effort description of what `fd` refers to, used as the mapping's path.
+fn fd_path(fd: RawFd) -> PathBuf {
+    #[cfg(target_os = "linux")]
+    if let Ok(target) = std::fs::read_link(format!("/proc/self/fd/{fd}")) {
+        return target;
+    }
+    PathBuf::from(format!("fd:{fd}"))
+}
+
+// Control buffer space for one descriptor.
+fn fd_control_space() -> usize {
+    // SAFETY: CMSG_SPACE is a pure size computation.
+    unsafe { libc::CMSG_SPACE(std::mem::size_of::<RawFd>() as u32) as usize }
+}
+
+// Zeroed control buffer of at least `space` bytes. The CMSG_* macros assume it is aligned
+// for `cmsghdr`, which a byte vector need not be; u64 storage is.
+fn control_buffer(space: usize) -> Vec<u64> {
+    vec![0u64; space.div_ceil(std::mem::size_of::<u64>())]
+}
+
+/// Send `fd` over a Unix domain socket as `SCM_RIGHTS` ancillary data.
+///
+/// # Errors
+///
+/// Returns `MmapIoError::Io` if `sendmsg` fails.
+pub fn send_fd(socket: &UnixStream, fd: BorrowedFd<'_>) -> Result<()> {
+    let raw = fd.as_raw_fd();
+    // One payload byte: some platforms drop ancillary data sent without any
+    let mut payload = [0u8; 1];
+    let mut iov = libc::iovec {
+        iov_base: payload.as_mut_ptr().cast(),
+        iov_len: payload.len(),
+    };
+    let space = fd_control_space();
+    let mut control = control_buffer(space);
+    // SAFETY: msghdr is plain data; all pointers set below stay valid during sendmsg.
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    // SAFETY: the control buffer is CMSG_SPACE bytes, enough for one header and one fd.
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<RawFd>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>(), raw);
+    }
+    // SAFETY: msg is fully initialized and the socket is open.
+    if unsafe { libc::sendmsg(socket.as_raw_fd(), &msg, 0) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Receive a descriptor sent with [`send_fd`].
+///
+/// # Errors
+///
+/// Returns `MmapIoError::Io` if `recvmsg` fails, the peer closed the socket, or the
+/// message carried no descriptor or more ancillary data than fits (any descriptors that
+/// did arrive are closed).
+pub fn recv_fd(socket: &UnixStream) -> Result<OwnedFd> {
+    let mut payload = [0u8; 1];
+    let mut iov = libc::iovec {
+        iov_base: payload.as_mut_ptr().cast(),
+        iov_len: payload.len(),
+    };
+    let space = fd_control_space();
+    let mut control = control_buffer(space);
+    // SAFETY: msghdr is plain data; all pointers set below stay valid during recvmsg.
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    #[cfg(target_os = "linux")]
+    let flags = libc::MSG_CMSG_CLOEXEC;
+    #[cfg(not(target_os = "linux"))]
+    let flags = 0;
+    // SAFETY: msg describes valid buffers and the socket is open.
+    let n = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, flags) };
+    if n < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    if n == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "socket closed").into());
+    }
+    // SAFETY: recvmsg filled the control buffer and set msg_controllen accordingly.
+    let fds = unsafe { received_fds(&msg) };
+    // Dropping `fds` closes whatever arrived, including extras beyond the first
+    if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "ancillary data was truncated; expected a single file descriptor",
+        )
+        .into());
+    }
+    fds.into_iter().next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message carried no file descriptor",
+        )
+        .into()
+    })
+}
+
+// Take ownership of every descriptor in the `SCM_RIGHTS` messages of `msg`.
+//
+// SAFETY: `msg` must have been filled by a successful `recvmsg`.
+unsafe fn received_fds(msg: &libc::msghdr) -> Vec<OwnedFd> {
+    let mut fds = Vec::new();
+    // SAFETY: walking the control buffer with the CMSG_* macros, as recvmsg laid it out.
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg);
+                let header = data as usize - cmsg as usize;
+                let count = ((*cmsg).cmsg_len as usize).saturating_sub(header)
+                    / std::mem::size_of::<RawFd>();
+                for i in 0..count {
+                    let raw = std::ptr::read_unaligned(data.cast::<RawFd>().add(i));
+                    fds.push(OwnedFd::from_raw_fd(raw));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+        }
+    }
+    fds
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memfd_shared_over_socket() {
+        let mmap = MemoryMappedFile::create_memfd("mmap-io-test", 8192).expect("memfd");
+        mmap.update_region(100, b"shared").expect("write");
+
+        let (tx, rx) = UnixStream::pair().expect("socketpair");
+        let sendable = mmap.as_sendable_fd().expect("dup");
+        send_fd(&tx, sendable.as_fd()).expect("send");
+        drop(sendable);
+        let received = recv_fd(&rx).expect("recv");
+
+        let peer = MemoryMappedFile::from_fd(received, MmapMode::ReadWrite, None).expect("from fd");
+        assert_eq!(peer.len(), 8192);
+        let mut buf = [0u8; 6];
+        peer.read_into(100, &mut buf).expect("read");
+        assert_eq!(&buf, b"shared");
+
+        // Same pages: writes show up on the other side
+        peer.update_region(0, b"reply").expect("peer write");
+        let mut reply = [0u8; 5];
+        mmap.read_into(0, &mut reply).expect("read reply");
+        assert_eq!(&reply, b"reply");
+
+        let ro = MemoryMappedFile::from_fd(
+            mmap.as_sendable_fd().expect("dup"),
+            MmapMode::ReadOnly,
+            Some(4096),
+        )
+        .expect("ro view");
+        assert_eq!(ro.as_slice(0, 5).expect("slice"), b"reply");
+        assert!(MemoryMappedFile::from_fd(
+            mmap.as_sendable_fd().expect("dup"),
+            MmapMode::ReadOnly,
+            Some(1 << 20),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_recv_fd_rejects_truncated_control_data() {
+        let (tx, rx) = UnixStream::pair().expect("socketpair");
+        // More descriptors in one message than recv_fd has room for
+        let raws = [tx.as_raw_fd(); 4];
+        let data_len = std::mem::size_of_val(&raws) as u32;
+        let mut payload = [0u8; 1];
+        let mut iov = libc::iovec {
+            iov_base: payload.as_mut_ptr().cast(),
+            iov_len: payload.len(),
+        };
+        // SAFETY: CMSG_SPACE is a pure size computation.
+        let space = unsafe { libc::CMSG_SPACE(data_len) } as usize;
+        let mut control = control_buffer(space);
+        // SAFETY: as in send_fd, with room for all four descriptors.
+        unsafe {
+            let mut msg: libc::msghdr = std::mem::zeroed();
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr().cast();
+            msg.msg_controllen = space as _;
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(data_len) as _;
+            std::ptr::copy_nonoverlapping(
+                raws.as_ptr(),
+                libc::CMSG_DATA(cmsg).cast::<RawFd>(),
+                raws.len(),
+            );
+            assert!(libc::sendmsg(tx.as_raw_fd(), &msg, 0) > 0, "sendmsg");
+        }
+        assert!(matches!(recv_fd(&rx), Err(MmapIoError::Io(_))));
+    }
+}
@@ -0,0 +1,581 @@
+//! C ABI over the core mapping and segment operations.
+//!
+//! Every function is `#[no_mangle] extern "C"`, takes and returns plain C types, and hands
+//! out mappings and segments as opaque heap handles ([`MmapIoHandle`], [`MmapIoSegment`])
+//! that must be released with [`mmap_io_close`] / [`mmap_io_segment_free`]. Functions
+//! return [`MMAP_IO_OK`] or a negative `MMAP_IO_E_*` code; the message of the last error on
+//! the calling thread is available from [`mmap_io_last_error`]. Panics never cross the
+//! boundary: they are caught and reported as [`MMAP_IO_E_PANIC`].
+//!
+//! Handles are thread-safe, so one handle may be used from several C threads at once;
+//! [`mmap_io_clone`] gives each owner its own reference to the same mapping.
+//!
+//! Build a shared library by compiling the crate with the `ffi` feature as a `cdylib`
+//! (e.g. `cargo rustc --release --features ffi --crate-type cdylib`), or re-export this
+//! module from a wrapper crate.
+//!
+//! ```c
+//! MmapIoHandle *map;
+//! if (mmap_io_create_rw("data.bin", 4096, &map) != MMAP_IO_OK) {
+//!     fprintf(stderr, "%s\n", mmap_io_last_error());
+//!     return 1;
+//! }
+//! mmap_io_write(map, 0, "hello", 5);
+//! mmap_io_flush(map);
+//! mmap_io_close(map);
+//! ```
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::Arc;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+use crate::segment::Segment;
+
+/// Success.
+pub const MMAP_IO_OK: c_int = 0;
+/// An operating-system I/O error.
+pub const MMAP_IO_E_IO: c_int = -1;
+/// A null pointer was passed, or (outside Unix) a path that is not valid UTF-8.
+pub const MMAP_IO_E_INVALID_ARGUMENT: c_int = -2;
+/// The operation is not allowed in the mapping's mode (e.g. writing a read-only map).
+pub const MMAP_IO_E_INVALID_MODE: c_int = -3;
+/// The requested range lies outside the mapping or segment.
+pub const MMAP_IO_E_OUT_OF_BOUNDS: c_int = -4;
+/// The running system lacks a facility the operation needs.
+pub const MMAP_IO_E_UNSUPPORTED: c_int = -5;
+/// Any other mmap-io error; see [`mmap_io_last_error`].
+pub const MMAP_IO_E_OTHER: c_int = -6;
+/// A panic was caught at the boundary.
+pub const MMAP_IO_E_PANIC: c_int = -7;
+/// The filesystem lacks the free space to back the file.
+pub const MMAP_IO_E_INSUFFICIENT_SPACE: c_int = -8;
+/// A guarded access hit pages the backing file can no longer provide.
+pub const MMAP_IO_E_MAPPING_FAULT: c_int = -9;
+
+/// Opaque handle to a mapping.
+pub struct MmapIoHandle(MemoryMappedFile);
+
+/// Opaque handle to a bounded window of a mapping.
+pub struct MmapIoSegment(Segment);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // Interior NULs would truncate the C string; replace them
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(message));
+}
+
+fn error_code(err: &MmapIoError) -> c_int {
+    match err {
+        MmapIoError::Io(_) => MMAP_IO_E_IO,
+        MmapIoError::InvalidMode(_) => MMAP_IO_E_INVALID_MODE,
+        MmapIoError::OutOfBounds { .. } => MMAP_IO_E_OUT_OF_BOUNDS,
+        MmapIoError::Unsupported(_) => MMAP_IO_E_UNSUPPORTED,
+        MmapIoError::InsufficientSpace { .. } => MMAP_IO_E_INSUFFICIENT_SPACE,
+        MmapIoError::MappingFault { .. } => MMAP_IO_E_MAPPING_FAULT,
+        _ => MMAP_IO_E_OTHER,
+    }
+}
+
+// Run `f`, translating its error or panic into a status code and the last-error message.
+fn call(f: impl FnOnce() -> std::result::Result<(), c_int>) -> c_int {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => MMAP_IO_OK,
+        Ok(Err(code)) => code,
+        Err(_) => {
+            set_last_error("panic inside mmap-io".into());
+            MMAP_IO_E_PANIC
+        }
+    }
+}
+
+fn check<T>(res: Result<T>) -> std::result::Result<T, c_int> {
+    res.map_err(|e| {
+        let code = error_code(&e);
+        set_last_error(e.to_string());
+        code
+    })
+}
+
+fn invalid(what: &str) -> c_int {
+    set_last_error(format!("invalid argument: {what}"));
+    MMAP_IO_E_INVALID_ARGUMENT
+}
+
+unsafe fn path_arg(path: *const c_char) -> std::result::Result<PathBuf, c_int> {
+    if path.is_null() {
+        return Err(invalid("path is null"));
+    }
+    // SAFETY: the caller passes a NUL-terminated string.
+    let path = unsafe { CStr::from_ptr(path) };
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(PathBuf::from(std::ffi::OsStr::from_bytes(path.to_bytes())))
+    }
+    #[cfg(not(unix))]
+    path.to_str()
+        .map(PathBuf::from)
+        .map_err(|_| invalid("path is not valid UTF-8"))
+}
+
+unsafe fn handle_arg<'a>(
+    handle: *const MmapIoHandle,
+) -> std::result::Result<&'a MemoryMappedFile, c_int> {
+    // SAFETY: the caller passes null or a live handle from this module.
+    unsafe { handle.as_ref() }
+        .map(|h| &h.0)
+        .ok_or_else(|| invalid("handle is null"))
+}
+
+unsafe fn segment_arg<'a>(
+    segment: *const MmapIoSegment,
+) -> std::result::Result<&'a Segment, c_int> {
+    // SAFETY: the caller passes null or a live segment from this module.
+    unsafe { segment.as_ref() }
+        .map(|s| &s.0)
+        .ok_or_else(|| invalid("segment is null"))
+}
+
+unsafe fn buf_arg<'a>(buf: *const u8, len: usize) -> std::result::Result<&'a [u8], c_int> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if buf.is_null() {
+        return Err(invalid("buffer is null"));
+    }
+    // SAFETY: the caller guarantees `len` readable bytes at `buf`.
+    Ok(unsafe { std::slice::from_raw_parts(buf, len) })
+}
+
+unsafe fn buf_mut_arg<'a>(buf: *mut u8, len: usize) -> std::result::Result<&'a mut [u8], c_int> {
+    if len == 0 {
+        return Ok(&mut []);
+    }
+    if buf.is_null() {
+        return Err(invalid("buffer is null"));
+    }
+    // SAFETY: the caller guarantees `len` writable bytes at `buf`, not aliased elsewhere.
+    Ok(unsafe { std::slice::from_raw_parts_mut(buf, len) })
+}
+
+unsafe fn store<T>(out: *mut *mut T, value: T) -> std::result::Result<(), c_int> {
+    if out.is_null() {
+        return Err(invalid("output pointer is null"));
+    }
+    // SAFETY: `out` is non-null and points to writable storage for a pointer.
+    unsafe { *out = Box::into_raw(Box::new(value)) };
+    Ok(())
+}
+
+unsafe fn open_with(
+    path: *const c_char,
+    out: *mut *mut MmapIoHandle,
+    open: impl FnOnce(PathBuf) -> Result<MemoryMappedFile>,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        let path = unsafe { path_arg(path)? };
+        let mmap = check(open(path))?;
+        // SAFETY: forwarded from the caller's contract.
+        unsafe { store(out, MmapIoHandle(mmap)) }
+    })
+}
+
+/// Message of the last error on this thread, or null if none occurred yet.
+///
+/// The string stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn mmap_io_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| slot.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// Create (or truncate) `path` with `size` bytes and map it read-write into `*out`.
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `out` a valid pointer to write a handle to.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_create_rw(
+    path: *const c_char,
+    size: u64,
+    out: *mut *mut MmapIoHandle,
+) -> c_int {
+    // SAFETY: forwarded from the caller's contract.
+    unsafe { open_with(path, out, |p| MemoryMappedFile::create_rw(p, size)) }
+}
+
+/// Map the existing file at `path` read-only into `*out`.
+///
+/// # Safety
+///
+/// Same as [`mmap_io_create_rw`].
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_open_ro(
+    path: *const c_char,
+    out: *mut *mut MmapIoHandle,
+) -> c_int {
+    // SAFETY: forwarded from the caller's contract.
+    unsafe { open_with(path, out, MemoryMappedFile::open_ro) }
+}
+
+/// Map the existing file at `path` read-write into `*out`.
+///
+/// # Safety
+///
+/// Same as [`mmap_io_create_rw`].
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_open_rw(
+    path: *const c_char,
+    out: *mut *mut MmapIoHandle,
+) -> c_int {
+    // SAFETY: forwarded from the caller's contract.
+    unsafe { open_with(path, out, MemoryMappedFile::open_rw) }
+}
+
+/// Store a second handle to the same mapping in `*out`; each must be closed separately.
+///
+/// # Safety
+///
+/// `handle` must be a live handle and `out` a valid pointer to write a handle to.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_clone(
+    handle: *const MmapIoHandle,
+    out: *mut *mut MmapIoHandle,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        let mmap = unsafe { handle_arg(handle)? }.clone();
+        // SAFETY: forwarded from the caller's contract.
+        unsafe { store(out, MmapIoHandle(mmap)) }
+    })
+}
+
+/// Release a handle. The mapping is unmapped once its last handle and segment are gone.
+/// Does not flush; call [`mmap_io_flush`] first. Null is ignored.
+///
+/// # Safety
+///
+/// `handle` must be null or a live handle, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_close(handle: *mut MmapIoHandle) {
+    if !handle.is_null() {
+        // SAFETY: the handle came from Box::into_raw in this module and is released once.
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Current length of the mapping in bytes, or 0 for a null handle.
+///
+/// # Safety
+///
+/// `handle` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_len(handle: *const MmapIoHandle) -> u64 {
+    // SAFETY: forwarded from the caller's contract.
+    unsafe { handle.as_ref() }.map_or(0, |h| h.0.len())
+}
+
+/// Copy `len` bytes at `offset` into `buf`.
+///
+/// # Safety
+///
+/// `handle` must be a live handle and `buf` valid for `len` bytes of writes.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_read(
+    handle: *const MmapIoHandle,
+    offset: u64,
+    buf: *mut u8,
+    len: usize,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        let (mmap, buf) = unsafe { (handle_arg(handle)?, buf_mut_arg(buf, len)?) };
+        check(mmap.read_into(offset, buf))
+    })
+}
+
+/// Copy `len` bytes from `data` into the mapping at `offset` (read-write mappings only).
+///
+/// # Safety
+///
+/// `handle` must be a live handle and `data` valid for `len` bytes of reads.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_write(
+    handle: *const MmapIoHandle,
+    offset: u64,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        let (mmap, data) = unsafe { (handle_arg(handle)?, buf_arg(data, len)?) };
+        check(mmap.update_region(offset, data))
+    })
+}
+
+/// Flush pending writes of the whole mapping.
+///
+/// # Safety
+///
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_flush(handle: *const MmapIoHandle) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        check(unsafe { handle_arg(handle)? }.flush())
+    })
+}
+
+/// Flush pending writes in `[offset, offset + len)`.
+///
+/// # Safety
+///
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_flush_range(
+    handle: *const MmapIoHandle,
+    offset: u64,
+    len: u64,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        check(unsafe { handle_arg(handle)? }.flush_range(offset, len))
+    })
+}
+
+/// Grow or shrink a read-write mapping and its file to `new_size` bytes.
+///
+/// Pointers from [`mmap_io_as_ptr`] are invalidated.
+///
+/// # Safety
+///
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_resize(handle: *const MmapIoHandle, new_size: u64) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        check(unsafe { handle_arg(handle)? }.resize(new_size))
+    })
+}
+
+/// Store a zero-copy pointer to `[offset, offset + len)` of a read-only mapping in `*out`.
+///
+/// Read-write and copy-on-write mappings are rejected because their bytes can change
+/// under the pointer; use [`mmap_io_read`] for those. The pointer stays valid while
+/// `handle` is open.
+///
+/// # Safety
+///
+/// `handle` must be a live handle and `out` a valid pointer to write a pointer to.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_as_ptr(
+    handle: *const MmapIoHandle,
+    offset: u64,
+    len: u64,
+    out: *mut *const u8,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        let slice = check(unsafe { handle_arg(handle)? }.as_slice(offset, len))?;
+        if out.is_null() {
+            return Err(invalid("output pointer is null"));
+        }
+        // SAFETY: `out` is non-null and points to writable storage for a pointer.
+        unsafe { *out = slice.as_ptr() };
+        Ok(())
+    })
+}
+
+/// Store a segment covering `[offset, offset + len)` of the mapping in `*out`.
+///
+/// The segment keeps the mapping alive; release it with [`mmap_io_segment_free`].
+///
+/// # Safety
+///
+/// `handle` must be a live handle and `out` a valid pointer to write a segment to.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_segment_new(
+    handle: *const MmapIoHandle,
+    offset: u64,
+    len: u64,
+    out: *mut *mut MmapIoSegment,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        let parent = Arc::new(unsafe { handle_arg(handle)? }.clone());
+        let segment = check(Segment::new(parent, offset, len))?;
+        // SAFETY: forwarded from the caller's contract.
+        unsafe { store(out, MmapIoSegment(segment)) }
+    })
+}
+
+/// Release a segment. Null is ignored.
+///
+/// # Safety
+///
+/// `segment` must be null or a live segment, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_segment_free(segment: *mut MmapIoSegment) {
+    if !segment.is_null() {
+        // SAFETY: the segment came from Box::into_raw in this module and is released once.
+        drop(unsafe { Box::from_raw(segment) });
+    }
+}
+
+/// Length of the segment in bytes, or 0 for a null segment.
+///
+/// # Safety
+///
+/// `segment` must be null or a live segment.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_segment_len(segment: *const MmapIoSegment) -> u64 {
+    // SAFETY: forwarded from the caller's contract.
+    unsafe { segment.as_ref() }.map_or(0, |s| s.0.len())
+}
+
+/// Copy `len` bytes at `rel_offset` within the segment into `buf`.
+///
+/// # Safety
+///
+/// `segment` must be a live segment and `buf` valid for `len` bytes of writes.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_segment_read(
+    segment: *const MmapIoSegment,
+    rel_offset: u64,
+    buf: *mut u8,
+    len: usize,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        let (segment, buf) = unsafe { (segment_arg(segment)?, buf_mut_arg(buf, len)?) };
+        check(segment.read_into(rel_offset, buf))
+    })
+}
+
+/// Copy `len` bytes from `data` into the segment at `rel_offset` (read-write mappings only).
+///
+/// # Safety
+///
+/// `segment` must be a live segment and `data` valid for `len` bytes of reads.
+#[no_mangle]
+pub unsafe extern "C" fn mmap_io_segment_write(
+    segment: *const MmapIoSegment,
+    rel_offset: u64,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    call(|| {
+        // SAFETY: forwarded from the caller's contract.
+        let (segment, data) = unsafe { (segment_arg(segment)?, buf_arg(data, len)?) };
+        check(crate::utils::ensure_in_bounds(
+            rel_offset,
+            data.len() as u64,
+            segment.len(),
+        ))?;
+        check(
+            segment
+                .parent()
+                .update_region(segment.offset() + rel_offset, data),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn tmp_path(name: &str) -> CString {
+        let mut p = std::env::temp_dir();
+        p.push(format!("mmap_io_ffi_test_{}_{}", name, std::process::id()));
+        CString::new(p.to_str().expect("utf-8 temp dir")).expect("no NUL")
+    }
+
+    fn last_error() -> String {
+        let msg = mmap_io_last_error();
+        assert!(!msg.is_null());
+        // SAFETY: non-null pointers from mmap_io_last_error are NUL-terminated.
+        unsafe { CStr::from_ptr(msg) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_ffi_round_trip() {
+        let path = tmp_path("round_trip");
+        let path_str = path.to_str().expect("utf-8").to_owned();
+        let _ = fs::remove_file(&path_str);
+
+        unsafe {
+            let mut map = ptr::null_mut();
+            assert_eq!(mmap_io_create_rw(path.as_ptr(), 64, &mut map), MMAP_IO_OK);
+            assert_eq!(mmap_io_len(map), 64);
+            assert_eq!(mmap_io_write(map, 8, b"hello".as_ptr(), 5), MMAP_IO_OK);
+            assert_eq!(
+                mmap_io_write(map, 62, b"xyz".as_ptr(), 3),
+                MMAP_IO_E_OUT_OF_BOUNDS
+            );
+            assert!(last_error().contains("out of bounds"));
+            assert_eq!(mmap_io_flush(map), MMAP_IO_OK);
+
+            // Segments read and write relative to their window
+            let mut seg = ptr::null_mut();
+            assert_eq!(mmap_io_segment_new(map, 8, 16, &mut seg), MMAP_IO_OK);
+            assert_eq!(mmap_io_segment_len(seg), 16);
+            assert_eq!(mmap_io_segment_write(seg, 5, b"!".as_ptr(), 1), MMAP_IO_OK);
+            assert_eq!(
+                mmap_io_segment_write(seg, 16, b"!".as_ptr(), 1),
+                MMAP_IO_E_OUT_OF_BOUNDS
+            );
+            let mut buf = [0u8; 6];
+            assert_eq!(
+                mmap_io_segment_read(seg, 0, buf.as_mut_ptr(), buf.len()),
+                MMAP_IO_OK
+            );
+            assert_eq!(&buf, b"hello!");
+            mmap_io_segment_free(seg);
+            assert_eq!(mmap_io_flush_range(map, 0, 64), MMAP_IO_OK);
+            assert_eq!(mmap_io_resize(map, 128), MMAP_IO_OK);
+            mmap_io_close(map);
+
+            // Zero-copy reads from a read-only mapping; writes are refused
+            let mut ro = ptr::null_mut();
+            assert_eq!(mmap_io_open_ro(path.as_ptr(), &mut ro), MMAP_IO_OK);
+            let mut other = ptr::null_mut();
+            assert_eq!(mmap_io_clone(ro, &mut other), MMAP_IO_OK);
+            mmap_io_close(ro);
+            let mut data = ptr::null();
+            assert_eq!(mmap_io_as_ptr(other, 8, 6, &mut data), MMAP_IO_OK);
+            assert_eq!(std::slice::from_raw_parts(data, 6), b"hello!");
+            assert_eq!(
+                mmap_io_write(other, 0, b"x".as_ptr(), 1),
+                MMAP_IO_E_INVALID_MODE
+            );
+            mmap_io_close(other);
+
+            // Bad arguments are reported, not dereferenced
+            let mut rw = ptr::null_mut();
+            assert_eq!(
+                mmap_io_open_rw(ptr::null(), &mut rw),
+                MMAP_IO_E_INVALID_ARGUMENT
+            );
+            assert_eq!(mmap_io_flush(ptr::null()), MMAP_IO_E_INVALID_ARGUMENT);
+            assert!(last_error().contains("handle is null"));
+            let missing = tmp_path("missing");
+            assert_eq!(mmap_io_open_rw(missing.as_ptr(), &mut rw), MMAP_IO_E_IO);
+            assert!(rw.is_null());
+            mmap_io_close(ptr::null_mut());
+        }
+
+        fs::remove_file(&path_str).expect("cleanup");
+    }
+}
@@ -0,0 +1,114 @@
+//! `std::os::*::fs::FileExt` for mapped files.
+//!
+//! With these impls, code generic over `T: FileExt` accepts a [`MemoryMappedFile`] as well
+//! as a plain `File`. Positional I/O on a mapping behaves like on a file of fixed size:
+//! reads past the end are short (0 at or beyond it), and writes past the end are short
+//! rather than growing the file, so `write_all_at` fails with `WriteZero` there.
+
+use std::io;
+
+use crate::mmap::MemoryMappedFile;
+
+impl MemoryMappedFile {
+    // Clamp `len` bytes at `offset` to the mapping; 0 past the end.
+    fn positional_len(&self, offset: u64, len: usize) -> io::Result<usize> {
+        let total = self.current_len()?;
+        Ok(total.saturating_sub(offset).min(len as u64) as usize)
+    }
+
+    pub(crate) fn positional_read(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let n = self.positional_len(offset, buf.len())?;
+        if n > 0 {
+            self.read_into(offset, &mut buf[..n])?;
+        }
+        Ok(n)
+    }
+
+    pub(crate) fn positional_write(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let n = self.positional_len(offset, buf.len())?;
+        if n > 0 {
+            self.update_region(offset, &buf[..n])?;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::fs::FileExt for MemoryMappedFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.positional_read(buf, offset)
+    }
+
+    /// Fails with `PermissionDenied` unless the mapping is `ReadWrite`.
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.positional_write(buf, offset)
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::fs::FileExt for MemoryMappedFile {
+    fn seek_read(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.positional_read(buf, offset)
+    }
+
+    /// Fails with `PermissionDenied` unless the mapping is `ReadWrite`.
+    fn seek_write(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.positional_write(buf, offset)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::os::unix::fs::FileExt;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_fileext_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    // Generic over positional I/O, as third-party code would be
+    fn stamp<F: FileExt>(target: &F) -> io::Result<[u8; 4]> {
+        target.write_all_at(b"abcd", 2)?;
+        let mut out = [0u8; 4];
+        target.read_exact_at(&mut out, 3)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_file_ext_on_mapping() {
+        let path = tmp_path("ext");
+        let _ = fs::remove_file(&path);
+        let mmap = MemoryMappedFile::create_rw(&path, 8).expect("create");
+        assert_eq!(&stamp(&mmap).expect("mapped"), b"bcd\0");
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .expect("open file");
+        assert_eq!(&stamp(&file).expect("plain file"), b"bcd\0");
+
+        // Short at the end, nothing past it, and no growth
+        let mut buf = [0u8; 8];
+        assert_eq!(mmap.read_at(&mut buf, 6).expect("short read"), 2);
+        assert_eq!(mmap.read_at(&mut buf, 100).expect("eof read"), 0);
+        assert_eq!(mmap.write_at(b"xyz", 7).expect("short write"), 1);
+        let err = mmap.write_all_at(b"xyz", 8).expect_err("write past end");
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(mmap.len(), 8);
+
+        let ro = MemoryMappedFile::open_ro(&path).expect("ro");
+        let err = ro.write_at(b"x", 0).expect_err("ro write");
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        drop((mmap, ro));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
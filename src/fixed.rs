@@ -0,0 +1,329 @@
+//! Shared read-write file mappings placed at a caller-chosen virtual address.
+//!
+//! memmap2 always lets the OS pick the address, so fixed placement is done here with the
+//! platform calls directly: `mmap` with `MAP_FIXED_NOREPLACE` on Linux (a plain hint checked
+//! after the fact elsewhere on Unix) and `MapViewOfFileEx` on Windows. Existing mappings at
+//! the address are never replaced; if it is taken, mapping fails instead.
+
+use std::fs::File;
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+/// Read-write shared view of a file at a fixed address.
+pub struct FixedMap {
+    ptr: *mut u8,
+    len: usize,
+    #[cfg(windows)]
+    sys: sys::View,
+}
+
+// SAFETY: the view is plain shared memory; access is synchronized by the owner's RwLock.
+unsafe impl Send for FixedMap {}
+unsafe impl Sync for FixedMap {}
+
+impl FixedMap {
+    /// Map the first `len` bytes of `file` at exactly `addr`.
+    pub(crate) fn map(file: &File, addr: usize, len: usize) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            let ptr = sys::map(file, addr, len)?;
+            Ok(Self { ptr, len })
+        }
+        #[cfg(windows)]
+        {
+            let (ptr, sys) = sys::View::map(file, addr, len)?;
+            Ok(Self { ptr, len, sys })
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = (file, addr, len);
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "fixed-address mappings are not supported on this platform",
+            ))
+        }
+    }
+
+    /// Remap at the same address after the file length changed to `new_len`.
+    ///
+    /// On failure the view keeps its old length, or is left empty (zero length) if the old
+    /// view could not be mapped back either; callers must re-read `len` after an error.
+    pub(crate) fn remap(&mut self, file: &File, new_len: usize) -> io::Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            // Resize in place; without MREMAP_MAYMOVE the kernel fails rather than relocating
+            let _ = file;
+            // SAFETY: [ptr, ptr + len) is our live mapping.
+            let res = unsafe { libc::mremap(self.ptr.cast(), self.len, new_len, 0) };
+            if res == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            self.len = new_len;
+            Ok(())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            // Release the old view first so the address is free to map again
+            let addr = self.ptr as usize;
+            let old_len = self.len;
+            let old = std::mem::replace(
+                self,
+                Self {
+                    ptr: std::ptr::null_mut(),
+                    len: 0,
+                    #[cfg(windows)]
+                    sys: sys::View::empty(),
+                },
+            );
+            drop(old);
+            match Self::map(file, addr, new_len) {
+                Ok(map) => {
+                    *self = map;
+                    Ok(())
+                }
+                Err(err) => {
+                    // Put the old view back so the mapping keeps working at its old size;
+                    // if that fails too, self stays the empty view set up above
+                    if let Ok(map) = Self::map(file, addr, old_len) {
+                        *self = map;
+                    }
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    /// Synchronously write back `[offset, offset + len)`.
+    pub(crate) fn flush_range(&self, offset: usize, len: usize) -> io::Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        #[cfg(unix)]
+        {
+            sys::flush(self.ptr, offset, len)
+        }
+        #[cfg(windows)]
+        {
+            self.sys.flush(self.ptr, offset, len)
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = offset;
+            Ok(())
+        }
+    }
+
+    /// Synchronously write back the whole view.
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        self.flush_range(0, self.len)
+    }
+}
+
+impl Deref for FixedMap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: ptr is a live mapping of len bytes for the lifetime of self.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl DerefMut for FixedMap {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        if self.len == 0 {
+            return &mut [];
+        }
+        // SAFETY: ptr is a live, writable mapping of len bytes borrowed mutably through self.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for FixedMap {
+    fn drop(&mut self) {
+        if self.ptr.is_null() {
+            return;
+        }
+        #[cfg(unix)]
+        // SAFETY: [ptr, ptr + len) is our live mapping and nothing borrows it any more.
+        unsafe {
+            libc::munmap(self.ptr.cast(), self.len);
+        }
+        #[cfg(windows)]
+        self.sys.unmap(self.ptr);
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    pub(super) fn map(file: &File, addr: usize, len: usize) -> io::Result<*mut u8> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let flags = libc::MAP_SHARED | libc::MAP_FIXED_NOREPLACE;
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let flags = libc::MAP_SHARED;
+        // SAFETY: a non-replacing request cannot clobber existing mappings; the result is
+        // checked below.
+        let ptr = unsafe {
+            libc::mmap(
+                addr as *mut libc::c_void,
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // Kernels without MAP_FIXED_NOREPLACE (and non-Linux Unix) treat the address as a
+        // hint, so make sure it was honoured
+        if ptr as usize != addr {
+            // SAFETY: we just created this mapping.
+            unsafe { libc::munmap(ptr, len) };
+            return Err(io::Error::from_raw_os_error(libc::EEXIST));
+        }
+        Ok(ptr.cast())
+    }
+
+    pub(super) fn flush(ptr: *mut u8, offset: usize, len: usize) -> io::Result<()> {
+        // msync wants a page-aligned start
+        let page = crate::utils::page_size();
+        let aligned = offset - offset % page;
+        // SAFETY: the range lies inside the live mapping starting at ptr.
+        let rc = unsafe {
+            libc::msync(
+                ptr.add(aligned).cast(),
+                len + (offset - aligned),
+                libc::MS_SYNC,
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+
+    const PAGE_READWRITE: u32 = 0x04;
+    const FILE_MAP_WRITE: u32 = 0x0002;
+    const FILE_MAP_READ: u32 = 0x0004;
+
+    extern "system" {
+        fn CreateFileMappingW(
+            file: *mut c_void,
+            attributes: *mut c_void,
+            protect: u32,
+            max_size_high: u32,
+            max_size_low: u32,
+            name: *const u16,
+        ) -> *mut c_void;
+        fn MapViewOfFileEx(
+            mapping: *mut c_void,
+            access: u32,
+            offset_high: u32,
+            offset_low: u32,
+            bytes: usize,
+            base: *mut c_void,
+        ) -> *mut c_void;
+        fn UnmapViewOfFile(base: *const c_void) -> i32;
+        fn FlushViewOfFile(base: *const c_void, bytes: usize) -> i32;
+        fn FlushFileBuffers(file: *mut c_void) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    pub(super) struct View {
+        mapping: *mut c_void,
+        file: Option<File>,
+    }
+
+    impl View {
+        pub(super) fn empty() -> Self {
+            Self {
+                mapping: std::ptr::null_mut(),
+                file: None,
+            }
+        }
+
+        pub(super) fn map(file: &File, addr: usize, len: usize) -> io::Result<(*mut u8, Self)> {
+            let file = file.try_clone()?;
+            // SAFETY: the file handle is valid; a zero maximum size means the file length.
+            let mapping = unsafe {
+                CreateFileMappingW(
+                    file.as_raw_handle().cast(),
+                    std::ptr::null_mut(),
+                    PAGE_READWRITE,
+                    0,
+                    0,
+                    std::ptr::null(),
+                )
+            };
+            if mapping.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: mapping is a live section handle; MapViewOfFileEx fails rather than
+            // replacing anything already at `addr`.
+            let ptr = unsafe {
+                MapViewOfFileEx(
+                    mapping,
+                    FILE_MAP_READ | FILE_MAP_WRITE,
+                    0,
+                    0,
+                    len,
+                    addr as *mut c_void,
+                )
+            };
+            if ptr.is_null() {
+                let err = io::Error::last_os_error();
+                // SAFETY: mapping is owned here and not yet shared.
+                unsafe { CloseHandle(mapping) };
+                return Err(err);
+            }
+            Ok((
+                ptr.cast(),
+                Self {
+                    mapping,
+                    file: Some(file),
+                },
+            ))
+        }
+
+        pub(super) fn flush(&self, ptr: *mut u8, offset: usize, len: usize) -> io::Result<()> {
+            // SAFETY: the range lies inside the live view starting at ptr.
+            if unsafe { FlushViewOfFile(ptr.add(offset).cast(), len) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if let Some(file) = &self.file {
+                // SAFETY: the duplicated file handle is owned by this view.
+                if unsafe { FlushFileBuffers(file.as_raw_handle().cast()) } == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        }
+
+        pub(super) fn unmap(&mut self, ptr: *mut u8) {
+            // SAFETY: ptr is the live view and mapping its section handle, both owned here.
+            unsafe {
+                UnmapViewOfFile(ptr.cast());
+                if !self.mapping.is_null() {
+                    CloseHandle(self.mapping);
+                }
+            }
+            self.mapping = std::ptr::null_mut();
+        }
+    }
+}
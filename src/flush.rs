@@ -2,6 +2,23 @@
 //! Flush policy configuration for MemoryMappedFile.
 //!
 //! Controls when writes to a RW mapping should be flushed to disk.
+//!
+//! # Durability levels
+//!
+//! | call                                   | what it guarantees after returning                  |
+//! |----------------------------------------|-----------------------------------------------------|
+//! | `flush` / `flush_range` / `flush_ranges` | dirty pages are handed to the OS for write-back; on Linux this is `msync(MS_ASYNC)`, so data survives a process crash but not necessarily a power loss |
+//! | `flush_and_sync_metadata` / `flush_parallel` | data is written synchronously and the file is `fsync`ed (`FlushFileBuffers` on Windows), so contents *and* length survive a power loss |
+//! | `sync_parent_directory`                | the directory entry of a newly created file is durable (Unix; a no-op on Windows, where NTFS journals it) |
+//!
+//! Use the first level for throughput, the second at commit points, and the third once
+//! after creating a file whose existence must survive a crash.
+//!
+//! Independently of durability, [`FlushOptions::invalidate`] asks the OS to make other
+//! mappings of the same file coherent with this one (`MS_INVALIDATE`), for setups where
+//! another process maps the file read-only.
+//!
+//! [`FlushOptions::invalidate`]: crate::flush::FlushOptions::invalidate
 
 /// Policy controlling when to flush dirty pages to disk.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -17,6 +34,116 @@ pub enum FlushPolicy {
     EveryBytes(usize),
     /// Flush after every W writes (calls to update_region).
     EveryWrites(usize),
-    /// Reserved for future time-based flushing (no-op for now).
+    /// Flush every N milliseconds while there are unflushed writes.
+    ///
+    /// A background thread per mapping wakes on the interval and stops when the mapping
+    /// is dropped; writers never block on it. Errors go to the mapping's observer, and
+    /// opening the mapping fails with `MmapIoError::Io` if the thread cannot be spawned.
+    /// `EveryMillis(0)` behaves like `Manual`.
     EveryMillis(u64),
 }
+
+/// Options for [`MemoryMappedFile::flush_with`](crate::MemoryMappedFile::flush_with) and
+/// [`flush_range_with`](crate::MemoryMappedFile::flush_range_with).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FlushOptions {
+    /// Also invalidate other mappings of the same file so they observe the written data.
+    ///
+    /// On Unix this adds `MS_INVALIDATE` to the `msync` call and always issues it, even
+    /// without pending writes. On Windows, views of the same file are already coherent, so
+    /// this only forces the flush (`FlushViewOfFile`) to happen.
+    pub invalidate: bool,
+}
+
+// Round `(offset, len)` ranges out to `page` boundaries (clamped to `total`), then merge
+// overlapping or touching ones. If more than `max_ranges` remain, the ranges separated by
+// the smallest gaps are joined until the limit holds: flushing a few clean pages in a gap
+// is cheaper than another syscall.
+pub(crate) fn coalesce_ranges(
+    ranges: &[(u64, u64)],
+    page: u64,
+    total: u64,
+    max_ranges: usize,
+) -> Vec<(u64, u64)> {
+    use crate::utils::{align_down, align_up};
+
+    let mut spans: Vec<(u64, u64)> = ranges
+        .iter()
+        .filter(|&&(_, len)| len > 0)
+        .map(|&(offset, len)| {
+            let end = align_up(offset.saturating_add(len), page).min(total);
+            (align_down(offset, page), end)
+        })
+        .collect();
+    spans.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let max_ranges = max_ranges.max(1);
+    if merged.len() > max_ranges {
+        // Join across the (len - max) smallest gaps
+        let mut gaps: Vec<(u64, usize)> = merged
+            .windows(2)
+            .enumerate()
+            .map(|(i, w)| (w[1].0 - w[0].1, i))
+            .collect();
+        gaps.sort_unstable();
+        let mut join = vec![false; merged.len()];
+        for &(_, i) in &gaps[..merged.len() - max_ranges] {
+            join[i] = true;
+        }
+        let mut limited: Vec<(u64, u64)> = Vec::with_capacity(max_ranges);
+        let mut extend = false;
+        for (i, &(start, end)) in merged.iter().enumerate() {
+            match limited.last_mut() {
+                Some(last) if extend => last.1 = end,
+                _ => limited.push((start, end)),
+            }
+            extend = join[i];
+        }
+        merged = limited;
+    }
+    merged.into_iter().map(|(s, e)| (s, e - s)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coalesce_ranges() {
+        // Overlapping, touching, and unsorted ranges collapse into page-aligned spans
+        let ranges = [
+            (5000, 10),
+            (0, 1),
+            (4095, 2),
+            (20_000, 1),
+            (12_288, 100),
+            (0, 0),
+        ];
+        assert_eq!(
+            coalesce_ranges(&ranges, 4096, 32_768, usize::MAX),
+            vec![(0, 8192), (12_288, 8192)]
+        );
+        // The tail is clamped to the file length
+        assert_eq!(
+            coalesce_ranges(&[(30_000, 10_000)], 4096, 32_000, 8),
+            vec![(28_672, 3328)]
+        );
+        // With a syscall budget, the closest neighbours are joined first
+        let far = [(0, 1), (8192, 1), (100 * 4096, 1), (101 * 4096 + 10, 1)];
+        assert_eq!(
+            coalesce_ranges(&far, 4096, 1 << 20, 2),
+            vec![(0, 12_288), (100 * 4096, 8192)]
+        );
+        assert_eq!(
+            coalesce_ranges(&far, 4096, 1 << 20, 0),
+            vec![(0, 102 * 4096)]
+        );
+    }
+}
@@ -0,0 +1,564 @@
+//! Shared background flushing with priorities and concurrency limits.
+//!
+//! Instead of each mapping blocking its writer on `flush()` or spawning its own thread, a
+//! [`FlushPool`] runs flushes on a fixed set of worker threads. Requests carry a
+//! [`FlushPriority`]: queued critical flushes (e.g. a write-ahead log) always start before
+//! normal and lazy ones, and each priority can be capped to a number of concurrently
+//! running flushes so bulk cache write-back never occupies every worker.
+//!
+//! Requests for a mapping that is already queued are merged: the caller gets the pending
+//! request's [`FlushTicket`], and the request is promoted if the new priority is higher.
+//!
+//! Mappings built with [`FlushPolicy::EveryMillis`](crate::flush::FlushPolicy::EveryMillis)
+//! instead get a dedicated timer thread that flushes them while they have pending writes.
+
+use std::collections::{HashMap, VecDeque};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, OnceLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::{Inner, MemoryMappedFile};
+
+/// Urgency of a background flush request, highest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FlushPriority {
+    /// Durability-critical data such as write-ahead logs.
+    Critical,
+    /// Regular application data.
+    Normal,
+    /// Opportunistic write-back of caches.
+    Lazy,
+}
+
+const PRIORITIES: [FlushPriority; 3] = [
+    FlushPriority::Critical,
+    FlushPriority::Normal,
+    FlushPriority::Lazy,
+];
+
+/// Completion handle for a queued flush.
+#[derive(Debug, Clone)]
+pub struct FlushTicket {
+    state: Arc<TicketState>,
+}
+
+#[derive(Debug, Default)]
+struct TicketState {
+    // None while pending; the error is kept as text because MmapIoError is not Clone
+    result: Mutex<Option<std::result::Result<(), String>>>,
+    done: Condvar,
+}
+
+impl FlushTicket {
+    fn new() -> Self {
+        Self {
+            state: Arc::new(TicketState::default()),
+        }
+    }
+
+    fn complete(&self, result: Result<()>) {
+        *self.state.result.lock() = Some(result.map_err(|e| e.to_string()));
+        self.state.done.notify_all();
+    }
+
+    /// Whether the flush has finished (successfully or not).
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.state.result.lock().is_some()
+    }
+
+    /// Block until the flush has finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::FlushFailed` with the worker's error if the flush failed.
+    pub fn wait(&self) -> Result<()> {
+        let mut result = self.state.result.lock();
+        while result.is_none() {
+            self.state.done.wait(&mut result);
+        }
+        match result.as_ref() {
+            Some(Err(msg)) => Err(MmapIoError::FlushFailed(msg.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+struct Job {
+    key: usize,
+    priority: FlushPriority,
+    mmap: MemoryMappedFile,
+}
+
+struct Pending {
+    priority: FlushPriority,
+    ticket: FlushTicket,
+}
+
+#[derive(Default)]
+struct State {
+    // One FIFO per priority, indexed like PRIORITIES; may hold stale entries of promoted jobs
+    queues: [VecDeque<Job>; 3],
+    pending: HashMap<usize, Pending>,
+    running: [usize; 3],
+    shutdown: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    work: Condvar,
+    limits: [usize; 3],
+}
+
+/// Builder for [`FlushPool`].
+#[derive(Debug, Clone)]
+pub struct FlushPoolBuilder {
+    threads: usize,
+    limits: [usize; 3],
+}
+
+impl FlushPoolBuilder {
+    /// Number of worker threads, i.e. the overall concurrency limit (at least 1).
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Run at most `limit` flushes of `priority` at the same time (at least 1).
+    pub fn max_concurrent(mut self, priority: FlushPriority, limit: usize) -> Self {
+        self.limits[priority as usize] = limit.max(1);
+        self
+    }
+
+    /// Start the worker threads.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if a worker thread cannot be spawned.
+    pub fn build(self) -> Result<FlushPool> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            work: Condvar::new(),
+            limits: self.limits,
+        });
+        let mut workers = Vec::with_capacity(self.threads);
+        for i in 0..self.threads {
+            let worker_shared = Arc::clone(&shared);
+            let worker = thread::Builder::new()
+                .name(format!("mmap-io-flush-{i}"))
+                .spawn(move || worker_loop(&worker_shared));
+            match worker {
+                Ok(handle) => workers.push(handle),
+                Err(e) => {
+                    // Stop the workers already started before reporting the failure
+                    drop(FlushPool { shared, workers });
+                    return Err(MmapIoError::Io(e));
+                }
+            }
+        }
+        Ok(FlushPool { shared, workers })
+    }
+}
+
+/// Pool of background flush workers shared by many mappings.
+///
+/// Dropping the pool finishes every queued flush, then joins the workers.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mmap_io::{MemoryMappedFile, flusher::{FlushPool, FlushPriority}};
+///
+/// let pool = FlushPool::builder()
+///     .threads(4)
+///     .max_concurrent(FlushPriority::Lazy, 1)
+///     .build()?;
+/// let wal = MemoryMappedFile::create_rw("wal.bin", 1 << 20)?;
+/// wal.update_region(0, b"commit")?;
+/// pool.submit(&wal, FlushPriority::Critical).wait()?;
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+pub struct FlushPool {
+    shared: Arc<Shared>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for FlushPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlushPool")
+            .field("threads", &self.workers.len())
+            .field("queued", &self.queued())
+            .finish()
+    }
+}
+
+impl FlushPool {
+    /// Builder with one worker per available CPU (at most 4) and no per-priority limits.
+    pub fn builder() -> FlushPoolBuilder {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get().min(4));
+        FlushPoolBuilder {
+            threads,
+            limits: [usize::MAX; 3],
+        }
+    }
+
+    /// Process-wide pool with default settings, started on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if the workers cannot be spawned.
+    pub fn global() -> Result<&'static FlushPool> {
+        static GLOBAL: OnceLock<FlushPool> = OnceLock::new();
+        if let Some(pool) = GLOBAL.get() {
+            return Ok(pool);
+        }
+        let pool = Self::builder().build()?;
+        Ok(GLOBAL.get_or_init(|| pool))
+    }
+
+    /// Queue a flush of `mmap` at `priority`.
+    ///
+    /// If the mapping already has a queued flush, its ticket is returned and it is promoted
+    /// to `priority` when that is higher. A flush that has already started is not merged;
+    /// a new one is queued so writes made meanwhile are covered.
+    pub fn submit(&self, mmap: &MemoryMappedFile, priority: FlushPriority) -> FlushTicket {
+        let key = Arc::as_ptr(&mmap.inner) as usize;
+        let mut state = self.shared.state.lock();
+        if let Some(pending) = state.pending.get_mut(&key) {
+            let ticket = pending.ticket.clone();
+            if priority < pending.priority {
+                // The old queue entry goes stale and is skipped by the workers
+                pending.priority = priority;
+                state.queues[priority as usize].push_back(Job {
+                    key,
+                    priority,
+                    mmap: mmap.clone(),
+                });
+                self.shared.work.notify_one();
+            }
+            return ticket;
+        }
+        let ticket = FlushTicket::new();
+        state.pending.insert(
+            key,
+            Pending {
+                priority,
+                ticket: ticket.clone(),
+            },
+        );
+        state.queues[priority as usize].push_back(Job {
+            key,
+            priority,
+            mmap: mmap.clone(),
+        });
+        self.shared.work.notify_one();
+        ticket
+    }
+
+    /// Number of flushes waiting to start.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.shared.state.lock().pending.len()
+    }
+}
+
+impl Drop for FlushPool {
+    fn drop(&mut self) {
+        self.shared.state.lock().shutdown = true;
+        self.shared.work.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn worker_loop(shared: &Shared) {
+    let mut state = shared.state.lock();
+    loop {
+        if let Some((job, ticket)) = next_job(&mut state, &shared.limits) {
+            let slot = job.priority as usize;
+            state.running[slot] += 1;
+            drop(state);
+            // A panicking observer fails this flush, not the worker and its running slot
+            let flushed = catch_unwind(AssertUnwindSafe(|| job.mmap.flush()))
+                .unwrap_or_else(|_| Err(MmapIoError::FlushFailed("flush panicked".into())));
+            ticket.complete(flushed);
+            let _ = catch_unwind(AssertUnwindSafe(move || drop(job)));
+            state = shared.state.lock();
+            state.running[slot] -= 1;
+            // A finished job may unblock a capped priority for another worker
+            shared.work.notify_all();
+            continue;
+        }
+        if state.shutdown && state.pending.is_empty() {
+            return;
+        }
+        shared.work.wait(&mut state);
+    }
+}
+
+// Take the highest-priority runnable job, skipping stale entries of promoted requests.
+fn next_job(state: &mut State, limits: &[usize; 3]) -> Option<(Job, FlushTicket)> {
+    for priority in PRIORITIES {
+        let slot = priority as usize;
+        if state.running[slot] >= limits[slot] {
+            continue;
+        }
+        while let Some(job) = state.queues[slot].pop_front() {
+            let current = state.pending.get(&job.key).map(|p| p.priority);
+            if current == Some(job.priority) {
+                let pending = state.pending.remove(&job.key)?;
+                return Some((job, pending.ticket));
+            }
+        }
+    }
+    None
+}
+
+// Timer thread behind `FlushPolicy::EveryMillis`. It only holds a weak reference, so the
+// mapping is dropped as usual, and is stopped when the mapping (owning this) goes away.
+pub(crate) struct IntervalFlusher {
+    interval: Duration,
+    // Stop flag and the condvar that wakes the thread early to see it
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+impl IntervalFlusher {
+    pub(crate) fn start(inner: Weak<Inner>, interval: Duration) -> std::io::Result<Self> {
+        let flusher = Self {
+            interval,
+            stop: Arc::new((Mutex::new(false), Condvar::new())),
+            thread: Mutex::new(None),
+        };
+        flusher.resume(inner)?;
+        Ok(flusher)
+    }
+
+    // Start the thread again after `stop`, for a handle that stopped it to take the mapping
+    // apart but turned out not to be the last one. No-op while the thread is running.
+    pub(crate) fn resume(&self, inner: Weak<Inner>) -> std::io::Result<()> {
+        let mut slot = self.thread.lock();
+        if slot.is_some() {
+            return Ok(());
+        }
+        *self.stop.0.lock() = false;
+        let interval = self.interval;
+        let thread_stop = Arc::clone(&self.stop);
+        let thread = thread::Builder::new()
+            .name("mmap-io-interval-flush".into())
+            .spawn(move || {
+                let (stopped, wake) = &*thread_stop;
+                loop {
+                    let deadline = Instant::now() + interval;
+                    let mut flag = stopped.lock();
+                    while !*flag && !wake.wait_until(&mut flag, deadline).timed_out() {}
+                    if *flag {
+                        return;
+                    }
+                    drop(flag);
+                    let Some(inner) = inner.upgrade() else {
+                        return;
+                    };
+                    // flush() skips mappings without pending writes; failures reach the
+                    // mapping's observer like any other flush error
+                    let _ = MemoryMappedFile { inner }.flush();
+                }
+            })?;
+        *slot = Some(thread);
+        Ok(())
+    }
+
+    // Stop the thread and wait for it, unless called from the thread itself (when its
+    // flush dropped the last handle to the mapping).
+    pub(crate) fn stop(&self) {
+        let (stopped, wake) = &*self.stop;
+        *stopped.lock() = true;
+        wake.notify_all();
+        if let Some(thread) = self.thread.lock().take() {
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+impl Drop for IntervalFlusher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observer::MmapObserver;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_flusher_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    struct Probe {
+        name: &'static str,
+        order: Arc<Mutex<Vec<&'static str>>>,
+        // Set on the blocker: announce the flush started, then wait for the release
+        gate: Option<Mutex<(mpsc::Sender<()>, mpsc::Receiver<()>)>>,
+    }
+
+    impl MmapObserver for Probe {
+        fn on_flush(&self, _offset: u64, _len: u64) {
+            if let Some(gate) = &self.gate {
+                let gate = gate.lock();
+                let _ = gate.0.send(());
+                let _ = gate.1.recv();
+            }
+            self.order.lock().push(self.name);
+        }
+    }
+
+    fn dirty_map(
+        name: &'static str,
+        order: &Arc<Mutex<Vec<&'static str>>>,
+        gate: Option<(mpsc::Sender<()>, mpsc::Receiver<()>)>,
+    ) -> (PathBuf, MemoryMappedFile) {
+        let path = tmp_path(name);
+        let mmap = MemoryMappedFile::create_rw(&path, 4096).expect("create");
+        mmap.update_region(0, name.as_bytes()).expect("write");
+        mmap.set_observer(Arc::new(Probe {
+            name,
+            order: Arc::clone(order),
+            gate: gate.map(Mutex::new),
+        }));
+        (path, mmap)
+    }
+
+    #[test]
+    fn test_every_millis_flushes_in_background() {
+        struct Notify(Mutex<mpsc::Sender<u64>>);
+        impl MmapObserver for Notify {
+            fn on_flush(&self, _offset: u64, len: u64) {
+                let _ = self.0.lock().send(len);
+            }
+        }
+
+        let path = tmp_path("every_millis");
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(4096)
+            .flush_policy(crate::flush::FlushPolicy::EveryMillis(10))
+            .create()
+            .expect("create");
+        let (tx, flushes) = mpsc::channel();
+        mmap.set_observer(Arc::new(Notify(Mutex::new(tx))));
+        // Nothing pending: the timer does not flush
+        assert!(flushes.recv_timeout(Duration::from_millis(100)).is_err());
+
+        mmap.update_region(0, b"tick").expect("write");
+        let len = flushes
+            .recv_timeout(Duration::from_secs(5))
+            .expect("background flush");
+        assert_eq!(len, 4096);
+        assert!(flushes.recv_timeout(Duration::from_millis(100)).is_err());
+
+        // A failed into_file on a shared mapping leaves the timer running
+        assert!(mmap.clone().into_file().is_err());
+        mmap.update_region(0, b"tock").expect("write");
+        flushes
+            .recv_timeout(Duration::from_secs(5))
+            .expect("background flush after failed into_file");
+
+        // The timer never holds a handle that would make into_file fail
+        let file = mmap.into_file().expect("into_file");
+        drop(file);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_flush_pool_priorities() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let (started_tx, started) = mpsc::channel();
+        let (release, gate_rx) = mpsc::channel();
+        let (p0, blocker) = dirty_map("blocker", &order, Some((started_tx, gate_rx)));
+        let (p1, lazy) = dirty_map("lazy", &order, None);
+        let (p2, normal) = dirty_map("normal", &order, None);
+        let (p3, critical) = dirty_map("critical", &order, None);
+
+        let pool = FlushPool::builder().threads(1).build().expect("pool");
+        let first = pool.submit(&blocker, FlushPriority::Lazy);
+        started.recv().expect("blocker started");
+
+        // Queued while the only worker is busy
+        let lazy_ticket = pool.submit(&lazy, FlushPriority::Lazy);
+        let normal_ticket = pool.submit(&normal, FlushPriority::Normal);
+        let critical_ticket = pool.submit(&critical, FlushPriority::Critical);
+        // Resubmitting merges with the queued request and promotes it
+        let promoted = pool.submit(&lazy, FlushPriority::Critical);
+        assert_eq!(pool.queued(), 3);
+
+        release.send(()).expect("release");
+        for ticket in [
+            &first,
+            &lazy_ticket,
+            &normal_ticket,
+            &critical_ticket,
+            &promoted,
+        ] {
+            ticket.wait().expect("flush");
+        }
+        assert!(promoted.is_done() && lazy_ticket.is_done());
+        assert_eq!(*order.lock(), vec!["blocker", "critical", "lazy", "normal"]);
+
+        drop(pool);
+        drop((blocker, lazy, normal, critical));
+        for p in [p0, p1, p2, p3] {
+            fs::remove_file(p).expect("cleanup");
+        }
+    }
+
+    #[test]
+    fn test_flush_pool_survives_panicking_observer() {
+        struct Panics;
+        impl MmapObserver for Panics {
+            fn on_flush(&self, _offset: u64, _len: u64) {
+                panic!("observer failed");
+            }
+        }
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let bad_path = tmp_path("panicking");
+        let bad = MemoryMappedFile::create_rw(&bad_path, 4096).expect("create");
+        bad.update_region(0, b"boom").expect("write");
+        bad.set_observer(Arc::new(Panics));
+        let (good_path, good) = dirty_map("after_panic", &order, None);
+
+        let pool = FlushPool::builder()
+            .threads(1)
+            .max_concurrent(FlushPriority::Normal, 1)
+            .build()
+            .expect("pool");
+        let failed = pool.submit(&bad, FlushPriority::Normal);
+        assert!(matches!(failed.wait(), Err(MmapIoError::FlushFailed(_))));
+        // The only worker and the only Normal slot are still available
+        pool.submit(&good, FlushPriority::Normal)
+            .wait()
+            .expect("flush after panic");
+        assert_eq!(*order.lock(), vec!["after_panic"]);
+
+        drop(pool);
+        drop((bad, good));
+        fs::remove_file(&bad_path).expect("cleanup");
+        fs::remove_file(&good_path).expect("cleanup");
+    }
+}
@@ -0,0 +1,208 @@
+//! Blocking wait and wake on mapped `u32` counters.
+//!
+//! [`MemoryMappedFile::wait_u32`] sleeps while a mapped `u32` still holds an expected value
+//! and [`MemoryMappedFile::wake_u32`] wakes sleepers after the value changed, so consumers
+//! block instead of spinning on [`atomic_u32`](MemoryMappedFile::atomic_u32) loads.
+//!
+//! On Linux this is a shared (non-private) `futex`, keyed by the underlying file page, so
+//! waiters and wakers in different processes, or using different mappings of the same
+//! file, reach each other. On Windows it uses `WaitOnAddress`, which only wakes waiters in
+//! the same process; use a [`Doorbell`](crate::doorbell::Doorbell) across processes there.
+
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::mmap::MemoryMappedFile;
+
+impl MemoryMappedFile {
+    /// Block while the `u32` at `offset` equals `expected`, for at most `timeout`.
+    ///
+    /// Returns `false` if the timeout elapsed, `true` otherwise: after a wake, if the value
+    /// already differed, or on a spurious wakeup. Re-check the value after returning.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` / `OutOfBounds` for a bad offset.
+    /// Returns `MmapIoError::Io` if the wait fails.
+    pub fn wait_u32(&self, offset: u64, expected: u32, timeout: Option<Duration>) -> Result<bool> {
+        let atomic = self.atomic_u32(offset)?;
+        sys::wait(atomic.as_ptr(), expected, timeout)
+    }
+
+    /// Wake up to `n` threads blocked in [`wait_u32`](Self::wait_u32) on `offset`
+    /// (`u32::MAX` wakes all). Update the value before waking.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` / `OutOfBounds` for a bad offset.
+    /// Returns `MmapIoError::Io` if the wake fails.
+    pub fn wake_u32(&self, offset: u64, n: u32) -> Result<()> {
+        let atomic = self.atomic_u32(offset)?;
+        sys::wake(atomic.as_ptr(), n)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use super::{Duration, Result};
+    use std::io;
+
+    pub(super) fn wait(addr: *mut u32, expected: u32, timeout: Option<Duration>) -> Result<bool> {
+        let ts = timeout.map(|t| libc::timespec {
+            tv_sec: libc::time_t::try_from(t.as_secs()).unwrap_or(libc::time_t::MAX),
+            tv_nsec: t.subsec_nanos() as libc::c_long,
+        });
+        let ts_ptr = ts
+            .as_ref()
+            .map_or(std::ptr::null(), |ts| ts as *const libc::timespec);
+        // SAFETY: addr points to an aligned, mapped u32 that outlives the call; shared
+        // (non-PRIVATE) futex so other processes mapping the file can wake us.
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                addr,
+                libc::FUTEX_WAIT,
+                expected,
+                ts_ptr,
+                std::ptr::null::<u32>(),
+                0,
+            )
+        };
+        if rc == 0 {
+            return Ok(true);
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::ETIMEDOUT) => Ok(false),
+            // EAGAIN: the value already changed; EINTR: spurious
+            Some(libc::EAGAIN) | Some(libc::EINTR) => Ok(true),
+            _ => Err(err.into()),
+        }
+    }
+
+    pub(super) fn wake(addr: *mut u32, n: u32) -> Result<()> {
+        let count = libc::c_int::try_from(n).unwrap_or(libc::c_int::MAX);
+        // SAFETY: addr points to an aligned, mapped u32; FUTEX_WAKE does not dereference it
+        // beyond looking up the futex key.
+        let rc = unsafe { libc::syscall(libc::SYS_futex, addr, libc::FUTEX_WAKE, count) };
+        if rc < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use super::{Duration, Result};
+    use std::ffi::c_void;
+    use std::io;
+
+    const ERROR_TIMEOUT: i32 = 1460;
+    const INFINITE: u32 = 0xFFFF_FFFF;
+
+    #[link(name = "synchronization")]
+    extern "system" {
+        fn WaitOnAddress(
+            address: *const c_void,
+            compare: *const c_void,
+            size: usize,
+            millis: u32,
+        ) -> i32;
+        fn WakeByAddressSingle(address: *const c_void);
+        fn WakeByAddressAll(address: *const c_void);
+    }
+
+    pub(super) fn wait(addr: *mut u32, expected: u32, timeout: Option<Duration>) -> Result<bool> {
+        let ms = timeout.map_or(INFINITE, |t| {
+            u32::try_from(t.as_millis()).unwrap_or(INFINITE - 1)
+        });
+        // SAFETY: addr points to an aligned, mapped u32 and `expected` lives for the call.
+        let ok = unsafe {
+            WaitOnAddress(
+                addr as *const c_void,
+                (&expected as *const u32).cast(),
+                4,
+                ms,
+            )
+        };
+        if ok != 0 {
+            return Ok(true);
+        }
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() == Some(ERROR_TIMEOUT) {
+            return Ok(false);
+        }
+        Err(err.into())
+    }
+
+    pub(super) fn wake(addr: *mut u32, n: u32) -> Result<()> {
+        // SAFETY: addr points to an aligned, mapped u32.
+        unsafe {
+            if n == 1 {
+                WakeByAddressSingle(addr as *const c_void);
+            } else if n > 1 {
+                // No bounded variant; extra waiters re-check their value and sleep again
+                WakeByAddressAll(addr as *const c_void);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::Instant;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_futex_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_wait_and_wake_u32() {
+        let path = tmp_path("wake");
+        let _ = fs::remove_file(&path);
+        let mmap = MemoryMappedFile::create_rw(&path, 64).expect("create");
+
+        // Value differs: returns immediately; unchanged: times out
+        assert!(mmap.wait_u32(8, 1, None).expect("mismatch"));
+        let started = Instant::now();
+        assert!(!mmap
+            .wait_u32(8, 0, Some(Duration::from_millis(20)))
+            .expect("timeout"));
+        assert!(started.elapsed() >= Duration::from_millis(15));
+        assert!(mmap.wait_u32(6, 0, None).is_err());
+
+        // The waker uses a separate mapping of the same file
+        let other = MemoryMappedFile::open_rw(&path).expect("second mapping");
+        let waiter = thread::spawn(move || {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while mmap.atomic_u32(8).expect("atomic").load(Ordering::Acquire) == 0 {
+                assert!(Instant::now() < deadline, "never woken");
+                mmap.wait_u32(8, 0, Some(Duration::from_secs(1)))
+                    .expect("wait");
+            }
+        });
+        thread::sleep(Duration::from_millis(20));
+        other
+            .atomic_u32(8)
+            .expect("atomic")
+            .store(1, Ordering::Release);
+        other.wake_u32(8, u32::MAX).expect("wake");
+        waiter.join().expect("waiter");
+
+        drop(other);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,363 @@
+//! Recovery from `SIGBUS` / `EXCEPTION_IN_PAGE_ERROR` in copies to and from guarded
+//! mappings.
+//!
+//! Touching a page of a shared mapping whose file was truncated underneath it, or whose
+//! block cannot be read from disk, raises `SIGBUS` on Unix and `EXCEPTION_IN_PAGE_ERROR` on
+//! Windows, which kill the process by default. For mappings built with
+//! [`guarded`](crate::mmap::MemoryMappedFileBuilder::guarded), the crate runs its copies
+//! through [`copy`]:
+//!
+//! - On Unix, a process-wide `SIGBUS` handler (installed on first use and chaining to any
+//!   previous handler) recognizes faults that hit the range the current thread is copying,
+//!   maps an anonymous zero page over the faulting page, and lets the copy run to
+//!   completion. Afterwards the file pages are mapped back in place. Writes that reach the
+//!   zero page from elsewhere in the process in the meantime are lost, which is why
+//!   guarded mappings refuse access that bypasses the map lock.
+//! - On Windows, the copy goes through `ReadProcessMemory` / `WriteProcessMemory` on the
+//!   current process. The kernel performs it under its own exception handling and fails
+//!   the call on an in-page error, so the view is never modified.
+//!
+//! Either way the copy then reports `MmapIoError::MappingFault`. No `setjmp`/`longjmp` is
+//! involved, so no Rust frames are skipped, and faults outside a guarded copy are handled
+//! as before.
+
+use std::fs::File;
+use std::ptr;
+
+use crate::errors::{MmapIoError, Result};
+
+/// One side of a copy to or from mapped bytes.
+pub(crate) enum Transfer<'a> {
+    /// Copy mapped bytes into the buffer.
+    Read(&'a mut [u8]),
+    /// Copy the buffer into mapped bytes.
+    Write(&'a [u8]),
+}
+
+impl Transfer<'_> {
+    #[cfg(unix)]
+    fn len(&self) -> usize {
+        match self {
+            Transfer::Read(buf) => buf.len(),
+            Transfer::Write(data) => data.len(),
+        }
+    }
+
+    /// Copy between the buffer and the mapped bytes at `view`, without any guard.
+    ///
+    /// # Safety
+    ///
+    /// `view` must be valid for the buffer's length, writable for `Write`, and no
+    /// reference to those bytes may be live.
+    pub(crate) unsafe fn run(self, view: *mut u8) {
+        // SAFETY: forwarded from the caller's contract; the buffer is a separate borrow.
+        unsafe {
+            match self {
+                Transfer::Read(buf) => ptr::copy_nonoverlapping(view, buf.as_mut_ptr(), buf.len()),
+                Transfer::Write(data) => ptr::copy_nonoverlapping(data.as_ptr(), view, data.len()),
+            }
+        }
+    }
+}
+
+/// Run `transfer` against the mapped bytes starting at `base + start` of the mapping at
+/// `base` of `file` from `file_offset`, turning a fault in that range into
+/// `MmapIoError::MappingFault`.
+///
+/// # Safety
+///
+/// As for [`Transfer::run`] on `base + start`.
+#[cfg(unix)]
+pub(crate) unsafe fn copy(
+    base: *mut u8,
+    start: usize,
+    file: &File,
+    file_offset: u64,
+    writable: bool,
+    transfer: Transfer<'_>,
+) -> Result<()> {
+    sys::install()?;
+    // SAFETY: `start` lies inside the mapping per the caller's contract.
+    let view = unsafe { base.add(start) };
+    let len = transfer.len();
+    // SAFETY: forwarded from the caller's contract.
+    let copy = || unsafe { transfer.run(view) };
+    let Some(faulted) = sys::guarded(view as usize, len, copy) else {
+        return Ok(());
+    };
+    sys::restore(base, faulted.clone(), file, file_offset, writable)?;
+    let offset = faulted.start.saturating_sub(base as usize).max(start);
+    Err(MmapIoError::MappingFault {
+        offset: offset as u64,
+    })
+}
+
+/// Run `transfer` against the mapped bytes starting at `base + start`, turning an in-page
+/// error in that range into `MmapIoError::MappingFault`.
+///
+/// # Safety
+///
+/// As for [`Transfer::run`] on `base + start`.
+#[cfg(windows)]
+pub(crate) unsafe fn copy(
+    base: *mut u8,
+    start: usize,
+    _file: &File,
+    _file_offset: u64,
+    _writable: bool,
+    transfer: Transfer<'_>,
+) -> Result<()> {
+    // SAFETY: `start` lies inside the mapping, and the rest is forwarded from the caller.
+    match unsafe { sys::transfer(base.add(start), transfer) } {
+        Ok(()) => Ok(()),
+        Err(copied) => Err(MmapIoError::MappingFault {
+            offset: (start + copied) as u64,
+        }),
+    }
+}
+
+// Guarded mappings are refused at build time elsewhere; copies run unguarded.
+#[cfg(not(any(unix, windows)))]
+pub(crate) unsafe fn copy(
+    base: *mut u8,
+    start: usize,
+    _file: &File,
+    _file_offset: u64,
+    _writable: bool,
+    transfer: Transfer<'_>,
+) -> Result<()> {
+    // SAFETY: forwarded from the caller's contract.
+    unsafe { transfer.run(base.add(start)) };
+    Ok(())
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+
+    use super::Transfer;
+
+    extern "system" {
+        fn GetCurrentProcess() -> *mut c_void;
+        fn ReadProcessMemory(
+            process: *mut c_void,
+            base: *const c_void,
+            buf: *mut c_void,
+            size: usize,
+            read: *mut usize,
+        ) -> i32;
+        fn WriteProcessMemory(
+            process: *mut c_void,
+            base: *mut c_void,
+            buf: *const c_void,
+            size: usize,
+            written: *mut usize,
+        ) -> i32;
+    }
+
+    // Copy through the kernel; on failure, returns how many bytes were copied before it.
+    pub(super) unsafe fn transfer(view: *mut u8, transfer: Transfer<'_>) -> Result<(), usize> {
+        let mut copied = 0;
+        // SAFETY: the caller guarantees `view` is valid (and writable for writes) for the
+        // buffer's length; the pseudo-handle of the current process needs no closing.
+        let ok = unsafe {
+            let process = GetCurrentProcess();
+            match transfer {
+                Transfer::Read(buf) => ReadProcessMemory(
+                    process,
+                    view.cast(),
+                    buf.as_mut_ptr().cast(),
+                    buf.len(),
+                    &mut copied,
+                ),
+                Transfer::Write(data) => WriteProcessMemory(
+                    process,
+                    view.cast(),
+                    data.as_ptr().cast(),
+                    data.len(),
+                    &mut copied,
+                ),
+            }
+        };
+        if ok != 0 {
+            Ok(())
+        } else {
+            Err(copied)
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::cell::Cell;
+    use std::ffi::{c_int, c_void};
+    use std::fs::File;
+    use std::io;
+    use std::ops::Range;
+    use std::os::fd::AsRawFd;
+    use std::ptr;
+    use std::sync::atomic::{compiler_fence, AtomicUsize, Ordering};
+    use std::sync::OnceLock;
+
+    // Handler in place before ours, for faults that are not ours to recover
+    static PREVIOUS: OnceLock<libc::sigaction> = OnceLock::new();
+    // Cached here because sysconf is not async-signal-safe
+    static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+    thread_local! {
+        // Address range the current thread is copying under guard; empty when idle
+        static ACTIVE: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+        // Span of pages replaced by the handler during the current copy
+        static FAULTED: Cell<(usize, usize)> = const { Cell::new((usize::MAX, 0)) };
+    }
+
+    pub(super) fn install() -> io::Result<()> {
+        static INSTALLED: OnceLock<Option<i32>> = OnceLock::new();
+        let failure = *INSTALLED.get_or_init(|| {
+            PAGE_SIZE.store(crate::utils::page_size(), Ordering::Relaxed);
+            // SAFETY: an all-zero sigaction is valid, and sigaction only reads `action`
+            // and writes `previous`.
+            unsafe {
+                let mut previous: libc::sigaction = std::mem::zeroed();
+                if libc::sigaction(libc::SIGBUS, ptr::null(), &mut previous) != 0 {
+                    return io::Error::last_os_error().raw_os_error();
+                }
+                let _ = PREVIOUS.set(previous);
+                let mut action: libc::sigaction = std::mem::zeroed();
+                let handler: extern "C" fn(c_int, *mut libc::siginfo_t, *mut c_void) = handler;
+                action.sa_sigaction = handler as usize;
+                action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+                libc::sigemptyset(&mut action.sa_mask);
+                if libc::sigaction(libc::SIGBUS, &action, ptr::null_mut()) != 0 {
+                    return io::Error::last_os_error().raw_os_error();
+                }
+            }
+            None
+        });
+        match failure {
+            Some(code) => Err(io::Error::from_raw_os_error(code)),
+            None => Ok(()),
+        }
+    }
+
+    // Run `copy` with `[start, start + len)` guarded; returns the replaced pages, if any.
+    pub(super) fn guarded(start: usize, len: usize, copy: impl FnOnce()) -> Option<Range<usize>> {
+        ACTIVE.with(|active| active.set((start, start + len)));
+        FAULTED.with(|faulted| faulted.set((usize::MAX, 0)));
+        // Keep the copy's memory accesses between setting and clearing the guard
+        compiler_fence(Ordering::SeqCst);
+        copy();
+        compiler_fence(Ordering::SeqCst);
+        ACTIVE.with(|active| active.set((0, 0)));
+        let (lo, hi) = FAULTED.with(Cell::get);
+        (lo < hi).then_some(lo..hi)
+    }
+
+    // Map the file back over `pages` of the shared mapping at `base`, which maps `file`
+    // from `file_offset`.
+    pub(super) fn restore(
+        base: *const u8,
+        pages: Range<usize>,
+        file: &File,
+        file_offset: u64,
+        writable: bool,
+    ) -> io::Result<()> {
+        let prot = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        // A window at an unaligned file offset starts mid-page, so the first page can begin
+        // before `base`; the wrapping arithmetic yields its (page-aligned) file offset.
+        let offset =
+            file_offset.wrapping_add((pages.start as u64).wrapping_sub(base as u64)) as libc::off_t;
+        // SAFETY: `pages` lies inside the mapping at `base`, and `offset` is the file offset
+        // they map; MAP_FIXED replaces the handler's anonymous pages with the file pages.
+        let res = unsafe {
+            libc::mmap(
+                pages.start as *mut c_void,
+                pages.end - pages.start,
+                prot,
+                libc::MAP_SHARED | libc::MAP_FIXED,
+                file.as_raw_fd(),
+                offset,
+            )
+        };
+        if res == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    unsafe fn fault_address(info: *mut libc::siginfo_t) -> usize {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        // SAFETY: the kernel passes a valid siginfo_t for SA_SIGINFO handlers.
+        let addr = unsafe { (*info).si_addr() };
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        // SAFETY: as above.
+        let addr = unsafe { (*info).si_addr };
+        addr as usize
+    }
+
+    // Only async-signal-safe operations here: thread-local Cells without destructors,
+    // atomics, mmap, and sigaction.
+    extern "C" fn handler(sig: c_int, info: *mut libc::siginfo_t, ctx: *mut c_void) {
+        // SAFETY: installed with SA_SIGINFO, so `info` is valid.
+        let addr = unsafe { fault_address(info) };
+        let (start, end) = ACTIVE.with(Cell::get);
+        if (start..end).contains(&addr) {
+            let page_size = PAGE_SIZE.load(Ordering::Relaxed);
+            let page = addr & !(page_size - 1);
+            // SAFETY: the page belongs to a mapping this thread is copying under guard;
+            // replacing it with a private zero page lets the faulting access complete.
+            let res = unsafe {
+                libc::mmap(
+                    page as *mut c_void,
+                    page_size,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED,
+                    -1,
+                    0,
+                )
+            };
+            if res != libc::MAP_FAILED {
+                FAULTED.with(|faulted| {
+                    let (lo, hi) = faulted.get();
+                    faulted.set((lo.min(page), hi.max(page + page_size)));
+                });
+                return;
+            }
+        }
+        // SAFETY: forwarding the signal as the kernel delivered it.
+        unsafe { chain(sig, info, ctx) };
+    }
+
+    unsafe fn chain(sig: c_int, info: *mut libc::siginfo_t, ctx: *mut c_void) {
+        match PREVIOUS.get() {
+            Some(prev)
+                if prev.sa_sigaction != libc::SIG_DFL && prev.sa_sigaction != libc::SIG_IGN =>
+            {
+                if prev.sa_flags & libc::SA_SIGINFO != 0 {
+                    // SAFETY: with SA_SIGINFO the handler has the three-argument signature.
+                    let f: extern "C" fn(c_int, *mut libc::siginfo_t, *mut c_void) =
+                        unsafe { std::mem::transmute(prev.sa_sigaction) };
+                    f(sig, info, ctx);
+                } else {
+                    // SAFETY: without SA_SIGINFO the handler takes only the signal number.
+                    let f: extern "C" fn(c_int) = unsafe { std::mem::transmute(prev.sa_sigaction) };
+                    f(sig);
+                }
+            }
+            _ => {
+                // Fall back to the default action; returning re-executes the faulting
+                // access, which then terminates the process as it would have without us
+                // SAFETY: an all-zero sigaction with SIG_DFL is valid.
+                unsafe {
+                    let mut action: libc::sigaction = std::mem::zeroed();
+                    action.sa_sigaction = libc::SIG_DFL;
+                    libc::sigaction(sig, &action, ptr::null_mut());
+                }
+            }
+        }
+    }
+}
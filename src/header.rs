@@ -0,0 +1,251 @@
+//! Versioned file header with migration hooks run on open.
+//!
+//! Long-lived mapped files outlive the application version that created them. A
+//! [`VersionedHeader`] stamps the start of the file with an application magic and format
+//! version; on [`VersionedHeader::open`] it runs the registered migrations (v1→v2, v2→v3, …)
+//! in order until the file reaches the current version, upgrading it in place.
+//!
+//! Each step is made durable before the next starts: the migration's writes are flushed,
+//! then the new version is written and flushed. A crash therefore leaves the file at a
+//! consistent version, and at worst the interrupted step is run again on the next open, so
+//! migrations should tolerate being re-applied to a partially migrated file.
+//!
+//! Header layout (all integers little-endian):
+//!
+//! | bytes  | field                           |
+//! |--------|---------------------------------|
+//! | 0..8   | application magic               |
+//! | 8..12  | format version                  |
+//! | 12..16 | reserved                        |
+//! | 16..   | application data                |
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+/// Size of the versioned header at the start of the file.
+pub const VERSIONED_HEADER_SIZE: u64 = 16;
+
+type Migration = Box<dyn Fn(&MemoryMappedFile) -> Result<()> + Send + Sync>;
+
+/// Application file format description: magic, current version, and upgrade steps.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mmap_io::{MemoryMappedFile, header::VersionedHeader};
+///
+/// let header = VersionedHeader::new(*b"MYAPPDB\0", 3)
+///     // v1 stored a u32 record count at 16; v2 widens it to u64
+///     .migration(1, |mmap| {
+///         let mut old = [0u8; 4];
+///         mmap.read_into(16, &mut old)?;
+///         mmap.update_region(16, &u64::from(u32::from_le_bytes(old)).to_le_bytes())
+///     })
+///     // v3 appends a 4 KiB index page
+///     .migration(2, |mmap| mmap.resize(mmap.len() + 4096));
+///
+/// let mmap = MemoryMappedFile::open_rw("app.db")?;
+/// let found = header.open(&mmap)?; // the file is now at version 3
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+pub struct VersionedHeader {
+    magic: [u8; 8],
+    version: u32,
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl fmt::Debug for VersionedHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedHeader")
+            .field("magic", &self.magic)
+            .field("version", &self.version)
+            .field("migrations", &self.migrations.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl VersionedHeader {
+    /// Describe a format identified by `magic` whose current version is `version`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `version` is zero; versions start at 1.
+    #[must_use]
+    pub fn new(magic: [u8; 8], version: u32) -> Self {
+        assert!(version > 0, "format versions start at 1");
+        Self {
+            magic,
+            version,
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Register the step upgrading a file from version `from` to `from + 1`.
+    ///
+    /// The migration gets the whole mapping and may rewrite or resize it; the header is
+    /// updated afterwards. Registering the same `from` twice replaces the earlier step.
+    #[must_use]
+    pub fn migration<F>(mut self, from: u32, migrate: F) -> Self
+    where
+        F: Fn(&MemoryMappedFile) -> Result<()> + Send + Sync + 'static,
+    {
+        self.migrations.insert(from, Box::new(migrate));
+        self
+    }
+
+    /// Current format version.
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Stamp a new file with the magic and the current version.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from resizing or writing the mapping (e.g. `InvalidMode` if read-only).
+    pub fn init(&self, mmap: &MemoryMappedFile) -> Result<()> {
+        if mmap.len() < VERSIONED_HEADER_SIZE {
+            mmap.resize(VERSIONED_HEADER_SIZE)?;
+        }
+        let mut header = [0u8; VERSIONED_HEADER_SIZE as usize];
+        header[0..8].copy_from_slice(&self.magic);
+        header[8..12].copy_from_slice(&self.version.to_le_bytes());
+        mmap.update_region(0, &header)?;
+        mmap.flush()
+    }
+
+    /// Validate the header and migrate the file to the current version.
+    ///
+    /// Returns the version found on disk before any migration ran. Files already at the
+    /// current version are left untouched, so read-only mappings can be opened as long as
+    /// no migration is needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the magic does not match.
+    /// Returns `MmapIoError::Migration` if the file is newer than this format or a step
+    /// between its version and the current one is not registered; nothing is migrated then.
+    /// Returns the error of a failing migration, leaving the file at the last completed step.
+    pub fn open(&self, mmap: &MemoryMappedFile) -> Result<u32> {
+        let found = self.stored_version(mmap)?;
+        if found > self.version {
+            return Err(MmapIoError::Migration(format!(
+                "file format version {found} is newer than supported version {}",
+                self.version
+            )));
+        }
+        // Check the whole chain up front rather than stopping halfway through it
+        if let Some(missing) = (found..self.version).find(|v| !self.migrations.contains_key(v)) {
+            return Err(MmapIoError::Migration(format!(
+                "no migration registered from version {missing} to {}",
+                missing + 1
+            )));
+        }
+        for from in found..self.version {
+            self.migrations[&from](mmap)?;
+            mmap.flush()?;
+            mmap.update_region(8, &(from + 1).to_le_bytes())?;
+            mmap.flush()?;
+        }
+        Ok(found)
+    }
+
+    /// Version recorded in the header of `mmap`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the file is too small or the magic does not match.
+    pub fn stored_version(&self, mmap: &MemoryMappedFile) -> Result<u32> {
+        if mmap.len() < VERSIONED_HEADER_SIZE {
+            return Err(MmapIoError::Corrupt(
+                "file too small for versioned header".into(),
+            ));
+        }
+        let mut header = [0u8; VERSIONED_HEADER_SIZE as usize];
+        mmap.read_into(0, &mut header)?;
+        if header[0..8] != self.magic {
+            return Err(MmapIoError::Corrupt(
+                "versioned header magic mismatch".into(),
+            ));
+        }
+        let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if version == 0 {
+            return Err(MmapIoError::Corrupt(
+                "versioned header has version 0".into(),
+            ));
+        }
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_header_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_versioned_header_migrates_in_place() {
+        let path = tmp_path("migrate");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 20).expect("create");
+        VersionedHeader::new(*b"TESTHDR\0", 1)
+            .init(&mmap)
+            .expect("init v1");
+        mmap.update_region(16, &7u32.to_le_bytes())
+            .expect("v1 data");
+        mmap.flush().expect("flush");
+        drop(mmap);
+
+        let v3 = || {
+            VersionedHeader::new(*b"TESTHDR\0", 3)
+                .migration(1, |mmap| {
+                    let mut old = [0u8; 4];
+                    mmap.read_into(16, &mut old)?;
+                    mmap.resize(24)?;
+                    mmap.update_region(16, &u64::from(u32::from_le_bytes(old)).to_le_bytes())
+                })
+                .migration(2, |mmap| mmap.resize(mmap.len() + 8))
+        };
+
+        let mmap = MemoryMappedFile::open_rw(&path).expect("open");
+        // A gap in the chain is refused before anything is touched
+        let gap = VersionedHeader::new(*b"TESTHDR\0", 3).migration(2, |_| Ok(()));
+        assert!(matches!(gap.open(&mmap), Err(MmapIoError::Migration(_))));
+        assert_eq!(gap.stored_version(&mmap).expect("version"), 1);
+
+        assert_eq!(v3().open(&mmap).expect("migrate"), 1);
+        assert_eq!(v3().stored_version(&mmap).expect("version"), 3);
+        assert_eq!(mmap.len(), 32);
+        let mut widened = [0u8; 8];
+        mmap.read_into(16, &mut widened).expect("read");
+        assert_eq!(u64::from_le_bytes(widened), 7);
+        drop(mmap);
+
+        // Already current: nothing to do, and read-only mappings are fine
+        let ro = MemoryMappedFile::open_ro(&path).expect("open ro");
+        assert_eq!(v3().open(&ro).expect("open current"), 3);
+        let older = VersionedHeader::new(*b"TESTHDR\0", 2);
+        assert!(matches!(older.open(&ro), Err(MmapIoError::Migration(_))));
+        let other = VersionedHeader::new(*b"OTHERAPP", 3);
+        assert!(matches!(other.open(&ro), Err(MmapIoError::Corrupt(_))));
+        drop(ro);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
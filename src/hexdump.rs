@@ -0,0 +1,162 @@
+//! Classic offset / hex / ASCII dumps of mapped regions for debugging.
+
+use std::fmt;
+
+use crate::errors::Result;
+use crate::mmap::MemoryMappedFile;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Bytes copied out of a mapping, formatted `hexdump -C` style by [`Display`](fmt::Display).
+///
+/// ```text
+/// 00000010  48 65 6c 6c 6f 2c 20 6d  6d 61 70 21 00 00 00 00  |Hello, mmap!....|
+/// ```
+///
+/// Offsets are absolute file offsets, so dumps of different regions line up with the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexDump {
+    offset: u64,
+    bytes: Vec<u8>,
+}
+
+impl HexDump {
+    /// Dump `bytes` as if they were read from file offset `offset`.
+    #[must_use]
+    pub fn new(offset: u64, bytes: Vec<u8>) -> Self {
+        Self { offset, bytes }
+    }
+
+    /// The dumped bytes.
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl fmt::Display for HexDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.bytes.chunks(BYTES_PER_LINE).enumerate() {
+            write!(f, "{:08x} ", self.offset + (i * BYTES_PER_LINE) as u64)?;
+            for col in 0..BYTES_PER_LINE {
+                if col == BYTES_PER_LINE / 2 {
+                    f.write_str(" ")?;
+                }
+                match line.get(col) {
+                    Some(b) => write!(f, " {b:02x}")?,
+                    None => f.write_str("   ")?,
+                }
+            }
+            f.write_str("  |")?;
+            for &b in line {
+                let c = if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f, "|")?;
+        }
+        Ok(())
+    }
+}
+
+// Bytes shown by the alternate (`{:#?}`) Debug output of segments.
+const DEBUG_DUMP_LIMIT: u64 = 256;
+
+// Debug for segment views: the usual struct form, plus a dump of the contents in
+// alternate mode.
+pub(crate) fn fmt_segment(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    parent: &MemoryMappedFile,
+    offset: u64,
+    len: u64,
+) -> fmt::Result {
+    f.debug_struct(name)
+        .field("parent", parent)
+        .field("offset", &offset)
+        .field("len", &len)
+        .finish()?;
+    if f.alternate() {
+        let shown = len.min(DEBUG_DUMP_LIMIT);
+        match parent.dump(offset, shown) {
+            Ok(dump) => write!(f, "\n{dump}")?,
+            Err(e) => write!(f, "\n<unreadable: {e}>")?,
+        }
+        if shown < len {
+            write!(f, "... {} more bytes", len - shown)?;
+        }
+    }
+    Ok(())
+}
+
+impl MemoryMappedFile {
+    /// Copy `[offset, offset + len)` into a [`HexDump`] for printing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the range exceeds the mapping.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mmap_io::MemoryMappedFile;
+    ///
+    /// let mmap = MemoryMappedFile::open_ro("data.bin")?;
+    /// println!("{}", mmap.dump(0, 64)?);
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    pub fn dump(&self, offset: u64, len: u64) -> Result<HexDump> {
+        crate::utils::ensure_in_bounds(offset, len, self.len())?;
+        let mut bytes = vec![0u8; len as usize];
+        self.read_into(offset, &mut bytes)?;
+        Ok(HexDump::new(offset, bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::create_mmap;
+    use crate::segment::Segment;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_hexdump_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_dump_format() {
+        let path = tmp_path("format");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 64).expect("create");
+        mmap.update_region(16, b"Hello, mmap!\x01").expect("write");
+        let dump = mmap.dump(16, 20).expect("dump").to_string();
+        assert_eq!(
+            dump,
+            "00000010  48 65 6c 6c 6f 2c 20 6d  6d 61 70 21 01 00 00 00  |Hello, mmap!....|\n\
+             00000020  00 00 00 00                                       |....|\n"
+        );
+        assert!(mmap.dump(60, 8).is_err());
+        assert_eq!(mmap.dump(0, 0).expect("empty").to_string(), "");
+
+        let mmap = Arc::new(mmap);
+        let seg = Segment::new(mmap.clone(), 16, 5).expect("segment");
+        let pretty = format!("{seg:#?}");
+        assert!(pretty.contains("|Hello|"), "{pretty}");
+        assert!(!format!("{seg:?}").contains('|'));
+
+        drop((seg, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
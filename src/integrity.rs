@@ -0,0 +1,278 @@
+//! Validation of a file's length and content digest before it is mapped.
+//!
+//! Recovery paths that reopen snapshots or checkpoints should refuse truncated or corrupted
+//! files instead of mapping them silently. Configure the expectations on the builder with
+//! [`MemoryMappedFileBuilder::expect_len`] and [`MemoryMappedFileBuilder::expect_hash`];
+//! a mismatch fails `open()` with [`MmapIoError::Integrity`] and no mapping is returned.
+//! Digests are computed over the mapped bytes, so the file is read only once.
+//!
+//! The algorithms are meant to catch accidental damage (torn writes, bit rot, truncation),
+//! not deliberate tampering.
+//!
+//! [`MemoryMappedFileBuilder::expect_len`]: crate::mmap::MemoryMappedFileBuilder::expect_len
+//! [`MemoryMappedFileBuilder::expect_hash`]: crate::mmap::MemoryMappedFileBuilder::expect_hash
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+// Bytes hashed per read when digesting a mapping.
+const DIGEST_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Digest algorithm for `expect_hash`, [`digest_file`], and [`digest_mapping`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgo {
+    /// CRC-32 (IEEE 802.3, as used by zlib and gzip); 4-byte big-endian digest.
+    Crc32,
+    /// 64-bit FNV-1a; 8-byte big-endian digest.
+    Fnv1a64,
+}
+
+impl HashAlgo {
+    /// Digest of `data`.
+    #[must_use]
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = Hasher::new(self);
+        hasher.update(data);
+        hasher.finish()
+    }
+}
+
+impl std::fmt::Display for HashAlgo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HashAlgo::Crc32 => "crc32",
+            HashAlgo::Fnv1a64 => "fnv1a64",
+        })
+    }
+}
+
+/// Why a file failed validation on open.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum IntegrityError {
+    /// The file is not the expected length, e.g. it was truncated.
+    #[error("length mismatch: expected {expected} bytes, found {actual}")]
+    LengthMismatch {
+        /// Length given to `expect_len`.
+        expected: u64,
+        /// Actual file length.
+        actual: u64,
+    },
+
+    /// The contents do not match the expected digest.
+    #[error("{algo} digest mismatch: expected {}, found {}", hex(.expected), hex(.actual))]
+    DigestMismatch {
+        /// Algorithm used.
+        algo: HashAlgo,
+        /// Digest given to `expect_hash`.
+        expected: Vec<u8>,
+        /// Digest of the file contents.
+        actual: Vec<u8>,
+    },
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// Incremental state for one of the supported algorithms.
+enum Hasher {
+    Crc32(u32),
+    Fnv1a64(u64),
+}
+
+impl Hasher {
+    fn new(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Crc32 => Hasher::Crc32(!0),
+            HashAlgo::Fnv1a64 => Hasher::Fnv1a64(0xCBF2_9CE4_8422_2325),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Crc32(crc) => {
+                for &b in data {
+                    *crc = CRC32_TABLE[((*crc ^ u32::from(b)) & 0xFF) as usize] ^ (*crc >> 8);
+                }
+            }
+            Hasher::Fnv1a64(h) => {
+                for &b in data {
+                    *h ^= u64::from(b);
+                    *h = h.wrapping_mul(0x0100_0000_01B3);
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        match self {
+            Hasher::Crc32(crc) => (!crc).to_be_bytes().to_vec(),
+            Hasher::Fnv1a64(h) => h.to_be_bytes().to_vec(),
+        }
+    }
+}
+
+// Reflected CRC-32 lookup table for polynomial 0xEDB88320.
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Digest of the whole file at `path`, for recording the value to pass to `expect_hash`.
+///
+/// # Errors
+///
+/// Returns `MmapIoError::Io` if the file cannot be read.
+pub fn digest_file<P: AsRef<Path>>(path: P, algo: HashAlgo) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Hasher::new(algo);
+    let mut buf = vec![0u8; DIGEST_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+/// Digest of the current contents of `mmap`.
+///
+/// # Errors
+///
+/// Returns errors from reading the mapping.
+pub fn digest_mapping(mmap: &MemoryMappedFile, algo: HashAlgo) -> Result<Vec<u8>> {
+    let total = mmap.current_len()?;
+    let mut hasher = Hasher::new(algo);
+    let mut buf = vec![0u8; DIGEST_CHUNK_SIZE];
+    let mut offset = 0u64;
+    while offset < total {
+        let n = (total - offset).min(DIGEST_CHUNK_SIZE as u64) as usize;
+        mmap.read_into(offset, &mut buf[..n])?;
+        hasher.update(&buf[..n]);
+        offset += n as u64;
+    }
+    Ok(hasher.finish())
+}
+
+// Check `mmap` against the builder's expectations.
+pub(crate) fn verify(
+    mmap: &MemoryMappedFile,
+    expect_len: Option<u64>,
+    expect_hash: Option<&(HashAlgo, Vec<u8>)>,
+) -> Result<()> {
+    if let Some(expected) = expect_len {
+        let actual = mmap.current_len()?;
+        if actual != expected {
+            return Err(MmapIoError::Integrity(IntegrityError::LengthMismatch {
+                expected,
+                actual,
+            }));
+        }
+    }
+    if let Some((algo, expected)) = expect_hash {
+        let actual = digest_mapping(mmap, *algo)?;
+        if actual != *expected {
+            return Err(MmapIoError::Integrity(IntegrityError::DigestMismatch {
+                algo: *algo,
+                expected: expected.clone(),
+                actual,
+            }));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_integrity_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_expect_len_and_hash_on_open() {
+        // Well-known check values
+        assert_eq!(
+            HashAlgo::Crc32.digest(b"123456789"),
+            0xCBF4_3926u32.to_be_bytes()
+        );
+        assert_eq!(
+            HashAlgo::Fnv1a64.digest(b"a"),
+            0xAF63_DC4C_8601_EC8Cu64.to_be_bytes()
+        );
+
+        let path = tmp_path("expect");
+        fs::write(&path, b"checkpoint contents").expect("write");
+        let crc = digest_file(&path, HashAlgo::Crc32).expect("digest");
+        assert_eq!(crc, HashAlgo::Crc32.digest(b"checkpoint contents"));
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .expect_len(19)
+            .expect_hash(HashAlgo::Crc32, &crc)
+            .open()
+            .expect("valid file opens");
+        drop(mmap);
+
+        let err = MemoryMappedFile::builder(&path)
+            .expect_len(4096)
+            .open()
+            .expect_err("short file");
+        assert!(matches!(
+            err,
+            MmapIoError::Integrity(IntegrityError::LengthMismatch {
+                expected: 4096,
+                actual: 19
+            })
+        ));
+
+        // Flip one byte; the digest no longer matches
+        fs::write(&path, b"checkpoint c0ntents").expect("corrupt");
+        let err = MemoryMappedFile::builder(&path)
+            .mode(crate::MmapMode::ReadWrite)
+            .expect_hash(HashAlgo::Crc32, &crc)
+            .open()
+            .expect_err("corrupt file");
+        assert!(matches!(
+            err,
+            MmapIoError::Integrity(IntegrityError::DigestMismatch {
+                algo: HashAlgo::Crc32,
+                ..
+            })
+        ));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
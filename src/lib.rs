@@ -29,29 +29,100 @@
 //!
 //! ## Modules
 //!
+//! - [`arena`]: Persistent bump allocator over a mapped segment
+//! - [`backup`]: Incremental backups into a directory of page-level patches
+//! - [`btree`]: Read-optimized, bulk-loaded B-tree index
+//! - [`capabilities`]: Runtime detection of optional OS facilities
+//! - [`diff`]: Byte-range comparison of two mappings
 //! - [`errors`]: Error types for all mmap operations
+//! - [`fdpass`]: Sharing mappings across processes by passing descriptors (Unix)
+//! - [`flusher`]: Shared background flush workers with priorities
+//! - [`header`]: Versioned file header with in-place migrations on open
+//! - [`hexdump`]: Offset/hex/ASCII dumps of mapped regions
+//! - [`integrity`]: Length and digest validation of files before they are mapped
 //! - [`utils`]: Utility functions for alignment and bounds checking
+//! - [`window`]: Sliding-window access to files larger than the address space budget
 //! - [`mmap`]: Core `MemoryMappedFile` implementation
+//! - [`mirror`]: Mappings mirrored to a secondary file with read failover
+//! - [`observer`]: Pluggable hooks for write, flush, resize, and error events
+//! - [`overlay`]: Durable copy-on-write views keeping changes in a delta file
+//! - [`patch`]: Compact binary patches for incremental updates of mapped files
+//! - [`relptr`]: Offset-based pointers for data structures stored in a mapping
+//! - [`registry`]: Named segment registry persisted in a header page
 //! - [`segment`]: Segmented views for working with file regions
+//! - [`slab`]: Persistent size-classed allocator with reusable free lists
+//! - [`split`]: Disjoint mutable parts of a mapping for parallel writers
+//! - [`stream`]: Streaming between mapped regions and `std::io` readers and writers
+//! - [`swap`]: Lock-free publication of replacement mappings
+//! - [`strings`]: Persistent append-only string interner
+//! - [`trace`]: Access-pattern recording and replay for pre-warming mappings
+//! - [`tracking`]: Opt-in accounting of bytes mapped across the process
+//! - [`typed`]: Typed array views over plain-old-data segments
 //! - [`manager`]: High-level convenience functions
 //!
 //! ## Feature Flags
 //!
 //! - `async`: Enables Tokio-based async file operations
+//! - `atomic`: Atomic views, a persistent [`bloom`] filter, and [`doorbell`] cross-process
+//!   wakeups, [`futex`] wait/wake on mapped counters on Linux and Windows, and cross-process
+//!   [`sync`] locks
+//! - `atomic128`: 16-byte `atomic_u128` views on x86_64 (`cmpxchg16b`) and aarch64
+//! - `watch`: File change notifications and self-reloading [`reload`] mappings
+//! - `dirty-tracking`: Kernel [`soft_dirty`] page tracking for incremental backups (Linux)
+//! - `uffd`: [`uffd`] mappings populated on first access by a page source (Linux)
+//! - `metrics`: Emits `metrics` crate counters/histograms for writes and flushes
+//! - `arrow`: Zero-copy `arrow_buffer::Buffer` views that keep the mapping alive
+//! - `serde`: Length-prefixed JSON/bincode records via `write_serialized`/`read_deserialized`
+//! - `positioned-io`: `positioned_io::ReadAt`/`WriteAt`/`Size` for mappings and segments
+//! - `zerocopy`: `read_pod`/`write_pod`/`as_typed_slice` for `zerocopy` plain-old-data types
+//! - `ffi`: C ABI in [`ffi`] for sharing mappings with C/C++ code
 
 #![cfg_attr(not(test), deny(clippy::unwrap_used))]
 #![deny(missing_docs)]
 #![doc(html_root_url = "https://docs.rs/mmap-io")]
 
+pub mod arena;
+pub mod backup;
+pub mod btree;
+pub mod capabilities;
+pub mod diff;
 pub mod errors;
+pub mod flusher;
+pub mod header;
+pub mod hexdump;
+pub mod integrity;
 pub mod manager;
+pub mod mirror;
 pub mod mmap;
+pub mod observer;
+pub mod overlay;
+pub mod patch;
+pub mod registry;
+pub mod relptr;
 pub mod segment;
+pub mod slab;
+pub mod split;
+pub mod stream;
+pub mod strings;
+pub mod swap;
+pub mod trace;
+pub mod tracking;
+pub mod typed;
 pub mod utils;
+pub mod window;
 
 /// Provides functions for flushing memory-mapped file changes to disk.
 pub mod flush;
 
+mod dirty;
+mod endian;
+mod fileext;
+mod fixed;
+mod guard;
+
+#[cfg(unix)]
+pub mod fdpass;
+
 #[cfg(feature = "advise")]
 pub mod advise;
 
@@ -64,20 +135,71 @@ pub mod lock;
 #[cfg(feature = "atomic")]
 pub mod atomic;
 
+#[cfg(feature = "atomic")]
+pub mod bloom;
+
+#[cfg(all(feature = "atomic", any(target_os = "linux", windows)))]
+pub mod doorbell;
+
+#[cfg(all(feature = "atomic", any(target_os = "linux", windows)))]
+pub mod futex;
+
+#[cfg(feature = "atomic")]
+pub mod sync;
+
+#[cfg(feature = "watch")]
+pub mod reload;
 #[cfg(feature = "watch")]
 pub mod watch;
 
+#[cfg(all(feature = "dirty-tracking", target_os = "linux"))]
+pub mod soft_dirty;
+
+#[cfg(all(
+    feature = "uffd",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv64")
+))]
+pub mod uffd;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+#[cfg(feature = "serde")]
+pub mod serde;
+
+#[cfg(feature = "positioned-io")]
+mod positioned;
+
+#[cfg(feature = "zerocopy")]
+mod pod;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+pub use capabilities::{capabilities, Capabilities};
 pub use errors::MmapIoError;
 pub use manager::{
-    copy_mmap, create_mmap, delete_mmap, flush, load_mmap, update_region, write_mmap,
+    copy_mmap, create_many, create_mmap, delete_mmap, flush, load_mmap, load_mmap_with,
+    update_region, write_mmap, LoadOptions, MmapCache, MmapRegistry,
 };
-pub use mmap::{MemoryMappedFile, MmapMode};
+pub use mmap::{
+    max_mmap_size, set_max_mmap_size, ForkBehavior, MemoryMappedFile, MmapMode, OwnedMappedSlice,
+};
+pub use observer::{MmapObserver, MmapOperation};
+pub use tracking::mapped_bytes;
 
 #[cfg(feature = "advise")]
-pub use advise::MmapAdvice;
+pub use advise::{MmapAdvice, PrefetchHandle};
 
 #[cfg(feature = "iterator")]
-pub use iterator::{ChunkIterator, PageIterator};
+pub use iterator::{ChunkIterator, ExactChunkIterator, PageIterator};
+
+#[cfg(all(feature = "iterator", feature = "advise"))]
+pub use iterator::PrefetchChunkIterator;
 
 #[cfg(feature = "watch")]
-pub use watch::{ChangeEvent, ChangeKind, WatchHandle};
+pub use watch::{ChangeEvent, ChangeKind, WatchHandle, WatchOptions};
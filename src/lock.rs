@@ -32,11 +32,12 @@ impl MemoryMappedFile {
         // Get the base pointer for the mapping
         let ptr = match &self.inner.map {
             crate::mmap::MapVariant::Ro(m) => m.as_ptr(),
-            crate::mmap::MapVariant::Rw(lock) => {
+            crate::mmap::MapVariant::Rw(lock) | crate::mmap::MapVariant::Cow(lock) => {
+                // Only the address is taken; pinning pages never touches their bytes, so
+                // split parts writing concurrently do not race with it
                 let guard = lock.read();
                 guard.as_ptr()
             }
-            crate::mmap::MapVariant::Cow(m) => m.as_ptr(),
         };
 
         // SAFETY: We've validated the range is within bounds
@@ -103,11 +104,12 @@ impl MemoryMappedFile {
         // Get the base pointer for the mapping
         let ptr = match &self.inner.map {
             crate::mmap::MapVariant::Ro(m) => m.as_ptr(),
-            crate::mmap::MapVariant::Rw(lock) => {
+            crate::mmap::MapVariant::Rw(lock) | crate::mmap::MapVariant::Cow(lock) => {
+                // Only the address is taken; pinning pages never touches their bytes, so
+                // split parts writing concurrently do not race with it
                 let guard = lock.read();
                 guard.as_ptr()
             }
-            crate::mmap::MapVariant::Cow(m) => m.as_ptr(),
         };
 
         // SAFETY: We've validated the range is within bounds
@@ -2,11 +2,21 @@
 //!
 //! Provides convenience functions that wrap low-level mmap operations.
 
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::SystemTime;
 
-use crate::errors::Result;
-use crate::mmap::{MemoryMappedFile, MmapMode};
+use parking_lot::Mutex;
+
+#[cfg(feature = "advise")]
+use crate::advise::MmapAdvice;
+use crate::errors::{MmapIoError, Result};
+use crate::flush::FlushPolicy;
+use crate::mmap::{ForkBehavior, Inner, MemoryMappedFile, MmapMode};
 
 /// Create a new read-write memory-mapped file of the given size.
 /// Truncates if the file already exists.
@@ -18,6 +28,50 @@ pub fn create_mmap<P: AsRef<Path>>(path: P, size: u64) -> Result<MemoryMappedFil
     MemoryMappedFile::create_rw(path, size)
 }
 
+/// Create and map many read-write files in parallel, one per `(path, size)` spec.
+///
+/// Work is spread over at most one thread per available CPU (capped at 8). Results are
+/// returned in the order of `specs`; one failing file does not affect the others.
+#[must_use]
+pub fn create_many(specs: &[(PathBuf, u64)]) -> Vec<Result<MemoryMappedFile>> {
+    let threads = thread::available_parallelism()
+        .map_or(1, |n| n.get().min(8))
+        .min(specs.len());
+    if threads <= 1 {
+        return specs
+            .iter()
+            .map(|(path, size)| MemoryMappedFile::create_rw(path, *size))
+            .collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, Result<MemoryMappedFile>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((path, size)) = specs.get(i) else {
+                            break;
+                        };
+                        done.push((i, MemoryMappedFile::create_rw(path, *size)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| match worker.join() {
+                Ok(done) => done,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    });
+    results.sort_unstable_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
 /// Load an existing memory-mapped file in the requested mode.
 ///
 /// # Errors
@@ -33,9 +87,88 @@ pub fn load_mmap<P: AsRef<Path>>(path: P, mode: MmapMode) -> Result<MemoryMapped
         MmapMode::CopyOnWrite => Err(crate::errors::MmapIoError::InvalidMode(
             "copy-on-write mode not enabled (feature `cow`)",
         )),
+        #[cfg(feature = "exec")]
+        MmapMode::ReadExecute => MemoryMappedFile::open_exec(path),
+        #[cfg(not(feature = "exec"))]
+        MmapMode::ReadExecute => Err(crate::errors::MmapIoError::InvalidMode(
+            "read-execute mode not enabled (feature `exec`)",
+        )),
+    }
+}
+
+/// Options for [`load_mmap_with`], mirroring the builder's open-time settings.
+///
+/// Construct with struct-update syntax so new fields do not break callers:
+/// `LoadOptions { mode: MmapMode::ReadWrite, populate: true, ..Default::default() }`.
+#[derive(Debug, Clone)]
+pub struct LoadOptions {
+    /// Access mode (default `ReadOnly`).
+    pub mode: MmapMode,
+    /// Prefault every page on open (ReadOnly and CopyOnWrite mappings).
+    pub populate: bool,
+    /// Access-pattern advice applied to the whole mapping after opening.
+    #[cfg(feature = "advise")]
+    pub advice: Option<MmapAdvice>,
+    /// Flush policy for ReadWrite mappings.
+    pub flush_policy: FlushPolicy,
+    /// Request huge pages (Linux).
+    #[cfg(feature = "hugepages")]
+    pub huge_pages: bool,
+    /// What child processes inherit after `fork()`.
+    pub fork_behavior: ForkBehavior,
+    /// Per-mapping size limit overriding [`max_mmap_size`](crate::mmap::max_mmap_size).
+    pub max_size: Option<u64>,
+    /// Make every flush durable on the storage device.
+    pub strict_flush: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            mode: MmapMode::ReadOnly,
+            populate: false,
+            #[cfg(feature = "advise")]
+            advice: None,
+            flush_policy: FlushPolicy::default(),
+            #[cfg(feature = "hugepages")]
+            huge_pages: false,
+            fork_behavior: ForkBehavior::default(),
+            max_size: None,
+            strict_flush: false,
+        }
     }
 }
 
+/// Load an existing memory-mapped file with the given options.
+///
+/// Equivalent to configuring [`MemoryMappedFile::builder`] and calling `open()`, followed
+/// by `advise` over the whole mapping when `options.advice` is set.
+///
+/// # Errors
+///
+/// Returns errors from `MemoryMappedFileBuilder::open` or `MemoryMappedFile::advise`.
+pub fn load_mmap_with<P: AsRef<Path>>(path: P, options: LoadOptions) -> Result<MemoryMappedFile> {
+    let mut builder = MemoryMappedFile::builder(path)
+        .mode(options.mode)
+        .populate(options.populate)
+        .flush_policy(options.flush_policy)
+        .fork_behavior(options.fork_behavior)
+        .strict_flush(options.strict_flush);
+    #[cfg(feature = "hugepages")]
+    {
+        builder = builder.huge_pages(options.huge_pages);
+    }
+    if let Some(limit) = options.max_size {
+        builder = builder.max_size(limit);
+    }
+    let mmap = builder.open()?;
+    #[cfg(feature = "advise")]
+    if let Some(advice) = options.advice {
+        mmap.advise(0, mmap.len(), advice)?;
+    }
+    Ok(mmap)
+}
+
 /// Write bytes at an offset into the specified file path (RW).
 /// Convenience wrapper around creating/loading and `update_region`.
 ///
@@ -87,6 +220,235 @@ pub fn delete_mmap<P: AsRef<Path>>(path: P) -> Result<()> {
     Ok(())
 }
 
+/// Set of open mappings that can be flushed together, e.g. at a checkpoint.
+///
+/// The registry holds weak references: registering a mapping does not keep it alive, and
+/// dropped mappings are pruned on the next call.
+#[derive(Default)]
+pub struct MmapRegistry {
+    mappings: Mutex<Vec<Weak<Inner>>>,
+}
+
+impl MmapRegistry {
+    /// Empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track `mmap` (or any clone of it). Registering the same mapping twice is a no-op.
+    pub fn register(&self, mmap: &MemoryMappedFile) {
+        let mut mappings = self.mappings.lock();
+        mappings.retain(|w| w.strong_count() > 0);
+        let weak = Arc::downgrade(&mmap.inner);
+        if !mappings.iter().any(|w| w.ptr_eq(&weak)) {
+            mappings.push(weak);
+        }
+    }
+
+    /// Stop tracking `mmap`. Returns `false` if it was not registered.
+    pub fn unregister(&self, mmap: &MemoryMappedFile) -> bool {
+        let weak = Arc::downgrade(&mmap.inner);
+        let mut mappings = self.mappings.lock();
+        let before = mappings.len();
+        mappings.retain(|w| w.strong_count() > 0 && !w.ptr_eq(&weak));
+        mappings.len() != before
+    }
+
+    /// Number of registered mappings that are still alive.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.live().len()
+    }
+
+    /// Whether no registered mapping is alive.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Live mappings, pruning dead entries; the lock is not held while flushing
+    fn live(&self) -> Vec<MemoryMappedFile> {
+        let mut mappings = self.mappings.lock();
+        mappings.retain(|w| w.strong_count() > 0);
+        mappings
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|inner| MemoryMappedFile { inner })
+            .collect()
+    }
+
+    /// Flush every registered mapping, continuing past failures.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::FlushAll` listing the path and error of each mapping that
+    /// failed to flush.
+    pub fn flush_all(&self) -> Result<()> {
+        let failures: Vec<_> = self
+            .live()
+            .into_iter()
+            .filter_map(|mmap| mmap.flush().err().map(|e| (mmap.path().to_path_buf(), e)))
+            .collect();
+        aggregate(failures)
+    }
+
+    /// Flush every registered mapping concurrently on the blocking thread pool.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`flush_all`](Self::flush_all).
+    #[cfg(feature = "async")]
+    pub async fn flush_all_async(&self) -> Result<()> {
+        let pending: Vec<_> = self
+            .live()
+            .into_iter()
+            .map(|mmap| {
+                let path = mmap.path().to_path_buf();
+                (path, tokio::task::spawn_blocking(move || mmap.flush()))
+            })
+            .collect();
+        let mut failures = Vec::new();
+        for (path, task) in pending {
+            let res = task
+                .await
+                .map_err(|e| MmapIoError::FlushFailed(format!("join error: {e}")))
+                .and_then(|r| r);
+            if let Err(e) = res {
+                failures.push((path, e));
+            }
+        }
+        aggregate(failures)
+    }
+}
+
+impl std::fmt::Debug for MmapRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MmapRegistry")
+            .field("mappings", &self.len())
+            .finish()
+    }
+}
+
+fn aggregate(failures: Vec<(PathBuf, MmapIoError)>) -> Result<()> {
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(MmapIoError::FlushAll(failures))
+    }
+}
+
+/// Deduplicating cache of read-only mappings.
+///
+/// Opening an unchanged file again returns the mapping already open for it. Entries are
+/// keyed by canonical path and validated against the file's identity (device and inode on
+/// Unix, plus length and modification time everywhere) on every lookup, so a file that
+/// was atomically replaced or modified gets a fresh mapping and the stale entry is dropped.
+/// Like [`MmapRegistry`], the cache holds weak references and never keeps a mapping alive.
+#[derive(Default)]
+pub struct MmapCache {
+    entries: Mutex<HashMap<PathBuf, CacheEntry>>,
+}
+
+struct CacheEntry {
+    id: FileId,
+    mapping: Weak<Inner>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileId {
+    #[cfg(unix)]
+    dev: u64,
+    #[cfg(unix)]
+    ino: u64,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileId {
+    fn of(meta: &fs::Metadata) -> Self {
+        #[cfg(unix)]
+        use std::os::unix::fs::MetadataExt;
+        Self {
+            #[cfg(unix)]
+            dev: meta.dev(),
+            #[cfg(unix)]
+            ino: meta.ino(),
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        }
+    }
+}
+
+impl MmapCache {
+    /// Empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read-only mapping of `path`, shared with earlier callers while the file is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from resolving the path or from `MemoryMappedFile::open_ro`.
+    pub fn open_ro<P: AsRef<Path>>(&self, path: P) -> Result<MemoryMappedFile> {
+        let key = fs::canonicalize(path.as_ref())?;
+        let current = FileId::of(&fs::metadata(&key)?);
+        let mut entries = self.entries.lock();
+        if let Some(entry) = entries.get(&key) {
+            if entry.id == current {
+                if let Some(inner) = entry.mapping.upgrade() {
+                    return Ok(MemoryMappedFile { inner });
+                }
+            }
+        }
+        let mmap = MemoryMappedFile::open_ro(&key)?;
+        // Identity of the file actually mapped, in case it was replaced since the stat above
+        let id = FileId::of(&mmap.inner.file.metadata()?);
+        entries.insert(
+            key,
+            CacheEntry {
+                id,
+                mapping: Arc::downgrade(&mmap.inner),
+            },
+        );
+        Ok(mmap)
+    }
+
+    /// Drop entries whose mapping is gone or whose file changed. Returns how many were
+    /// removed.
+    pub fn purge(&self) -> usize {
+        let mut entries = self.entries.lock();
+        let before = entries.len();
+        entries.retain(|path, entry| {
+            entry.mapping.strong_count() > 0
+                && fs::metadata(path).is_ok_and(|meta| FileId::of(&meta) == entry.id)
+        });
+        before - entries.len()
+    }
+
+    /// Number of cached entries, including ones not yet purged.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    /// Whether the cache has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Debug for MmapCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MmapCache")
+            .field("entries", &self.len())
+            .finish()
+    }
+}
+
 #[cfg(feature = "async")]
 pub mod r#async {
     //! Async helpers (Tokio) for creating and copying files without blocking the current thread.
@@ -110,7 +472,7 @@ pub mod r#async {
             .write(true)
             .read(true)
             .truncate(true)
-            .open(path_ref)
+            .open(crate::utils::os_path(path_ref))
             .await?;
         file.set_len(size).await?;
         drop(file);
@@ -137,3 +499,144 @@ pub mod r#async {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_manager_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_create_many_in_parallel() {
+        let mut specs: Vec<(PathBuf, u64)> = (0..32u64)
+            .map(|i| (tmp_path(&format!("shard_{i}")), 1024 + i))
+            .collect();
+        // A spec whose parent directory does not exist fails on its own
+        specs.insert(5, (tmp_path("missing_dir").join("shard"), 64));
+
+        let results = create_many(&specs);
+        assert_eq!(results.len(), specs.len());
+        for ((path, size), result) in specs.iter().zip(&results) {
+            if path.ends_with("shard") {
+                assert!(result.is_err());
+                continue;
+            }
+            let mmap = result.as_ref().expect("created");
+            assert_eq!(mmap.len(), *size);
+            assert_eq!(mmap.path(), path.as_path());
+        }
+        assert!(create_many(&[]).is_empty());
+
+        drop(results);
+        for (path, _) in &specs {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_load_mmap_with_options() {
+        let path = tmp_path("load_with");
+        create_mmap(&path, 8192).expect("create");
+
+        let ro = load_mmap_with(
+            &path,
+            LoadOptions {
+                populate: true,
+                ..Default::default()
+            },
+        )
+        .expect("load ro");
+        assert_eq!(ro.mode(), MmapMode::ReadOnly);
+        drop(ro);
+
+        let rw = load_mmap_with(
+            &path,
+            LoadOptions {
+                mode: MmapMode::ReadWrite,
+                flush_policy: FlushPolicy::Always,
+                ..Default::default()
+            },
+        )
+        .expect("load rw");
+        rw.update_region(0, b"opts").expect("write");
+        assert_eq!(&fs::read(&path).expect("read")[..4], b"opts");
+        drop(rw);
+
+        let limited = load_mmap_with(
+            &path,
+            LoadOptions {
+                max_size: Some(4096),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(limited, Err(MmapIoError::ResizeFailed(_))));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_cache_dedups_until_replaced() {
+        let path = tmp_path("cache");
+        let staging = tmp_path("cache_staging");
+        fs::write(&path, b"version one").expect("write");
+        let cache = MmapCache::new();
+
+        let a = cache.open_ro(&path).expect("open a");
+        let b = cache.open_ro(&path).expect("open b");
+        assert!(Arc::ptr_eq(&a.inner, &b.inner));
+        assert_eq!(cache.len(), 1);
+
+        // Atomic replace: new inode, so a fresh mapping with the new contents
+        fs::write(&staging, b"version two!").expect("stage");
+        fs::rename(&staging, &path).expect("replace");
+        let c = cache.open_ro(&path).expect("open c");
+        assert!(!Arc::ptr_eq(&a.inner, &c.inner));
+        assert_eq!(c.as_slice(0, 12).expect("slice"), b"version two!");
+        assert_eq!(a.as_slice(0, 11).expect("old slice"), b"version one");
+
+        drop(c);
+        assert_eq!(cache.purge(), 1);
+        assert!(cache.is_empty());
+
+        drop((a, b));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_registry_flush_all() {
+        let a = tmp_path("registry_a");
+        let b = tmp_path("registry_b");
+        let registry = MmapRegistry::new();
+        let ma = create_mmap(&a, 4096).expect("create a");
+        let mb = create_mmap(&b, 4096).expect("create b");
+        registry.register(&ma);
+        registry.register(&ma.clone());
+        registry.register(&mb);
+        assert_eq!(registry.len(), 2);
+
+        ma.update_region(0, b"alpha").expect("write a");
+        mb.update_region(0, b"beta").expect("write b");
+        registry.flush_all().expect("flush all");
+        assert_eq!(&fs::read(&a).expect("read a")[..5], b"alpha");
+        assert_eq!(&fs::read(&b).expect("read b")[..4], b"beta");
+
+        // Dropped mappings are pruned, unregistered ones are skipped
+        drop(mb);
+        assert_eq!(registry.len(), 1);
+        assert!(registry.unregister(&ma));
+        assert!(!registry.unregister(&ma));
+        assert!(registry.is_empty());
+        registry.flush_all().expect("flush none");
+
+        drop(ma);
+        fs::remove_file(&a).expect("cleanup a");
+        fs::remove_file(&b).expect("cleanup b");
+    }
+}
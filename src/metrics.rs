@@ -0,0 +1,157 @@
+//! Integration with the [`metrics`](https://docs.rs/metrics) crate facade.
+//!
+//! When the `metrics` feature is enabled, mappings emit the following series to whatever
+//! recorder the application has installed (e.g. a Prometheus exporter):
+//!
+//! - `mmap_io_bytes_written_total` (counter): bytes written through `update_region`.
+//! - `mmap_io_flushes_total` (counter): completed flush calls that performed I/O.
+//! - `mmap_io_flush_duration_seconds` (histogram): wall-clock duration of those flushes.
+//!
+//! Per-path labels are disabled by default to keep series cardinality bounded; enable them
+//! with [`set_path_labels`].
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Metric name for the bytes-written counter.
+pub const BYTES_WRITTEN_TOTAL: &str = "mmap_io_bytes_written_total";
+/// Metric name for the flush counter.
+pub const FLUSHES_TOTAL: &str = "mmap_io_flushes_total";
+/// Metric name for the flush duration histogram.
+pub const FLUSH_DURATION_SECONDS: &str = "mmap_io_flush_duration_seconds";
+
+static PATH_LABELS: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable the `path` label on emitted metrics (process-wide).
+///
+/// Labelling by path is useful with a handful of long-lived mappings but can produce
+/// unbounded cardinality for applications that map many short-lived files.
+pub fn set_path_labels(enabled: bool) {
+    PATH_LABELS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the `path` label is currently attached to emitted metrics.
+#[must_use]
+pub fn path_labels() -> bool {
+    PATH_LABELS.load(Ordering::Relaxed)
+}
+
+pub(crate) fn record_write(path: &Path, bytes: u64) {
+    if path_labels() {
+        ::metrics::counter!(BYTES_WRITTEN_TOTAL, "path" => path.display().to_string())
+            .increment(bytes);
+    } else {
+        ::metrics::counter!(BYTES_WRITTEN_TOTAL).increment(bytes);
+    }
+}
+
+pub(crate) fn record_flush(path: &Path, elapsed: Duration) {
+    if path_labels() {
+        let label = path.display().to_string();
+        ::metrics::counter!(FLUSHES_TOTAL, "path" => label.clone()).increment(1);
+        ::metrics::histogram!(FLUSH_DURATION_SECONDS, "path" => label)
+            .record(elapsed.as_secs_f64());
+    } else {
+        ::metrics::counter!(FLUSHES_TOTAL).increment(1);
+        ::metrics::histogram!(FLUSH_DURATION_SECONDS).record(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use ::metrics::{
+        Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+        SharedString, Unit,
+    };
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::Arc;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_metrics_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[derive(Default)]
+    struct Totals {
+        bytes_written: AtomicU64,
+        flushes: AtomicU64,
+        flush_samples: AtomicU64,
+    }
+
+    struct Cell(Arc<Totals>, &'static str);
+
+    impl CounterFn for Cell {
+        fn increment(&self, value: u64) {
+            match self.1 {
+                BYTES_WRITTEN_TOTAL => self.0.bytes_written.fetch_add(value, Ordering::SeqCst),
+                _ => self.0.flushes.fetch_add(value, Ordering::SeqCst),
+            };
+        }
+
+        fn absolute(&self, _value: u64) {}
+    }
+
+    impl HistogramFn for Cell {
+        fn record(&self, _value: f64) {
+            self.0.flush_samples.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct TestRecorder(Arc<Totals>);
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            let name = if key.name() == BYTES_WRITTEN_TOTAL {
+                BYTES_WRITTEN_TOTAL
+            } else {
+                FLUSHES_TOTAL
+            };
+            Counter::from_arc(Arc::new(Cell(self.0.clone(), name)))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::from_arc(Arc::new(Cell(self.0.clone(), FLUSH_DURATION_SECONDS)))
+        }
+    }
+
+    #[test]
+    fn test_write_and_flush_are_recorded() {
+        let path = tmp_path("write_flush");
+        let _ = fs::remove_file(&path);
+
+        let totals = Arc::new(Totals::default());
+        let recorder = TestRecorder(totals.clone());
+        ::metrics::with_local_recorder(&recorder, || {
+            let mmap = create_mmap(&path, 4096).expect("create");
+            mmap.update_region(0, &[1u8; 100]).expect("write 1");
+            mmap.update_region(100, &[2u8; 28]).expect("write 2");
+            mmap.flush().expect("flush");
+            // No pending writes: the skipped flush must not be recorded
+            mmap.flush().expect("flush noop");
+        });
+
+        assert_eq!(totals.bytes_written.load(Ordering::SeqCst), 128);
+        assert_eq!(totals.flushes.load(Ordering::SeqCst), 1);
+        assert_eq!(totals.flush_samples.load(Ordering::SeqCst), 1);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,390 @@
+//! Mirrored mappings: every write and flush applied to two backing files.
+//!
+//! [`MirroredMmap`] keeps a primary and a secondary file, typically on different disks,
+//! byte-for-byte identical. Writes are serialized so both copies see them in the same
+//! order. When an operation fails on one copy but succeeds on the other, the failing copy
+//! is marked as failed and dropped from further writes, and reads fail over to the
+//! survivor, so the mapping keeps working through the loss of one disk. Check
+//! [`MirroredMmap::failed_side`] to notice the degraded state, and
+//! [`MirroredMmap::resync`] to bring a replaced copy back in.
+//!
+//! Both copies are ordinary files and either can be opened on its own, e.g. to recover
+//! from the secondary after the primary disk is gone.
+
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use parking_lot::Mutex;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::{MemoryMappedFile, MmapMode};
+
+// Bytes copied per step while resynchronizing
+const RESYNC_CHUNK_SIZE: usize = 1024 * 1024;
+
+const HEALTHY: u8 = 0;
+const PRIMARY_FAILED: u8 = 1;
+const SECONDARY_FAILED: u8 = 2;
+
+/// One of the two copies of a [`MirroredMmap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorSide {
+    /// The primary file, preferred for reads.
+    Primary,
+    /// The secondary file.
+    Secondary,
+}
+
+impl MirrorSide {
+    fn other(self) -> Self {
+        match self {
+            Self::Primary => Self::Secondary,
+            Self::Secondary => Self::Primary,
+        }
+    }
+
+    fn failed_state(self) -> u8 {
+        match self {
+            Self::Primary => PRIMARY_FAILED,
+            Self::Secondary => SECONDARY_FAILED,
+        }
+    }
+}
+
+/// A read-write mapping whose contents are mirrored to a second file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mmap_io::mirror::MirroredMmap;
+///
+/// let state = MirroredMmap::create("/data/a/state.bin", "/data/b/state.bin", 1 << 20)?;
+/// state.update_region(0, b"epoch=42")?;
+/// state.flush()?;
+/// if let Some(side) = state.failed_side() {
+///     eprintln!("running degraded, {side:?} copy failed");
+/// }
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct MirroredMmap {
+    primary: MemoryMappedFile,
+    secondary: MemoryMappedFile,
+    // Serializes writes so both copies apply them in the same order
+    writer: Mutex<()>,
+    // HEALTHY, or the copy dropped from writes after a failure
+    failed: AtomicU8,
+}
+
+impl MirroredMmap {
+    /// Create (or truncate) both files with `size` bytes and map them read-write.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from [`MemoryMappedFile::create_rw`] for either file.
+    pub fn create<P: AsRef<Path>, S: AsRef<Path>>(
+        primary: P,
+        secondary: S,
+        size: u64,
+    ) -> Result<Self> {
+        Self::from_parts(
+            MemoryMappedFile::create_rw(primary, size)?,
+            MemoryMappedFile::create_rw(secondary, size)?,
+        )
+    }
+
+    /// Map two existing files read-write as a mirrored pair.
+    ///
+    /// The contents are not compared; call [`verify`](Self::verify) for that.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the files differ in length, and errors from
+    /// [`MemoryMappedFile::open_rw`] for either file.
+    pub fn open<P: AsRef<Path>, S: AsRef<Path>>(primary: P, secondary: S) -> Result<Self> {
+        Self::from_parts(
+            MemoryMappedFile::open_rw(primary)?,
+            MemoryMappedFile::open_rw(secondary)?,
+        )
+    }
+
+    /// Pair two existing read-write mappings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` unless both are `ReadWrite`, and
+    /// `MmapIoError::Corrupt` if their lengths differ.
+    pub fn from_parts(primary: MemoryMappedFile, secondary: MemoryMappedFile) -> Result<Self> {
+        if primary.mode() != MmapMode::ReadWrite || secondary.mode() != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(
+                "mirrored mappings require ReadWrite mode",
+            ));
+        }
+        let (a, b) = (primary.len(), secondary.len());
+        if a != b {
+            return Err(MmapIoError::Corrupt(format!(
+                "mirror copies differ in length: primary {a} bytes, secondary {b} bytes"
+            )));
+        }
+        Ok(Self {
+            primary,
+            secondary,
+            writer: Mutex::new(()),
+            failed: AtomicU8::new(HEALTHY),
+        })
+    }
+
+    /// Length of the mirrored contents in bytes.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.primary.len()
+    }
+
+    /// Whether the mirrored contents are empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The primary mapping. Writing to it directly bypasses mirroring.
+    #[must_use]
+    pub fn primary(&self) -> &MemoryMappedFile {
+        &self.primary
+    }
+
+    /// The secondary mapping. Writing to it directly bypasses mirroring.
+    #[must_use]
+    pub fn secondary(&self) -> &MemoryMappedFile {
+        &self.secondary
+    }
+
+    /// The copy dropped from writes after a failure, if any.
+    #[must_use]
+    pub fn failed_side(&self) -> Option<MirrorSide> {
+        match self.failed.load(Ordering::Acquire) {
+            PRIMARY_FAILED => Some(MirrorSide::Primary),
+            SECONDARY_FAILED => Some(MirrorSide::Secondary),
+            _ => None,
+        }
+    }
+
+    /// Write `data` at `offset` to both copies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if the write fails on every copy still in use; a failure on
+    /// one copy alone marks it as failed (see [`failed_side`](Self::failed_side)).
+    pub fn update_region(&self, offset: u64, data: &[u8]) -> Result<()> {
+        let _writer = self.writer.lock();
+        self.apply(|m| m.update_region(offset, data))
+    }
+
+    /// Flush both copies.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`update_region`](Self::update_region).
+    pub fn flush(&self) -> Result<()> {
+        let _writer = self.writer.lock();
+        self.apply(MemoryMappedFile::flush)
+    }
+
+    /// Flush `[offset, offset + len)` of both copies.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`update_region`](Self::update_region).
+    pub fn flush_range(&self, offset: u64, len: u64) -> Result<()> {
+        let _writer = self.writer.lock();
+        self.apply(|m| m.flush_range(offset, len))
+    }
+
+    /// Read `buf.len()` bytes at `offset`, from the primary unless it has failed.
+    ///
+    /// If the read fails on the preferred copy, it is retried on the other one.
+    ///
+    /// # Errors
+    ///
+    /// Returns the preferred copy's error if the read fails on both.
+    pub fn read_into(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let preferred = match self.failed_side() {
+            Some(MirrorSide::Primary) => MirrorSide::Secondary,
+            _ => MirrorSide::Primary,
+        };
+        self.side(preferred).read_into(offset, buf).or_else(|e| {
+            self.side(preferred.other())
+                .read_into(offset, buf)
+                .map_err(|_| e)
+        })
+    }
+
+    /// Compare the two copies and return the byte ranges where they differ.
+    ///
+    /// Takes the write lock, so the result is not skewed by concurrent mirrored writes.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from reading either copy.
+    pub fn verify(&self) -> Result<Vec<Range<u64>>> {
+        let _writer = self.writer.lock();
+        self.primary.diff(&self.secondary)
+    }
+
+    /// Make the other copy identical to `source` and flush it, then clear the failed mark.
+    ///
+    /// Only differing ranges are copied. Use it after replacing a failed disk, or after
+    /// [`verify`](Self::verify) reported differences, naming the copy known to be good.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from reading `source` or writing and flushing the target; the failed
+    /// mark is kept in that case.
+    pub fn resync(&self, source: MirrorSide) -> Result<()> {
+        let _writer = self.writer.lock();
+        let (from, to) = (self.side(source), self.side(source.other()));
+        let mut buf = vec![0u8; RESYNC_CHUNK_SIZE];
+        for range in from.diff(to)? {
+            let mut offset = range.start;
+            while offset < range.end {
+                let n = (range.end - offset).min(RESYNC_CHUNK_SIZE as u64) as usize;
+                from.read_into(offset, &mut buf[..n])?;
+                to.update_region(offset, &buf[..n])?;
+                offset += n as u64;
+            }
+        }
+        to.flush()?;
+        self.failed.store(HEALTHY, Ordering::Release);
+        Ok(())
+    }
+
+    fn side(&self, side: MirrorSide) -> &MemoryMappedFile {
+        match side {
+            MirrorSide::Primary => &self.primary,
+            MirrorSide::Secondary => &self.secondary,
+        }
+    }
+
+    // Run `op` on every copy still in use. Called with the write lock held.
+    fn apply(&self, op: impl Fn(&MemoryMappedFile) -> Result<()>) -> Result<()> {
+        let failed = self.failed_side();
+        let mut succeeded = false;
+        let mut first_err = None;
+        for side in [MirrorSide::Primary, MirrorSide::Secondary] {
+            if failed == Some(side) {
+                continue;
+            }
+            match op(self.side(side)) {
+                Ok(()) => succeeded = true,
+                Err(e) => first_err = first_err.or(Some((side, e))),
+            }
+        }
+        match first_err {
+            None => Ok(()),
+            // One copy failed, the other carries on alone
+            Some((side, _)) if succeeded => {
+                self.failed.store(side.failed_state(), Ordering::Release);
+                Ok(())
+            }
+            Some((_, e)) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_mirror_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_mirrored_writes_and_resync() {
+        let (a, b) = (tmp_path("a"), tmp_path("b"));
+        let _ = fs::remove_file(&a);
+        let _ = fs::remove_file(&b);
+
+        let mirror = MirroredMmap::create(&a, &b, 8192).expect("create");
+        mirror.update_region(100, b"mirrored").expect("write");
+        mirror.flush().expect("flush");
+        assert_eq!(mirror.failed_side(), None);
+        assert!(mirror.verify().expect("verify").is_empty());
+        assert_eq!(
+            &fs::read(&b).expect("read secondary")[100..108],
+            b"mirrored"
+        );
+
+        // Out-of-bounds writes fail on both copies and mark neither
+        assert!(mirror.update_region(8190, b"xyz").is_err());
+        assert_eq!(mirror.failed_side(), None);
+
+        // Divergence is reported and repaired from the named copy
+        mirror
+            .secondary()
+            .update_region(5000, b"bitrot")
+            .expect("direct write");
+        assert_eq!(mirror.verify().expect("verify"), vec![5000..5006]);
+        mirror.resync(MirrorSide::Primary).expect("resync");
+        assert!(mirror.verify().expect("verify").is_empty());
+        drop(mirror);
+
+        // Reopening checks lengths
+        let mirror = MirroredMmap::open(&a, &b).expect("open");
+        let mut buf = [0u8; 8];
+        mirror.read_into(100, &mut buf).expect("read");
+        assert_eq!(&buf, b"mirrored");
+        drop(mirror);
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&b)
+            .and_then(|f| f.set_len(4096))
+            .expect("truncate secondary");
+        assert!(matches!(
+            MirroredMmap::open(&a, &b),
+            Err(MmapIoError::Corrupt(_))
+        ));
+
+        fs::remove_file(&a).expect("cleanup a");
+        fs::remove_file(&b).expect("cleanup b");
+    }
+
+    #[test]
+    fn test_mirror_failover() {
+        let (a, b) = (tmp_path("failover_a"), tmp_path("failover_b"));
+        let mirror = MirroredMmap::create(&a, &b, 4096).expect("create");
+        mirror.update_region(0, b"before").expect("write");
+
+        // Simulate losing the primary disk
+        mirror.failed.store(PRIMARY_FAILED, Ordering::Release);
+        mirror.update_region(0, b"after!").expect("write degraded");
+        let mut buf = [0u8; 6];
+        mirror.read_into(0, &mut buf).expect("read degraded");
+        assert_eq!(&buf, b"after!");
+        mirror
+            .primary()
+            .read_into(0, &mut buf)
+            .expect("read primary");
+        assert_eq!(&buf, b"before", "failed copy no longer receives writes");
+
+        mirror.resync(MirrorSide::Secondary).expect("resync");
+        assert_eq!(mirror.failed_side(), None);
+        mirror
+            .primary()
+            .read_into(0, &mut buf)
+            .expect("read primary");
+        assert_eq!(&buf, b"after!");
+        drop(mirror);
+
+        fs::remove_file(&a).expect("cleanup a");
+        fs::remove_file(&b).expect("cleanup b");
+    }
+}
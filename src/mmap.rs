@@ -2,25 +2,40 @@
 
 use std::{
     fs::{File, OpenOptions},
+    io::IoSliceMut,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
-use memmap2::{Mmap, MmapMut};
+use memmap2::{Mmap, MmapMut, MmapOptions};
 
-use crate::flush::FlushPolicy;
+use crate::flush::{FlushOptions, FlushPolicy};
 
-#[cfg(feature = "cow")]
-use memmap2::MmapOptions;
-
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
 use crate::errors::{MmapIoError, Result};
-use crate::utils::{ensure_in_bounds, slice_range};
+use crate::guard::Transfer;
+use crate::integrity::HashAlgo;
+use crate::observer::{MmapObserver, MmapOperation};
+use crate::trace::{AccessKind, AccessRecorder};
+use crate::tracking::Registration;
+use crate::utils::{ensure_in_bounds, os_path, slice_range};
 
 // Error message constants
-const ERR_ZERO_SIZE: &str = "Size must be greater than zero";
+pub(crate) const ERR_ZERO_SIZE: &str = "Size must be greater than zero";
 const ERR_ZERO_LENGTH_FILE: &str = "Cannot map zero-length file";
+const ERR_ADDRESS_HINT_MODE: &str = "address_hint requires ReadWrite mode";
+const ERR_TOO_LARGE_TO_MAP: &str = "use window::WindowedMmap for windowed access";
+const ERR_SPLIT_ACTIVE: &str = "cannot resize while split_into_mut parts are alive";
+const ERR_SPLIT_ACCESS: &str = "mapping is split; access it through its parts until they drop";
+const ERR_GUARDED_LOCK_FREE: &str = "guarded mappings only allow access through the map lock";
+
+/// Default [`growth_granularity`](MemoryMappedFileBuilder::growth_granularity) for
+/// auto-growing mappings: 1 MiB.
+pub const DEFAULT_GROWTH_GRANULARITY: u64 = 1024 * 1024;
 
 // Maximum safe mmap size: 128TB (reasonable limit for most systems)
 // This prevents accidental exhaustion of address space or disk
@@ -32,6 +47,132 @@ const MAX_MMAP_SIZE: u64 = 128 * (1 << 40); // 128 TB on 64-bit systems
 #[cfg(target_pointer_width = "32")]
 const MAX_MMAP_SIZE: u64 = 2 * (1 << 30); // 2 GB on 32-bit systems (practical limit)
 
+// Process-wide mapping size limit; defaults to MAX_MMAP_SIZE.
+static SIZE_LIMIT: AtomicU64 = AtomicU64::new(MAX_MMAP_SIZE);
+
+/// Set the process-wide maximum size of a single mapping.
+///
+/// Creating, opening, or resizing a mapping beyond the limit fails with
+/// `MmapIoError::ResizeFailed` naming the limit. The default is 128 TB on 64-bit targets
+/// and 2 GB on 32-bit ones; services can lower it as a policy guard, or raise it on systems
+/// with a larger address space. Mappings built with
+/// [`MemoryMappedFileBuilder::max_size`] use their own limit instead.
+pub fn set_max_mmap_size(limit: u64) {
+    SIZE_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// The current process-wide maximum size of a single mapping.
+#[must_use]
+pub fn max_mmap_size() -> u64 {
+    SIZE_LIMIT.load(Ordering::Relaxed)
+}
+
+// Reject sizes above the per-mapping limit, or the process-wide one if none was set.
+pub(crate) fn ensure_within_limit(size: u64, limit: Option<u64>) -> Result<()> {
+    let limit = limit.unwrap_or_else(max_mmap_size);
+    if size > limit {
+        return Err(MmapIoError::ResizeFailed(format!(
+            "Size {size} exceeds the mapping size limit of {limit} bytes"
+        )));
+    }
+    Ok(())
+}
+
+// Reject growing `file` to `size` bytes when the filesystem cannot back the blocks it does
+// not have allocated yet. The check is advisory: other writers may consume the space later.
+fn ensure_free_space(file: &File, path: &Path, size: u64) -> Result<()> {
+    let required = size.saturating_sub(allocated_bytes(file)?);
+    if required == 0 {
+        return Ok(());
+    }
+    let available = available_space(file, path)?;
+    if required > available {
+        return Err(MmapIoError::InsufficientSpace {
+            required,
+            available,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn allocated_bytes(file: &File) -> Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(file.metadata()?.blocks() * 512)
+}
+
+// NTFS files are not sparse unless marked so, so their whole length is allocated.
+#[cfg(windows)]
+fn allocated_bytes(file: &File) -> Result<u64> {
+    Ok(file.metadata()?.len())
+}
+
+#[cfg(unix)]
+fn available_space(file: &File, _path: &Path) -> Result<u64> {
+    use std::os::fd::AsRawFd;
+    // SAFETY: an all-zero statvfs is a valid value for fstatvfs to overwrite.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: the descriptor is open and `stat` is writable.
+    if unsafe { libc::fstatvfs(file.as_raw_fd(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    // Field widths vary by target
+    #[allow(clippy::unnecessary_cast)]
+    let available = (stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64);
+    Ok(available)
+}
+
+#[cfg(windows)]
+fn available_space(_file: &File, path: &Path) -> Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            free_to_caller: *mut u64,
+            total: *mut u64,
+            total_free: *mut u64,
+        ) -> i32;
+    }
+
+    let dir = path
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let wide: Vec<u16> = os_path(dir)
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated; the other outputs are optional and passed as null.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(available)
+}
+
+// Reject existing files too large to map whole (e.g. >2 GB on 32-bit), pointing callers at
+// windowed access instead of failing inside mmap.
+fn ensure_mappable(len: u64, limit: Option<u64>) -> Result<()> {
+    let limit = limit.unwrap_or_else(max_mmap_size);
+    if len > limit {
+        return Err(MmapIoError::ResizeFailed(format!(
+            "File of {len} bytes exceeds the mapping size limit of {limit} bytes; {ERR_TOO_LARGE_TO_MAP}"
+        )));
+    }
+    Ok(())
+}
+
 /// Access mode for a memory-mapped file.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MmapMode {
@@ -41,6 +182,26 @@ pub enum MmapMode {
     ReadWrite,
     /// Copy-on-Write mapping (private). Writes affect this mapping only; the underlying file remains unchanged.
     CopyOnWrite,
+    /// Read-execute mapping for JIT output or loaded code. Never writable.
+    ReadExecute,
+}
+
+/// What a child process inherits of a mapping after `fork()`.
+///
+/// Daemons that fork workers can use this to keep mapped secrets out of children.
+/// Applied with `madvise` on Linux; Windows has no `fork`, so every value is accepted there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ForkBehavior {
+    /// Default: the child shares the mapping with the parent.
+    #[default]
+    Inherit,
+    /// `MADV_DONTFORK`: the range is absent in the child; touching it there faults.
+    DontFork,
+    /// `MADV_WIPEONFORK`: the child sees the range zero-filled (Linux 4.14+).
+    ///
+    /// The kernel only accepts this for private mappings, so it is rejected for
+    /// shared file-backed ReadWrite mappings with an I/O error (`EINVAL`).
+    WipeOnFork,
 }
 
 #[doc(hidden)]
@@ -58,14 +219,97 @@ pub struct Inner {
     // Huge pages preference (builder-set), effective on supported platforms
     #[cfg(feature = "hugepages")]
     pub(crate) huge_pages: bool,
+    // Optional operation observer (shared by all clones)
+    pub(crate) observer: RwLock<Option<Arc<dyn MmapObserver>>>,
+    // Post-fork inheritance of the mapping (builder-set), reapplied after remaps
+    pub(crate) fork_behavior: ForkBehavior,
+    // Per-mapping size limit (builder-set); None defers to the process-wide limit
+    pub(crate) size_limit: Option<u64>,
+    // Entry in the global mapped-bytes registry, if tracking was enabled at creation
+    pub(crate) tracking: Option<Registration>,
+    // Strict durability (builder-set): synchronous msync, plus FlushFileBuffers on Windows
+    pub(crate) strict_flush: bool,
+    // Access trace sink while recording (shared by all clones)
+    pub(crate) recorder: RwLock<Option<Arc<AccessRecorder>>>,
+    // Live `split_into_mut` parts; resizing and byte access are refused while any exist
+    pub(crate) split_parts: AtomicUsize,
+    // Zero and flush released bytes before shrinking (builder-set)
+    pub(crate) scrub_on_shrink: bool,
+    // Check free disk space before growing the file (builder-set)
+    pub(crate) check_free_space: bool,
+    // Recover from SIGBUS in read_into/update_region copies (builder-set); the copies hold
+    // this lock so no other copy can see the zero page while a fault is being recovered
+    pub(crate) guarded: Option<Mutex<()>>,
+    // File offset of a builder-set window; None when the whole file is mapped
+    pub(crate) window_offset: Option<u64>,
+    // Growth granularity for writes past the end, locked while growing (builder-set)
+    pub(crate) auto_grow: Option<Mutex<u64>>,
+    // Timer thread for FlushPolicy::EveryMillis, started by from_inner
+    pub(crate) interval_flusher: Option<crate::flusher::IntervalFlusher>,
+    // Pages written since they were last flushed
+    pub(crate) dirty: Mutex<crate::dirty::DirtyPages>,
 }
 
 #[doc(hidden)]
 pub enum MapVariant {
+    /// Immutable mapping; also backs `ReadExecute` (the mode distinguishes the protection).
     Ro(Mmap),
-    Rw(RwLock<MmapMut>),
+    Rw(RwLock<RwMap>),
     /// Private, per-process copy-on-write mapping. Underlying file is not modified by writes.
-    Cow(Mmap),
+    Cow(RwLock<RwMap>),
+}
+
+/// Writable view: a regular memmap2 mapping, or one placed at a fixed address.
+#[doc(hidden)]
+pub enum RwMap {
+    Std(MmapMut),
+    Fixed(crate::fixed::FixedMap),
+}
+
+impl RwMap {
+    fn flush(&self) -> std::io::Result<()> {
+        match self {
+            RwMap::Std(m) => m.flush(),
+            RwMap::Fixed(m) => m.flush(),
+        }
+    }
+
+    fn flush_range(&self, offset: usize, len: usize) -> std::io::Result<()> {
+        match self {
+            RwMap::Std(m) => m.flush_range(offset, len),
+            RwMap::Fixed(m) => m.flush_range(offset, len),
+        }
+    }
+
+    // Remap after the file length changed, keeping a fixed view at its address.
+    fn remap(&mut self, file: &File, new_len: u64) -> Result<()> {
+        match self {
+            // SAFETY: the file is open read-write for the lifetime of the mapping.
+            RwMap::Std(m) => *m = unsafe { MmapMut::map_mut(file)? },
+            RwMap::Fixed(m) => m.remap(file, new_len as usize)?,
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::Deref for RwMap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            RwMap::Std(m) => m,
+            RwMap::Fixed(m) => m,
+        }
+    }
+}
+
+impl std::ops::DerefMut for RwMap {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            RwMap::Std(m) => m,
+            RwMap::Fixed(m) => m,
+        }
+    }
 }
 
 /// Memory-mapped file with safe, zero-copy region access.
@@ -96,7 +340,7 @@ pub enum MapVariant {
 /// ```
 ///
 /// Cloning this struct is cheap; it clones an Arc to the inner state.
-/// For read-write mappings, interior mutability is protected with an `RwLock`.
+/// For read-write and copy-on-write mappings, interior mutability is protected with an `RwLock`.
 #[derive(Clone)]
 pub struct MemoryMappedFile {
     pub(crate) inner: Arc<Inner>,
@@ -116,6 +360,42 @@ impl std::fmt::Debug for MemoryMappedFile {
     }
 }
 
+/// The backing file's descriptor, for `fcntl`/`ioctl` operations the crate does not wrap.
+///
+/// Changing the file's length through it bypasses the mapping's bookkeeping; use
+/// [`MemoryMappedFile::resize`] for that.
+#[cfg(unix)]
+impl std::os::fd::AsFd for MemoryMappedFile {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        std::os::fd::AsFd::as_fd(&self.inner.file)
+    }
+}
+
+#[cfg(unix)]
+impl std::os::fd::AsRawFd for MemoryMappedFile {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        std::os::fd::AsRawFd::as_raw_fd(&self.inner.file)
+    }
+}
+
+/// The backing file's handle, for `DeviceIoControl` and other calls the crate does not wrap.
+///
+/// Changing the file's length through it bypasses the mapping's bookkeeping; use
+/// [`MemoryMappedFile::resize`] for that.
+#[cfg(windows)]
+impl std::os::windows::io::AsHandle for MemoryMappedFile {
+    fn as_handle(&self) -> std::os::windows::io::BorrowedHandle<'_> {
+        std::os::windows::io::AsHandle::as_handle(&self.inner.file)
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::io::AsRawHandle for MemoryMappedFile {
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        std::os::windows::io::AsRawHandle::as_raw_handle(&self.inner.file)
+    }
+}
+
 impl MemoryMappedFile {
     /// Builder for constructing a MemoryMappedFile with custom options.
     ///
@@ -137,6 +417,26 @@ impl MemoryMappedFile {
             flush_policy: FlushPolicy::default(),
             #[cfg(feature = "hugepages")]
             huge_pages: false,
+            populate: false,
+            create_new: false,
+            truncate: true,
+            contents: None,
+            fork_behavior: ForkBehavior::Inherit,
+            address_hint: None,
+            size_limit: None,
+            label: None,
+            strict_flush: false,
+            expect_len: None,
+            expect_hash: None,
+            share_mode: None,
+            permissions: None,
+            scrub_on_shrink: false,
+            check_free_space: false,
+            guarded: false,
+            window_offset: None,
+            window_len: None,
+            auto_grow: false,
+            growth_granularity: DEFAULT_GROWTH_GRANULARITY,
         }
     }
 
@@ -156,18 +456,14 @@ impl MemoryMappedFile {
         if size == 0 {
             return Err(MmapIoError::ResizeFailed(ERR_ZERO_SIZE.into()));
         }
-        if size > MAX_MMAP_SIZE {
-            return Err(MmapIoError::ResizeFailed(format!(
-                "Size {size} exceeds maximum safe limit of {MAX_MMAP_SIZE} bytes"
-            )));
-        }
+        ensure_within_limit(size, None)?;
         let path_ref = path.as_ref();
         let file = OpenOptions::new()
             .create(true)
             .write(true)
             .read(true)
             .truncate(true)
-            .open(path_ref)?;
+            .open(os_path(path_ref))?;
         file.set_len(size)?;
         // SAFETY: The file has been created with the correct size and permissions.
         // memmap2 handles platform-specific mmap details safely.
@@ -178,15 +474,39 @@ impl MemoryMappedFile {
             file,
             mode: MmapMode::ReadWrite,
             cached_len: RwLock::new(size),
-            map: MapVariant::Rw(RwLock::new(mmap)),
+            map: MapVariant::Rw(RwLock::new(RwMap::Std(mmap))),
             flush_policy: FlushPolicy::default(),
             written_since_last_flush: RwLock::new(0),
             #[cfg(feature = "hugepages")]
             huge_pages: false,
+            observer: RwLock::new(None),
+            fork_behavior: ForkBehavior::Inherit,
+            size_limit: None,
+            tracking: None,
+            strict_flush: false,
+            recorder: RwLock::new(None),
+            split_parts: AtomicUsize::new(0),
+            scrub_on_shrink: false,
+            check_free_space: false,
+            guarded: None,
+            window_offset: None,
+            auto_grow: None,
+            interval_flusher: None,
+            dirty: Mutex::default(),
         };
-        Ok(Self {
-            inner: Arc::new(inner),
-        })
+        Self::from_inner(inner)
+    }
+
+    /// Create a file holding exactly `data` and memory-map it read-write.
+    ///
+    /// Truncates an existing file. The bytes are flushed to disk before returning.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::ResizeFailed` if `data` is empty or exceeds the maximum safe limit.
+    /// Returns `MmapIoError::Io` if file creation, mapping, or the flush fails.
+    pub fn create_from_slice<P: AsRef<Path>>(path: P, data: &[u8]) -> Result<Self> {
+        Self::builder(path).contents(data).create()
     }
 
     /// Open an existing file and memory-map it read-only.
@@ -196,8 +516,9 @@ impl MemoryMappedFile {
     /// Returns `MmapIoError::Io` if file opening or mapping fails.
     pub fn open_ro<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path_ref = path.as_ref();
-        let file = OpenOptions::new().read(true).open(path_ref)?;
+        let file = OpenOptions::new().read(true).open(os_path(path_ref))?;
         let len = file.metadata()?.len();
+        ensure_mappable(len, None)?;
         // SAFETY: The file is opened read-only and memmap2 ensures safe mapping.
         let mmap = unsafe { Mmap::map(&file)? };
         let inner = Inner {
@@ -210,10 +531,22 @@ impl MemoryMappedFile {
             written_since_last_flush: RwLock::new(0),
             #[cfg(feature = "hugepages")]
             huge_pages: false,
+            observer: RwLock::new(None),
+            fork_behavior: ForkBehavior::Inherit,
+            size_limit: None,
+            tracking: None,
+            strict_flush: false,
+            recorder: RwLock::new(None),
+            split_parts: AtomicUsize::new(0),
+            scrub_on_shrink: false,
+            check_free_space: false,
+            guarded: None,
+            window_offset: None,
+            auto_grow: None,
+            interval_flusher: None,
+            dirty: Mutex::default(),
         };
-        Ok(Self {
-            inner: Arc::new(inner),
-        })
+        Self::from_inner(inner)
     }
 
     /// Open an existing file and memory-map it read-write.
@@ -224,8 +557,12 @@ impl MemoryMappedFile {
     /// Returns `MmapIoError::Io` if file opening or mapping fails.
     pub fn open_rw<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path_ref = path.as_ref();
-        let file = OpenOptions::new().read(true).write(true).open(path_ref)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(os_path(path_ref))?;
         let len = file.metadata()?.len();
+        ensure_mappable(len, None)?;
         if len == 0 {
             return Err(MmapIoError::ResizeFailed(ERR_ZERO_LENGTH_FILE.into()));
         }
@@ -238,15 +575,41 @@ impl MemoryMappedFile {
             file,
             mode: MmapMode::ReadWrite,
             cached_len: RwLock::new(len),
-            map: MapVariant::Rw(RwLock::new(mmap)),
+            map: MapVariant::Rw(RwLock::new(RwMap::Std(mmap))),
             flush_policy: FlushPolicy::default(),
             written_since_last_flush: RwLock::new(0),
             #[cfg(feature = "hugepages")]
             huge_pages: false,
+            observer: RwLock::new(None),
+            fork_behavior: ForkBehavior::Inherit,
+            size_limit: None,
+            tracking: None,
+            strict_flush: false,
+            recorder: RwLock::new(None),
+            split_parts: AtomicUsize::new(0),
+            scrub_on_shrink: false,
+            check_free_space: false,
+            guarded: None,
+            window_offset: None,
+            auto_grow: None,
+            interval_flusher: None,
+            dirty: Mutex::default(),
         };
-        Ok(Self {
-            inner: Arc::new(inner),
-        })
+        Self::from_inner(inner)
+    }
+
+    /// Open `path` read-write, creating it with `size` bytes if it does not exist.
+    ///
+    /// Unlike [`create_rw`](Self::create_rw), an existing file is never truncated: it is
+    /// mapped at its current length with its contents intact. An existing empty file is
+    /// grown to `size`. Shorthand for `builder(path).size(size).truncate(false).create()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::ResizeFailed` if `size` is zero or exceeds the maximum safe limit.
+    /// Returns `MmapIoError::Io` if file opening, creation, or mapping fails.
+    pub fn open_or_create<P: AsRef<Path>>(path: P, size: u64) -> Result<Self> {
+        Self::builder(path).size(size).truncate(false).create()
     }
 
     /// Return current mapping mode.
@@ -268,7 +631,7 @@ impl MemoryMappedFile {
     }
 
     /// Get a zero-copy read-only slice for the given [offset, offset+len).
-    /// For RW mappings, cannot return a reference bound to a temporary guard; use `read_into` instead.
+    /// For RW and COW mappings, cannot return a reference bound to a temporary guard; use `read_into` instead.
     ///
     /// # Performance
     ///
@@ -279,8 +642,9 @@ impl MemoryMappedFile {
     /// # Errors
     ///
     /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
-    /// Returns `MmapIoError::InvalidMode` for RW mappings (use `read_into` instead).
+    /// Returns `MmapIoError::InvalidMode` for RW and COW mappings (use `read_into` instead).
     pub fn as_slice(&self, offset: u64, len: u64) -> Result<&[u8]> {
+        self.accessed(offset, len, AccessKind::Read);
         let total = self.current_len()?;
         ensure_in_bounds(offset, len, total)?;
         match &self.inner.map {
@@ -289,40 +653,35 @@ impl MemoryMappedFile {
                 Ok(&m[start..end])
             }
             MapVariant::Rw(_lock) => Err(MmapIoError::InvalidMode("use read_into for RW mappings")),
-            MapVariant::Cow(m) => {
-                let (start, end) = slice_range(offset, len, total)?;
-                Ok(&m[start..end])
-            }
+            MapVariant::Cow(_lock) => Err(MmapIoError::InvalidMode(
+                "use read_into for copy-on-write mappings",
+            )),
         }
     }
 
     /// Get a zero-copy mutable slice for the given [offset, offset+len).
-    /// Only available in `ReadWrite` mode.
+    /// Available in `ReadWrite` and `CopyOnWrite` mode; in the latter, writes change only
+    /// this process's private copy of the pages.
     ///
     /// # Errors
     ///
-    /// Returns `MmapIoError::InvalidMode` if not in `ReadWrite` mode.
+    /// Returns `MmapIoError::InvalidMode` if not in `ReadWrite` or `CopyOnWrite` mode.
     /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
     pub fn as_slice_mut(&self, offset: u64, len: u64) -> Result<MappedSliceMut<'_>> {
+        self.accessed(offset, len, AccessKind::Write);
         let (start, end) = slice_range(offset, len, self.current_len()?)?;
         match &self.inner.map {
             MapVariant::Ro(_) => Err(MmapIoError::InvalidMode(
                 "mutable access on read-only mapping",
             )),
-            MapVariant::Rw(lock) => {
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
                 let guard = lock.write();
+                self.check_unsplit()?;
                 Ok(MappedSliceMut {
                     guard,
                     range: start..end,
                 })
             }
-            MapVariant::Cow(_) => {
-                // Phase-1: COW is read-only for safety. Writable COW will be added with a persistent
-                // private RW view in a follow-up change.
-                Err(MmapIoError::InvalidMode(
-                    "mutable access on copy-on-write mapping (phase-1 read-only)",
-                ))
-            }
         }
     }
 
@@ -337,34 +696,270 @@ impl MemoryMappedFile {
     ///
     /// # Errors
     ///
-    /// Returns `MmapIoError::InvalidMode` if not in `ReadWrite` mode.
-    /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
+    /// Returns `MmapIoError::InvalidMode` if not in `ReadWrite` or `CopyOnWrite` mode.
+    /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds; mappings built with
+    /// [`auto_grow`](MemoryMappedFileBuilder::auto_grow) grow instead and return any
+    /// [`resize`](Self::resize) error.
+    /// Returns `MmapIoError::MappingFault` if the mapping is
+    /// [`guarded`](MemoryMappedFileBuilder::guarded) and the file cannot back the bytes.
     pub fn update_region(&self, offset: u64, data: &[u8]) -> Result<()> {
+        self.accessed(offset, data.len() as u64, AccessKind::Write);
+        let res = self.update_region_impl(offset, data);
+        self.observed(MmapOperation::Write, res)
+    }
+
+    fn update_region_impl(&self, offset: u64, data: &[u8]) -> Result<()> {
         if data.is_empty() {
             return Ok(());
         }
-        if self.inner.mode != MmapMode::ReadWrite {
+        if !matches!(self.inner.mode, MmapMode::ReadWrite | MmapMode::CopyOnWrite) {
             return Err(MmapIoError::InvalidMode(
-                "Update region requires ReadWrite mode.",
+                "Update region requires ReadWrite or CopyOnWrite mode.",
             ));
         }
         let len = data.len() as u64;
+        self.grow_to_fit(offset, len)?;
         let (start, end) = slice_range(offset, len, self.current_len()?)?;
         match &self.inner.map {
             MapVariant::Ro(_) => Err(MmapIoError::InvalidMode(
                 "Cannot write to read-only mapping",
             )),
-            MapVariant::Rw(lock) => {
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
                 {
                     let mut guard = lock.write();
-                    guard[start..end].copy_from_slice(data);
+                    self.check_unsplit()?;
+                    debug_assert_eq!(end - start, data.len());
+                    // SAFETY: the range was bounds-checked, and the write lock keeps every
+                    // other user of the view out.
+                    unsafe {
+                        self.guarded_copy(guard.as_mut_ptr(), start, Transfer::Write(data))?;
+                    }
                 }
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_write(&self.inner.path, len);
+                self.observe(|o| o.on_write(offset, data));
                 // Apply flush policy
-                self.apply_flush_policy(len)?;
+                self.apply_flush_policy(offset, len)?;
                 Ok(())
             }
-            MapVariant::Cow(_) => Err(MmapIoError::InvalidMode(
-                "Cannot write to copy-on-write mapping (phase-1 read-only)",
+        }
+    }
+
+    // Parts from `split_into_mut` write their ranges without the map lock. `split_with`
+    // raises the count under the write lock, so callers check it while holding the lock.
+    pub(crate) fn check_unsplit(&self) -> Result<()> {
+        if self.inner.split_parts.load(Ordering::Acquire) > 0 {
+            return Err(MmapIoError::InvalidMode(ERR_SPLIT_ACCESS));
+        }
+        Ok(())
+    }
+
+    // Guarded recovery briefly maps a private page over the view, which would swallow
+    // writes that bypass the map lock; APIs handing out such access call this first.
+    pub(crate) fn check_unguarded(&self) -> Result<()> {
+        if self.inner.guarded.is_some() {
+            return Err(MmapIoError::InvalidMode(ERR_GUARDED_LOCK_FREE));
+        }
+        Ok(())
+    }
+
+    // For auto-growing mappings, grow the file so `[offset, offset + len)` fits.
+    fn grow_to_fit(&self, offset: u64, len: u64) -> Result<()> {
+        let Some(granularity) = &self.inner.auto_grow else {
+            return Ok(());
+        };
+        // Overflowing ranges are left for the bounds check to report
+        let Some(end) = offset.checked_add(len) else {
+            return Ok(());
+        };
+        if end <= self.current_len()? {
+            return Ok(());
+        }
+        // Held across the resize so concurrent growers never shrink each other's result
+        let granularity = granularity.lock();
+        if end <= self.current_len()? {
+            return Ok(());
+        }
+        let mut new_size = crate::utils::align_up(end, *granularity).max(end);
+        if let Some(limit) = self.inner.size_limit.filter(|&limit| end <= limit) {
+            new_size = new_size.min(limit);
+        }
+        self.resize_impl(new_size)
+    }
+
+    /// Write `data` at `offset` and copy the bytes it replaced into `prev`.
+    ///
+    /// The read and the write happen under a single write-lock acquisition, so no other
+    /// writer can slip in between; useful for building undo logs.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for read-only mappings or if `prev` and
+    /// `data` differ in length.
+    /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
+    pub fn swap_region(&self, offset: u64, data: &[u8], prev: &mut [u8]) -> Result<()> {
+        self.accessed(offset, data.len() as u64, AccessKind::Write);
+        let res = self.swap_region_impl(offset, data, prev);
+        self.observed(MmapOperation::Write, res)
+    }
+
+    fn swap_region_impl(&self, offset: u64, data: &[u8], prev: &mut [u8]) -> Result<()> {
+        if prev.len() != data.len() {
+            return Err(MmapIoError::InvalidMode(
+                "swap_region requires prev and data of the same length",
+            ));
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+        let len = data.len() as u64;
+        let (start, end) = slice_range(offset, len, self.current_len()?)?;
+        match &self.inner.map {
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
+                {
+                    let mut guard = lock.write();
+                    self.check_unsplit()?;
+                    prev.copy_from_slice(&guard[start..end]);
+                    guard[start..end].copy_from_slice(data);
+                }
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_write(&self.inner.path, len);
+                self.observe(|o| o.on_write(offset, data));
+                self.apply_flush_policy(offset, len)
+            }
+            MapVariant::Ro(_) => Err(MmapIoError::InvalidMode(
+                "Swap region requires ReadWrite or CopyOnWrite mode.",
+            )),
+        }
+    }
+
+    /// Write `new` at `offset` only if the bytes there currently equal `expected`.
+    ///
+    /// The comparison and the write happen under the write lock, so cooperating writers
+    /// can use this for optimistic concurrency. Returns `true` if the write happened and
+    /// `false` (leaving the mapping untouched) if the contents differed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for read-only mappings or if `expected` and
+    /// `new` differ in length.
+    /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
+    pub fn compare_and_swap_region(
+        &self,
+        offset: u64,
+        expected: &[u8],
+        new: &[u8],
+    ) -> Result<bool> {
+        self.accessed(offset, new.len() as u64, AccessKind::Write);
+        let res = self.compare_and_swap_region_impl(offset, expected, new);
+        self.observed(MmapOperation::Write, res)
+    }
+
+    fn compare_and_swap_region_impl(
+        &self,
+        offset: u64,
+        expected: &[u8],
+        new: &[u8],
+    ) -> Result<bool> {
+        if expected.len() != new.len() {
+            return Err(MmapIoError::InvalidMode(
+                "compare_and_swap_region requires expected and new of the same length",
+            ));
+        }
+        let len = new.len() as u64;
+        let (start, end) = slice_range(offset, len, self.current_len()?)?;
+        match &self.inner.map {
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
+                {
+                    let mut guard = lock.write();
+                    self.check_unsplit()?;
+                    if guard[start..end] != *expected {
+                        return Ok(false);
+                    }
+                    guard[start..end].copy_from_slice(new);
+                }
+                if len > 0 {
+                    #[cfg(feature = "metrics")]
+                    crate::metrics::record_write(&self.inner.path, len);
+                    self.observe(|o| o.on_write(offset, new));
+                    self.apply_flush_policy(offset, len)?;
+                }
+                Ok(true)
+            }
+            MapVariant::Ro(_) => Err(MmapIoError::InvalidMode(
+                "Compare-and-swap region requires ReadWrite or CopyOnWrite mode.",
+            )),
+        }
+    }
+
+    /// Set every byte of `[offset, offset + len)` to `byte` under a single write lock.
+    /// Counts as a write for flush policy, metrics, and observers.
+    pub(crate) fn fill_region(&self, offset: u64, len: u64, byte: u8) -> Result<()> {
+        let res = self.fill_region_impl(offset, len, byte);
+        self.observed(MmapOperation::Write, res)
+    }
+
+    fn fill_region_impl(&self, offset: u64, len: u64, byte: u8) -> Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        let (start, end) = slice_range(offset, len, self.current_len()?)?;
+        match &self.inner.map {
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
+                {
+                    let mut guard = lock.write();
+                    self.check_unsplit()?;
+                    guard[start..end].fill(byte);
+                }
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_write(&self.inner.path, len);
+                self.observe(|o| o.on_write(offset, &vec![byte; end - start]));
+                self.apply_flush_policy(offset, len)
+            }
+            MapVariant::Ro(_) => Err(MmapIoError::InvalidMode(
+                "Fill region requires ReadWrite or CopyOnWrite mode.",
+            )),
+        }
+    }
+
+    /// Copy `len` bytes from `src_offset` to `dst_offset` within this mapping under a single
+    /// write lock (overlapping ranges are handled like `memmove`).
+    pub(crate) fn copy_within_region(
+        &self,
+        src_offset: u64,
+        dst_offset: u64,
+        len: u64,
+    ) -> Result<()> {
+        let res = self.copy_within_region_impl(src_offset, dst_offset, len);
+        self.observed(MmapOperation::Write, res)
+    }
+
+    fn copy_within_region_impl(&self, src_offset: u64, dst_offset: u64, len: u64) -> Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        let total = self.current_len()?;
+        let (src_start, src_end) = slice_range(src_offset, len, total)?;
+        let (dst_start, dst_end) = slice_range(dst_offset, len, total)?;
+        match &self.inner.map {
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
+                {
+                    let mut guard = lock.write();
+                    self.check_unsplit()?;
+                    guard.copy_within(src_start..src_end, dst_start);
+                }
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_write(&self.inner.path, len);
+                self.observe(|o| {
+                    let mut copied = vec![0u8; dst_end - dst_start];
+                    if self.read_into(dst_offset, &mut copied).is_ok() {
+                        o.on_write(dst_offset, &copied);
+                    }
+                });
+                self.apply_flush_policy(dst_offset, len)
+            }
+            MapVariant::Ro(_) => Err(MmapIoError::InvalidMode(
+                "Copy region requires ReadWrite or CopyOnWrite mode.",
             )),
         }
     }
@@ -403,6 +998,11 @@ impl MemoryMappedFile {
     ///
     /// Returns `MmapIoError::FlushFailed` if flush operation fails.
     pub fn flush(&self) -> Result<()> {
+        let res = self.flush_impl();
+        self.observed(MmapOperation::Flush, res)
+    }
+
+    fn flush_impl(&self) -> Result<()> {
         match &self.inner.map {
             MapVariant::Ro(_) => Ok(()),
             MapVariant::Cow(_) => Ok(()), // no-op for COW
@@ -412,28 +1012,165 @@ impl MemoryMappedFile {
                     return Ok(());
                 }
 
-                // Platform-optimized path: Linux MS_ASYNC best-effort
-                #[cfg(all(unix, target_os = "linux"))]
-                {
-                    if let Ok(len) = self.current_len() {
-                        if len > 0 {
-                            if self.try_linux_async_flush(len as usize)? {
-                                return Ok(());
-                            }
-                        }
-                    }
+                #[cfg(feature = "metrics")]
+                let started = std::time::Instant::now();
+                self.flush_rw(lock)?;
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_flush(&self.inner.path, started.elapsed());
+                let len = self.current_len()?;
+                self.observe(|o| o.on_flush(0, len));
+                Ok(())
+            }
+        }
+    }
+
+    // Flush the whole RW view once we know there are pending writes.
+    fn flush_rw(&self, lock: &RwLock<RwMap>) -> Result<()> {
+        // Platform-optimized path: Linux MS_ASYNC best-effort
+        #[cfg(all(unix, target_os = "linux"))]
+        if !self.inner.strict_flush {
+            if let Ok(len) = self.current_len() {
+                if len > 0 && self.try_linux_async_flush(len as usize)? {
+                    return Ok(());
                 }
+            }
+        }
 
-                // Fallback/full flush using memmap2 API
-                let guard = lock.read();
-                guard
-                    .flush()
-                    .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
-                // Reset accumulator after a successful flush
-                *self.inner.written_since_last_flush.write() = 0;
-                Ok(())
+        // Fallback/full flush using memmap2 API
+        let guard = lock.read();
+        guard
+            .flush()
+            .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
+        self.sync_strict()?;
+        // Reset accumulator after a successful flush
+        self.reset_pending();
+        Ok(())
+    }
+
+    /// Flush all pages synchronously and sync the file's data and metadata to storage.
+    ///
+    /// Unlike [`flush`](Self::flush), which may only schedule write-back, this waits for the
+    /// pages to be written and then `fsync`s the file (`FlushFileBuffers` on Windows), so a
+    /// length change from [`resize`](Self::resize) is durable too. Always performs I/O, even
+    /// without pending writes. For read-only or COW mappings, this is a no-op. See the
+    /// [`flush`](crate::flush) module for the guarantees of each level.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::FlushFailed` if writing the pages or syncing the file fails.
+    pub fn flush_and_sync_metadata(&self) -> Result<()> {
+        let res = self.flush_and_sync_metadata_impl();
+        self.observed(MmapOperation::Flush, res)
+    }
+
+    fn flush_and_sync_metadata_impl(&self) -> Result<()> {
+        let MapVariant::Rw(lock) = &self.inner.map else {
+            return Ok(());
+        };
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        lock.read()
+            .flush()
+            .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
+        self.inner
+            .file
+            .sync_all()
+            .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
+        self.reset_pending();
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_flush(&self.inner.path, started.elapsed());
+        let len = self.current_len()?;
+        self.observe(|o| o.on_flush(0, len));
+        Ok(())
+    }
+
+    /// Like [`flush_and_sync_metadata`](Self::flush_and_sync_metadata), but writes the pages
+    /// back from up to `concurrency` threads before the final `fsync`.
+    ///
+    /// The mapping is split into page-aligned ranges of equal size, each flushed
+    /// synchronously (`msync(MS_SYNC)` / `FlushViewOfFile`) on its own thread, so write-back
+    /// of a very large dirty mapping is not limited by a single thread. A `concurrency` of
+    /// 0 or 1 flushes on the calling thread. For read-only or COW mappings, this is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::FlushFailed` if any range fails to flush or the file fails to
+    /// sync; the `fsync` is skipped if a range failed.
+    pub fn flush_parallel(&self, concurrency: usize) -> Result<()> {
+        let res = self.flush_parallel_impl(concurrency);
+        self.observed(MmapOperation::Flush, res)
+    }
+
+    fn flush_parallel_impl(&self, concurrency: usize) -> Result<()> {
+        let MapVariant::Rw(lock) = &self.inner.map else {
+            return Ok(());
+        };
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        {
+            let guard = lock.read();
+            let map: &RwMap = &guard;
+            let len = map.len();
+            let page = crate::utils::page_size();
+            let pages = len.div_ceil(page);
+            let workers = concurrency.clamp(1, pages.max(1));
+            let per_worker = pages.div_ceil(workers) * page;
+            let flush = |start: usize| -> Result<()> {
+                let n = per_worker.min(len - start);
+                map.flush_range(start, n)
+                    .map_err(|e| MmapIoError::FlushFailed(e.to_string()))
+            };
+            if workers == 1 {
+                flush(0)?;
+            } else {
+                std::thread::scope(|scope| {
+                    let handles: Vec<_> = (0..len)
+                        .step_by(per_worker)
+                        .map(|start| scope.spawn(move || flush(start)))
+                        .collect();
+                    handles.into_iter().try_for_each(|handle| {
+                        handle.join().unwrap_or_else(|_| {
+                            Err(MmapIoError::FlushFailed("flush thread panicked".into()))
+                        })
+                    })
+                })?;
             }
         }
+        self.inner
+            .file
+            .sync_all()
+            .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
+        self.reset_pending();
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_flush(&self.inner.path, started.elapsed());
+        let len = self.current_len()?;
+        self.observe(|o| o.on_flush(0, len));
+        Ok(())
+    }
+
+    /// Make the directory entry of the mapped file durable by syncing its parent directory.
+    ///
+    /// Needed once after creating a file whose existence must survive a power loss; syncing
+    /// the file itself does not persist its name. A no-op on Windows.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::FlushFailed` if the directory cannot be opened or synced.
+    pub fn sync_parent_directory(&self) -> Result<()> {
+        #[cfg(unix)]
+        {
+            let parent = match self.inner.path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            File::open(parent)
+                .and_then(|dir| dir.sync_all())
+                .map_err(|e| MmapIoError::FlushFailed(format!("{}: {e}", parent.display())))
+        }
+        #[cfg(not(unix))]
+        {
+            Ok(())
+        }
     }
 
     /// Async flush changes to disk. For read-only or COW mappings, this is a no-op.
@@ -456,17 +1193,47 @@ impl MemoryMappedFile {
             .map_err(|e| MmapIoError::FlushFailed(format!("join error: {e}")))?
     }
 
-    /// Flush a specific byte range to disk.
+    /// Close this handle from async code: finish any access recording, flush and `fsync`
+    /// on the blocking pool, then drop the handle there.
     ///
-    /// Smart internal guards:
-    /// - Skip I/O when there are no pending writes in accumulator
-    /// - On Linux, prefer msync(MS_ASYNC) for the range; fall back to full range flush on error
+    /// Dropping a mapping never flushes and cannot report errors, and doing the final
+    /// sync inline would block the runtime. Other clones of the mapping stay usable; the
+    /// mapping is unmapped once the last one is gone. For read-only or COW mappings only
+    /// the unmap happens.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from finishing the recording or
+    /// [`flush_and_sync_metadata`](Self::flush_and_sync_metadata), or
+    /// `MmapIoError::FlushFailed` if the blocking task panicked.
+    #[cfg(feature = "async")]
+    pub async fn close_async(self) -> Result<()> {
+        tokio::task::spawn_blocking(move || {
+            self.stop_recording()?;
+            self.flush_and_sync_metadata()?;
+            drop(self);
+            Ok(())
+        })
+        .await
+        .map_err(|e| MmapIoError::FlushFailed(format!("join error: {e}")))?
+    }
+
+    /// Flush a specific byte range to disk.
+    ///
+    /// Smart internal guards:
+    /// - Skip I/O when there are no pending writes in accumulator
+    /// - On Linux, prefer msync(MS_ASYNC) for the range; fall back to full range flush on error
     ///
     /// # Errors
     ///
     /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
     /// Returns `MmapIoError::FlushFailed` if flush operation fails.
     pub fn flush_range(&self, offset: u64, len: u64) -> Result<()> {
+        let res = self.flush_range_impl(offset, len);
+        self.observed(MmapOperation::Flush, res)
+    }
+
+    fn flush_range_impl(&self, offset: u64, len: u64) -> Result<()> {
         if len == 0 {
             return Ok(());
         }
@@ -481,38 +1248,246 @@ impl MemoryMappedFile {
                 }
 
                 let (start, end) = slice_range(offset, len, self.current_len()?)?;
-                let range_len = end - start;
+                #[cfg(feature = "metrics")]
+                let started = std::time::Instant::now();
+                self.flush_range_rw(lock, start, end - start)?;
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_flush(&self.inner.path, started.elapsed());
+                self.observe(|o| o.on_flush(offset, len));
+                Ok(())
+            }
+        }
+    }
+
+    /// Flush the whole mapping with explicit [`FlushOptions`].
+    ///
+    /// Without any option set this is [`flush`](Self::flush). With
+    /// [`invalidate`](FlushOptions::invalidate), other mappings of the file (including
+    /// read-only ones in other processes) are made to observe the written data; this is
+    /// also accepted on read-only mappings. A no-op for COW mappings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::FlushFailed` if flush operation fails.
+    pub fn flush_with(&self, options: FlushOptions) -> Result<()> {
+        if !options.invalidate {
+            return self.flush();
+        }
+        let res = self
+            .current_len()
+            .and_then(|len| self.flush_invalidate_impl(0, len));
+        self.observed(MmapOperation::Flush, res)
+    }
+
+    /// Flush a specific byte range with explicit [`FlushOptions`].
+    ///
+    /// Without any option set this is [`flush_range`](Self::flush_range); see
+    /// [`flush_with`](Self::flush_with) for [`invalidate`](FlushOptions::invalidate).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
+    /// Returns `MmapIoError::FlushFailed` if flush operation fails.
+    pub fn flush_range_with(&self, offset: u64, len: u64, options: FlushOptions) -> Result<()> {
+        if !options.invalidate {
+            return self.flush_range(offset, len);
+        }
+        let res = self.flush_invalidate_impl(offset, len);
+        self.observed(MmapOperation::Flush, res)
+    }
 
-                // Linux MS_ASYNC optimization
-                #[cfg(all(unix, target_os = "linux"))]
+    // Flush `[offset, offset + len)` and invalidate other mappings of it, regardless of
+    // the pending-write accumulator.
+    fn flush_invalidate_impl(&self, offset: u64, len: u64) -> Result<()> {
+        let (start, end) = slice_range(offset, len, self.current_len()?)?;
+        if start == end {
+            return Ok(());
+        }
+        match &self.inner.map {
+            MapVariant::Cow(_) => Ok(()),
+            #[cfg(unix)]
+            MapVariant::Ro(m) => msync_invalidate(m.as_ptr(), start, end, false),
+            #[cfg(not(unix))]
+            MapVariant::Ro(_) => Ok(()),
+            MapVariant::Rw(lock) => {
+                #[cfg(feature = "metrics")]
+                let started = std::time::Instant::now();
                 {
-                    // SAFETY: msync on a valid mapped range. We translate to a pointer within the map.
-                    let msync_res: i32 = {
-                        let guard = lock.read();
-                        let base = guard.as_ptr();
-                        let ptr = unsafe { base.add(start) } as *mut libc::c_void;
-                        let ret = unsafe { libc::msync(ptr, range_len, libc::MS_ASYNC) };
-                        ret
-                    };
-                    if msync_res == 0 {
-                        // Consider MS_ASYNC success and reset accumulator
-                        *self.inner.written_since_last_flush.write() = 0;
-                        return Ok(());
+                    let guard = lock.read();
+                    #[cfg(unix)]
+                    {
+                        // Match `flush`: schedule write-back on Linux unless strict
+                        let sync = self.inner.strict_flush || !cfg!(target_os = "linux");
+                        msync_invalidate(guard.as_ptr(), start, end, sync)?;
                     }
-                    // else fall through to full flush_range
+                    #[cfg(not(unix))]
+                    guard
+                        .flush_range(start, end - start)
+                        .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
                 }
+                self.sync_strict()?;
+                self.reset_pending_range(offset, len);
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_flush(&self.inner.path, started.elapsed());
+                self.observe(|o| o.on_flush(offset, len));
+                Ok(())
+            }
+        }
+    }
+
+    /// Page-aligned `(offset, len)` runs written since they were last flushed, clamped to
+    /// the mapping length.
+    ///
+    /// Covers writes through `update_region` and the other copying write methods,
+    /// streaming writes, and split parts. Writes through mutable slices, iterators, atomics,
+    /// and raw pointers are not seen; flush those with [`flush`](Self::flush).
+    #[must_use]
+    pub fn dirty_ranges(&self) -> Vec<(u64, u64)> {
+        let total = *self.inner.cached_len.read();
+        self.inner.dirty.lock().ranges(total)
+    }
+
+    /// Flush only the pages in [`dirty_ranges`](Self::dirty_ranges), one ranged flush per
+    /// run, instead of the whole mapping. Returns the number of flush calls issued.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::FlushFailed` if a flush operation fails; runs flushed before
+    /// the failure are no longer dirty.
+    pub fn flush_dirty(&self) -> Result<usize> {
+        let res = self.flush_dirty_impl();
+        self.observed(MmapOperation::Flush, res)
+    }
+
+    fn flush_dirty_impl(&self) -> Result<usize> {
+        let MapVariant::Rw(lock) = &self.inner.map else {
+            return Ok(0);
+        };
+        let total = self.current_len()?;
+        let spans = self.dirty_ranges();
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        for &(offset, len) in &spans {
+            let (start, end) = slice_range(offset, len, total)?;
+            self.flush_range_rw(lock, start, end - start)?;
+        }
+        #[cfg(feature = "metrics")]
+        if !spans.is_empty() {
+            crate::metrics::record_flush(&self.inner.path, started.elapsed());
+        }
+        for &(offset, len) in &spans {
+            self.observe(|o| o.on_flush(offset, len));
+        }
+        Ok(spans.len())
+    }
+
+    /// Flush many byte ranges with as few syscalls as possible.
+    ///
+    /// `ranges` are `(offset, len)` pairs, e.g. the records touched by a commit. They are
+    /// rounded out to page boundaries, sorted, and merged where they overlap or touch. If
+    /// more than `max_syscalls` spans remain, the spans separated by the smallest gaps are
+    /// joined until the limit holds (a value of 0 is treated as 1). Returns the number of
+    /// flush calls issued.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if any range exceeds file bounds.
+    /// Returns `MmapIoError::FlushFailed` if a flush operation fails.
+    pub fn flush_ranges(&self, ranges: &[(u64, u64)], max_syscalls: usize) -> Result<usize> {
+        let res = self.flush_ranges_impl(ranges, max_syscalls);
+        self.observed(MmapOperation::Flush, res)
+    }
+
+    fn flush_ranges_impl(&self, ranges: &[(u64, u64)], max_syscalls: usize) -> Result<usize> {
+        let total = self.current_len()?;
+        for &(offset, len) in ranges {
+            ensure_in_bounds(offset, len, total)?;
+        }
+        let MapVariant::Rw(lock) = &self.inner.map else {
+            return Ok(0);
+        };
+        if *self.inner.written_since_last_flush.read() == 0 {
+            return Ok(0);
+        }
+        let spans = crate::flush::coalesce_ranges(
+            ranges,
+            crate::utils::page_size() as u64,
+            total,
+            max_syscalls,
+        );
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        for &(offset, len) in &spans {
+            let (start, end) = slice_range(offset, len, total)?;
+            self.flush_range_rw(lock, start, end - start)?;
+        }
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_flush(&self.inner.path, started.elapsed());
+        for &(offset, len) in &spans {
+            self.observe(|o| o.on_flush(offset, len));
+        }
+        Ok(spans.len())
+    }
 
+    // Flush `[start, start + range_len)` of the RW view once we know there are pending writes.
+    fn flush_range_rw(&self, lock: &RwLock<RwMap>, start: usize, range_len: usize) -> Result<()> {
+        // Linux MS_ASYNC optimization
+        #[cfg(all(unix, target_os = "linux"))]
+        if !self.inner.strict_flush {
+            // SAFETY: msync on a valid mapped range. We translate to a pointer within the map.
+            let msync_res: i32 = {
                 let guard = lock.read();
-                guard
-                    .flush_range(start, range_len)
-                    .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
-                // Reset accumulator after a successful flush
-                *self.inner.written_since_last_flush.write() = 0;
-                Ok(())
+                let base = guard.as_ptr();
+                let (ptr, len) = crate::utils::page_span(base.wrapping_add(start), range_len);
+                unsafe { libc::msync(ptr.cast(), len, libc::MS_ASYNC) }
+            };
+            if msync_res == 0 {
+                // Consider MS_ASYNC success and reset accumulator
+                self.reset_pending_range(start as u64, range_len as u64);
+                return Ok(());
             }
+            // else fall through to full flush_range
+        }
+
+        let guard = lock.read();
+        guard
+            .flush_range(start, range_len)
+            .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
+        self.sync_strict()?;
+        // Reset accumulator after a successful flush
+        self.reset_pending_range(start as u64, range_len as u64);
+        Ok(())
+    }
+
+    // After flushing the whole mapping: nothing is pending any more.
+    fn reset_pending(&self) {
+        *self.inner.written_since_last_flush.write() = 0;
+        self.inner.dirty.lock().clear_all();
+    }
+
+    // After flushing `[offset, offset + len)`: writes outside the range stay pending, so
+    // a later `flush()` is not skipped.
+    fn reset_pending_range(&self, offset: u64, len: u64) {
+        let mut dirty = self.inner.dirty.lock();
+        dirty.clear(offset, len);
+        if dirty.is_empty() {
+            *self.inner.written_since_last_flush.write() = 0;
         }
     }
 
+    // In strict mode on Windows, push the file's cached data to the device after
+    // FlushViewOfFile; Unix msync(MS_SYNC) already waits for the write.
+    fn sync_strict(&self) -> Result<()> {
+        #[cfg(windows)]
+        if self.inner.strict_flush {
+            self.inner
+                .file
+                .sync_data()
+                .map_err(|e| MmapIoError::FlushFailed(e.to_string()))?;
+        }
+        Ok(())
+    }
+
     /// Resize (grow or shrink) the mapped file (RW only). This remaps the file internally.
     ///
     /// # Performance
@@ -524,25 +1499,38 @@ impl MemoryMappedFile {
     ///
     /// # Errors
     ///
-    /// Returns `MmapIoError::InvalidMode` if not in `ReadWrite` mode.
+    /// Returns `MmapIoError::InvalidMode` if not in `ReadWrite` mode or if the mapping is a
+    /// file window (see [`MemoryMappedFileBuilder::offset`]).
     /// Returns `MmapIoError::ResizeFailed` if new size is zero or exceeds the maximum safe limit.
+    /// Returns `MmapIoError::InsufficientSpace` if the mapping was built with
+    /// [`check_free_space`](MemoryMappedFileBuilder::check_free_space) and the disk cannot
+    /// back the grown file.
     /// Returns `MmapIoError::Io` if resize operation fails.
     pub fn resize(&self, new_size: u64) -> Result<()> {
+        let res = self.resize_impl(new_size);
+        self.observed(MmapOperation::Resize, res)
+    }
+
+    fn resize_impl(&self, new_size: u64) -> Result<()> {
         if self.inner.mode != MmapMode::ReadWrite {
             return Err(MmapIoError::InvalidMode("Resize requires ReadWrite mode"));
         }
+        if self.inner.window_offset.is_some() {
+            return Err(MmapIoError::InvalidMode(
+                "a mapped file window cannot be resized",
+            ));
+        }
         if new_size == 0 {
             return Err(MmapIoError::ResizeFailed(
                 "New size must be greater than zero".into(),
             ));
         }
-        if new_size > MAX_MMAP_SIZE {
-            return Err(MmapIoError::ResizeFailed(format!(
-                "New size {new_size} exceeds maximum safe limit of {MAX_MMAP_SIZE} bytes"
-            )));
-        }
+        ensure_within_limit(new_size, self.inner.size_limit)?;
 
         let current = self.current_len()?;
+        if self.inner.split_parts.load(Ordering::Acquire) > 0 {
+            return Err(MmapIoError::ResizeFailed(ERR_SPLIT_ACTIVE.into()));
+        }
 
         // On Windows, shrinking a file with an active mapping fails with:
         // "The requested operation cannot be performed on a file with a user-mapped section open."
@@ -553,8 +1541,14 @@ impl MemoryMappedFile {
             use std::cmp::Ordering;
             match new_size.cmp(&current) {
                 Ordering::Less => {
+                    if let MapVariant::Rw(lock) = &self.inner.map {
+                        if self.inner.scrub_on_shrink {
+                            scrub_range(&mut lock.write(), new_size, current)?;
+                        }
+                    }
                     // Virtually shrink: only update the cached length.
                     *self.inner.cached_len.write() = new_size;
+                    self.observe(|o| o.on_resize(current, new_size));
                     return Ok(());
                 }
                 Ordering::Equal => {
@@ -566,13 +1560,6 @@ impl MemoryMappedFile {
             }
         }
 
-        // Update length on disk for non-windows, or for growing on windows.
-        // Silence unused variable warning when the Windows shrink early-return path is compiled.
-        let _ = &current;
-        self.inner.file.set_len(new_size)?;
-
-        // Remap with the new size.
-        let new_map = unsafe { MmapMut::map_mut(&self.inner.file)? };
         match &self.inner.map {
             MapVariant::Ro(_) => Err(MmapIoError::InvalidMode(
                 "Cannot remap read-only mapping as read-write",
@@ -581,10 +1568,36 @@ impl MemoryMappedFile {
                 "resize not supported on copy-on-write mapping",
             )),
             MapVariant::Rw(lock) => {
-                let mut guard = lock.write();
-                *guard = new_map;
-                // Update cached length
-                *self.inner.cached_len.write() = new_size;
+                {
+                    let mut guard = lock.write();
+                    // Checked under the lock so no split can start mid-resize
+                    if self.inner.split_parts.load(Ordering::Acquire) > 0 {
+                        return Err(MmapIoError::ResizeFailed(ERR_SPLIT_ACTIVE.into()));
+                    }
+                    if self.inner.scrub_on_shrink && new_size < current {
+                        scrub_range(&mut guard, new_size, current)?;
+                    }
+                    if self.inner.check_free_space && new_size > current {
+                        ensure_free_space(&self.inner.file, &self.inner.path, new_size)?;
+                    }
+                    // Update length on disk for non-windows, or for growing on windows.
+                    self.inner.file.set_len(new_size)?;
+                    // Remap with the new size (fixed-address views stay in place).
+                    if let Err(err) = guard.remap(&self.inner.file, new_size) {
+                        // A fixed view that could not be restored is left empty; never let
+                        // the cached length claim more than the view still holds
+                        let mut cached = self.inner.cached_len.write();
+                        *cached = (*cached).min(guard.len() as u64);
+                        return Err(err);
+                    }
+                    apply_fork_behavior(guard.as_ptr(), guard.len(), self.inner.fork_behavior)?;
+                    // Update cached length
+                    *self.inner.cached_len.write() = new_size;
+                }
+                if let Some(tracking) = &self.inner.tracking {
+                    tracking.set_len(new_size);
+                }
+                self.observe(|o| o.on_resize(current, new_size));
                 Ok(())
             }
         }
@@ -610,14 +1623,14 @@ impl MemoryMappedFile {
         match &self.inner.map {
             MapVariant::Rw(lock) => {
                 let guard = lock.read();
-                let ptr = guard.as_ptr() as *mut libc::c_void;
+                let (ptr, len) = crate::utils::page_span(guard.as_ptr(), len);
 
                 // SAFETY: msync requires a valid mapping address/len; memmap2 handles mapping
-                let ret = unsafe { libc::msync(ptr, len, libc::MS_ASYNC) };
+                let ret = unsafe { libc::msync(ptr.cast(), len, libc::MS_ASYNC) };
 
                 if ret == 0 {
                     // MS_ASYNC succeeded, reset accumulator
-                    *self.inner.written_since_last_flush.write() = 0;
+                    self.reset_pending();
                     Ok(true)
                 } else {
                     // Fall back to full flush
@@ -646,7 +1659,7 @@ fn map_mut_with_options(file: &File, len: u64, huge: bool) -> Result<MmapMut> {
     #[cfg(all(unix, target_os = "linux"))]
     {
         // Create the standard mapping first
-        let mmap = unsafe { MmapMut::map_mut(file) }.map_err(|e| MmapIoError::Io(e.into()))?;
+        let mmap = unsafe { MmapMut::map_mut(file) }.map_err(MmapIoError::Io)?;
 
         if huge {
             // Request Transparent Huge Pages (THP) for this mapping
@@ -674,61 +1687,397 @@ fn map_mut_with_options(file: &File, len: u64, huge: bool) -> Result<MmapMut> {
     {
         // Huge pages are Linux-specific, ignore the flag on other platforms
         let _ = (len, huge);
-        unsafe { MmapMut::map_mut(file) }.map_err(|e| MmapIoError::Io(e.into()))
+        unsafe { MmapMut::map_mut(file) }.map_err(MmapIoError::Io)
+    }
+}
+
+impl MemoryMappedFile {
+    /// Fork behavior this mapping was created with.
+    #[must_use]
+    pub fn fork_behavior(&self) -> ForkBehavior {
+        self.inner.fork_behavior
+    }
+
+    /// File offset of the mapping's first byte: the builder's
+    /// [`offset`](MemoryMappedFileBuilder::offset) for a file window, otherwise 0.
+    #[must_use]
+    pub fn file_offset(&self) -> u64 {
+        self.inner.window_offset.unwrap_or(0)
+    }
+
+    fn apply_fork_behavior(&self) -> Result<()> {
+        if self.inner.fork_behavior == ForkBehavior::Inherit {
+            return Ok(());
+        }
+        match &self.inner.map {
+            MapVariant::Ro(m) => apply_fork_behavior(m.as_ptr(), m.len(), self.inner.fork_behavior),
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
+                let guard = lock.read();
+                apply_fork_behavior(guard.as_ptr(), guard.len(), self.inner.fork_behavior)
+            }
+        }
     }
 }
 
+// madvise the whole mapping with the requested fork inheritance.
+fn apply_fork_behavior(ptr: *const u8, len: usize, behavior: ForkBehavior) -> Result<()> {
+    if behavior == ForkBehavior::Inherit || len == 0 {
+        return Ok(());
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let advice = match behavior {
+            ForkBehavior::Inherit => return Ok(()),
+            ForkBehavior::DontFork => libc::MADV_DONTFORK,
+            ForkBehavior::WipeOnFork => libc::MADV_WIPEONFORK,
+        };
+        let (ptr, len) = crate::utils::page_span(ptr, len);
+        // SAFETY: [ptr, ptr + len) is a live mapping rounded out to the page it starts on.
+        let rc = unsafe { libc::madvise(ptr.cast(), len, advice) };
+        if rc != 0 {
+            return Err(MmapIoError::Io(std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+    #[cfg(windows)]
+    {
+        // No fork() on Windows; child processes never inherit views.
+        let _ = ptr;
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        let _ = ptr;
+        Err(MmapIoError::InvalidMode(
+            "fork behavior other than Inherit requires Linux",
+        ))
+    }
+}
+
+/// Map `file` read-only from `offset` (optionally only `len` bytes), prefaulting all pages
+/// when `populate` is set.
+fn map_readonly(file: &File, offset: u64, len: Option<usize>, populate: bool) -> Result<Mmap> {
+    let mut opts = MmapOptions::new();
+    opts.offset(offset);
+    if let Some(len) = len {
+        opts.len(len);
+    }
+    if populate {
+        // MAP_POPULATE on Linux; ignored by memmap2 elsewhere
+        opts.populate();
+    }
+    // SAFETY: memmap2 handles platform specifics; the mapping is never written through.
+    let mmap = unsafe { opts.map(file)? };
+    #[cfg(not(target_os = "linux"))]
+    if populate {
+        prefault(&mmap);
+    }
+    Ok(mmap)
+}
+
+// Private writable mapping of `len` bytes of `file` from `offset` (MAP_PRIVATE / WRITECOPY):
+// writes stay in this process's copy of the pages and never reach the file.
+#[cfg(feature = "cow")]
+pub(crate) fn map_private(file: &File, offset: u64, len: usize, populate: bool) -> Result<RwMap> {
+    let mut opts = MmapOptions::new();
+    opts.offset(offset).len(len);
+    if populate {
+        opts.populate();
+    }
+    // SAFETY: memmap2 handles platform specifics; a private mapping only needs read access
+    // to the file, and writes through it are never carried back.
+    let mmap = unsafe { opts.map_copy(file)? };
+    #[cfg(not(target_os = "linux"))]
+    if populate {
+        prefault(&mmap);
+    }
+    Ok(RwMap::Std(mmap))
+}
+
+// Touch one byte per page so the OS faults the whole mapping in up front.
+#[cfg(not(target_os = "linux"))]
+fn prefault(bytes: &[u8]) {
+    let step = crate::utils::page_size().max(1);
+    let mut sum = 0u8;
+    for byte in bytes.iter().step_by(step) {
+        // SAFETY: `byte` is a valid reference; volatile keeps the read from being optimized away.
+        sum = sum.wrapping_add(unsafe { std::ptr::read_volatile(byte) });
+    }
+    std::hint::black_box(sum);
+}
+
 #[cfg(feature = "cow")]
 impl MemoryMappedFile {
     /// Open an existing file and memory-map it copy-on-write (private).
     /// Changes through this mapping are visible only within this process; the underlying file remains unchanged.
+    ///
+    /// Writes go through the same APIs as for `ReadWrite` mappings (`update_region`,
+    /// `as_slice_mut`, mutable iterators); flushing is a no-op. Pages are copied on the
+    /// first write to each, and other processes' changes to the file stay visible only on
+    /// pages not yet written here.
     pub fn open_cow<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path_ref = path.as_ref();
-        let file = OpenOptions::new().read(true).open(path_ref)?;
+        let file = OpenOptions::new().read(true).open(os_path(path_ref))?;
         let len = file.metadata()?.len();
+        ensure_mappable(len, None)?;
         if len == 0 {
             return Err(MmapIoError::ResizeFailed(ERR_ZERO_LENGTH_FILE.into()));
         }
-        // SAFETY: memmap2 handles platform specifics. We request a private (copy-on-write) mapping.
-        let mmap = unsafe {
-            let mut opts = MmapOptions::new();
-            opts.len(len as usize);
-            #[cfg(unix)]
-            {
-                // memmap2 currently does not expose a stable .private() on all Rust/MSRV combos.
-                // On Unix, map() of a read-only file yields an immutable mapping; for COW semantics
-                // we rely on platform-specific behavior when writing is disallowed here in phase-1.
-                // When writable COW is introduced, we will use platform flags via memmap2 internals.
-                opts.map(&file)?
-            }
-            #[cfg(not(unix))]
-            {
-                // On Windows, memmap2 maps with appropriate WRITECOPY semantics internally for private mappings.
-                opts.map(&file)?
-            }
-        };
+        let mmap = map_private(&file, 0, len as usize, false)?;
         let inner = Inner {
             path: path_ref.to_path_buf(),
             file,
             mode: MmapMode::CopyOnWrite,
             cached_len: RwLock::new(len),
-            map: MapVariant::Cow(mmap),
-            // COW never flushes underlying file in phase-1
+            map: MapVariant::Cow(RwLock::new(mmap)),
+            // Private pages never reach the file
             flush_policy: FlushPolicy::Never,
             written_since_last_flush: RwLock::new(0),
             #[cfg(feature = "hugepages")]
             huge_pages: false,
+            observer: RwLock::new(None),
+            fork_behavior: ForkBehavior::Inherit,
+            size_limit: None,
+            tracking: None,
+            strict_flush: false,
+            recorder: RwLock::new(None),
+            split_parts: AtomicUsize::new(0),
+            scrub_on_shrink: false,
+            check_free_space: false,
+            guarded: None,
+            window_offset: None,
+            auto_grow: None,
+            interval_flusher: None,
+            dirty: Mutex::default(),
         };
-        Ok(Self {
-            inner: Arc::new(inner),
-        })
+        Self::from_inner(inner)
+    }
+}
+
+#[cfg(feature = "exec")]
+impl MemoryMappedFile {
+    /// Open an existing file and map it read + execute (e.g. a prebuilt code blob).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if the file cannot be opened or mapped (some systems
+    /// forbid executable mappings of files on `noexec` mounts).
+    pub fn open_exec<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path_ref = path.as_ref();
+        let file = OpenOptions::new().read(true).open(os_path(path_ref))?;
+        let len = file.metadata()?.len();
+        ensure_mappable(len, None)?;
+        if len == 0 {
+            return Err(MmapIoError::ResizeFailed(ERR_ZERO_LENGTH_FILE.into()));
+        }
+        // SAFETY: memmap2 handles platform specifics; the mapping is never writable.
+        let mmap = unsafe { MmapOptions::new().len(len as usize).map_exec(&file)? };
+        flush_icache(mmap.as_ptr(), mmap.len());
+        let inner = Inner {
+            path: path_ref.to_path_buf(),
+            file,
+            mode: MmapMode::ReadExecute,
+            cached_len: RwLock::new(len),
+            map: MapVariant::Ro(mmap),
+            flush_policy: FlushPolicy::Never,
+            written_since_last_flush: RwLock::new(0),
+            #[cfg(feature = "hugepages")]
+            huge_pages: false,
+            observer: RwLock::new(None),
+            fork_behavior: ForkBehavior::Inherit,
+            size_limit: None,
+            tracking: None,
+            strict_flush: false,
+            recorder: RwLock::new(None),
+            split_parts: AtomicUsize::new(0),
+            scrub_on_shrink: false,
+            check_free_space: false,
+            guarded: None,
+            window_offset: None,
+            auto_grow: None,
+            interval_flusher: None,
+            dirty: Mutex::default(),
+        };
+        Self::from_inner(inner)
+    }
+
+    /// Turn a `ReadWrite` mapping into a `ReadExecute` one (W^X transition).
+    ///
+    /// Pending writes are flushed, the pages are re-protected read + execute, and the
+    /// instruction cache is synchronized on architectures that need it (ARM). The mapping
+    /// is never writable and executable at the same time.
+    ///
+    /// This handle must be the only clone of the mapping, since other clones could
+    /// otherwise keep writing to now-executable pages. On error the handle is consumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the mapping is not `ReadWrite` or is shared.
+    /// Returns `MmapIoError::Io` if flushing or changing the protection fails.
+    pub fn make_executable(self) -> Result<Self> {
+        if self.inner.mode != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(
+                "make_executable requires ReadWrite mode",
+            ));
+        }
+        self.flush()?;
+        let inner =
+            self.try_into_inner("make_executable requires the only handle to the mapping")?;
+        let Inner {
+            path,
+            file,
+            cached_len,
+            map,
+            #[cfg(feature = "hugepages")]
+            huge_pages,
+            observer,
+            fork_behavior,
+            size_limit,
+            tracking,
+            recorder,
+            ..
+        } = inner;
+        let MapVariant::Rw(lock) = map else {
+            return Err(MmapIoError::InvalidMode(
+                "make_executable requires ReadWrite mode",
+            ));
+        };
+        let RwMap::Std(mmap) = lock.into_inner() else {
+            return Err(MmapIoError::InvalidMode(
+                "make_executable is not supported for fixed-address mappings",
+            ));
+        };
+        let mmap = mmap.make_exec()?;
+        flush_icache(mmap.as_ptr(), mmap.len());
+        let inner = Inner {
+            path,
+            file,
+            mode: MmapMode::ReadExecute,
+            cached_len,
+            map: MapVariant::Ro(mmap),
+            flush_policy: FlushPolicy::Never,
+            written_since_last_flush: RwLock::new(0),
+            #[cfg(feature = "hugepages")]
+            huge_pages,
+            observer,
+            fork_behavior,
+            size_limit,
+            tracking,
+            strict_flush: false,
+            recorder,
+            split_parts: AtomicUsize::new(0),
+            scrub_on_shrink: false,
+            check_free_space: false,
+            guarded: None,
+            window_offset: None,
+            auto_grow: None,
+            interval_flusher: None,
+            dirty: Mutex::default(),
+        };
+        Self::from_inner(inner)
+    }
+
+    /// Pointer to executable code at `offset` in a `ReadExecute` mapping.
+    ///
+    /// Casting the pointer to a function type and calling it is up to the caller and is
+    /// only sound if the bytes there are valid code for the target ABI.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the mapping is not `ReadExecute`.
+    /// Returns `MmapIoError::OutOfBounds` if `offset` is past the end of the mapping.
+    pub fn exec_ptr(&self, offset: u64) -> Result<*const u8> {
+        if self.inner.mode != MmapMode::ReadExecute {
+            return Err(MmapIoError::InvalidMode(
+                "exec_ptr requires ReadExecute mode",
+            ));
+        }
+        let (start, _) = slice_range(offset, 1, self.current_len()?)?;
+        match &self.inner.map {
+            // SAFETY: `start` is within the mapping per slice_range.
+            MapVariant::Ro(m) => Ok(unsafe { m.as_ptr().add(start) }),
+            _ => Err(MmapIoError::InvalidMode(
+                "exec_ptr requires ReadExecute mode",
+            )),
+        }
+    }
+}
+
+// Zero `[from, to)` of a RW view and write it back synchronously, so the bytes a shrink
+// releases are overwritten on disk before the file is truncated.
+fn scrub_range(map: &mut RwMap, from: u64, to: u64) -> Result<()> {
+    let (start, end) = (from as usize, to as usize);
+    map[start..end].fill(0);
+    map.flush_range(start, end - start)
+        .map_err(|e| MmapIoError::ResizeFailed(format!("scrubbing released bytes failed: {e}")))
+}
+
+// msync `[start, end)` of the mapping at `base` with MS_INVALIDATE, rounding the start
+// down to a page boundary as msync requires.
+#[cfg(unix)]
+fn msync_invalidate(base: *const u8, start: usize, end: usize, sync: bool) -> Result<()> {
+    let (addr, len) = crate::utils::page_span(base.wrapping_add(start), end - start);
+    let mode = if sync { libc::MS_SYNC } else { libc::MS_ASYNC };
+    // SAFETY: `base` is the start of a live mapping covering `[start, end)`, and the page
+    // holding `start` is mapped in full.
+    let rc = unsafe { libc::msync(addr.cast(), len, mode | libc::MS_INVALIDATE) };
+    if rc != 0 {
+        return Err(MmapIoError::FlushFailed(format!(
+            "msync(MS_INVALIDATE) failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+// Make freshly written code visible to instruction fetch. x86 keeps caches coherent;
+// ARM needs an explicit clean/invalidate.
+#[cfg(feature = "exec")]
+fn flush_icache(ptr: *const u8, len: usize) {
+    #[cfg(all(any(target_arch = "aarch64", target_arch = "arm"), not(windows)))]
+    {
+        extern "C" {
+            fn __clear_cache(start: *mut std::ffi::c_void, end: *mut std::ffi::c_void);
+        }
+        // SAFETY: [ptr, ptr + len) is a live mapping owned by the caller.
+        unsafe {
+            __clear_cache(
+                ptr as *mut std::ffi::c_void,
+                ptr.add(len) as *mut std::ffi::c_void,
+            );
+        }
+    }
+    #[cfg(windows)]
+    {
+        extern "system" {
+            fn GetCurrentProcess() -> *mut std::ffi::c_void;
+            fn FlushInstructionCache(
+                process: *mut std::ffi::c_void,
+                base: *const std::ffi::c_void,
+                size: usize,
+            ) -> i32;
+        }
+        // SAFETY: the pseudo-handle from GetCurrentProcess needs no cleanup.
+        unsafe {
+            FlushInstructionCache(GetCurrentProcess(), ptr as *const std::ffi::c_void, len);
+        }
+    }
+    #[cfg(not(any(windows, target_arch = "aarch64", target_arch = "arm")))]
+    {
+        let _ = (ptr, len);
     }
 }
 
 impl MemoryMappedFile {
-    fn apply_flush_policy(&self, written: u64) -> Result<()> {
+    // Record a write of `written` bytes at `offset`, then flush if the policy says so.
+    pub(crate) fn apply_flush_policy(&self, offset: u64, written: u64) -> Result<()> {
+        self.inner.dirty.lock().mark(offset, written);
         match self.inner.flush_policy {
-            FlushPolicy::Never | FlushPolicy::Manual => Ok(()),
+            FlushPolicy::Never | FlushPolicy::Manual => {
+                // Record pending bytes so an explicit flush() is not skipped
+                *self.inner.written_since_last_flush.write() += written;
+                Ok(())
+            }
             FlushPolicy::Always => {
                 // Record then flush immediately
                 *self.inner.written_since_last_flush.write() += written;
@@ -762,8 +2111,9 @@ impl MemoryMappedFile {
                     Ok(())
                 }
             }
-            FlushPolicy::EveryMillis(_ms) => {
-                // Phase-1: treat as Manual; user drives time-based flushing externally.
+            FlushPolicy::EveryMillis(_) => {
+                // Record pending bytes; the interval flusher thread picks them up
+                *self.inner.written_since_last_flush.write() += written;
                 Ok(())
             }
         }
@@ -791,42 +2141,241 @@ impl MemoryMappedFile {
     /// # Errors
     ///
     /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
+    /// Returns `MmapIoError::MappingFault` if the mapping is
+    /// [`guarded`](MemoryMappedFileBuilder::guarded) and the file cannot provide the bytes.
     pub fn read_into(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        self.accessed(offset, buf.len() as u64, AccessKind::Read);
         let total = self.current_len()?;
         let len = buf.len() as u64;
         ensure_in_bounds(offset, len, total)?;
         match &self.inner.map {
             MapVariant::Ro(m) => {
-                let (start, end) = slice_range(offset, len, total)?;
-                buf.copy_from_slice(&m[start..end]);
-                Ok(())
+                let (start, _) = slice_range(offset, len, total)?;
+                // SAFETY: the range was bounds-checked, and a read-only view is only read.
+                unsafe { self.guarded_copy(m.as_ptr().cast_mut(), start, Transfer::Read(buf)) }
+            }
+            MapVariant::Rw(lock) if self.inner.guarded.is_some() => {
+                // Recovery swaps pages of the view, so nobody else may use it meanwhile
+                let mut guard = lock.write();
+                self.check_unsplit()?;
+                let (start, _) = slice_range(offset, len, total)?;
+                // SAFETY: bounds-checked, and the write lock keeps other users out.
+                unsafe { self.guarded_copy(guard.as_mut_ptr(), start, Transfer::Read(buf)) }
             }
             MapVariant::Rw(lock) => {
                 let guard = lock.read();
+                self.check_unsplit()?;
                 let (start, end) = slice_range(offset, len, total)?;
                 buf.copy_from_slice(&guard[start..end]);
                 Ok(())
             }
-            MapVariant::Cow(m) => {
+            MapVariant::Cow(lock) => {
+                let guard = lock.read();
                 let (start, end) = slice_range(offset, len, total)?;
-                buf.copy_from_slice(&m[start..end]);
+                buf.copy_from_slice(&guard[start..end]);
                 Ok(())
             }
         }
     }
-}
 
-/// Builder for MemoryMappedFile construction with options.
-pub struct MemoryMappedFileBuilder {
-    path: PathBuf,
-    size: Option<u64>,
-    mode: Option<MmapMode>,
-    flush_policy: FlushPolicy,
-    #[cfg(feature = "hugepages")]
-    huge_pages: bool,
-}
+    // Run `transfer` against the view at `base` from `start`, recovering from faults when
+    // the mapping was built with `guarded`.
+    //
+    // SAFETY: as for `Transfer::run` on `base + start`.
+    unsafe fn guarded_copy(
+        &self,
+        base: *mut u8,
+        start: usize,
+        transfer: Transfer<'_>,
+    ) -> Result<()> {
+        let Some(serial) = &self.inner.guarded else {
+            // SAFETY: forwarded from the caller's contract.
+            unsafe { transfer.run(base.add(start)) };
+            return Ok(());
+        };
+        // `ReadOnly` copies take no map lock, so recovery must not overlap another copy
+        let _serial = serial.lock();
+        let writable = self.inner.mode == MmapMode::ReadWrite;
+        let file_offset = self.file_offset();
+        // SAFETY: forwarded from the caller's contract.
+        unsafe {
+            crate::guard::copy(
+                base,
+                start,
+                &self.inner.file,
+                file_offset,
+                writable,
+                transfer,
+            )
+        }
+    }
+
+    /// Copy `len` bytes starting at `offset` into a new `Vec`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
+    pub fn read_to_vec(&self, offset: u64, len: u64) -> Result<Vec<u8>> {
+        let total = self.current_len()?;
+        // Check before allocating so a bogus length cannot trigger a huge allocation
+        ensure_in_bounds(offset, len, total)?;
+        let (start, end) = slice_range(offset, len, total)?;
+        let mut buf = vec![0u8; end - start];
+        self.read_into(offset, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Copy the whole mapping into a new `Vec`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the mapping shrank concurrently.
+    pub fn read_all(&self) -> Result<Vec<u8>> {
+        self.read_to_vec(0, self.current_len()?)
+    }
+
+    /// Flush, unmap, and return the backing `File` for ordinary file I/O.
+    ///
+    /// An active access recording is finished first. Copy-on-write changes are discarded
+    /// along with the mapping. This handle must be the only clone of the mapping; on error
+    /// the handle is consumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if other clones of the mapping exist.
+    /// Returns errors from flushing or finishing the recording.
+    pub fn into_file(self) -> Result<File> {
+        self.flush()?;
+        let inner = self.try_into_inner("into_file requires the only handle to the mapping")?;
+        let Inner {
+            file,
+            map,
+            recorder,
+            ..
+        } = inner;
+        // Unmap before handing out the file
+        drop(map);
+        if let Some(recorder) = recorder.into_inner() {
+            recorder.finish()?;
+        }
+        Ok(file)
+    }
+
+    /// Intentionally leak a `ReadOnly` or `CopyOnWrite` mapping, returning its bytes as a
+    /// `'static` slice.
+    ///
+    /// The mapping (and its file handle) stays alive until the process exits. Meant for
+    /// configuration or assets mapped once at startup. This handle must be the only clone,
+    /// so no copy-on-write writes can race with the returned slice; the slice includes the
+    /// private changes made before. On error the handle is consumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for `ReadWrite` mappings or if other clones exist.
+    pub fn leak(self) -> Result<&'static [u8]> {
+        if matches!(self.inner.map, MapVariant::Rw(_)) {
+            return Err(MmapIoError::InvalidMode(
+                "leak requires a ReadOnly or CopyOnWrite mapping",
+            ));
+        }
+        let inner = Arc::try_unwrap(self.inner).map_err(|_| {
+            MmapIoError::InvalidMode("leak requires the only handle to the mapping")
+        })?;
+        let inner: &'static mut Inner = Box::leak(Box::new(inner));
+        match &mut inner.map {
+            MapVariant::Ro(m) => Ok(&m[..]),
+            // No handle is left to take the lock, so the view can never change again
+            MapVariant::Cow(lock) => Ok(&lock.get_mut()[..]),
+            MapVariant::Rw(_) => Err(MmapIoError::InvalidMode(
+                "leak requires a ReadOnly or CopyOnWrite mapping",
+            )),
+        }
+    }
+
+    /// Fill each buffer in `bufs` in turn from consecutive bytes starting at `offset`.
+    ///
+    /// All buffers are filled under a single lock acquisition, so they observe one consistent
+    /// snapshot of a ReadWrite mapping. Returns the total number of bytes read.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the combined length exceeds file bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::IoSliceMut;
+    /// use mmap_io::MemoryMappedFile;
+    ///
+    /// let mmap = MemoryMappedFile::open_ro("frames.bin")?;
+    /// let (mut header, mut body) = ([0u8; 16], vec![0u8; 4096]);
+    /// let mut bufs = [IoSliceMut::new(&mut header), IoSliceMut::new(&mut body)];
+    /// mmap.read_vectored_into(0, &mut bufs)?;
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    pub fn read_vectored_into(&self, offset: u64, bufs: &mut [IoSliceMut<'_>]) -> Result<u64> {
+        let len: u64 = bufs.iter().map(|b| b.len() as u64).sum();
+        self.accessed(offset, len, AccessKind::Read);
+        let total = self.current_len()?;
+        let (start, end) = slice_range(offset, len, total)?;
+        let scatter = |src: &[u8], bufs: &mut [IoSliceMut<'_>]| {
+            let mut pos = 0;
+            for buf in bufs.iter_mut() {
+                let n = buf.len();
+                buf.copy_from_slice(&src[pos..pos + n]);
+                pos += n;
+            }
+        };
+        match &self.inner.map {
+            MapVariant::Ro(m) => scatter(&m[start..end], bufs),
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
+                let guard = lock.read();
+                self.check_unsplit()?;
+                scatter(&guard[start..end], bufs);
+            }
+        }
+        Ok(len)
+    }
+}
+
+/// Builder for MemoryMappedFile construction with options.
+pub struct MemoryMappedFileBuilder {
+    path: PathBuf,
+    size: Option<u64>,
+    mode: Option<MmapMode>,
+    flush_policy: FlushPolicy,
+    #[cfg(feature = "hugepages")]
+    huge_pages: bool,
+    populate: bool,
+    create_new: bool,
+    truncate: bool,
+    contents: Option<Vec<u8>>,
+    fork_behavior: ForkBehavior,
+    address_hint: Option<usize>,
+    size_limit: Option<u64>,
+    label: Option<String>,
+    strict_flush: bool,
+    expect_len: Option<u64>,
+    expect_hash: Option<(HashAlgo, Vec<u8>)>,
+    share_mode: Option<u32>,
+    permissions: Option<u32>,
+    scrub_on_shrink: bool,
+    check_free_space: bool,
+    guarded: bool,
+    window_offset: Option<u64>,
+    window_len: Option<u64>,
+    auto_grow: bool,
+    growth_granularity: u64,
+}
 
 impl MemoryMappedFileBuilder {
+    /// `FILE_SHARE_READ`: others may open the file for reading (see [`share_mode`](Self::share_mode)).
+    pub const SHARE_READ: u32 = 0x1;
+    /// `FILE_SHARE_WRITE`: others may open the file for writing.
+    pub const SHARE_WRITE: u32 = 0x2;
+    /// `FILE_SHARE_DELETE`: others may delete or rename the file while it is mapped.
+    pub const SHARE_DELETE: u32 = 0x4;
+
     /// Specify the size (required for create/ReadWrite new files).
     pub fn size(mut self, size: u64) -> Self {
         self.size = Some(size);
@@ -852,37 +2401,443 @@ impl MemoryMappedFileBuilder {
         self
     }
 
+    /// Prefault every page when opening ReadOnly or CopyOnWrite mappings.
+    ///
+    /// On Linux this uses `MAP_POPULATE`; elsewhere each page is touched once after mapping.
+    /// Useful for read-mostly services that want to warm an entire file at startup.
+    pub fn populate(mut self, enable: bool) -> Self {
+        self.populate = enable;
+        self
+    }
+
+    /// Make `create()` fail instead of truncating when the file already exists.
+    ///
+    /// Uses `O_EXCL` / `CREATE_NEW`, so the existence check and creation are atomic and
+    /// another process's data cannot be clobbered in a race. Only valid in ReadWrite mode.
+    pub fn create_new(mut self, enable: bool) -> Self {
+        self.create_new = enable;
+        self
+    }
+
+    /// Whether `create()` truncates an existing file (the default) or keeps its contents.
+    ///
+    /// With `false`, an existing non-empty file is mapped at its current length with its
+    /// data intact, and [`size`](Self::size) and [`contents`](Self::contents) only apply
+    /// when the file is created (or was empty). Only valid in ReadWrite mode; has no effect
+    /// together with [`create_new`](Self::create_new).
+    pub fn truncate(mut self, enable: bool) -> Self {
+        self.truncate = enable;
+        self
+    }
+
+    /// Initial contents for `create()`: the bytes are copied in and flushed before returning.
+    ///
+    /// The file size defaults to `data.len()`; an explicit `size` may only make it larger.
+    /// Only valid in ReadWrite mode.
+    pub fn contents(mut self, data: &[u8]) -> Self {
+        self.contents = Some(data.to_vec());
+        self
+    }
+
+    /// Control what child processes inherit of the mapping after `fork()`.
+    ///
+    /// The advice is applied when the mapping is created and again after every resize.
+    pub fn fork_behavior(mut self, behavior: ForkBehavior) -> Self {
+        self.fork_behavior = behavior;
+        self
+    }
+
+    /// Map the file at exactly this virtual address (ReadWrite mode only).
+    ///
+    /// Cooperating processes that map a shared file at the same address can store absolute
+    /// pointers inside it. Uses `MAP_FIXED_NOREPLACE` on Linux and `MapViewOfFileEx` on
+    /// Windows; other Unix systems pass the address as a hint and verify the result. An
+    /// occupied address is never replaced: `create`/`open` fail with an I/O error instead.
+    /// The address must be a multiple of the allocation granularity. Resizing keeps the
+    /// mapping at the same address, failing if the space after it is taken.
+    pub fn address_hint(mut self, addr: usize) -> Self {
+        self.address_hint = Some(addr);
+        self
+    }
+
+    /// Maximum size of this mapping, overriding the process-wide [`max_mmap_size`].
+    ///
+    /// Applies to `create`, `open`, and later resizes; exceeding it fails with
+    /// `MmapIoError::ResizeFailed` naming the limit.
+    pub fn max_size(mut self, limit: u64) -> Self {
+        self.size_limit = Some(limit);
+        self
+    }
+
+    /// Make every flush durable on the storage device, not just handed to the OS.
+    ///
+    /// Without it, `flush()` on Linux only schedules write-back (`MS_ASYNC`), and on Windows
+    /// `FlushViewOfFile` returns before the data leaves the disk cache. In strict mode flushes
+    /// use a synchronous `msync(MS_SYNC)` on Unix and follow `FlushViewOfFile` with
+    /// `FlushFileBuffers` on Windows, so the durability contract holds on every platform.
+    pub fn strict_flush(mut self, enable: bool) -> Self {
+        self.strict_flush = enable;
+        self
+    }
+
+    /// Zero the bytes released by a shrinking [`resize`](MemoryMappedFile::resize) and
+    /// flush them synchronously before the file is truncated.
+    ///
+    /// Truncation only drops the blocks from the file; their old contents can linger on
+    /// the device until reused. Scrubbing overwrites them first, for mapped credential
+    /// stores and similar data. On Windows, where a mapped file is shrunk only virtually,
+    /// the hidden tail is zeroed and flushed instead.
+    pub fn scrub_on_shrink(mut self, enable: bool) -> Self {
+        self.scrub_on_shrink = enable;
+        self
+    }
+
+    /// Check that the filesystem has room for the file's unallocated blocks before
+    /// `create()` sizes it and before each growing [`resize`](MemoryMappedFile::resize).
+    ///
+    /// Growing a file on most filesystems only records a new length, leaving a sparse file
+    /// whose pages are allocated on first write; if the disk is full by then, the write
+    /// faults with `SIGBUS` (Unix) or an in-page exception (Windows) instead of returning an
+    /// error. With this option such a size is refused up front with
+    /// `MmapIoError::InsufficientSpace`. The check is a snapshot: space consumed by other
+    /// writers afterwards can still run the disk out.
+    pub fn check_free_space(mut self, enable: bool) -> Self {
+        self.check_free_space = enable;
+        self
+    }
+
+    /// Make `read_into` and `update_region` return `MmapIoError::MappingFault` instead of
+    /// crashing the process when the backing file can no longer provide a page.
+    ///
+    /// Touching a page that another process truncated away, or whose block fails to read,
+    /// raises `SIGBUS` (`EXCEPTION_IN_PAGE_ERROR` on Windows). On Unix, guarded mappings run
+    /// those two copies under a process-wide handler, installed on first use and chaining
+    /// to any previous one, that maps a zero page over the faulting page, lets the copy
+    /// finish, then maps the file back in. On Windows the copies go through
+    /// `ReadProcessMemory` / `WriteProcessMemory`, which fail instead of faulting.
+    ///
+    /// Writes landing on the zero page from elsewhere in the process would be lost, so
+    /// guarded `ReadWrite` copies hold the write lock, and atomic views, `split_into_mut`,
+    /// and the [`sync`](crate::sync) locks fail with `MmapIoError::InvalidMode`. Guarded
+    /// copies of one mapping run one at a time, so a copy never reads another's zero page.
+    /// Zero-copy reads through slices and segments of `ReadOnly` mappings stay unguarded
+    /// and may briefly see zeros while a fault is being recovered.
+    ///
+    /// Applies to `ReadOnly` and `ReadWrite` mappings without huge pages; other
+    /// combinations fail with `MmapIoError::InvalidMode`. Other platforms than Unix and
+    /// Windows fail with `MmapIoError::Unsupported`.
+    pub fn guarded(mut self, enable: bool) -> Self {
+        self.guarded = enable;
+        self
+    }
+
+    /// Map only the part of the file starting at byte `offset` (`open()` only).
+    ///
+    /// Every API of the resulting mapping then addresses the window: offset 0 is byte
+    /// `offset` of the file, and `len()` is the window length. The offset need not be
+    /// aligned; the mapping is rounded out to the platform's granularity internally and the
+    /// extra bytes are never exposed. Without [`len`](Self::len) the window extends to the
+    /// end of the file. See [`len`](Self::len) for the restrictions on windowed mappings.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.window_offset = Some(offset);
+        self
+    }
+
+    /// Map only `len` bytes of the file, starting at [`offset`](Self::offset) (`open()` only).
+    ///
+    /// `open()` fails with `MmapIoError::OutOfBounds` if the window reaches past the end of
+    /// the file and with `MmapIoError::ResizeFailed` if it is empty. A windowed mapping
+    /// cannot be resized, and `create()`, `ReadExecute`, address hints, and huge pages are
+    /// refused with `MmapIoError::InvalidMode`.
+    pub fn len(mut self, len: u64) -> Self {
+        self.window_len = Some(len);
+        self
+    }
+
+    fn is_windowed(&self) -> bool {
+        self.window_offset.is_some() || self.window_len.is_some()
+    }
+
+    // Resolve the window against a `file_len`-byte file as `(offset, len)`; the whole file
+    // when no window was requested.
+    fn window(&self, file_len: u64) -> Result<(u64, u64)> {
+        if !self.is_windowed() {
+            return Ok((0, file_len));
+        }
+        if self.address_hint.is_some() {
+            return Err(MmapIoError::InvalidMode(
+                "a file window cannot be combined with an address hint",
+            ));
+        }
+        #[cfg(feature = "hugepages")]
+        if self.huge_pages {
+            return Err(MmapIoError::InvalidMode(
+                "a file window cannot be combined with huge pages",
+            ));
+        }
+        let offset = self.window_offset.unwrap_or(0);
+        let len = self
+            .window_len
+            .unwrap_or_else(|| file_len.saturating_sub(offset));
+        ensure_in_bounds(offset, len, file_len)?;
+        if len == 0 {
+            return Err(MmapIoError::ResizeFailed("file window is empty".into()));
+        }
+        Ok((offset, len))
+    }
+
+    // Reject guarded access where faults cannot be recovered page by page.
+    fn check_guarded(&self, mode: MmapMode) -> Result<()> {
+        if !self.guarded {
+            return Ok(());
+        }
+        if cfg!(not(any(unix, windows))) {
+            return Err(MmapIoError::Unsupported(
+                "guarded access is only implemented on Unix and Windows",
+            ));
+        }
+        if !matches!(mode, MmapMode::ReadOnly | MmapMode::ReadWrite) {
+            return Err(MmapIoError::InvalidMode(
+                "guarded access requires ReadOnly or ReadWrite mode",
+            ));
+        }
+        #[cfg(feature = "hugepages")]
+        if self.huge_pages {
+            return Err(MmapIoError::InvalidMode(
+                "guarded access cannot be combined with huge pages",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Grow the file instead of failing when `update_region` (and so `SegmentMut::write`)
+    /// writes past the end (ReadWrite mode only).
+    ///
+    /// The new length is the end of the write rounded up to the
+    /// [`growth_granularity`](Self::growth_granularity), capped at [`max_size`](Self::max_size),
+    /// so an append-only log remaps once per granule rather than on every record. Growing
+    /// goes through [`resize`](MemoryMappedFile::resize): views borrowed before it become
+    /// invalid, and the mapping cannot be a file window.
+    pub fn auto_grow(mut self, enable: bool) -> Self {
+        self.auto_grow = enable;
+        self
+    }
+
+    /// Multiple that [`auto_grow`](Self::auto_grow) rounds the new file length up to.
+    ///
+    /// Defaults to [`DEFAULT_GROWTH_GRANULARITY`] (1 MiB); 0 grows to exactly the end of
+    /// the write.
+    pub fn growth_granularity(mut self, bytes: u64) -> Self {
+        self.growth_granularity = bytes;
+        self
+    }
+
+    // Reject auto_grow where the mapping cannot be resized.
+    fn check_auto_grow(&self, mode: MmapMode) -> Result<()> {
+        if self.auto_grow && (mode != MmapMode::ReadWrite || self.is_windowed()) {
+            return Err(MmapIoError::InvalidMode(
+                "auto_grow requires a whole-file ReadWrite mapping",
+            ));
+        }
+        Ok(())
+    }
+
+    fn auto_grow_lock(&self) -> Option<Mutex<u64>> {
+        self.auto_grow.then(|| Mutex::new(self.growth_granularity))
+    }
+
+    /// Make `open()` fail with `MmapIoError::Integrity` unless the file is exactly `len` bytes.
+    pub fn expect_len(mut self, len: u64) -> Self {
+        self.expect_len = Some(len);
+        self
+    }
+
+    /// Make `open()` fail with `MmapIoError::Integrity` unless the file contents hash to
+    /// `digest` under `algo` (see [`crate::integrity`]).
+    ///
+    /// The whole file is read once to compute the digest before the mapping is returned.
+    pub fn expect_hash(mut self, algo: HashAlgo, digest: impl AsRef<[u8]>) -> Self {
+        self.expect_hash = Some((algo, digest.as_ref().to_vec()));
+        self
+    }
+
+    /// Label the mapping in the global tracking registry (see [`crate::tracking`]).
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Windows sharing flags for the file handle, a combination of
+    /// [`SHARE_READ`](Self::SHARE_READ), [`SHARE_WRITE`](Self::SHARE_WRITE), and
+    /// [`SHARE_DELETE`](Self::SHARE_DELETE).
+    ///
+    /// Decides whether other processes may open, write, delete, or rename the file while
+    /// it is mapped. The default is the standard library's (all three), which keeps log
+    /// rotation working; pass fewer flags to keep others out. Ignored for `ReadExecute`
+    /// and on non-Windows platforms, where sharing is not enforced by the OS.
+    pub fn share_mode(mut self, mode: u32) -> Self {
+        self.share_mode = Some(mode);
+        self
+    }
+
+    /// Unix permission bits (e.g. `0o600`) for the file written by `create()`.
+    ///
+    /// Passed to `open(2)` so a new file never exists with wider permissions, then set
+    /// explicitly so the result does not depend on the umask and also applies to an
+    /// existing file being truncated. Ignored by `open()` and on Windows.
+    pub fn permissions(mut self, mode: u32) -> Self {
+        self.permissions = Some(mode);
+        self
+    }
+
+    // Open options carrying the platform-specific settings of this builder.
+    fn open_options(&self) -> OpenOptions {
+        #[allow(unused_mut)]
+        let mut opts = OpenOptions::new();
+        #[cfg(windows)]
+        if let Some(mode) = self.share_mode {
+            std::os::windows::fs::OpenOptionsExt::share_mode(&mut opts, mode);
+        }
+        opts
+    }
+
+    // Map `len` bytes of `file` from `offset` read-write, honouring the address hint and
+    // huge page options (which only apply to whole-file mappings).
+    fn map_rw(&self, file: &File, offset: u64, len: u64) -> Result<RwMap> {
+        if self.is_windowed() {
+            // SAFETY: the file is open read-write for the lifetime of the mapping, and the
+            // window was checked to lie within it.
+            let mmap = unsafe {
+                MmapOptions::new()
+                    .offset(offset)
+                    .len(len as usize)
+                    .map_mut(file)?
+            };
+            return Ok(RwMap::Std(mmap));
+        }
+        if let Some(addr) = self.address_hint {
+            let granularity = crate::utils::allocation_granularity();
+            if addr == 0 || addr % granularity != 0 {
+                return Err(MmapIoError::Misaligned {
+                    required: granularity as u64,
+                    offset: addr as u64,
+                });
+            }
+            return Ok(RwMap::Fixed(crate::fixed::FixedMap::map(
+                file,
+                addr,
+                len as usize,
+            )?));
+        }
+        #[cfg(feature = "hugepages")]
+        let mmap = map_mut_with_options(file, len, self.huge_pages)?;
+        #[cfg(not(feature = "hugepages"))]
+        // SAFETY: the file is open read-write for the lifetime of the mapping.
+        let mmap = unsafe { MmapMut::map_mut(file)? };
+        Ok(RwMap::Std(mmap))
+    }
+
     /// Create a new mapping; for ReadWrite requires size for creation.
-    pub fn create(self) -> Result<MemoryMappedFile> {
+    pub fn create(mut self) -> Result<MemoryMappedFile> {
+        let label = self.label.take();
+        let mmap = self.create_mapping()?;
+        mmap.apply_fork_behavior()?;
+        if let Some(label) = label {
+            mmap.set_label(label);
+        }
+        Ok(mmap)
+    }
+
+    fn create_mapping(self) -> Result<MemoryMappedFile> {
         let mode = self.mode.unwrap_or(MmapMode::ReadWrite);
+        self.check_guarded(mode)?;
+        self.check_auto_grow(mode)?;
+        if self.is_windowed() {
+            return Err(MmapIoError::InvalidMode(
+                "a file window can only be mapped by open()",
+            ));
+        }
+        if self.create_new && mode != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(
+                "create_new requires ReadWrite mode",
+            ));
+        }
+        if !self.truncate && mode != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(
+                "truncate(false) requires ReadWrite mode",
+            ));
+        }
+        if self.contents.is_some() && mode != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(
+                "initial contents require ReadWrite mode",
+            ));
+        }
+        if self.address_hint.is_some() && mode != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(ERR_ADDRESS_HINT_MODE));
+        }
         match mode {
             MmapMode::ReadWrite => {
-                let size = self.size.ok_or_else(|| {
+                let contents_len = self.contents.as_ref().map(|c| c.len() as u64);
+                let size = self.size.or(contents_len).ok_or_else(|| {
                     MmapIoError::ResizeFailed(
                         "Size must be set for create() in ReadWrite mode".into(),
                     )
                 })?;
-                if size == 0 {
-                    return Err(MmapIoError::ResizeFailed(ERR_ZERO_SIZE.into()));
-                }
-                if size > MAX_MMAP_SIZE {
+                if let Some(needed) = contents_len.filter(|&n| n > size) {
                     return Err(MmapIoError::ResizeFailed(format!(
-                        "Size {size} exceeds maximum safe limit of {MAX_MMAP_SIZE} bytes"
+                        "Size {size} is smaller than the {needed} bytes of initial contents"
                     )));
                 }
+                if size == 0 {
+                    return Err(MmapIoError::ResizeFailed(ERR_ZERO_SIZE.into()));
+                }
+                ensure_within_limit(size, self.size_limit)?;
                 let path_ref = &self.path;
-                let file = OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .read(true)
-                    .truncate(true)
-                    .open(path_ref)?;
-                file.set_len(size)?;
-                // Map with consideration for huge pages if requested
-                #[cfg(feature = "hugepages")]
-                let mmap = map_mut_with_options(&file, size, self.huge_pages)?;
-                #[cfg(not(feature = "hugepages"))]
-                let mmap = unsafe { MmapMut::map_mut(&file)? };
+                let mut opts = self.open_options();
+                opts.write(true).read(true);
+                if self.create_new {
+                    opts.create_new(true);
+                } else {
+                    opts.create(true).truncate(self.truncate);
+                }
+                #[cfg(unix)]
+                if let Some(mode) = self.permissions {
+                    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, mode);
+                }
+                let file = opts.open(os_path(path_ref)).map_err(|e| {
+                    if self.create_new && e.kind() == std::io::ErrorKind::AlreadyExists {
+                        MmapIoError::AlreadyExists(path_ref.clone())
+                    } else {
+                        MmapIoError::Io(e)
+                    }
+                })?;
+                #[cfg(unix)]
+                if let Some(mode) = self.permissions {
+                    use std::os::unix::fs::PermissionsExt;
+                    file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+                }
+                // Without truncation an existing, non-empty file keeps its length and data
+                let existing = if self.truncate {
+                    0
+                } else {
+                    file.metadata()?.len()
+                };
+                let size = if existing > 0 {
+                    ensure_mappable(existing, self.size_limit)?;
+                    existing
+                } else {
+                    if self.check_free_space {
+                        ensure_free_space(&file, path_ref, size)?;
+                    }
+                    file.set_len(size)?;
+                    size
+                };
+                // Map with consideration for huge pages / address hint if requested
+                let mut mmap = self.map_rw(&file, 0, size)?;
+                if let Some(contents) = self.contents.as_ref().filter(|_| existing == 0) {
+                    mmap[..contents.len()].copy_from_slice(contents);
+                    mmap.flush()?;
+                }
                 let inner = Inner {
                     path: path_ref.clone(),
                     file,
@@ -893,16 +2848,29 @@ impl MemoryMappedFileBuilder {
                     written_since_last_flush: RwLock::new(0),
                     #[cfg(feature = "hugepages")]
                     huge_pages: self.huge_pages,
+                    observer: RwLock::new(None),
+                    fork_behavior: self.fork_behavior,
+                    size_limit: self.size_limit,
+                    tracking: None,
+                    strict_flush: self.strict_flush,
+                    recorder: RwLock::new(None),
+                    split_parts: AtomicUsize::new(0),
+                    scrub_on_shrink: self.scrub_on_shrink,
+                    check_free_space: self.check_free_space,
+                    guarded: self.guarded.then(|| Mutex::new(())),
+                    window_offset: None,
+                    auto_grow: self.auto_grow_lock(),
+                    interval_flusher: None,
+                    dirty: Mutex::default(),
                 };
-                Ok(MemoryMappedFile {
-                    inner: Arc::new(inner),
-                })
+                MemoryMappedFile::from_inner(inner)
             }
             MmapMode::ReadOnly => {
                 let path_ref = &self.path;
-                let file = OpenOptions::new().read(true).open(path_ref)?;
+                let file = self.open_options().read(true).open(os_path(path_ref))?;
                 let len = file.metadata()?.len();
-                let mmap = unsafe { Mmap::map(&file)? };
+                ensure_mappable(len, self.size_limit)?;
+                let mmap = map_readonly(&file, 0, None, self.populate)?;
                 let inner = Inner {
                     path: path_ref.clone(),
                     file,
@@ -913,39 +2881,60 @@ impl MemoryMappedFileBuilder {
                     written_since_last_flush: RwLock::new(0),
                     #[cfg(feature = "hugepages")]
                     huge_pages: false,
+                    observer: RwLock::new(None),
+                    fork_behavior: self.fork_behavior,
+                    size_limit: self.size_limit,
+                    tracking: None,
+                    strict_flush: self.strict_flush,
+                    recorder: RwLock::new(None),
+                    split_parts: AtomicUsize::new(0),
+                    scrub_on_shrink: false,
+                    check_free_space: false,
+                    guarded: self.guarded.then(|| Mutex::new(())),
+                    window_offset: None,
+                    auto_grow: None,
+                    interval_flusher: None,
+                    dirty: Mutex::default(),
                 };
-                Ok(MemoryMappedFile {
-                    inner: Arc::new(inner),
-                })
+                MemoryMappedFile::from_inner(inner)
             }
             MmapMode::CopyOnWrite => {
                 #[cfg(feature = "cow")]
                 {
                     let path_ref = &self.path;
-                    let file = OpenOptions::new().read(true).open(path_ref)?;
+                    let file = self.open_options().read(true).open(os_path(path_ref))?;
                     let len = file.metadata()?.len();
+                    ensure_mappable(len, self.size_limit)?;
                     if len == 0 {
                         return Err(MmapIoError::ResizeFailed(ERR_ZERO_LENGTH_FILE.into()));
                     }
-                    let mmap = unsafe {
-                        let mut opts = MmapOptions::new();
-                        opts.len(len as usize);
-                        opts.map(&file)?
-                    };
+                    let mmap = map_private(&file, 0, len as usize, self.populate)?;
                     let inner = Inner {
                         path: path_ref.clone(),
                         file,
                         mode,
                         cached_len: RwLock::new(len),
-                        map: MapVariant::Cow(mmap),
+                        map: MapVariant::Cow(RwLock::new(mmap)),
                         flush_policy: FlushPolicy::Never,
                         written_since_last_flush: RwLock::new(0),
                         #[cfg(feature = "hugepages")]
                         huge_pages: false,
+                        observer: RwLock::new(None),
+                        fork_behavior: self.fork_behavior,
+                        size_limit: self.size_limit,
+                        tracking: None,
+                        strict_flush: self.strict_flush,
+                        recorder: RwLock::new(None),
+                        split_parts: AtomicUsize::new(0),
+                        scrub_on_shrink: false,
+                        check_free_space: false,
+                        guarded: None,
+                        window_offset: None,
+                        auto_grow: None,
+                        interval_flusher: None,
+                        dirty: Mutex::default(),
                     };
-                    Ok(MemoryMappedFile {
-                        inner: Arc::new(inner),
-                    })
+                    MemoryMappedFile::from_inner(inner)
                 }
                 #[cfg(not(feature = "cow"))]
                 {
@@ -954,18 +2943,56 @@ impl MemoryMappedFileBuilder {
                     ))
                 }
             }
+            MmapMode::ReadExecute => {
+                #[cfg(feature = "exec")]
+                {
+                    let mut mmap = MemoryMappedFile::open_exec(&self.path)?;
+                    if let Some(inner) = Arc::get_mut(&mut mmap.inner) {
+                        inner.fork_behavior = self.fork_behavior;
+                        inner.size_limit = self.size_limit;
+                        inner.strict_flush = self.strict_flush;
+                    }
+                    Ok(mmap)
+                }
+                #[cfg(not(feature = "exec"))]
+                {
+                    Err(MmapIoError::InvalidMode(
+                        "ReadExecute mode requires 'exec' feature",
+                    ))
+                }
+            }
         }
     }
 
     /// Open an existing file with provided mode (size ignored).
-    pub fn open(self) -> Result<MemoryMappedFile> {
+    pub fn open(mut self) -> Result<MemoryMappedFile> {
+        let label = self.label.take();
+        let expect_len = self.expect_len.take();
+        let expect_hash = self.expect_hash.take();
+        let mmap = self.open_mapping()?;
+        crate::integrity::verify(&mmap, expect_len, expect_hash.as_ref())?;
+        mmap.apply_fork_behavior()?;
+        if let Some(label) = label {
+            mmap.set_label(label);
+        }
+        Ok(mmap)
+    }
+
+    fn open_mapping(self) -> Result<MemoryMappedFile> {
         let mode = self.mode.unwrap_or(MmapMode::ReadOnly);
+        self.check_guarded(mode)?;
+        self.check_auto_grow(mode)?;
+        if self.address_hint.is_some() && mode != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(ERR_ADDRESS_HINT_MODE));
+        }
         match mode {
             MmapMode::ReadOnly => {
                 let path_ref = &self.path;
-                let file = OpenOptions::new().read(true).open(path_ref)?;
-                let len = file.metadata()?.len();
-                let mmap = unsafe { Mmap::map(&file)? };
+                let file = self.open_options().read(true).open(os_path(path_ref))?;
+                let (offset, len) = self.window(file.metadata()?.len())?;
+                ensure_mappable(len, self.size_limit)?;
+                let map_len = self.is_windowed().then_some(len as usize);
+                let mmap = map_readonly(&file, offset, map_len, self.populate)?;
                 let inner = Inner {
                     path: path_ref.clone(),
                     file,
@@ -976,22 +3003,36 @@ impl MemoryMappedFileBuilder {
                     written_since_last_flush: RwLock::new(0),
                     #[cfg(feature = "hugepages")]
                     huge_pages: false,
+                    observer: RwLock::new(None),
+                    fork_behavior: self.fork_behavior,
+                    size_limit: self.size_limit,
+                    tracking: None,
+                    strict_flush: self.strict_flush,
+                    recorder: RwLock::new(None),
+                    split_parts: AtomicUsize::new(0),
+                    scrub_on_shrink: false,
+                    check_free_space: false,
+                    guarded: self.guarded.then(|| Mutex::new(())),
+                    window_offset: self.is_windowed().then_some(offset),
+                    auto_grow: None,
+                    interval_flusher: None,
+                    dirty: Mutex::default(),
                 };
-                Ok(MemoryMappedFile {
-                    inner: Arc::new(inner),
-                })
+                MemoryMappedFile::from_inner(inner)
             }
             MmapMode::ReadWrite => {
                 let path_ref = &self.path;
-                let file = OpenOptions::new().read(true).write(true).open(path_ref)?;
-                let len = file.metadata()?.len();
+                let file = self
+                    .open_options()
+                    .read(true)
+                    .write(true)
+                    .open(os_path(path_ref))?;
+                let (offset, len) = self.window(file.metadata()?.len())?;
+                ensure_mappable(len, self.size_limit)?;
                 if len == 0 {
                     return Err(MmapIoError::ResizeFailed(ERR_ZERO_LENGTH_FILE.into()));
                 }
-                #[cfg(feature = "hugepages")]
-                let mmap = map_mut_with_options(&file, len, self.huge_pages)?;
-                #[cfg(not(feature = "hugepages"))]
-                let mmap = unsafe { MmapMut::map_mut(&file)? };
+                let mmap = self.map_rw(&file, offset, len)?;
                 let inner = Inner {
                     path: path_ref.clone(),
                     file,
@@ -1002,39 +3043,60 @@ impl MemoryMappedFileBuilder {
                     written_since_last_flush: RwLock::new(0),
                     #[cfg(feature = "hugepages")]
                     huge_pages: self.huge_pages,
+                    observer: RwLock::new(None),
+                    fork_behavior: self.fork_behavior,
+                    size_limit: self.size_limit,
+                    tracking: None,
+                    strict_flush: self.strict_flush,
+                    recorder: RwLock::new(None),
+                    split_parts: AtomicUsize::new(0),
+                    scrub_on_shrink: self.scrub_on_shrink,
+                    check_free_space: self.check_free_space,
+                    guarded: self.guarded.then(|| Mutex::new(())),
+                    window_offset: self.is_windowed().then_some(offset),
+                    auto_grow: self.auto_grow_lock(),
+                    interval_flusher: None,
+                    dirty: Mutex::default(),
                 };
-                Ok(MemoryMappedFile {
-                    inner: Arc::new(inner),
-                })
+                MemoryMappedFile::from_inner(inner)
             }
             MmapMode::CopyOnWrite => {
                 #[cfg(feature = "cow")]
                 {
                     let path_ref = &self.path;
-                    let file = OpenOptions::new().read(true).open(path_ref)?;
-                    let len = file.metadata()?.len();
+                    let file = self.open_options().read(true).open(os_path(path_ref))?;
+                    let (offset, len) = self.window(file.metadata()?.len())?;
+                    ensure_mappable(len, self.size_limit)?;
                     if len == 0 {
                         return Err(MmapIoError::ResizeFailed(ERR_ZERO_LENGTH_FILE.into()));
                     }
-                    let mmap = unsafe {
-                        let mut opts = MmapOptions::new();
-                        opts.len(len as usize);
-                        opts.map(&file)?
-                    };
+                    let mmap = map_private(&file, offset, len as usize, self.populate)?;
                     let inner = Inner {
                         path: path_ref.clone(),
                         file,
                         mode,
                         cached_len: RwLock::new(len),
-                        map: MapVariant::Cow(mmap),
+                        map: MapVariant::Cow(RwLock::new(mmap)),
                         flush_policy: FlushPolicy::Never,
                         written_since_last_flush: RwLock::new(0),
                         #[cfg(feature = "hugepages")]
                         huge_pages: false,
+                        observer: RwLock::new(None),
+                        fork_behavior: self.fork_behavior,
+                        size_limit: self.size_limit,
+                        tracking: None,
+                        strict_flush: self.strict_flush,
+                        recorder: RwLock::new(None),
+                        split_parts: AtomicUsize::new(0),
+                        scrub_on_shrink: false,
+                        check_free_space: false,
+                        guarded: None,
+                        window_offset: self.is_windowed().then_some(offset),
+                        auto_grow: None,
+                        interval_flusher: None,
+                        dirty: Mutex::default(),
                     };
-                    Ok(MemoryMappedFile {
-                        inner: Arc::new(inner),
-                    })
+                    MemoryMappedFile::from_inner(inner)
                 }
                 #[cfg(not(feature = "cow"))]
                 {
@@ -1043,6 +3105,29 @@ impl MemoryMappedFileBuilder {
                     ))
                 }
             }
+            MmapMode::ReadExecute => {
+                #[cfg(feature = "exec")]
+                {
+                    if self.is_windowed() {
+                        return Err(MmapIoError::InvalidMode(
+                            "a file window cannot be mapped ReadExecute",
+                        ));
+                    }
+                    let mut mmap = MemoryMappedFile::open_exec(&self.path)?;
+                    if let Some(inner) = Arc::get_mut(&mut mmap.inner) {
+                        inner.fork_behavior = self.fork_behavior;
+                        inner.size_limit = self.size_limit;
+                        inner.strict_flush = self.strict_flush;
+                    }
+                    Ok(mmap)
+                }
+                #[cfg(not(feature = "exec"))]
+                {
+                    Err(MmapIoError::InvalidMode(
+                        "ReadExecute mode requires 'exec' feature",
+                    ))
+                }
+            }
         }
     }
 }
@@ -1050,7 +3135,7 @@ impl MemoryMappedFileBuilder {
 /// Wrapper for a mutable slice that holds a write lock guard,
 /// ensuring exclusive access for the lifetime of the slice.
 pub struct MappedSliceMut<'a> {
-    guard: parking_lot::lock_api::RwLockWriteGuard<'a, parking_lot::RawRwLock, MmapMut>,
+    guard: parking_lot::lock_api::RwLockWriteGuard<'a, parking_lot::RawRwLock, RwMap>,
     range: std::ops::Range<usize>,
 }
 
@@ -1067,3 +3152,1219 @@ impl MappedSliceMut<'_> {
         &mut self.guard[start..end]
     }
 }
+
+/// Reference-counted view of a `ReadOnly` or `ReadExecute` region, created by
+/// [`MemoryMappedFile::slice_owned`].
+///
+/// Keeps the mapping alive on its own, so it is `Send + Sync + 'static` and can be moved
+/// into spawned threads or tasks. Cloning is cheap.
+#[derive(Clone)]
+pub struct OwnedMappedSlice {
+    inner: Arc<Inner>,
+    range: std::ops::Range<usize>,
+}
+
+impl std::ops::Deref for OwnedMappedSlice {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.inner.map {
+            MapVariant::Ro(m) => &m[self.range.clone()],
+            // Rejected by slice_owned
+            MapVariant::Rw(_) | MapVariant::Cow(_) => &[],
+        }
+    }
+}
+
+impl AsRef<[u8]> for OwnedMappedSlice {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl std::fmt::Debug for OwnedMappedSlice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OwnedMappedSlice")
+            .field("path", &self.inner.path)
+            .field("range", &self.range)
+            .finish()
+    }
+}
+
+impl MemoryMappedFile {
+    /// Zero-copy view of `[offset, offset + len)` that owns a reference to the mapping.
+    ///
+    /// Like [`as_slice`](Self::as_slice), but the result is not tied to `&self`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for `ReadWrite` mappings, which can be remapped
+    /// by a resize, and `CopyOnWrite` mappings, which can be written; use
+    /// [`read_to_vec`](Self::read_to_vec) there.
+    /// Returns `MmapIoError::OutOfBounds` if range exceeds file bounds.
+    pub fn slice_owned(&self, offset: u64, len: u64) -> Result<OwnedMappedSlice> {
+        if !matches!(self.inner.map, MapVariant::Ro(_)) {
+            return Err(MmapIoError::InvalidMode(
+                "use read_to_vec for RW and copy-on-write mappings",
+            ));
+        }
+        self.accessed(offset, len, AccessKind::Read);
+        let (start, end) = slice_range(offset, len, self.current_len()?)?;
+        Ok(OwnedMappedSlice {
+            inner: Arc::clone(&self.inner),
+            range: start..end,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!("mmap_io_mmap_test_{}_{}", name, std::process::id()));
+        p
+    }
+
+    #[test]
+    fn test_builder_populate_read_only() {
+        let path = tmp_path("populate");
+        let _ = fs::remove_file(&path);
+        fs::write(&path, vec![7u8; 3 * 4096 + 10]).expect("seed");
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadOnly)
+            .populate(true)
+            .open()
+            .expect("open populated");
+        assert_eq!(mmap.len(), 3 * 4096 + 10);
+        assert_eq!(mmap.as_slice(3 * 4096, 10).expect("slice"), &[7u8; 10]);
+        drop(mmap);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    #[cfg(feature = "exec")]
+    fn test_make_executable_and_open_exec() {
+        let path = tmp_path("exec");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 4096).expect("create");
+        mmap.update_region(0, &[0xC3; 16]).expect("write code");
+        let shared = mmap.clone();
+        assert!(shared.make_executable().is_err());
+
+        let exec = mmap.make_executable().expect("make executable");
+        assert_eq!(exec.mode(), MmapMode::ReadExecute);
+        assert_eq!(exec.as_slice(0, 16).expect("read"), &[0xC3; 16]);
+        assert!(exec.update_region(0, b"x").is_err());
+        assert!(exec.as_slice_mut(0, 1).is_err());
+        assert!(exec.resize(8192).is_err());
+        assert!(exec.exec_ptr(4095).is_ok());
+        assert!(exec.exec_ptr(4096).is_err());
+        drop(exec);
+
+        let reopened = MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadExecute)
+            .open()
+            .expect("open exec");
+        assert_eq!(reopened.as_slice(0, 16).expect("read"), &[0xC3; 16]);
+        drop(reopened);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_builder_create_new_is_exclusive() {
+        let path = tmp_path("create_new");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(128)
+            .create_new(true)
+            .create()
+            .expect("exclusive create");
+        mmap.update_region(0, b"keep").expect("write");
+        mmap.flush().expect("flush");
+        drop(mmap);
+
+        let err = MemoryMappedFile::builder(&path)
+            .size(64)
+            .create_new(true)
+            .create()
+            .expect_err("file exists");
+        assert!(matches!(err, MmapIoError::AlreadyExists(p) if p == path));
+        // Existing data and length are untouched
+        let data = fs::read(&path).expect("read back");
+        assert_eq!(data.len(), 128);
+        assert_eq!(&data[..4], b"keep");
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_open_or_create_keeps_existing_data() {
+        let path = tmp_path("open_or_create");
+        let _ = fs::remove_file(&path);
+
+        // Absent: created at the requested size, with initial contents
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(128)
+            .contents(b"init")
+            .truncate(false)
+            .create()
+            .expect("create");
+        assert_eq!(mmap.len(), 128);
+        mmap.update_region(4, b"-kept").expect("write");
+        mmap.flush().expect("flush");
+        drop(mmap);
+
+        // Present: mapped at its own length, data intact, contents not reapplied
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(64)
+            .contents(b"XXXX")
+            .truncate(false)
+            .create()
+            .expect("reopen");
+        assert_eq!(mmap.len(), 128);
+        assert_eq!(mmap.read_to_vec(0, 9).expect("read"), b"init-kept");
+        drop(mmap);
+        let mmap = MemoryMappedFile::open_or_create(&path, 4096).expect("open_or_create");
+        assert_eq!(mmap.len(), 128);
+        drop(mmap);
+
+        // An empty file is grown to the requested size
+        fs::write(&path, b"").expect("empty");
+        let mmap = MemoryMappedFile::open_or_create(&path, 256).expect("grow empty");
+        assert_eq!(mmap.len(), 256);
+        drop(mmap);
+
+        assert!(matches!(
+            MemoryMappedFile::builder(&path)
+                .mode(MmapMode::ReadOnly)
+                .truncate(false)
+                .create(),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_builder_auto_grow() {
+        let path = tmp_path("auto_grow");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(100)
+            .auto_grow(true)
+            .growth_granularity(4096)
+            .max_size(10_000)
+            .create()
+            .expect("create");
+        mmap.update_region(90, &[7u8; 20]).expect("write past end");
+        assert_eq!(mmap.len(), 4096);
+        assert_eq!(mmap.read_to_vec(90, 20).expect("read"), vec![7u8; 20]);
+        // Rounded up, but never past max_size
+        mmap.update_region(9000, b"tail").expect("write near limit");
+        assert_eq!(mmap.len(), 10_000);
+        assert!(matches!(
+            mmap.update_region(9998, b"over"),
+            Err(MmapIoError::ResizeFailed(_))
+        ));
+        drop(mmap);
+        assert_eq!(fs::metadata(&path).expect("metadata").len(), 10_000);
+
+        // Concurrent appenders never shrink each other's growth
+        let mmap = Arc::new(
+            MemoryMappedFile::builder(&path)
+                .mode(MmapMode::ReadWrite)
+                .auto_grow(true)
+                .growth_granularity(0)
+                .open()
+                .expect("open"),
+        );
+        let writers: Vec<_> = (0..4u8)
+            .map(|t| {
+                let mmap = Arc::clone(&mmap);
+                std::thread::spawn(move || {
+                    for i in 0..50u64 {
+                        let offset = 10_000 + (i * 4 + u64::from(t)) * 8;
+                        mmap.update_region(offset, &[t + 1; 8]).expect("append");
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().expect("join");
+        }
+        assert_eq!(mmap.len(), 10_000 + 200 * 8);
+        for slot in 0..200u64 {
+            let expected = (slot % 4) as u8 + 1;
+            let bytes = mmap.read_to_vec(10_000 + slot * 8, 8).expect("read");
+            assert_eq!(bytes, vec![expected; 8]);
+        }
+        drop(mmap);
+
+        // Only whole-file ReadWrite mappings can grow
+        assert!(matches!(
+            MemoryMappedFile::builder(&path)
+                .mode(MmapMode::ReadOnly)
+                .auto_grow(true)
+                .open(),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_builder_share_mode() {
+        let path = tmp_path("share_mode");
+        let _ = fs::remove_file(&path);
+
+        // Read-only sharing: other readers are fine, writers are refused on Windows
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(64)
+            .share_mode(MemoryMappedFileBuilder::SHARE_READ)
+            .create()
+            .expect("create");
+        fs::File::open(&path).expect("shared reader");
+        let writer = fs::OpenOptions::new().write(true).open(&path);
+        assert_eq!(writer.is_err(), cfg!(windows));
+        drop((mmap, writer));
+
+        // Full sharing lets the file be deleted while mapped (log rotation)
+        let mmap = MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadOnly)
+            .share_mode(
+                MemoryMappedFileBuilder::SHARE_READ
+                    | MemoryMappedFileBuilder::SHARE_WRITE
+                    | MemoryMappedFileBuilder::SHARE_DELETE,
+            )
+            .open()
+            .expect("open");
+        fs::remove_file(&path).expect("delete while mapped");
+        assert_eq!(mmap.len(), 64);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_builder_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = tmp_path("permissions");
+        let _ = fs::remove_file(&path);
+        let mode_of = |p: &PathBuf| fs::metadata(p).expect("metadata").permissions().mode() & 0o777;
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(64)
+            .permissions(0o600)
+            .create()
+            .expect("create");
+        assert_eq!(mode_of(&path), 0o600);
+        drop(mmap);
+
+        // Truncating an existing, wider file tightens it too; the umask does not widen it
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o666)).expect("chmod");
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(64)
+            .permissions(0o640)
+            .create()
+            .expect("recreate");
+        assert_eq!(mode_of(&path), 0o640);
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_builder_check_free_space() {
+        let path = tmp_path("check_free_space");
+        let _ = fs::remove_file(&path);
+        // Far beyond any test machine's disk, yet under the mapping size limit
+        let huge = 1u64 << 46;
+
+        let err = MemoryMappedFile::builder(&path)
+            .size(huge)
+            .check_free_space(true)
+            .create()
+            .expect_err("64 TB cannot be backed");
+        assert!(matches!(err, MmapIoError::InsufficientSpace { required, .. } if required == huge));
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(4096)
+            .check_free_space(true)
+            .create()
+            .expect("create small");
+        mmap.resize(8192).expect("small growth fits");
+        let err = mmap.resize(huge).expect_err("huge growth refused");
+        assert!(
+            matches!(err, MmapIoError::InsufficientSpace { available, .. } if available < huge)
+        );
+        assert_eq!(mmap.len(), 8192);
+        assert_eq!(fs::metadata(&path).expect("metadata").len(), 8192);
+        mmap.resize(4096).expect("shrinking is never checked");
+        drop(mmap);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[cfg(feature = "cow")]
+    #[test]
+    fn test_cow_writes_stay_private() {
+        let path = tmp_path("cow_writes");
+        let _ = fs::remove_file(&path);
+        fs::write(&path, vec![b'o'; 8192]).expect("seed");
+
+        let cow = MemoryMappedFile::open_cow(&path).expect("open cow");
+        cow.update_region(10, b"private").expect("update cow");
+        cow.as_slice_mut(4096, 3)
+            .expect("slice_mut cow")
+            .as_mut()
+            .copy_from_slice(b"mut");
+        #[cfg(feature = "iterator")]
+        cow.chunks_mut(4096)
+            .for_each_mut(|_, chunk| {
+                chunk[0] = b'#';
+                Ok::<(), ()>(())
+            })
+            .expect("iterate")
+            .expect("callback");
+        cow.flush().expect("flush is a no-op");
+
+        let mut buf = [0u8; 7];
+        cow.read_into(10, &mut buf).expect("read cow");
+        assert_eq!(&buf, b"private");
+        cow.read_into(4096, &mut buf[..3]).expect("read cow");
+        assert_eq!(
+            &buf[..3],
+            if cfg!(feature = "iterator") {
+                b"#ut"
+            } else {
+                b"mut"
+            }
+        );
+        assert!(matches!(
+            cow.as_slice(0, 4),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+        assert!(cow.resize(16384).is_err());
+
+        // Neither the file nor another private view sees the changes
+        assert!(fs::read(&path)
+            .expect("read file")
+            .iter()
+            .all(|&b| b == b'o'));
+        let other = MemoryMappedFile::open_cow(&path).expect("open second cow");
+        other.read_into(10, &mut buf).expect("read other");
+        assert_eq!(&buf, b"ooooooo");
+        drop(other);
+
+        let leaked = cow.leak().expect("leak cow");
+        assert_eq!(&leaked[10..17], b"private");
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_builder_guarded_recovers_from_truncation() {
+        let path = tmp_path("guarded");
+        let _ = fs::remove_file(&path);
+        let ps = crate::utils::page_size() as u64;
+        let truncate = |len: u64| {
+            OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .and_then(|f| f.set_len(len))
+                .expect("set_len");
+        };
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(4 * ps)
+            .guarded(true)
+            .create()
+            .expect("create guarded");
+        mmap.update_region(0, b"head").expect("write");
+        // Lock-free access could write into a page while it is swapped out
+        assert!(matches!(
+            unsafe { mmap.split_into_mut(2) },
+            Err(MmapIoError::InvalidMode(_))
+        ));
+        #[cfg(feature = "atomic")]
+        assert!(matches!(
+            mmap.atomic_u64(8),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+
+        // Someone else truncates the file underneath the mapping
+        truncate(ps);
+        let mut buf = [0u8; 8];
+        let err = mmap
+            .read_into(2 * ps + 3, &mut buf)
+            .expect_err("read past EOF");
+        assert!(matches!(err, MmapIoError::MappingFault { offset } if offset == 2 * ps + 3));
+        let err = mmap
+            .update_region(ps - 2, b"span")
+            .expect_err("write past EOF");
+        assert!(matches!(err, MmapIoError::MappingFault { offset } if offset == ps));
+        mmap.read_into(0, &mut buf[..4]).expect("read before EOF");
+        assert_eq!(&buf[..4], b"head");
+
+        // The file pages are mapped back, so accesses work again once the file regrows
+        truncate(4 * ps);
+        mmap.update_region(3 * ps, b"back")
+            .expect("write after regrow");
+        mmap.read_into(3 * ps, &mut buf[..4])
+            .expect("read after regrow");
+        assert_eq!(&buf[..4], b"back");
+        drop(mmap);
+
+        let ro = MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadOnly)
+            .guarded(true)
+            .open()
+            .expect("open guarded");
+        truncate(ps);
+        let err = ro.read_into(3 * ps, &mut buf).expect_err("read past EOF");
+        assert!(matches!(err, MmapIoError::MappingFault { .. }));
+        // Concurrent readers never see another reader's zero page as data
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let mut buf = [0u8; 8];
+                    for _ in 0..200 {
+                        let err = ro.read_into(3 * ps, &mut buf).expect_err("read past EOF");
+                        assert!(matches!(err, MmapIoError::MappingFault { .. }));
+                    }
+                });
+            }
+        });
+        drop(ro);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_builder_file_window() {
+        let path = tmp_path("file_window");
+        let _ = fs::remove_file(&path);
+        let ps = crate::utils::page_size() as u64;
+        let file_len = 4 * ps;
+        let pattern: Vec<u8> = (0..file_len).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &pattern).expect("write file");
+
+        // Unaligned offset; all offsets are relative to the window
+        let start = ps + 100;
+        let ro = MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadOnly)
+            .offset(start)
+            .len(1000)
+            .open()
+            .expect("open ro window");
+        assert_eq!(ro.len(), 1000);
+        assert_eq!(ro.file_offset(), start);
+        let start_usize = start as usize;
+        assert_eq!(
+            ro.as_slice(0, 1000).expect("slice"),
+            &pattern[start_usize..start_usize + 1000]
+        );
+        assert!(matches!(
+            ro.read_into(990, &mut [0u8; 20]),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        drop(ro);
+
+        // Without a length the window runs to the end of the file
+        let rw = MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadWrite)
+            .offset(start)
+            .open()
+            .expect("open rw window");
+        assert_eq!(rw.len(), file_len - start);
+        rw.update_region(0, b"window").expect("write");
+        rw.flush_with(FlushOptions { invalidate: true })
+            .expect("flush");
+        rw.update_region(ps, b"later").expect("write");
+        rw.flush_range(ps, 5).expect("flush range");
+        assert!(matches!(rw.resize(ps), Err(MmapIoError::InvalidMode(_))));
+        drop(rw);
+        let on_disk = fs::read(&path).expect("read");
+        assert_eq!(on_disk.len() as u64, file_len);
+        assert_eq!(&on_disk[start_usize..start_usize + 6], b"window");
+        assert_eq!(&on_disk[start_usize + ps as usize..][..5], b"later");
+        assert_eq!(&on_disk[..start_usize], &pattern[..start_usize]);
+
+        // Windows past the end, empty windows, and create() are refused
+        let open = |offset: u64, len: Option<u64>| {
+            let builder = MemoryMappedFile::builder(&path).offset(offset);
+            match len {
+                Some(len) => builder.len(len).open(),
+                None => builder.open(),
+            }
+        };
+        assert!(matches!(
+            open(3 * ps, Some(ps + 1)),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            open(file_len, None),
+            Err(MmapIoError::ResizeFailed(_))
+        ));
+        assert!(matches!(
+            MemoryMappedFile::builder(&path).size(ps).len(ps).create(),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_builder_guarded_file_window() {
+        let path = tmp_path("guarded_window");
+        let _ = fs::remove_file(&path);
+        let ps = crate::utils::page_size() as u64;
+        fs::write(&path, vec![0u8; 4 * ps as usize]).expect("write file");
+        let truncate = |len: u64| {
+            OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .and_then(|f| f.set_len(len))
+                .expect("set_len");
+        };
+
+        let start = ps + 100;
+        let mmap = MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadWrite)
+            .offset(start)
+            .len(2 * ps)
+            .guarded(true)
+            .open()
+            .expect("open guarded window");
+        truncate(2 * ps);
+        let err = mmap.update_region(ps, b"lost").expect_err("write past EOF");
+        assert!(matches!(err, MmapIoError::MappingFault { offset } if offset == ps));
+
+        // The pages mapped back are the window's, not the start of the file
+        truncate(4 * ps);
+        mmap.update_region(ps, b"back").expect("write after regrow");
+        drop(mmap);
+        let on_disk = fs::read(&path).expect("read");
+        assert_eq!(&on_disk[(start + ps) as usize..][..4], b"back");
+        assert!(on_disk[..(start + ps) as usize].iter().all(|&b| b == 0));
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_builder_scrub_on_shrink() {
+        let path = tmp_path("scrub_on_shrink");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(8192)
+            .scrub_on_shrink(true)
+            .create()
+            .expect("create");
+        mmap.update_region(0, &[0xA5; 8192]).expect("write");
+        mmap.flush().expect("flush");
+
+        // The scrub writes zeros through to the file, not just the mapping
+        if let MapVariant::Rw(lock) = &mmap.inner.map {
+            scrub_range(&mut lock.write(), 6000, 8192).expect("scrub");
+        }
+        let on_disk = fs::read(&path).expect("read");
+        assert!(on_disk[..6000].iter().all(|&b| b == 0xA5));
+        assert!(on_disk[6000..].iter().all(|&b| b == 0));
+
+        mmap.resize(4096).expect("shrink");
+        assert_eq!(mmap.len(), 4096);
+        assert!(mmap.read_all().expect("read").iter().all(|&b| b == 0xA5));
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_create_with_contents() {
+        let path = tmp_path("contents");
+        let _ = fs::remove_file(&path);
+
+        let mmap =
+            MemoryMappedFile::create_from_slice(&path, b"hello mapped world").expect("create");
+        assert_eq!(mmap.len(), 18);
+        let mut buf = [0u8; 18];
+        mmap.read_into(0, &mut buf).expect("read");
+        assert_eq!(&buf, b"hello mapped world");
+        assert_eq!(fs::read(&path).expect("on disk"), b"hello mapped world");
+        drop(mmap);
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .contents(b"abc")
+            .size(8)
+            .create()
+            .expect("create padded");
+        assert_eq!(mmap.len(), 8);
+        drop(mmap);
+        assert_eq!(fs::read(&path).expect("on disk"), b"abc\0\0\0\0\0");
+
+        assert!(MemoryMappedFile::builder(&path)
+            .contents(b"too long")
+            .size(4)
+            .create()
+            .is_err());
+        assert!(MemoryMappedFile::create_from_slice(&path, b"").is_err());
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_builder_fork_behavior() {
+        let path = tmp_path("fork");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(4096)
+            .fork_behavior(ForkBehavior::DontFork)
+            .create()
+            .expect("create dontfork");
+        assert_eq!(mmap.fork_behavior(), ForkBehavior::DontFork);
+        mmap.resize(8192).expect("resize reapplies advice");
+        mmap.update_region(4096, b"ok").expect("write");
+        drop(mmap);
+
+        // The kernel rejects WIPEONFORK on shared file mappings
+        assert!(MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadWrite)
+            .fork_behavior(ForkBehavior::WipeOnFork)
+            .open()
+            .is_err());
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_read_vectored_into() {
+        let path = tmp_path("vectored");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 64).expect("create");
+        mmap.update_region(8, b"headerbody-bytes").expect("write");
+        let (mut a, mut b, mut empty) = ([0u8; 6], [0u8; 10], [0u8; 0]);
+        let mut bufs = [
+            IoSliceMut::new(&mut a),
+            IoSliceMut::new(&mut empty),
+            IoSliceMut::new(&mut b),
+        ];
+        assert_eq!(mmap.read_vectored_into(8, &mut bufs).expect("read"), 16);
+        assert_eq!((&a, &b), (b"header", b"body-bytes"));
+
+        let mut big = [0u8; 60];
+        assert!(mmap
+            .read_vectored_into(8, &mut [IoSliceMut::new(&mut big)])
+            .is_err());
+
+        drop(mmap);
+        let ro = MemoryMappedFile::open_ro(&path).expect("open ro");
+        let mut bufs = [IoSliceMut::new(&mut a)];
+        ro.read_vectored_into(14, &mut bufs).expect("read ro");
+        assert_eq!(&a, b"body-b");
+
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_read_to_vec_and_read_all() {
+        let path = tmp_path("read_to_vec");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 16).expect("create");
+        mmap.update_region(4, b"owned").expect("write");
+        assert_eq!(mmap.read_to_vec(4, 5).expect("read"), b"owned");
+        assert!(mmap.read_to_vec(4, 0).expect("empty").is_empty());
+        assert!(mmap.read_to_vec(12, 5).is_err());
+        assert!(mmap.read_to_vec(0, u64::MAX).is_err());
+        let all = mmap.read_all().expect("read all");
+        assert_eq!(all.len(), 16);
+        assert_eq!(&all[4..9], b"owned");
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_swap_region_returns_previous_bytes() {
+        let path = tmp_path("swap_region");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 16).expect("create");
+        mmap.update_region(2, b"before").expect("write");
+        let mut prev = [0u8; 6];
+        mmap.swap_region(2, b"after!", &mut prev).expect("swap");
+        assert_eq!(&prev, b"before");
+        assert_eq!(mmap.read_to_vec(2, 6).expect("read"), b"after!");
+
+        // Undo by swapping the old bytes back
+        let mut undone = [0u8; 6];
+        mmap.swap_region(2, &prev, &mut undone).expect("undo");
+        assert_eq!(&undone, b"after!");
+        assert_eq!(mmap.read_to_vec(2, 6).expect("read"), b"before");
+
+        assert!(matches!(
+            mmap.swap_region(0, b"abc", &mut [0u8; 2]),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+        assert!(matches!(
+            mmap.swap_region(14, b"abc", &mut [0u8; 3]),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        drop(mmap);
+        let ro = MemoryMappedFile::open_ro(&path).expect("ro");
+        assert!(matches!(
+            ro.swap_region(0, b"x", &mut [0u8; 1]),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_compare_and_swap_region() {
+        let path = tmp_path("cas_region");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 16).expect("create");
+        mmap.update_region(0, b"v1").expect("write");
+        assert!(mmap
+            .compare_and_swap_region(0, b"v1", b"v2")
+            .expect("cas hit"));
+        // A stale expectation fails and leaves the bytes alone
+        assert!(!mmap
+            .compare_and_swap_region(0, b"v1", b"v3")
+            .expect("cas miss"));
+        assert_eq!(mmap.read_to_vec(0, 2).expect("read"), b"v2");
+
+        assert!(matches!(
+            mmap.compare_and_swap_region(0, b"v2", b"v22"),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+        assert!(matches!(
+            mmap.compare_and_swap_region(15, b"ab", b"cd"),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+
+        // Racing increments of a shared counter: every success is counted exactly once
+        let mmap = Arc::new(mmap);
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let mmap = Arc::clone(&mmap);
+                std::thread::spawn(move || {
+                    for _ in 0..250 {
+                        loop {
+                            let current = mmap.read_to_vec(8, 8).expect("read");
+                            let n = u64::from_le_bytes(current.clone().try_into().expect("u64"));
+                            if mmap
+                                .compare_and_swap_region(8, &current, &(n + 1).to_le_bytes())
+                                .expect("cas")
+                            {
+                                break;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().expect("worker");
+        }
+        assert_eq!(mmap.read_to_vec(8, 8).expect("read"), 1000u64.to_le_bytes());
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_raw_fd_accessors() {
+        use std::os::fd::{AsFd, AsRawFd};
+
+        let path = tmp_path("raw_fd");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 4096).expect("create");
+        let file = File::from(mmap.as_fd().try_clone_to_owned().expect("dup"));
+        assert_eq!(file.metadata().expect("fstat").len(), 4096);
+        // SAFETY: fstat on a valid descriptor into a zeroed stat buffer.
+        let mut st: libc::stat = unsafe { std::mem::zeroed() };
+        assert_eq!(unsafe { libc::fstat(mmap.as_raw_fd(), &mut st) }, 0);
+        assert_eq!(st.st_size, 4096);
+
+        drop((mmap, file));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_into_file_returns_backing_file() {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let path = tmp_path("into_file");
+        let _ = fs::remove_file(&path);
+
+        let trace = tmp_path("into_file_trace");
+        let mmap = MemoryMappedFile::create_rw(&path, 32).expect("create");
+        mmap.start_recording(&trace).expect("record");
+        mmap.update_region(0, b"mapped").expect("write");
+        let clone = mmap.clone();
+        assert!(matches!(
+            clone.into_file(),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+        // The failed call leaves the shared recording alone
+        assert!(mmap.is_recording());
+
+        let mut file = mmap.into_file().expect("into file");
+        assert!(fs::metadata(&trace).expect("trace").len() > 0);
+        let mut buf = [0u8; 6];
+        file.seek(SeekFrom::Start(0)).expect("seek");
+        file.read_exact(&mut buf).expect("read");
+        assert_eq!(&buf, b"mapped");
+        assert_eq!(file.metadata().expect("metadata").len(), 32);
+
+        drop(file);
+        fs::remove_file(&path).expect("cleanup");
+        fs::remove_file(&trace).expect("cleanup trace");
+    }
+
+    #[test]
+    fn test_leak_read_only_mapping() {
+        let path = tmp_path("leak");
+        let _ = fs::remove_file(&path);
+        fs::write(&path, b"static config").expect("write");
+
+        let rw = MemoryMappedFile::open_rw(&path).expect("open rw");
+        assert!(matches!(rw.leak(), Err(MmapIoError::InvalidMode(_))));
+        let ro = MemoryMappedFile::open_ro(&path).expect("open ro");
+        assert!(ro.clone().leak().is_err());
+
+        let bytes: &'static [u8] = ro.leak().expect("leak");
+        let handle = std::thread::spawn(move || bytes.len());
+        assert_eq!(handle.join().expect("join"), 13);
+        assert_eq!(bytes, b"static config");
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_slice_owned_outlives_handle() {
+        let path = tmp_path("slice_owned");
+        let _ = fs::remove_file(&path);
+        fs::write(&path, b"header|payload").expect("write");
+
+        let mmap = MemoryMappedFile::open_ro(&path).expect("open ro");
+        let payload = mmap.slice_owned(7, 7).expect("slice");
+        assert!(mmap.slice_owned(10, 7).is_err());
+        drop(mmap);
+        let worker = std::thread::spawn(move || payload.to_vec());
+        assert_eq!(worker.join().expect("join"), b"payload");
+
+        let rw = MemoryMappedFile::open_rw(&path).expect("open rw");
+        assert!(matches!(
+            rw.slice_owned(0, 6),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+
+        drop(rw);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_builder_address_hint() {
+        let path = tmp_path("address_hint");
+        let _ = fs::remove_file(&path);
+
+        // Find free address space by reserving and releasing it
+        let free = MmapMut::map_anon(4 << 20).expect("reserve");
+        let addr = crate::utils::align_up(free.as_ptr() as u64, 1 << 20) as usize;
+        drop(free);
+        let base =
+            |m: &MemoryMappedFile| m.as_slice_mut(0, 1).expect("slice").as_mut().as_ptr() as usize;
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(8192)
+            .address_hint(addr)
+            .create()
+            .expect("create at hint");
+        mmap.update_region(0, b"fixed").expect("write");
+        assert_eq!(base(&mmap), addr);
+
+        // Occupied addresses are never replaced
+        assert!(MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadWrite)
+            .address_hint(addr)
+            .open()
+            .is_err());
+
+        mmap.resize(3 * 4096).expect("grow in place");
+        assert_eq!(base(&mmap), addr);
+        mmap.update_region(8192, b"grown").expect("write grown");
+        mmap.flush().expect("flush");
+        drop(mmap);
+
+        let reopened = MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadWrite)
+            .address_hint(addr)
+            .open()
+            .expect("reopen at hint");
+        let mut buf = [0u8; 5];
+        reopened.read_into(8192, &mut buf).expect("read");
+        assert_eq!(&buf, b"grown");
+        drop(reopened);
+
+        assert!(MemoryMappedFile::builder(&path)
+            .address_hint(addr)
+            .open()
+            .is_err());
+        assert!(MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadWrite)
+            .address_hint(addr + 1)
+            .open()
+            .is_err());
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_builder_max_size() {
+        let path = tmp_path("max_size");
+        let _ = fs::remove_file(&path);
+
+        let err = MemoryMappedFile::builder(&path)
+            .size(8192)
+            .max_size(4096)
+            .create()
+            .expect_err("over limit");
+        assert!(err.to_string().contains("limit of 4096 bytes"), "{err}");
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(4096)
+            .max_size(4096)
+            .create()
+            .expect("create at limit");
+        assert!(mmap.resize(4097).is_err());
+        mmap.resize(2048).expect("shrink");
+        drop(mmap);
+
+        assert!(MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadWrite)
+            .max_size(1024)
+            .open()
+            .is_err());
+        // Without an override the process-wide default applies
+        assert_eq!(max_mmap_size(), MAX_MMAP_SIZE);
+        MemoryMappedFile::builder(&path)
+            .mode(MmapMode::ReadWrite)
+            .open()
+            .expect("open without override")
+            .resize(8192)
+            .expect("grow");
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_flush_ranges_coalesces() {
+        let path = tmp_path("flush_ranges");
+        let _ = fs::remove_file(&path);
+
+        let page = crate::utils::page_size() as u64;
+        let mmap = MemoryMappedFile::create_rw(&path, 64 * page).expect("create");
+        // Nothing written yet: no syscalls
+        assert_eq!(mmap.flush_ranges(&[(0, 10)], 8).expect("noop"), 0);
+
+        let mut ranges = Vec::new();
+        for i in 0..1000u64 {
+            let offset = (i * 37) % (16 * page);
+            mmap.update_region(offset, &[1]).expect("write");
+            ranges.push((offset, 1));
+        }
+        mmap.update_region(40 * page, b"far").expect("write far");
+        ranges.push((40 * page, 3));
+        assert_eq!(mmap.flush_ranges(&ranges, 8).expect("flush"), 2);
+        mmap.update_region(0, b"x").expect("write");
+        assert_eq!(mmap.flush_ranges(&ranges, 1).expect("flush"), 1);
+        assert!(mmap.flush_ranges(&[(64 * page, 1)], 8).is_err());
+        drop(mmap);
+
+        let ro = MemoryMappedFile::open_ro(&path).expect("open");
+        assert_eq!(ro.as_slice(40 * page, 3).expect("slice"), b"far");
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_flush_with_invalidate() {
+        let path = tmp_path("flush_invalidate");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 3 * 4096).expect("create");
+        let reader = MemoryMappedFile::open_ro(&path).expect("reader");
+        let invalidate = FlushOptions { invalidate: true };
+
+        mmap.update_region(4100, b"shared").expect("write");
+        // Unaligned ranges are rounded to pages internally
+        mmap.flush_range_with(4100, 6, invalidate)
+            .expect("flush range");
+        assert_eq!(reader.as_slice(4100, 6).expect("read"), b"shared");
+        // Runs without pending writes too, and on the read-only side
+        mmap.flush_with(invalidate).expect("flush all");
+        reader.flush_with(invalidate).expect("reader invalidate");
+        mmap.flush_with(FlushOptions::default())
+            .expect("plain flush");
+        assert!(matches!(
+            mmap.flush_range_with(3 * 4096 - 1, 2, invalidate),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+
+        drop((mmap, reader));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_close_async_persists_writes() {
+        let path = tmp_path("close_async");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 64).expect("create");
+        let other = mmap.clone();
+        mmap.update_region(0, b"closing").expect("write");
+        mmap.close_async().await.expect("close");
+        // The surviving clone still works and sees the data
+        assert_eq!(other.read_to_vec(0, 7).expect("read"), b"closing");
+        other.close_async().await.expect("close last");
+        assert_eq!(&fs::read(&path).expect("read file")[..7], b"closing");
+
+        let ro = MemoryMappedFile::open_ro(&path).expect("ro");
+        ro.close_async().await.expect("close ro");
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_flush_dirty_tracks_written_pages() {
+        let path = tmp_path("flush_dirty");
+        let _ = fs::remove_file(&path);
+
+        let page = crate::utils::page_size() as u64;
+        let mmap = MemoryMappedFile::create_rw(&path, 16 * page + 100).expect("create");
+        assert!(mmap.dirty_ranges().is_empty());
+        mmap.update_region(page + 5, b"a").expect("write");
+        mmap.update_region(7 * page + 10, &vec![1u8; page as usize])
+            .expect("write across pages");
+        mmap.update_region(16 * page + 50, b"tail")
+            .expect("write tail");
+        assert_eq!(
+            mmap.dirty_ranges(),
+            vec![(page, page), (7 * page, 2 * page), (16 * page, 100)]
+        );
+        assert_eq!(mmap.flush_dirty().expect("flush dirty"), 3);
+        assert!(mmap.dirty_ranges().is_empty());
+        assert_eq!(mmap.flush_dirty().expect("nothing dirty"), 0);
+
+        // A ranged flush leaves other pages pending for the next flush
+        mmap.update_region(0, b"x").expect("write");
+        mmap.update_region(10 * page, b"y").expect("write");
+        mmap.flush_range(0, 1).expect("flush range");
+        assert_eq!(mmap.dirty_ranges(), vec![(10 * page, page)]);
+        mmap.flush().expect("flush");
+        assert!(mmap.dirty_ranges().is_empty());
+        drop(mmap);
+
+        let on_disk = fs::read(&path).expect("read");
+        assert_eq!(on_disk[(page + 5) as usize], b'a');
+        assert_eq!(on_disk[(10 * page) as usize], b'y');
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_flush_parallel() {
+        let path = tmp_path("flush_parallel");
+        let _ = fs::remove_file(&path);
+
+        let page = crate::utils::page_size() as u64;
+        let len = 10 * page + 123;
+        let mmap = MemoryMappedFile::create_rw(&path, len).expect("create");
+        for i in 0..=10 {
+            mmap.update_region(i * page, &[i as u8 + 1; 100])
+                .expect("write");
+        }
+        // More workers than pages, several, and the inline path all cover every byte
+        for concurrency in [64, 3, 0] {
+            mmap.flush_parallel(concurrency).expect("flush");
+        }
+        let on_disk = fs::read(&path).expect("read");
+        assert_eq!(on_disk.len() as u64, len);
+        for i in 0..=10u64 {
+            assert_eq!(on_disk[(i * page) as usize], i as u8 + 1);
+        }
+
+        drop(mmap);
+        let ro = MemoryMappedFile::open_ro(&path).expect("ro");
+        ro.flush_parallel(4).expect("ro no-op");
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_flush_and_sync_metadata() {
+        let path = tmp_path("sync_metadata");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 4096).expect("create");
+        mmap.sync_parent_directory().expect("sync dir");
+        mmap.resize(8192).expect("grow");
+        mmap.update_region(8000, b"tail").expect("write");
+        mmap.flush_and_sync_metadata().expect("sync");
+        // Durable even without pending writes
+        mmap.flush_and_sync_metadata().expect("sync again");
+        drop(mmap);
+
+        assert_eq!(fs::metadata(&path).expect("meta").len(), 8192);
+        let ro = MemoryMappedFile::open_ro(&path).expect("open");
+        assert_eq!(ro.as_slice(8000, 4).expect("slice"), b"tail");
+        ro.flush_and_sync_metadata().expect("ro no-op");
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_builder_strict_flush() {
+        let path = tmp_path("strict_flush");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(8192)
+            .strict_flush(true)
+            .create()
+            .expect("create");
+        mmap.update_region(0, b"strict").expect("write");
+        mmap.flush().expect("flush");
+        mmap.update_region(4096, b"range").expect("write");
+        mmap.flush_range(4096, 5).expect("flush range");
+        drop(mmap);
+
+        let ro = MemoryMappedFile::open_ro(&path).expect("open");
+        assert_eq!(ro.as_slice(0, 6).expect("slice"), b"strict");
+        assert_eq!(ro.as_slice(4096, 5).expect("slice"), b"range");
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
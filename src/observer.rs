@@ -0,0 +1,159 @@
+//! Pluggable observer hooks for mapping operations.
+//!
+//! An observer receives a callback for every successful write, flush, and resize performed
+//! through a [`MemoryMappedFile`](crate::MemoryMappedFile), plus any error those operations
+//! return. Typical uses are replicating writes to a follower or feeding custom telemetry.
+
+use std::sync::Arc;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+/// Operation reported alongside an error to [`MmapObserver::on_error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MmapOperation {
+    /// `update_region` and the APIs built on it.
+    Write,
+    /// `flush` / `flush_range`.
+    Flush,
+    /// `resize`.
+    Resize,
+}
+
+/// Callbacks invoked by a mapping after operations complete.
+///
+/// All methods default to no-ops, so implementors only override what they need.
+/// Callbacks run synchronously on the calling thread after internal locks are released;
+/// keep them cheap or hand work off to another thread.
+pub trait MmapObserver: Send + Sync {
+    /// Called after `data` has been copied into the mapping at `offset`.
+    ///
+    /// Writes performed directly through `as_slice_mut` guards are not reported.
+    fn on_write(&self, offset: u64, data: &[u8]) {
+        let _ = (offset, data);
+    }
+
+    /// Called after a flush that performed I/O for `[offset, offset + len)`.
+    fn on_flush(&self, offset: u64, len: u64) {
+        let _ = (offset, len);
+    }
+
+    /// Called after the mapping has been resized from `old_len` to `new_len` bytes.
+    fn on_resize(&self, old_len: u64, new_len: u64) {
+        let _ = (old_len, new_len);
+    }
+
+    /// Called when `op` fails with `error` (before the error is returned to the caller).
+    fn on_error(&self, op: MmapOperation, error: &MmapIoError) {
+        let _ = (op, error);
+    }
+}
+
+impl MemoryMappedFile {
+    /// Install an observer for this mapping, replacing any previous one.
+    ///
+    /// The observer is shared by all clones of this `MemoryMappedFile`.
+    pub fn set_observer(&self, observer: Arc<dyn MmapObserver>) {
+        *self.inner.observer.write() = Some(observer);
+    }
+
+    /// Remove the installed observer, returning it if one was set.
+    pub fn clear_observer(&self) -> Option<Arc<dyn MmapObserver>> {
+        self.inner.observer.write().take()
+    }
+
+    // Run `f` against the current observer, if any, without holding the slot lock.
+    pub(crate) fn observe<F: FnOnce(&dyn MmapObserver)>(&self, f: F) {
+        let observer = self.inner.observer.read().clone();
+        if let Some(observer) = observer {
+            f(observer.as_ref());
+        }
+    }
+
+    // Report an error result for `op` to the observer and pass the result through.
+    pub(crate) fn observed<T>(&self, op: MmapOperation, res: Result<T>) -> Result<T> {
+        if let Err(e) = &res {
+            self.observe(|o| o.on_error(op, e));
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use parking_lot::Mutex;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_observer_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[derive(Default)]
+    struct Recording {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl MmapObserver for Recording {
+        fn on_write(&self, offset: u64, data: &[u8]) {
+            self.events
+                .lock()
+                .push(format!("write {offset} {}", data.len()));
+        }
+
+        fn on_flush(&self, offset: u64, len: u64) {
+            self.events.lock().push(format!("flush {offset} {len}"));
+        }
+
+        fn on_resize(&self, old_len: u64, new_len: u64) {
+            self.events
+                .lock()
+                .push(format!("resize {old_len} {new_len}"));
+        }
+
+        fn on_error(&self, op: MmapOperation, _error: &MmapIoError) {
+            self.events.lock().push(format!("error {op:?}"));
+        }
+    }
+
+    #[test]
+    fn test_observer_receives_events() {
+        let path = tmp_path("events");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 1024).expect("create");
+        let obs = Arc::new(Recording::default());
+        mmap.set_observer(obs.clone());
+
+        mmap.update_region(10, b"hello").expect("write");
+        mmap.flush().expect("flush");
+        mmap.resize(2048).expect("resize");
+        assert!(mmap.update_region(2047, b"xx").is_err());
+
+        assert_eq!(
+            *obs.events.lock(),
+            vec![
+                "write 10 5".to_string(),
+                "flush 0 1024".to_string(),
+                "resize 1024 2048".to_string(),
+                "error Write".to_string(),
+            ]
+        );
+
+        // After clearing, no further events are delivered
+        assert!(mmap.clear_observer().is_some());
+        mmap.update_region(0, b"x").expect("write");
+        assert_eq!(obs.events.lock().len(), 4);
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
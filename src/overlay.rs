@@ -0,0 +1,570 @@
+//! Durable copy-on-write overlay: a writable delta file layered over a base mapping.
+//!
+//! [`OverlayMmap`] never modifies the base. The first write to a page copies that page
+//! into the delta file and every later read or write of it goes there; untouched pages
+//! are read straight from the base. Unlike `MAP_PRIVATE` copy-on-write, the delta is an
+//! ordinary file, so changes survive process exit and are picked up again by
+//! [`OverlayMmap::open`]. [`OverlayMmap::merge`] folds the delta back into a writable base
+//! and empties it.
+//!
+//! Delta file layout (all integers little-endian):
+//!
+//! | bytes  | field                                                    |
+//! |--------|----------------------------------------------------------|
+//! | 0..8   | magic `MMIOOVLY`                                         |
+//! | 8..12  | format version (currently 1)                             |
+//! | 12..16 | overlay page size ([`OVERLAY_PAGE_SIZE`])                |
+//! | 16..24 | length of the base the delta was created against         |
+//! | 24..32 | number of committed slots                                |
+//! | 32..   | slots: base page number (8) followed by the page (4096)  |
+//!
+//! A slot is filled before the slot count is bumped, so a crash mid-write loses at most the
+//! page being copied in, never the index.
+//!
+//! To ship changes elsewhere, [`OverlayMmap::export_delta`] writes the modified pages as a
+//! [`Patch`] file and [`apply_delta`] applies it to another copy of the base.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use parking_lot::Mutex;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::{MemoryMappedFile, MmapMode};
+use crate::patch::{Patch, PatchOp};
+use crate::utils::ensure_in_bounds;
+
+/// Granularity at which pages are copied into the delta.
+pub const OVERLAY_PAGE_SIZE: u64 = 4096;
+
+const MAGIC: &[u8; 8] = b"MMIOOVLY";
+const FORMAT_VERSION: u32 = 1;
+const HEADER_LEN: u64 = 32;
+const COUNT_OFFSET: u64 = 24;
+const SLOT_LEN: u64 = 8 + OVERLAY_PAGE_SIZE;
+// Slots added per delta resize
+const GROW_SLOTS: u64 = 16;
+
+/// Writable view of a base mapping whose changes are kept in a separate delta file.
+pub struct OverlayMmap {
+    base: MemoryMappedFile,
+    state: Mutex<Delta>,
+}
+
+struct Delta {
+    file: MemoryMappedFile,
+    // Base page number -> slot index
+    index: HashMap<u64, u64>,
+    // Slot count committed in the header; the next page goes in this slot
+    slots: u64,
+}
+
+impl Delta {
+    fn slot_data(slot: u64) -> u64 {
+        HEADER_LEN + slot * SLOT_LEN + 8
+    }
+
+    fn read_u64(&self, offset: u64) -> Result<u64> {
+        let mut raw = [0u8; 8];
+        self.file.read_into(offset, &mut raw)?;
+        Ok(u64::from_le_bytes(raw))
+    }
+}
+
+impl OverlayMmap {
+    /// Layer the delta file at `delta_path` over `base`, creating it if it does not exist.
+    ///
+    /// An existing delta must have been created against a base of the same length.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the delta has a bad header, a different page size
+    /// or base length, or an out-of-range or repeated slot.
+    /// Returns `MmapIoError::Io` if the delta cannot be created or mapped.
+    pub fn open<P: AsRef<Path>>(base: MemoryMappedFile, delta_path: P) -> Result<Self> {
+        let path = delta_path.as_ref();
+        let base_len = base.current_len()?;
+        let file = if path.exists() {
+            MemoryMappedFile::open_rw(path)?
+        } else {
+            let file = MemoryMappedFile::create_rw(path, HEADER_LEN)?;
+            let mut header = [0u8; HEADER_LEN as usize];
+            header[0..8].copy_from_slice(MAGIC);
+            header[8..12].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+            header[12..16].copy_from_slice(&(OVERLAY_PAGE_SIZE as u32).to_le_bytes());
+            header[16..24].copy_from_slice(&base_len.to_le_bytes());
+            file.update_region(0, &header)?;
+            file.flush()?;
+            file
+        };
+        let delta = Self::load(file, base_len)?;
+        Ok(Self {
+            base,
+            state: Mutex::new(delta),
+        })
+    }
+
+    fn load(file: MemoryMappedFile, base_len: u64) -> Result<Delta> {
+        let mut header = [0u8; HEADER_LEN as usize];
+        file.read_into(0, &mut header)
+            .map_err(|_| MmapIoError::Corrupt("overlay delta too short for header".into()))?;
+        if &header[0..8] != MAGIC {
+            return Err(MmapIoError::Corrupt("missing overlay delta magic".into()));
+        }
+        let field = |at: usize| {
+            let mut b = [0u8; 8];
+            b.copy_from_slice(&header[at..at + 8]);
+            u64::from_le_bytes(b)
+        };
+        let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if version != FORMAT_VERSION {
+            return Err(MmapIoError::Corrupt(format!(
+                "unsupported overlay delta version {version}"
+            )));
+        }
+        let page = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+        if u64::from(page) != OVERLAY_PAGE_SIZE {
+            return Err(MmapIoError::Corrupt(format!(
+                "overlay delta page size {page} differs from {OVERLAY_PAGE_SIZE}"
+            )));
+        }
+        if field(16) != base_len {
+            return Err(MmapIoError::Corrupt(format!(
+                "overlay delta was created for a {}-byte base, not {base_len}",
+                field(16)
+            )));
+        }
+        let count = field(COUNT_OFFSET as usize);
+        let end = count
+            .checked_mul(SLOT_LEN)
+            .and_then(|n| n.checked_add(HEADER_LEN));
+        let file_len = file.current_len()?;
+        if !matches!(end, Some(end) if end <= file_len) {
+            return Err(MmapIoError::Corrupt(format!(
+                "overlay delta slot count {count} exceeds the file"
+            )));
+        }
+        // Not pre-sized: the count comes from the file
+        let mut delta = Delta {
+            file,
+            index: HashMap::new(),
+            slots: count,
+        };
+        let pages = base_len.div_ceil(OVERLAY_PAGE_SIZE);
+        for slot in 0..count {
+            let page_no = delta.read_u64(HEADER_LEN + slot * SLOT_LEN)?;
+            if page_no >= pages {
+                return Err(MmapIoError::Corrupt(format!(
+                    "overlay slot {slot} names page {page_no} past the end of the base"
+                )));
+            }
+            if delta.index.insert(page_no, slot).is_some() {
+                return Err(MmapIoError::Corrupt(format!(
+                    "overlay slot {slot} repeats page {page_no}"
+                )));
+            }
+        }
+        Ok(delta)
+    }
+
+    /// Length of the view, which is always the base length.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.base.len()
+    }
+
+    /// Whether the view is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The underlying base mapping (without the delta applied).
+    #[must_use]
+    pub fn base(&self) -> &MemoryMappedFile {
+        &self.base
+    }
+
+    /// Number of base pages currently held in the delta.
+    #[must_use]
+    pub fn dirty_pages(&self) -> usize {
+        self.state.lock().index.len()
+    }
+
+    /// Copy `buf.len()` bytes at `offset` of the overlaid view into `buf`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the range exceeds the base.
+    pub fn read_into(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let total = self.len();
+        ensure_in_bounds(offset, buf.len() as u64, total)?;
+        let state = self.state.lock();
+        let mut done = 0usize;
+        while done < buf.len() {
+            let pos = offset + done as u64;
+            let page_no = pos / OVERLAY_PAGE_SIZE;
+            let in_page = pos % OVERLAY_PAGE_SIZE;
+            let n = ((OVERLAY_PAGE_SIZE - in_page) as usize).min(buf.len() - done);
+            let chunk = &mut buf[done..done + n];
+            match state.index.get(&page_no) {
+                Some(&slot) => state
+                    .file
+                    .read_into(Delta::slot_data(slot) + in_page, chunk)?,
+                None => self.base.read_into(pos, chunk)?,
+            }
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// Write `data` at `offset` into the delta, copying touched pages from the base first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the range exceeds the base.
+    /// Returns `MmapIoError::Io` / `ResizeFailed` if the delta cannot grow.
+    pub fn update_region(&self, offset: u64, data: &[u8]) -> Result<()> {
+        let total = self.len();
+        ensure_in_bounds(offset, data.len() as u64, total)?;
+        let mut state = self.state.lock();
+        let mut done = 0usize;
+        while done < data.len() {
+            let pos = offset + done as u64;
+            let page_no = pos / OVERLAY_PAGE_SIZE;
+            let in_page = pos % OVERLAY_PAGE_SIZE;
+            let n = ((OVERLAY_PAGE_SIZE - in_page) as usize).min(data.len() - done);
+            let slot = match state.index.get(&page_no) {
+                Some(&slot) => slot,
+                None => self.copy_in(&mut state, page_no, total)?,
+            };
+            state
+                .file
+                .update_region(Delta::slot_data(slot) + in_page, &data[done..done + n])?;
+            done += n;
+        }
+        Ok(())
+    }
+
+    // Copy base page `page_no` into a new slot and commit it to the index.
+    fn copy_in(&self, state: &mut Delta, page_no: u64, total: u64) -> Result<u64> {
+        let slot = state.slots;
+        let needed = HEADER_LEN + (slot + 1) * SLOT_LEN;
+        if state.file.current_len()? < needed {
+            state.file.resize(needed + (GROW_SLOTS - 1) * SLOT_LEN)?;
+        }
+        let start = page_no * OVERLAY_PAGE_SIZE;
+        let mut page = vec![0u8; OVERLAY_PAGE_SIZE as usize];
+        let n = (total - start).min(OVERLAY_PAGE_SIZE) as usize;
+        self.base.read_into(start, &mut page[..n])?;
+        let slot_at = HEADER_LEN + slot * SLOT_LEN;
+        state.file.update_region(slot_at + 8, &page)?;
+        state.file.update_region(slot_at, &page_no.to_le_bytes())?;
+        // Commit point: the slot only counts once the header says so
+        state
+            .file
+            .update_region(COUNT_OFFSET, &(slot + 1).to_le_bytes())?;
+        state.slots = slot + 1;
+        state.index.insert(page_no, slot);
+        Ok(slot)
+    }
+
+    /// Flush the delta file to disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from [`MemoryMappedFile::flush`].
+    pub fn flush(&self) -> Result<()> {
+        self.state.lock().file.flush()
+    }
+
+    /// Write every delta page back into the base, flush it, and empty the delta.
+    ///
+    /// If this fails part-way the delta is left intact, so it can simply be retried.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` unless the base is mapped `ReadWrite`.
+    /// Returns errors from writing or flushing the base and the delta.
+    pub fn merge(&self) -> Result<()> {
+        if self.base.mode() != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(
+                "merging an overlay requires a ReadWrite base",
+            ));
+        }
+        let mut state = self.state.lock();
+        let total = self.len();
+        let mut page = vec![0u8; OVERLAY_PAGE_SIZE as usize];
+        for (&page_no, &slot) in &state.index {
+            let start = page_no * OVERLAY_PAGE_SIZE;
+            let n = (total - start).min(OVERLAY_PAGE_SIZE) as usize;
+            state
+                .file
+                .read_into(Delta::slot_data(slot), &mut page[..n])?;
+            self.base.update_region(start, &page[..n])?;
+        }
+        self.base.flush()?;
+        // Base is durable; now drop the slots
+        state
+            .file
+            .update_region(COUNT_OFFSET, &0u64.to_le_bytes())?;
+        state.file.flush()?;
+        state.index.clear();
+        state.slots = 0;
+        state.file.resize(HEADER_LEN)?;
+        Ok(())
+    }
+}
+
+impl OverlayMmap {
+    /// The modified pages as a [`Patch`] against the base, adjacent pages merged into one op.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from reading the delta.
+    pub fn delta_patch(&self) -> Result<Patch> {
+        let state = self.state.lock();
+        let total = self.len();
+        let mut pages: Vec<(u64, u64)> = state.index.iter().map(|(&p, &s)| (p, s)).collect();
+        pages.sort_unstable();
+        let mut ops: Vec<PatchOp> = Vec::new();
+        for (page_no, slot) in pages {
+            let start = page_no * OVERLAY_PAGE_SIZE;
+            let n = (total - start).min(OVERLAY_PAGE_SIZE) as usize;
+            let mut page = vec![0u8; n];
+            state.file.read_into(Delta::slot_data(slot), &mut page)?;
+            match ops.last_mut() {
+                Some(op) if op.offset + op.data.len() as u64 == start => {
+                    op.data.extend_from_slice(&page);
+                }
+                _ => ops.push(PatchOp {
+                    offset: start,
+                    data: page,
+                }),
+            }
+        }
+        Ok(Patch::from_ops(total, total, ops))
+    }
+
+    /// Write the modified pages to `path` in the [`Patch`] wire format, for [`apply_delta`].
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from reading the delta or writing `path`.
+    pub fn export_delta<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.delta_patch()?.to_bytes())?;
+        Ok(())
+    }
+}
+
+/// Apply a delta written by [`OverlayMmap::export_delta`] to `base`, reproducing the
+/// overlaid contents in place.
+///
+/// # Errors
+///
+/// Returns `MmapIoError::Corrupt` if the file is not a valid patch or was exported against a
+/// base of a different length.
+/// Returns errors from [`MemoryMappedFile::apply_patch`] (e.g. `InvalidMode` for RO maps).
+pub fn apply_delta<P: AsRef<Path>>(base: &MemoryMappedFile, delta_path: P) -> Result<()> {
+    let patch = Patch::from_bytes(&fs::read(delta_path)?)?;
+    base.apply_patch(&patch)
+}
+
+impl std::fmt::Debug for OverlayMmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("OverlayMmap")
+            .field("base", &self.base.path())
+            .field("delta", &state.file.path())
+            .field("dirty_pages", &state.index.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_overlay_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_overlay_persists_and_merges() {
+        let base_path = tmp_path("base");
+        let delta_path = tmp_path("delta");
+        let _ = fs::remove_file(&delta_path);
+        let len = 3 * OVERLAY_PAGE_SIZE + 100;
+        let base = MemoryMappedFile::create_rw(&base_path, len).expect("create base");
+        base.update_region(0, &vec![b'b'; len as usize])
+            .expect("fill");
+        base.flush().expect("flush base");
+        drop(base);
+
+        let overlay = OverlayMmap::open(
+            MemoryMappedFile::open_ro(&base_path).expect("ro base"),
+            &delta_path,
+        )
+        .expect("open overlay");
+        // Straddles pages 0 and 1; the last, partial page is written too
+        overlay
+            .update_region(OVERLAY_PAGE_SIZE - 2, b"wxyz")
+            .expect("write");
+        overlay.update_region(len - 1, b"!").expect("write tail");
+        assert_eq!(overlay.dirty_pages(), 3);
+        assert!(overlay.update_region(len, b"x").is_err());
+        let mut buf = [0u8; 6];
+        overlay
+            .read_into(OVERLAY_PAGE_SIZE - 3, &mut buf)
+            .expect("read");
+        assert_eq!(&buf, b"bwxyzb");
+        // The base is untouched
+        assert_eq!(
+            fs::read(&base_path).expect("read base")[OVERLAY_PAGE_SIZE as usize],
+            b'b'
+        );
+        assert!(matches!(overlay.merge(), Err(MmapIoError::InvalidMode(_))));
+        overlay.flush().expect("flush");
+        drop(overlay);
+
+        // Changes survive reopening, and merge folds them into a writable base
+        let overlay = OverlayMmap::open(
+            MemoryMappedFile::open_rw(&base_path).expect("rw base"),
+            &delta_path,
+        )
+        .expect("reopen");
+        assert_eq!(overlay.dirty_pages(), 3);
+        overlay.merge().expect("merge");
+        assert_eq!(overlay.dirty_pages(), 0);
+        let on_disk = fs::read(&base_path).expect("read base");
+        assert_eq!(&on_disk[(OVERLAY_PAGE_SIZE - 2) as usize..][..4], b"wxyz");
+        assert_eq!(on_disk[len as usize - 1], b'!');
+        assert_eq!(on_disk.len() as u64, len);
+        drop(overlay);
+
+        let short = MemoryMappedFile::create_rw(tmp_path("short"), 10).expect("short base");
+        assert!(matches!(
+            OverlayMmap::open(short, &delta_path),
+            Err(MmapIoError::Corrupt(_))
+        ));
+
+        fs::remove_file(&base_path).expect("cleanup base");
+        fs::remove_file(&delta_path).expect("cleanup delta");
+        fs::remove_file(tmp_path("short")).expect("cleanup short");
+    }
+
+    #[test]
+    fn test_export_and_apply_delta() {
+        let base_path = tmp_path("export_base");
+        let replica_path = tmp_path("export_replica");
+        let delta_path = tmp_path("export_delta");
+        let patch_path = tmp_path("export_patch");
+        let _ = fs::remove_file(&delta_path);
+        let len = 8 * OVERLAY_PAGE_SIZE;
+        MemoryMappedFile::create_rw(&base_path, len).expect("create base");
+        fs::copy(&base_path, &replica_path).expect("copy replica");
+
+        let overlay = OverlayMmap::open(
+            MemoryMappedFile::open_ro(&base_path).expect("ro base"),
+            &delta_path,
+        )
+        .expect("open overlay");
+        overlay.update_region(10, b"first").expect("write 0");
+        overlay
+            .update_region(2 * OVERLAY_PAGE_SIZE - 1, b"ab")
+            .expect("write 1-2");
+        overlay
+            .update_region(6 * OVERLAY_PAGE_SIZE, b"far")
+            .expect("write 6");
+        let patch = overlay.delta_patch().expect("patch");
+        // Pages 0-2 merge into one op; page 6 stands alone
+        let spans: Vec<_> = patch
+            .ops()
+            .iter()
+            .map(|op| (op.offset, op.data.len() as u64))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 3 * OVERLAY_PAGE_SIZE),
+                (6 * OVERLAY_PAGE_SIZE, OVERLAY_PAGE_SIZE)
+            ]
+        );
+        overlay.export_delta(&patch_path).expect("export");
+
+        let replica = MemoryMappedFile::open_rw(&replica_path).expect("replica");
+        apply_delta(&replica, &patch_path).expect("apply");
+        let mut expected = vec![0u8; len as usize];
+        let mut actual = vec![0u8; len as usize];
+        overlay.read_into(0, &mut expected).expect("read overlay");
+        replica.read_into(0, &mut actual).expect("read replica");
+        assert_eq!(actual, expected);
+        let short = MemoryMappedFile::create_rw(tmp_path("export_short"), 10).expect("short");
+        assert!(matches!(
+            apply_delta(&short, &patch_path),
+            Err(MmapIoError::Corrupt(_))
+        ));
+
+        drop((overlay, replica, short));
+        for path in [
+            base_path,
+            replica_path,
+            delta_path,
+            patch_path,
+            tmp_path("export_short"),
+        ] {
+            fs::remove_file(path).expect("cleanup");
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_bad_slots() {
+        let base_path = tmp_path("bad_base");
+        let delta_path = tmp_path("bad_delta");
+        let _ = fs::remove_file(&delta_path);
+        let base = MemoryMappedFile::create_rw(&base_path, 4 * OVERLAY_PAGE_SIZE).expect("base");
+        let overlay = OverlayMmap::open(base, &delta_path).expect("open overlay");
+        overlay.update_region(0, b"a").expect("write page 0");
+        overlay
+            .update_region(OVERLAY_PAGE_SIZE, b"b")
+            .expect("write page 1");
+        overlay.flush().expect("flush");
+        drop(overlay);
+        let reopen = || {
+            OverlayMmap::open(
+                MemoryMappedFile::open_ro(&base_path).expect("ro base"),
+                &delta_path,
+            )
+        };
+
+        // Slot 1 names page 0 again
+        let delta = MemoryMappedFile::open_rw(&delta_path).expect("delta");
+        delta
+            .update_region(HEADER_LEN + SLOT_LEN, &0u64.to_le_bytes())
+            .expect("dup page");
+        delta.flush().expect("flush delta");
+        drop(delta);
+        assert!(matches!(reopen(), Err(MmapIoError::Corrupt(_))));
+
+        // A count whose slot table would overflow u64
+        let delta = MemoryMappedFile::open_rw(&delta_path).expect("delta");
+        delta
+            .update_region(COUNT_OFFSET, &u64::MAX.to_le_bytes())
+            .expect("bad count");
+        delta.flush().expect("flush delta");
+        drop(delta);
+        assert!(matches!(reopen(), Err(MmapIoError::Corrupt(_))));
+
+        fs::remove_file(&base_path).expect("cleanup base");
+        fs::remove_file(&delta_path).expect("cleanup delta");
+    }
+}
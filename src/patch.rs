@@ -0,0 +1,324 @@
+//! Binary patches between mappings, built on [`MemoryMappedFile::diff`].
+//!
+//! A [`Patch`] records the bytes of `new` that differ from `old`, plus both lengths. Its
+//! serialized form is compact (LEB128 varints, offsets delta-encoded), so shipping a patch is
+//! much cheaper than shipping a large file when only a few regions changed.
+//!
+//! Serialized layout:
+//!
+//! ```text
+//! magic "MMIOPTCH" | version u8 | old_len varint | new_len varint | op count varint
+//! per op: gap since previous op end varint | data len varint | data bytes
+//! ```
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+const MAGIC: &[u8; 8] = b"MMIOPTCH";
+const FORMAT_VERSION: u8 = 1;
+// Differences separated by fewer equal bytes than this are sent as one op, since a
+// separate op would cost about as much in varint headers as the bytes it skips
+const MERGE_GAP: u64 = 8;
+
+/// One replacement: write `data` at `offset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchOp {
+    /// Absolute offset in the patched file.
+    pub offset: u64,
+    /// Replacement bytes.
+    pub data: Vec<u8>,
+}
+
+/// Set of byte replacements that turns one mapping's contents into another's.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Patch {
+    old_len: u64,
+    new_len: u64,
+    ops: Vec<PatchOp>,
+}
+
+/// Compute the patch that transforms the contents of `old` into those of `new`.
+///
+/// # Errors
+///
+/// Returns errors from reading either mapping.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mmap_io::{MemoryMappedFile, patch::create_patch};
+///
+/// let old = MemoryMappedFile::open_ro("v1.bin")?;
+/// let new = MemoryMappedFile::open_ro("v2.bin")?;
+/// let bytes = create_patch(&old, &new)?.to_bytes();
+///
+/// // On the receiving side:
+/// let target = MemoryMappedFile::open_rw("replica.bin")?;
+/// target.apply_patch(&mmap_io::patch::Patch::from_bytes(&bytes)?)?;
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+pub fn create_patch(old: &MemoryMappedFile, new: &MemoryMappedFile) -> Result<Patch> {
+    let new_len = new.len();
+    let mut merged: Vec<std::ops::Range<u64>> = Vec::new();
+    for r in old.diff(new)? {
+        // Bytes past the end of `new` are removed by truncation, not by an op
+        let r = r.start..r.end.min(new_len);
+        if r.is_empty() {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if r.start - last.end < MERGE_GAP => last.end = r.end,
+            _ => merged.push(r),
+        }
+    }
+
+    let mut ops = Vec::with_capacity(merged.len());
+    for r in merged {
+        let mut data = vec![0u8; (r.end - r.start) as usize];
+        new.read_into(r.start, &mut data)?;
+        ops.push(PatchOp {
+            offset: r.start,
+            data,
+        });
+    }
+    Ok(Patch {
+        old_len: old.len(),
+        new_len,
+        ops,
+    })
+}
+
+impl Patch {
+    // For producers other than `create_patch`; `ops` must be sorted and non-overlapping.
+    pub(crate) fn from_ops(old_len: u64, new_len: u64, ops: Vec<PatchOp>) -> Self {
+        Self {
+            old_len,
+            new_len,
+            ops,
+        }
+    }
+
+    /// Length of the file the patch applies to.
+    #[must_use]
+    pub fn old_len(&self) -> u64 {
+        self.old_len
+    }
+
+    /// Length of the file after applying the patch.
+    #[must_use]
+    pub fn new_len(&self) -> u64 {
+        self.new_len
+    }
+
+    /// Replacement operations, sorted by offset.
+    #[must_use]
+    pub fn ops(&self) -> &[PatchOp] {
+        &self.ops
+    }
+
+    /// Whether applying the patch changes nothing.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty() && self.old_len == self.new_len
+    }
+
+    /// Serialize to the compact wire format.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload: usize = self.ops.iter().map(|op| op.data.len()).sum();
+        let mut out = Vec::with_capacity(MAGIC.len() + 1 + 30 + self.ops.len() * 6 + payload);
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+        put_varint(&mut out, self.old_len);
+        put_varint(&mut out, self.new_len);
+        put_varint(&mut out, self.ops.len() as u64);
+        let mut prev_end = 0u64;
+        for op in &self.ops {
+            put_varint(&mut out, op.offset - prev_end);
+            put_varint(&mut out, op.data.len() as u64);
+            out.extend_from_slice(&op.data);
+            prev_end = op.offset + op.data.len() as u64;
+        }
+        out
+    }
+
+    /// Parse the wire format produced by [`to_bytes`](Self::to_bytes).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the input is truncated, has a bad magic or version,
+    /// or describes ops that are out of order or past `new_len`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader { bytes, pos: 0 };
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(MmapIoError::Corrupt("missing patch magic".into()));
+        }
+        let version = r.take(1)?[0];
+        if version != FORMAT_VERSION {
+            return Err(MmapIoError::Corrupt(format!(
+                "unsupported patch format version {version}"
+            )));
+        }
+        let old_len = r.varint()?;
+        let new_len = r.varint()?;
+        let count = r.varint()?;
+        let mut ops = Vec::new();
+        let mut prev_end = 0u64;
+        for _ in 0..count {
+            let offset = prev_end
+                .checked_add(r.varint()?)
+                .ok_or_else(|| MmapIoError::Corrupt("patch offset overflows".into()))?;
+            let len = r.varint()?;
+            let end = offset
+                .checked_add(len)
+                .filter(|&e| e <= new_len)
+                .ok_or_else(|| MmapIoError::Corrupt("patch op exceeds new length".into()))?;
+            let len = usize::try_from(len)
+                .map_err(|_| MmapIoError::Corrupt("patch op too large".into()))?;
+            ops.push(PatchOp {
+                offset,
+                data: r.take(len)?.to_vec(),
+            });
+            prev_end = end;
+        }
+        if r.pos != bytes.len() {
+            return Err(MmapIoError::Corrupt("trailing bytes after patch".into()));
+        }
+        Ok(Self {
+            old_len,
+            new_len,
+            ops,
+        })
+    }
+}
+
+impl MemoryMappedFile {
+    /// Apply `patch`, resizing to its new length and writing every replacement.
+    ///
+    /// Writes go through `update_region`, so the flush policy and observers apply as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if this mapping's length does not match the patch's
+    /// base length. Returns errors from resizing or writing (e.g. `InvalidMode` for RO maps).
+    pub fn apply_patch(&self, patch: &Patch) -> Result<()> {
+        let len = self.len();
+        if len != patch.old_len {
+            return Err(MmapIoError::Corrupt(format!(
+                "patch expects a {}-byte file, mapping is {len} bytes",
+                patch.old_len
+            )));
+        }
+        if patch.new_len != len {
+            self.resize(patch.new_len)?;
+        }
+        for op in &patch.ops {
+            self.update_region(op.offset, &op.data)?;
+        }
+        Ok(())
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&e| e <= self.bytes.len())
+            .ok_or_else(|| MmapIoError::Corrupt("truncated patch".into()))?;
+        let out = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.take(1)?[0];
+            v |= u64::from(b & 0x7F) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(MmapIoError::Corrupt("patch varint too long".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_patch_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_patch_round_trip() {
+        let paths: Vec<PathBuf> = ["old", "new", "replica"]
+            .iter()
+            .map(|n| tmp_path(n))
+            .collect();
+        for p in &paths {
+            let _ = fs::remove_file(p);
+        }
+
+        let old = create_mmap(&paths[0], 100_000).expect("old");
+        old.update_region(0, b"header v1").expect("write");
+        let new = create_mmap(&paths[1], 120_000).expect("new");
+        new.update_region(0, b"header v2").expect("write");
+        new.update_region(50_000, b"a").expect("write");
+        new.update_region(50_004, b"b").expect("write");
+        new.update_region(119_990, b"tail").expect("write");
+
+        let patch = create_patch(&old, &new).expect("patch");
+        // Nearby differences are merged into one op; the grown tail is another
+        assert_eq!(patch.ops().len(), 3);
+        assert_eq!(patch.ops()[1].offset, 50_000);
+        assert_eq!(patch.ops()[1].data.len(), 5);
+
+        let bytes = patch.to_bytes();
+        assert!(bytes.len() < 20_100, "patch too large: {}", bytes.len());
+        let decoded = Patch::from_bytes(&bytes).expect("decode");
+        assert_eq!(decoded, patch);
+        assert!(Patch::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        fs::copy(&paths[0], &paths[2]).expect("copy");
+        let replica = crate::MemoryMappedFile::open_rw(&paths[2]).expect("replica");
+        replica.apply_patch(&decoded).expect("apply");
+        assert!(replica.diff(&new).expect("diff").is_empty());
+        // Applying again fails: the base length no longer matches
+        assert!(replica.apply_patch(&decoded).is_err());
+
+        // Shrinking patches truncate
+        let shrink = create_patch(&new, &old).expect("reverse");
+        replica.apply_patch(&shrink).expect("apply reverse");
+        assert!(replica.diff(&old).expect("diff").is_empty());
+
+        drop((old, new, replica));
+        for p in &paths {
+            fs::remove_file(p).expect("cleanup");
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Typed plain-old-data access through `zerocopy` traits (feature `zerocopy`).
+//!
+//! [`read_pod`](MemoryMappedFile::read_pod) and [`write_pod`](MemoryMappedFile::write_pod)
+//! copy a single value and work at any offset and in every mode.
+//! [`as_typed_slice`](MemoryMappedFile::as_typed_slice) borrows records in place, so it
+//! needs an immutable mapping and an address aligned for the element type.
+
+use std::mem::{align_of, size_of};
+
+use zerocopy::{FromBytes, Immutable, IntoBytes};
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+impl MemoryMappedFile {
+    /// Copy a `T` out of the mapping at `offset`.
+    ///
+    /// The value is copied, so `offset` need not be aligned for `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `size_of::<T>()` bytes at `offset` exceed the
+    /// mapping.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mmap_io::MemoryMappedFile;
+    ///
+    /// let mmap = MemoryMappedFile::open_ro("index.bin")?;
+    /// let magic: u32 = mmap.read_pod(0)?;
+    /// # let _ = magic;
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    pub fn read_pod<T: FromBytes + IntoBytes>(&self, offset: u64) -> Result<T> {
+        let mut value = T::new_zeroed();
+        self.read_into(offset, value.as_mut_bytes())?;
+        Ok(value)
+    }
+
+    /// Copy `value` into the mapping at `offset`.
+    ///
+    /// Goes through [`update_region`](Self::update_region), so the flush policy, dirty-page
+    /// tracking, metrics, and observers see it like any other write. `offset` need not be
+    /// aligned for `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for ReadOnly mappings and
+    /// `MmapIoError::OutOfBounds` if the value would extend past the mapping.
+    pub fn write_pod<T: IntoBytes + Immutable>(&self, offset: u64, value: &T) -> Result<()> {
+        self.update_region(offset, value.as_bytes())
+    }
+
+    /// Borrow `count` records of `T` starting at `offset` without copying.
+    ///
+    /// Like [`as_slice`](Self::as_slice), only ReadOnly mappings hand out references; the
+    /// mapped address of `offset` must also be aligned for `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` if the records are not aligned for `T`,
+    /// `MmapIoError::OutOfBounds` if they exceed the mapping, and
+    /// `MmapIoError::InvalidMode` for ReadWrite and CopyOnWrite mappings.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mmap_io::MemoryMappedFile;
+    ///
+    /// let mmap = MemoryMappedFile::open_ro("samples.f32")?;
+    /// let samples: &[f32] = mmap.as_typed_slice(0, (mmap.len() / 4) as usize)?;
+    /// # let _ = samples;
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    pub fn as_typed_slice<T: FromBytes + Immutable>(
+        &self,
+        offset: u64,
+        count: usize,
+    ) -> Result<&[T]> {
+        let len =
+            (size_of::<T>() as u64)
+                .checked_mul(count as u64)
+                .ok_or(MmapIoError::OutOfBounds {
+                    offset,
+                    len: u64::MAX,
+                    total: self.len(),
+                })?;
+        let bytes = self.as_slice(offset, len)?;
+        // Alignment is checked on the address: a window at an unaligned file offset shifts
+        // every offset relative to the page-aligned mapping.
+        <[T]>::ref_from_bytes_with_elems(bytes, count).map_err(|_| MmapIoError::Misaligned {
+            required: align_of::<T>() as u64,
+            offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use zerocopy::KnownLayout;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!("mmap_io_pod_test_{}_{}", name, std::process::id()));
+        p
+    }
+
+    #[derive(Debug, PartialEq, FromBytes, IntoBytes, Immutable, KnownLayout)]
+    #[repr(C)]
+    struct Entry {
+        key: u64,
+        value: u32,
+        flags: u32,
+    }
+
+    #[test]
+    fn test_pod_read_write_and_typed_slice() {
+        let path = tmp_path("records");
+        let _ = fs::remove_file(&path);
+
+        let mmap = MemoryMappedFile::create_rw(&path, 4096).expect("create");
+        for i in 0..4u64 {
+            let entry = Entry {
+                key: i,
+                value: i as u32 * 10,
+                flags: 1,
+            };
+            mmap.write_pod(16 * i, &entry).expect("write entry");
+        }
+        // Copies do not need alignment
+        mmap.write_pod(1001, &0xdead_beefu32)
+            .expect("unaligned write");
+        assert_eq!(mmap.read_pod::<u32>(1001).expect("read"), 0xdead_beef);
+        let entry: Entry = mmap.read_pod(32).expect("read entry");
+        assert_eq!(entry.value, 20);
+        assert!(mmap.read_pod::<u64>(4090).is_err());
+        assert!(mmap.write_pod(4094, &0u32).is_err());
+        assert!(mmap.as_typed_slice::<Entry>(0, 4).is_err());
+        drop(mmap);
+
+        let ro = MemoryMappedFile::open_ro(&path).expect("open ro");
+        let entries: &[Entry] = ro.as_typed_slice(0, 4).expect("typed slice");
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[3].key, 3);
+        assert_eq!(entries[3].value, 30);
+        assert!(ro.as_typed_slice::<Entry>(0, 0).expect("empty").is_empty());
+        assert!(matches!(
+            ro.as_typed_slice::<u64>(4, 1),
+            Err(MmapIoError::Misaligned { required: 8, .. })
+        ));
+        assert!(matches!(
+            ro.as_typed_slice::<u64>(0, 513),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            ro.as_typed_slice::<u64>(0, usize::MAX),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        assert!(ro.write_pod(0, &1u8).is_err());
+
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
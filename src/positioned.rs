@@ -0,0 +1,125 @@
+//! `positioned_io` trait implementations (feature `positioned-io`).
+//!
+//! [`MemoryMappedFile`], [`Segment`], and [`SegmentMut`] implement `ReadAt`, `WriteAt`, and
+//! `Size` as applicable, so they can be handed to libraries built on those traits. As with
+//! the `FileExt` impls, positions are relative to the start of the mapping or segment, reads
+//! past the end are short, and writes never grow the file.
+
+use std::io;
+
+use positioned_io::{ReadAt, Size, WriteAt};
+
+use crate::mmap::MemoryMappedFile;
+use crate::segment::{Segment, SegmentMut};
+
+impl ReadAt for MemoryMappedFile {
+    fn read_at(&self, pos: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.positional_read(buf, pos)
+    }
+}
+
+impl WriteAt for MemoryMappedFile {
+    fn write_at(&mut self, pos: u64, buf: &[u8]) -> io::Result<usize> {
+        self.positional_write(buf, pos)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(MemoryMappedFile::flush(self)?)
+    }
+}
+
+impl Size for MemoryMappedFile {
+    fn size(&self) -> io::Result<Option<u64>> {
+        Ok(Some(self.current_len()?))
+    }
+}
+
+impl ReadAt for Segment {
+    fn read_at(&self, pos: u64, buf: &mut [u8]) -> io::Result<usize> {
+        if pos >= self.len() {
+            return Ok(0);
+        }
+        Ok(Segment::read_at(self, pos, buf)?)
+    }
+}
+
+impl Size for Segment {
+    fn size(&self) -> io::Result<Option<u64>> {
+        Ok(Some(self.len()))
+    }
+}
+
+impl WriteAt for SegmentMut {
+    fn write_at(&mut self, pos: u64, buf: &[u8]) -> io::Result<usize> {
+        let n = self.len().saturating_sub(pos).min(buf.len() as u64) as usize;
+        if n > 0 {
+            SegmentMut::write_at(self, pos, &buf[..n])?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(SegmentMut::flush(self)?)
+    }
+}
+
+impl Size for SegmentMut {
+    fn size(&self) -> io::Result<Option<u64>> {
+        Ok(Some(self.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_positioned_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    // Shaped like a consumer of the traits, e.g. an archive reader
+    fn read_tail<R: ReadAt + Size>(source: &R, n: usize) -> io::Result<Vec<u8>> {
+        let size = source.size()?.expect("sized");
+        let mut out = vec![0u8; n];
+        source.read_exact_at(size - n as u64, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_positioned_io_traits() {
+        let path = tmp_path("traits");
+        let _ = fs::remove_file(&path);
+        let mut mmap = MemoryMappedFile::create_rw(&path, 16).expect("create");
+        mmap.write_all_at(10, b"footer").expect("write");
+        WriteAt::flush(&mut mmap).expect("flush");
+        assert_eq!(read_tail(&mmap, 6).expect("tail"), b"footer");
+        assert_eq!(mmap.write_at(14, b"xyz").expect("short write"), 2);
+
+        let parent = Arc::new(mmap);
+        let seg = Segment::new(parent.clone(), 8, 6).expect("segment");
+        assert_eq!(read_tail(&seg, 4).expect("segment tail"), b"foot");
+        let mut buf = [0u8; 4];
+        assert_eq!(ReadAt::read_at(&seg, 6, &mut buf).expect("eof"), 0);
+
+        let mut seg_mut = SegmentMut::new(parent.clone(), 0, 4).expect("segment mut");
+        assert_eq!(
+            WriteAt::write_at(&mut seg_mut, 2, b"head").expect("clamped"),
+            2
+        );
+        assert_eq!(seg_mut.size().expect("size"), Some(4));
+        parent.read_into(2, &mut buf[..2]).expect("read back");
+        assert_eq!(&buf[..2], b"he");
+
+        drop((seg, seg_mut, parent));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,376 @@
+//! Named segment registry persisted in a header page of the mapped file.
+//!
+//! The first [`REGISTRY_HEADER_SIZE`] bytes of the file hold a small table mapping names to
+//! `(offset, len, version)` triples. Segments are allocated after the header, and the file is
+//! grown as needed, so every tool that opens the file agrees on where each region lives.
+//!
+//! Header layout (all integers little-endian):
+//!
+//! | bytes   | field                                   |
+//! |---------|-----------------------------------------|
+//! | 0..8    | magic `MMIOSREG`                        |
+//! | 8..12   | format version (currently 1)            |
+//! | 12..16  | entry count                             |
+//! | 16..24  | next free offset for allocation         |
+//! | 24..    | entries of 56 bytes: name (32, NUL-padded UTF-8), offset (8), len (8), version (4), reserved (4) |
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+use crate::segment::{Segment, SegmentMut};
+use crate::utils::align_up;
+
+/// Size in bytes reserved at the start of the file for the registry header.
+pub const REGISTRY_HEADER_SIZE: u64 = 4096;
+/// Maximum length in bytes of a segment name.
+pub const MAX_NAME_LEN: usize = 32;
+
+const MAGIC: &[u8; 8] = b"MMIOSREG";
+const FORMAT_VERSION: u32 = 1;
+const FIXED_LEN: usize = 24;
+const ENTRY_LEN: usize = 56;
+const MAX_ENTRIES: usize = (REGISTRY_HEADER_SIZE as usize - FIXED_LEN) / ENTRY_LEN;
+// Alignment of newly allocated segments
+const SEGMENT_ALIGN: u64 = 8;
+
+/// A registered segment as recorded in the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedEntry {
+    /// Segment name.
+    pub name: String,
+    /// Absolute offset of the segment in the file.
+    pub offset: u64,
+    /// Segment length in bytes.
+    pub len: u64,
+    /// Application-defined layout version, starting at 1.
+    pub version: u32,
+}
+
+/// Registry of named segments stored in the header page of a mapping.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, registry::SegmentRegistry};
+///
+/// let mmap = Arc::new(MemoryMappedFile::create_rw("store.bin", 4096)?);
+/// let registry = SegmentRegistry::create(mmap)?;
+/// let index = registry.create_named("index", 1024)?;
+/// index.write(b"...")?;
+///
+/// // Later, possibly from another tool:
+/// let mmap = Arc::new(MemoryMappedFile::open_rw("store.bin")?);
+/// let registry = SegmentRegistry::open(mmap)?;
+/// let index = registry.open_named("index")?;
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct SegmentRegistry {
+    mmap: Arc<MemoryMappedFile>,
+    // Serializes header updates within this process
+    lock: Mutex<()>,
+}
+
+impl SegmentRegistry {
+    /// Initialize an empty registry header at the start of `mmap`, growing the file to hold it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the mapping is not `ReadWrite`.
+    /// Returns errors from resizing or writing the header.
+    pub fn create(mmap: Arc<MemoryMappedFile>) -> Result<Self> {
+        if mmap.len() < REGISTRY_HEADER_SIZE {
+            mmap.resize(REGISTRY_HEADER_SIZE)?;
+        }
+        let mut fixed = [0u8; FIXED_LEN];
+        fixed[0..8].copy_from_slice(MAGIC);
+        fixed[8..12].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        fixed[16..24].copy_from_slice(&REGISTRY_HEADER_SIZE.to_le_bytes());
+        mmap.update_region(0, &fixed)?;
+        mmap.fill_region(FIXED_LEN as u64, REGISTRY_HEADER_SIZE - FIXED_LEN as u64, 0)?;
+        Ok(Self {
+            mmap,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Open an existing registry, validating its header.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Registry` if the header is missing or has an unsupported version.
+    pub fn open(mmap: Arc<MemoryMappedFile>) -> Result<Self> {
+        if mmap.len() < REGISTRY_HEADER_SIZE {
+            return Err(MmapIoError::Registry(
+                "file too small for registry header".into(),
+            ));
+        }
+        let mut fixed = [0u8; FIXED_LEN];
+        mmap.read_into(0, &mut fixed)?;
+        if &fixed[0..8] != MAGIC {
+            return Err(MmapIoError::Registry("missing registry magic".into()));
+        }
+        let version = read_u32(&fixed, 8);
+        if version != FORMAT_VERSION {
+            return Err(MmapIoError::Registry(format!(
+                "unsupported registry format version {version}"
+            )));
+        }
+        Ok(Self {
+            mmap,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Allocate a new segment of `len` bytes named `name`, growing the file if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Registry` if the name is invalid, already registered, or the
+    /// header is full. Returns errors from resizing or writing the mapping.
+    pub fn create_named(&self, name: &str, len: u64) -> Result<SegmentMut> {
+        let encoded = encode_name(name)?;
+        let _guard = self.lock.lock();
+        let (count, next_free) = self.read_fixed()?;
+        if self.find(name, count)?.is_some() {
+            return Err(MmapIoError::Registry(format!(
+                "segment '{name}' already exists"
+            )));
+        }
+        if count as usize >= MAX_ENTRIES {
+            return Err(MmapIoError::Registry("registry header is full".into()));
+        }
+
+        let offset = align_up(next_free, SEGMENT_ALIGN);
+        let end = offset
+            .checked_add(len)
+            .ok_or_else(|| MmapIoError::Registry("segment length overflows".into()))?;
+        if end > self.mmap.len() {
+            self.mmap.resize(end)?;
+        }
+
+        let mut entry = [0u8; ENTRY_LEN];
+        entry[..MAX_NAME_LEN].copy_from_slice(&encoded);
+        entry[32..40].copy_from_slice(&offset.to_le_bytes());
+        entry[40..48].copy_from_slice(&len.to_le_bytes());
+        entry[48..52].copy_from_slice(&1u32.to_le_bytes());
+        self.mmap
+            .update_region(entry_offset(count as usize), &entry)?;
+
+        let mut fixed_tail = [0u8; 12];
+        fixed_tail[0..4].copy_from_slice(&(count + 1).to_le_bytes());
+        fixed_tail[4..12].copy_from_slice(&end.to_le_bytes());
+        self.mmap.update_region(12, &fixed_tail)?;
+
+        SegmentMut::new(self.mmap.clone(), offset, len)
+    }
+
+    /// Open the read-only view of a registered segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Registry` if no segment with that name exists.
+    pub fn open_named(&self, name: &str) -> Result<Segment> {
+        let entry = self.require(name)?;
+        Segment::new(self.mmap.clone(), entry.offset, entry.len)
+    }
+
+    /// Open the writable view of a registered segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Registry` if no segment with that name exists.
+    pub fn open_named_mut(&self, name: &str) -> Result<SegmentMut> {
+        let entry = self.require(name)?;
+        SegmentMut::new(self.mmap.clone(), entry.offset, entry.len)
+    }
+
+    /// Look up a registered segment by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from reading the header.
+    pub fn get(&self, name: &str) -> Result<Option<NamedEntry>> {
+        let (count, _) = self.read_fixed()?;
+        Ok(self.find(name, count)?.map(|(_, e)| e))
+    }
+
+    /// All registered segments in allocation order.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from reading the header.
+    pub fn entries(&self) -> Result<Vec<NamedEntry>> {
+        let (count, _) = self.read_fixed()?;
+        (0..count as usize).map(|i| self.read_entry(i)).collect()
+    }
+
+    /// Increment the recorded layout version of `name`, returning the new version.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Registry` if no segment with that name exists.
+    pub fn bump_version(&self, name: &str) -> Result<u32> {
+        let _guard = self.lock.lock();
+        let (count, _) = self.read_fixed()?;
+        let (idx, entry) = self
+            .find(name, count)?
+            .ok_or_else(|| MmapIoError::Registry(format!("segment '{name}' not found")))?;
+        let version = entry.version.wrapping_add(1);
+        self.mmap
+            .update_region(entry_offset(idx) + 48, &version.to_le_bytes())?;
+        Ok(version)
+    }
+
+    /// The mapping this registry lives in.
+    #[must_use]
+    pub fn mapping(&self) -> &Arc<MemoryMappedFile> {
+        &self.mmap
+    }
+
+    fn require(&self, name: &str) -> Result<NamedEntry> {
+        self.get(name)?
+            .ok_or_else(|| MmapIoError::Registry(format!("segment '{name}' not found")))
+    }
+
+    fn read_fixed(&self) -> Result<(u32, u64)> {
+        let mut fixed = [0u8; FIXED_LEN];
+        self.mmap.read_into(0, &mut fixed)?;
+        let count = read_u32(&fixed, 12);
+        if count as usize > MAX_ENTRIES {
+            return Err(MmapIoError::Registry(format!(
+                "corrupt registry entry count {count}"
+            )));
+        }
+        Ok((count, read_u64(&fixed, 16)))
+    }
+
+    fn read_entry(&self, idx: usize) -> Result<NamedEntry> {
+        let mut raw = [0u8; ENTRY_LEN];
+        self.mmap.read_into(entry_offset(idx), &mut raw)?;
+        let name_len = raw[..MAX_NAME_LEN]
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(MAX_NAME_LEN);
+        let name = std::str::from_utf8(&raw[..name_len])
+            .map_err(|_| MmapIoError::Registry(format!("entry {idx} has a non-UTF-8 name")))?;
+        Ok(NamedEntry {
+            name: name.to_owned(),
+            offset: read_u64(&raw, 32),
+            len: read_u64(&raw, 40),
+            version: read_u32(&raw, 48),
+        })
+    }
+
+    fn find(&self, name: &str, count: u32) -> Result<Option<(usize, NamedEntry)>> {
+        for idx in 0..count as usize {
+            let entry = self.read_entry(idx)?;
+            if entry.name == name {
+                return Ok(Some((idx, entry)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn entry_offset(idx: usize) -> u64 {
+    (FIXED_LEN + idx * ENTRY_LEN) as u64
+}
+
+fn encode_name(name: &str) -> Result<[u8; MAX_NAME_LEN]> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN || name.as_bytes().contains(&0) {
+        return Err(MmapIoError::Registry(format!(
+            "segment names must be 1..={MAX_NAME_LEN} bytes without NUL"
+        )));
+    }
+    let mut out = [0u8; MAX_NAME_LEN];
+    out[..name.len()].copy_from_slice(name.as_bytes());
+    Ok(out)
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    let mut b = [0u8; 4];
+    b.copy_from_slice(&buf[at..at + 4]);
+    u32::from_le_bytes(b)
+}
+
+fn read_u64(buf: &[u8], at: usize) -> u64 {
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&buf[at..at + 8]);
+    u64::from_le_bytes(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_registry_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_registry_create_and_reopen() {
+        let path = tmp_path("reopen");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(MemoryMappedFile::create_rw(&path, 16).expect("create"));
+        let registry = SegmentRegistry::create(mmap.clone()).expect("registry");
+        let index = registry.create_named("index", 100).expect("index");
+        let data = registry.create_named("data", 3).expect("data");
+        assert_eq!(index.offset(), REGISTRY_HEADER_SIZE);
+        assert_eq!(data.offset(), REGISTRY_HEADER_SIZE + 104); // 8-byte aligned
+        index.write(b"idx").expect("write");
+        assert!(registry.create_named("index", 1).is_err());
+        assert_eq!(registry.bump_version("data").expect("bump"), 2);
+        mmap.flush().expect("flush");
+        drop((registry, index, data, mmap));
+
+        let mmap = Arc::new(MemoryMappedFile::open_rw(&path).expect("open"));
+        let registry = SegmentRegistry::open(mmap).expect("open registry");
+        let entries = registry.entries().expect("entries");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].name, "data");
+        assert_eq!(entries[1].version, 2);
+
+        let index = registry.open_named("index").expect("open index");
+        assert_eq!(index.len(), 100);
+        let mut buf = [0u8; 3];
+        index.read_into(0, &mut buf).expect("read");
+        assert_eq!(&buf, b"idx");
+        assert!(matches!(
+            registry.open_named("missing"),
+            Err(MmapIoError::Registry(_))
+        ));
+        drop((registry, index));
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_registry_rejects_bad_input() {
+        let path = tmp_path("bad_input");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(MemoryMappedFile::create_rw(&path, 8192).expect("create"));
+        assert!(SegmentRegistry::open(mmap.clone()).is_err());
+
+        let registry = SegmentRegistry::create(mmap).expect("registry");
+        assert!(registry.create_named("", 1).is_err());
+        assert!(registry.create_named(&"x".repeat(33), 1).is_err());
+        drop(registry);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
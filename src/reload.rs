@@ -0,0 +1,221 @@
+//! Read-only mappings that follow a file as it is replaced on disk.
+//!
+//! [`ReloadingMmap`] watches its file and, when the file changes, maps the new contents and
+//! swaps them in through a [`SwappableMmap`]. Readers take lock-free `Arc` snapshots with
+//! [`ReloadingMmap::current`]; a snapshot stays valid (and unchanged) for as long as it is
+//! held, even after newer contents have been swapped in. This is the usual hot-reload
+//! pattern for configuration files, lookup tables, and assets.
+//!
+//! Writers should publish new contents by writing a temporary file and renaming it over the
+//! watched path; a file that is deleted and later recreated is picked up as well. Truncating
+//! or rewriting the file in place can still fault readers of an older snapshot, exactly as
+//! with any shared mapping.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+use crate::errors::Result;
+use crate::mmap::MemoryMappedFile;
+use crate::swap::SwappableMmap;
+use crate::watch::{ChangeKind, WatchHandle, WatchOptions};
+
+/// Self-reloading read-only mapping of a file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mmap_io::reload::ReloadingMmap;
+///
+/// let config = ReloadingMmap::open("settings.bin")?;
+/// // Each request works on a consistent snapshot
+/// let snapshot = config.current();
+/// let mut header = [0u8; 16];
+/// snapshot.read_into(0, &mut header)?;
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+pub struct ReloadingMmap {
+    shared: Arc<Shared>,
+    // Dropping the handle stops the watcher thread
+    _watch: WatchHandle,
+}
+
+struct Shared {
+    path: PathBuf,
+    current: SwappableMmap,
+    generation: AtomicU64,
+}
+
+impl Shared {
+    fn reload(&self) -> Result<()> {
+        let fresh = Arc::new(MemoryMappedFile::open_ro(&self.path)?);
+        self.current.store(fresh);
+        self.generation.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ReloadingMmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReloadingMmap")
+            .field("path", &self.shared.path)
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
+
+impl ReloadingMmap {
+    /// Map `path` read-only and reload it whenever its modification time or identity
+    /// (device and inode) changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from the initial [`MemoryMappedFile::open_ro`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with(path, WatchOptions::default())
+    }
+
+    /// Like [`open`](Self::open), with explicit watch options; enable
+    /// [`WatchOptions::verify_content`] to skip reloads on timestamp-only updates.
+    /// [`WatchOptions::follow_recreation`] is always enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from the initial [`MemoryMappedFile::open_ro`].
+    pub fn open_with<P: AsRef<Path>>(path: P, options: WatchOptions) -> Result<Self> {
+        let mmap = MemoryMappedFile::open_ro(path.as_ref())?;
+        let shared = Arc::new(Shared {
+            path: path.as_ref().to_path_buf(),
+            current: SwappableMmap::new(Arc::new(mmap)),
+            generation: AtomicU64::new(0),
+        });
+        // The watcher only holds a weak reference so dropping the handle frees the mapping
+        let weak: Weak<Shared> = Arc::downgrade(&shared);
+        let options = options.follow_recreation(true);
+        let watch = shared.current.load().watch_with(options, move |event| {
+            if event.kind != ChangeKind::Modified {
+                return;
+            }
+            if let Some(shared) = weak.upgrade() {
+                // A failed reload (e.g. a half-written file) keeps the previous snapshot;
+                // the next change retries
+                if let Err(e) = shared.reload() {
+                    log::warn!("reloading {} failed: {e}", shared.path.display());
+                }
+            }
+        })?;
+        Ok(Self {
+            shared,
+            _watch: watch,
+        })
+    }
+
+    /// The most recently loaded contents.
+    #[must_use]
+    pub fn current(&self) -> Arc<MemoryMappedFile> {
+        self.shared.current.load()
+    }
+
+    /// Remap the file now, without waiting for the watcher.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from [`MemoryMappedFile::open_ro`]; the previous snapshot is kept.
+    pub fn reload(&self) -> Result<()> {
+        self.shared.reload()
+    }
+
+    /// Number of reloads so far; changes whenever [`current`](Self::current) does.
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.shared.generation.load(Ordering::Acquire)
+    }
+
+    /// Path of the watched file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.shared.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_reload_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_reloading_mmap_swaps_on_replace() {
+        let path = tmp_path("reload");
+        let staging = tmp_path("reload_staging");
+        fs::write(&path, b"version-1").expect("write v1");
+
+        let options = WatchOptions::new().poll_interval(Duration::from_millis(20));
+        let reloading = ReloadingMmap::open_with(&path, options).expect("open");
+        let old = reloading.current();
+        assert_eq!(old.as_slice(0, 9).expect("slice"), b"version-1");
+
+        // Publish new contents atomically
+        thread::sleep(Duration::from_millis(30));
+        fs::write(&staging, b"version-2 longer").expect("write v2");
+        fs::rename(&staging, &path).expect("rename");
+
+        let wait_past = |generation: u64| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while reloading.generation() <= generation && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(10));
+            }
+            assert!(reloading.generation() > generation, "reload not observed");
+        };
+        wait_past(0);
+        let new = reloading.current();
+        assert_eq!(new.as_slice(0, 16).expect("slice"), b"version-2 longer");
+        // The old snapshot is untouched
+        assert_eq!(old.as_slice(0, 9).expect("slice"), b"version-1");
+
+        reloading.reload().expect("manual reload");
+
+        // A replacement with the same length and timestamp is still a different file
+        let mtime = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .expect("mtime");
+        fs::write(&staging, b"version-3 longer").expect("write v3");
+        fs::File::options()
+            .write(true)
+            .open(&staging)
+            .and_then(|f| f.set_modified(mtime))
+            .expect("keep mtime");
+        let before = reloading.generation();
+        fs::rename(&staging, &path).expect("rename");
+        wait_past(before);
+        assert_eq!(
+            reloading.current().as_slice(0, 16).expect("slice"),
+            b"version-3 longer"
+        );
+
+        // Deleting and recreating the file is followed too
+        fs::remove_file(&path).expect("remove");
+        thread::sleep(Duration::from_millis(60));
+        let before = reloading.generation();
+        fs::write(&path, b"version-4").expect("write v4");
+        wait_past(before);
+        assert_eq!(
+            reloading.current().as_slice(0, 9).expect("slice"),
+            b"version-4"
+        );
+
+        drop((old, new, reloading));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
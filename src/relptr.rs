@@ -0,0 +1,463 @@
+//! Offset-based pointers for data structures stored inside a mapping.
+//!
+//! Absolute addresses are meaningless once a file is mapped somewhere else (another process,
+//! or the same process after a restart). [`RelPtr`] and [`RelSlice`] instead store byte
+//! offsets from the start of the mapping and are resolved against a [`MemoryMappedFile`]
+//! on every access, with alignment checked at construction and bounds checked on use.
+//! Both are [`Pod`], so they can themselves be stored in mapped records.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::sync::Arc;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+use crate::typed::{check_alignment, read_pod, write_pod, Pod, SegmentTyped};
+
+const NULL_OFFSET: u64 = u64::MAX;
+
+/// Offset of a `T` within a mapping.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mmap_io::{MemoryMappedFile, relptr::RelPtr};
+///
+/// let mmap = MemoryMappedFile::create_rw("graph.bin", 4096)?;
+/// let next = RelPtr::<u64>::new(128)?;
+/// next.write(&mmap, 7)?;
+/// // Store the pointer itself in the file, e.g. in a header
+/// RelPtr::<RelPtr<u64>>::new(0)?.write(&mmap, next)?;
+/// assert_eq!(RelPtr::<RelPtr<u64>>::new(0)?.read(&mmap)?.read(&mmap)?, 7);
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[repr(transparent)]
+pub struct RelPtr<T: Pod> {
+    offset: u64,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Clone for RelPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Pod> Copy for RelPtr<T> {}
+
+impl<T: Pod> PartialEq for RelPtr<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset
+    }
+}
+
+impl<T: Pod> Eq for RelPtr<T> {}
+
+impl<T: Pod> fmt::Debug for RelPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_null() {
+            f.write_str("RelPtr(null)")
+        } else {
+            write!(f, "RelPtr({:#x})", self.offset)
+        }
+    }
+}
+
+impl<T: Pod> Default for RelPtr<T> {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+// SAFETY: a transparent wrapper around u64; every bit pattern is a valid (possibly null or
+// out-of-bounds) offset, which is checked on access.
+unsafe impl<T: Pod> Pod for RelPtr<T> {}
+
+impl<T: Pod> RelPtr<T> {
+    /// Pointer to the `T` at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` if `offset` is not aligned for `T`, and
+    /// `MmapIoError::OutOfBounds` for `u64::MAX`, which is reserved for null.
+    pub fn new(offset: u64) -> Result<Self> {
+        if offset == NULL_OFFSET {
+            return Err(MmapIoError::OutOfBounds {
+                offset,
+                len: size_of::<T>() as u64,
+                total: u64::MAX,
+            });
+        }
+        check_alignment::<T>(offset)?;
+        Ok(Self {
+            offset,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Pointer to the `T` at `offset`, also checking that it lies inside `mmap`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` for a misaligned offset and
+    /// `MmapIoError::OutOfBounds` if the value does not fit in the mapping.
+    pub fn within(mmap: &MemoryMappedFile, offset: u64) -> Result<Self> {
+        let ptr = Self::new(offset)?;
+        crate::utils::ensure_in_bounds(offset, size_of::<T>() as u64, mmap.len())?;
+        Ok(ptr)
+    }
+
+    /// The null pointer, which fails to resolve.
+    #[must_use]
+    pub const fn null() -> Self {
+        Self {
+            offset: NULL_OFFSET,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Whether this is the null pointer.
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        self.offset == NULL_OFFSET
+    }
+
+    /// Byte offset from the start of the mapping, or `None` for null.
+    #[must_use]
+    pub fn offset(&self) -> Option<u64> {
+        (!self.is_null()).then_some(self.offset)
+    }
+
+    /// Pointer `count` elements further on, like `pointer::add`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for null and `MmapIoError::OutOfBounds` on overflow.
+    pub fn add(&self, count: u64) -> Result<Self> {
+        let base = self.resolve_offset()?;
+        let offset = count
+            .checked_mul(size_of::<T>() as u64)
+            .and_then(|d| base.checked_add(d))
+            .filter(|&o| o != NULL_OFFSET)
+            .ok_or(MmapIoError::OutOfBounds {
+                offset: base,
+                len: count,
+                total: u64::MAX,
+            })?;
+        Self::new(offset)
+    }
+
+    /// Copy the pointee out of `mmap`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for null and `MmapIoError::OutOfBounds` if the
+    /// value does not fit in the mapping.
+    pub fn read(&self, mmap: &MemoryMappedFile) -> Result<T> {
+        read_pod(mmap, self.resolve_offset()?)
+    }
+
+    /// Overwrite the pointee in `mmap`.
+    ///
+    /// # Errors
+    ///
+    /// As [`read`](Self::read), plus `MmapIoError::InvalidMode` if `mmap` is read-only.
+    pub fn write(&self, mmap: &MemoryMappedFile, value: T) -> Result<()> {
+        write_pod(mmap, self.resolve_offset()?, &value)
+    }
+
+    fn resolve_offset(&self) -> Result<u64> {
+        self.offset()
+            .ok_or(MmapIoError::InvalidMode("cannot dereference a null RelPtr"))
+    }
+}
+
+/// Offset and length of a run of `T`s within a mapping.
+///
+/// Like [`RelPtr`], it is [`Pod`] and can be stored in the mapping it points into.
+#[repr(C)]
+pub struct RelSlice<T: Pod> {
+    offset: u64,
+    len: u64,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Clone for RelSlice<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Pod> Copy for RelSlice<T> {}
+
+impl<T: Pod> PartialEq for RelSlice<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.offset, self.len) == (other.offset, other.len)
+    }
+}
+
+impl<T: Pod> Eq for RelSlice<T> {}
+
+impl<T: Pod> fmt::Debug for RelSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RelSlice({:#x}; {})", self.offset, self.len)
+    }
+}
+
+impl<T: Pod> Default for RelSlice<T> {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// SAFETY: repr(C) pair of u64s with no padding; every bit pattern is a valid (possibly
+// out-of-bounds) slice, which is checked on access.
+unsafe impl<T: Pod> Pod for RelSlice<T> {}
+
+impl<T: Pod> RelSlice<T> {
+    /// Slice of `len` elements starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` if `offset` is not aligned for `T`, and
+    /// `MmapIoError::OutOfBounds` if the byte length overflows.
+    pub fn new(offset: u64, len: u64) -> Result<Self> {
+        check_alignment::<T>(offset)?;
+        len.checked_mul(size_of::<T>() as u64)
+            .and_then(|bytes| offset.checked_add(bytes))
+            .ok_or(MmapIoError::OutOfBounds {
+                offset,
+                len: u64::MAX,
+                total: u64::MAX,
+            })?;
+        Ok(Self {
+            offset,
+            len,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Slice of `len` elements at `offset`, also checking that it lies inside `mmap`.
+    ///
+    /// # Errors
+    ///
+    /// As [`new`](Self::new), plus `MmapIoError::OutOfBounds` if it exceeds the mapping.
+    pub fn within(mmap: &MemoryMappedFile, offset: u64, len: u64) -> Result<Self> {
+        let slice = Self::new(offset, len)?;
+        crate::utils::ensure_in_bounds(offset, slice.byte_len()?, mmap.len())?;
+        Ok(slice)
+    }
+
+    /// Byte offset of the first element.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Number of elements.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the slice has no elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Size of the slice in bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the size overflows, which only a slice read
+    /// from corrupt bytes can do.
+    pub fn byte_len(&self) -> Result<u64> {
+        self.len
+            .checked_mul(size_of::<T>() as u64)
+            .ok_or(MmapIoError::OutOfBounds {
+                offset: self.offset,
+                len: u64::MAX,
+                total: u64::MAX,
+            })
+    }
+
+    /// Pointer to element `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `index >= len()`.
+    pub fn at(&self, index: u64) -> Result<RelPtr<T>> {
+        if index >= self.len {
+            return Err(MmapIoError::OutOfBounds {
+                offset: index,
+                len: 1,
+                total: self.len,
+            });
+        }
+        let offset = index
+            .checked_mul(size_of::<T>() as u64)
+            .and_then(|d| self.offset.checked_add(d))
+            .ok_or(MmapIoError::OutOfBounds {
+                offset: self.offset,
+                len: index,
+                total: u64::MAX,
+            })?;
+        RelPtr::new(offset)
+    }
+
+    /// Copy element `index` out of `mmap`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `index >= len()` or the element is outside
+    /// the mapping.
+    pub fn get(&self, mmap: &MemoryMappedFile, index: u64) -> Result<T> {
+        self.at(index)?.read(mmap)
+    }
+
+    /// Overwrite element `index` in `mmap`.
+    ///
+    /// # Errors
+    ///
+    /// As [`get`](Self::get), plus `MmapIoError::InvalidMode` if `mmap` is read-only.
+    pub fn set(&self, mmap: &MemoryMappedFile, index: u64, value: T) -> Result<()> {
+        self.at(index)?.write(mmap, value)
+    }
+
+    /// Typed array view of the slice in `mmap`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the slice exceeds the mapping.
+    pub fn view(&self, mmap: Arc<MemoryMappedFile>) -> Result<SegmentTyped<T>> {
+        let count = usize::try_from(self.len).map_err(|_| MmapIoError::OutOfBounds {
+            offset: self.offset,
+            len: self.byte_len().unwrap_or(u64::MAX),
+            total: mmap.len(),
+        })?;
+        SegmentTyped::new(mmap, self.offset, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_relptr_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(C)]
+    struct Node {
+        value: u64,
+        next: RelPtr<Node>,
+    }
+
+    unsafe impl Pod for Node {}
+
+    #[test]
+    fn test_linked_list_survives_reopen() {
+        let path = tmp_path("list");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 4096).expect("create");
+        let head = RelPtr::<RelPtr<Node>>::new(0).expect("head");
+        let nodes = RelSlice::<Node>::within(&mmap, 64, 3).expect("nodes");
+        let mut next = RelPtr::null();
+        for i in (0..3).rev() {
+            nodes
+                .set(
+                    &mmap,
+                    i,
+                    Node {
+                        value: i * 10,
+                        next,
+                    },
+                )
+                .expect("set");
+            next = nodes.at(i).expect("at");
+        }
+        head.write(&mmap, next).expect("write head");
+        drop(mmap);
+
+        let mmap = crate::MemoryMappedFile::open_ro(&path).expect("reopen");
+        let mut values = Vec::new();
+        let mut cur = head.read(&mmap).expect("read head");
+        while !cur.is_null() {
+            let node = cur.read(&mmap).expect("read node");
+            values.push(node.value);
+            cur = node.next;
+        }
+        assert_eq!(values, vec![0, 10, 20]);
+
+        assert!(RelPtr::<Node>::null().read(&mmap).is_err());
+        assert!(RelPtr::<u64>::new(3).is_err());
+        assert!(matches!(
+            RelPtr::<u8>::new(u64::MAX),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        assert!(RelPtr::<u64>::within(&mmap, 4092).is_err());
+        assert!(RelSlice::<u64>::within(&mmap, 4000, 13).is_err());
+        assert!(nodes.at(3).is_err());
+        assert_eq!(
+            RelPtr::<u32>::new(8).expect("ptr").add(3).expect("add"),
+            RelPtr::new(20).expect("ptr")
+        );
+        let view = nodes.view(Arc::new(mmap)).expect("view");
+        assert_eq!(view.get(2).expect("get").value, 20);
+
+        drop(view);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_corrupt_slice_from_mapping_is_rejected() {
+        let path = tmp_path("corrupt_slice");
+        let _ = fs::remove_file(&path);
+
+        // A slice header whose length overflows any byte size, as a damaged file might hold
+        let mmap = create_mmap(&path, 64).expect("create");
+        mmap.update_region(0, &8u64.to_ne_bytes()).expect("offset");
+        mmap.update_region(8, &u64::MAX.to_ne_bytes()).expect("len");
+        let slice = RelPtr::<RelSlice<u64>>::new(0)
+            .expect("ptr")
+            .read(&mmap)
+            .expect("read header");
+        assert_eq!((slice.offset(), slice.len()), (8, u64::MAX));
+
+        assert!(matches!(
+            slice.byte_len(),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            slice.at(u64::MAX - 1),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            slice.get(&mmap, 1 << 61),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        assert!(slice.get(&mmap, 100).is_err());
+        assert_eq!(slice.get(&mmap, 0).expect("in bounds"), u64::MAX);
+        assert!(RelSlice::<u64>::within(&mmap, 8, u64::MAX).is_err());
+        assert!(slice.view(Arc::new(mmap)).is_err());
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
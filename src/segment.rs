@@ -4,7 +4,10 @@ use std::sync::Arc;
 
 use crate::errors::Result;
 use crate::mmap::MemoryMappedFile;
-use crate::utils::slice_range;
+use crate::utils::{ensure_in_bounds, slice_range};
+
+// Chunk size used when copying between segments of different mappings
+const COPY_CHUNK_SIZE: u64 = 64 * 1024;
 
 /// Immutable view into a region of a memory-mapped file.
 ///
@@ -23,13 +26,20 @@ use crate::utils::slice_range;
 /// let data = segment.as_slice()?;
 /// # Ok::<(), mmap_io::MmapIoError>(())
 /// ```
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Segment {
     parent: Arc<MemoryMappedFile>,
     offset: u64,
     len: u64,
 }
 
+/// `{:?}` prints the view's bounds; `{:#?}` also hex-dumps up to 256 bytes of its contents.
+impl std::fmt::Debug for Segment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        crate::hexdump::fmt_segment(f, "Segment", &self.parent, self.offset, self.len)
+    }
+}
+
 impl Segment {
     /// Create a new immutable segment view. Performs bounds checks.
     ///
@@ -60,6 +70,41 @@ impl Segment {
         self.parent.as_slice(self.offset, self.len)
     }
 
+    /// Read `buf.len()` bytes starting at `rel_offset` relative to the start of this segment.
+    /// Works for RO, RW, and COW parents.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `[rel_offset, rel_offset + buf.len())` exceeds the segment.
+    pub fn read_into(&self, rel_offset: u64, buf: &mut [u8]) -> Result<()> {
+        ensure_in_bounds(rel_offset, buf.len() as u64, self.len)?;
+        self.parent.read_into(self.offset + rel_offset, buf)
+    }
+
+    /// Read up to `buf.len()` bytes starting at `rel_offset`, stopping at the end of the segment.
+    /// Returns the number of bytes read (zero when `rel_offset` is at the end of the segment).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `rel_offset` is past the end of the segment.
+    pub fn read_at(&self, rel_offset: u64, buf: &mut [u8]) -> Result<usize> {
+        ensure_in_bounds(rel_offset, 0, self.len)?;
+        let n = (self.len - rel_offset).min(buf.len() as u64) as usize;
+        self.parent
+            .read_into(self.offset + rel_offset, &mut buf[..n])?;
+        Ok(n)
+    }
+
+    /// Create a child view of `[rel_offset, rel_offset + len)` relative to this segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the child range exceeds this segment.
+    pub fn slice(&self, rel_offset: u64, len: u64) -> Result<Segment> {
+        ensure_in_bounds(rel_offset, len, self.len)?;
+        Segment::new(self.parent.clone(), self.offset + rel_offset, len)
+    }
+
     /// Length of the segment.
     #[must_use]
     pub fn len(&self) -> u64 {
@@ -103,13 +148,20 @@ impl Segment {
 /// segment.write(b"Hello from segment!")?;
 /// # Ok::<(), mmap_io::MmapIoError>(())
 /// ```
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct SegmentMut {
     parent: Arc<MemoryMappedFile>,
     offset: u64,
     len: u64,
 }
 
+/// `{:?}` prints the view's bounds; `{:#?}` also hex-dumps up to 256 bytes of its contents.
+impl std::fmt::Debug for SegmentMut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        crate::hexdump::fmt_segment(f, "SegmentMut", &self.parent, self.offset, self.len)
+    }
+}
+
 impl SegmentMut {
     /// Create a new mutable segment view. Performs bounds checks.
     ///
@@ -154,6 +206,96 @@ impl SegmentMut {
         self.parent.update_region(self.offset, data)
     }
 
+    /// Write bytes at `rel_offset` relative to the start of this segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `[rel_offset, rel_offset + data.len())` exceeds the segment.
+    /// Returns errors from the underlying `MemoryMappedFile::update_region` call.
+    pub fn write_at(&self, rel_offset: u64, data: &[u8]) -> Result<()> {
+        ensure_in_bounds(rel_offset, data.len() as u64, self.len)?;
+        self.parent.update_region(self.offset + rel_offset, data)
+    }
+
+    /// Copy the contents of `src` into the start of this segment.
+    ///
+    /// When both segments share the same parent mapping the copy is a single `memmove`
+    /// under one write lock (overlap is allowed). Across different mappings the data is
+    /// streamed in bounded chunks.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `src` is longer than this segment.
+    /// Returns `MmapIoError::InvalidMode` if this segment's parent is read-only.
+    pub fn copy_from(&self, src: &Segment) -> Result<()> {
+        ensure_in_bounds(0, src.len(), self.len)?;
+        if Arc::ptr_eq(&self.parent.inner, &src.parent.inner) {
+            return self
+                .parent
+                .copy_within_region(src.offset, self.offset, src.len);
+        }
+        let mut buf = vec![0u8; src.len.min(COPY_CHUNK_SIZE) as usize];
+        let mut done = 0u64;
+        while done < src.len {
+            let n = (src.len - done).min(COPY_CHUNK_SIZE) as usize;
+            src.read_into(done, &mut buf[..n])?;
+            self.parent.update_region(self.offset + done, &buf[..n])?;
+            done += n as u64;
+        }
+        Ok(())
+    }
+
+    /// Set every byte of this segment to `byte` under a single lock acquisition.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the parent is read-only.
+    /// Returns `MmapIoError::OutOfBounds` if the parent shrank below this segment.
+    pub fn fill(&self, byte: u8) -> Result<()> {
+        self.parent.fill_region(self.offset, self.len, byte)
+    }
+
+    /// Zero every byte of this segment. Equivalent to `fill(0)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from [`SegmentMut::fill`].
+    pub fn zero(&self) -> Result<()> {
+        self.fill(0)
+    }
+
+    /// Flush exactly this segment's byte range to disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from the underlying `MemoryMappedFile::flush_range` call.
+    pub fn flush(&self) -> Result<()> {
+        self.parent.flush_range(self.offset, self.len)
+    }
+
+    // Sub-view `[rel_offset, rel_offset + len)` of this segment.
+    pub(crate) fn sub(&self, rel_offset: u64, len: u64) -> Result<SegmentMut> {
+        ensure_in_bounds(rel_offset, len, self.len)?;
+        SegmentMut::new(self.parent.clone(), self.offset + rel_offset, len)
+    }
+
+    /// Split this segment into two adjacent views at `rel_offset`:
+    /// `[0, rel_offset)` and `[rel_offset, len)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `rel_offset` is greater than the segment length.
+    pub fn split_at(&self, rel_offset: u64) -> Result<(SegmentMut, SegmentMut)> {
+        ensure_in_bounds(rel_offset, 0, self.len)?;
+        let left = SegmentMut::new(self.parent.clone(), self.offset, rel_offset)?;
+        let right = SegmentMut::new(
+            self.parent.clone(),
+            self.offset + rel_offset,
+            self.len - rel_offset,
+        )?;
+        Ok((left, right))
+    }
+
     /// Length of the segment.
     #[must_use]
     pub fn len(&self) -> u64 {
@@ -178,3 +320,466 @@ impl SegmentMut {
         &self.parent
     }
 }
+
+/// One logical, contiguous view over several (possibly non-adjacent) segments.
+///
+/// Relative offsets address the concatenation of the pieces in the order given,
+/// which makes it easy to reassemble records stored in scattered regions.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, segment::{Segment, SegmentChain}};
+///
+/// let mmap = Arc::new(MemoryMappedFile::open_ro("data.bin")?);
+/// let chain = SegmentChain::new(vec![
+///     Segment::new(mmap.clone(), 4096, 100)?,
+///     Segment::new(mmap.clone(), 0, 50)?,
+/// ]);
+///
+/// let mut record = vec![0u8; chain.len() as usize];
+/// chain.read_into(0, &mut record)?;
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct SegmentChain {
+    pieces: Vec<Segment>,
+    // Logical start offset of each piece within the chain
+    starts: Vec<u64>,
+    len: u64,
+}
+
+impl SegmentChain {
+    /// Create a chain from segments in logical order.
+    #[must_use]
+    pub fn new(pieces: Vec<Segment>) -> Self {
+        let mut starts = Vec::with_capacity(pieces.len());
+        let mut len = 0u64;
+        for piece in &pieces {
+            starts.push(len);
+            len += piece.len();
+        }
+        Self {
+            pieces,
+            starts,
+            len,
+        }
+    }
+
+    /// Read `buf.len()` bytes starting at logical offset `rel_offset`, crossing piece
+    /// boundaries as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the range exceeds the chain length.
+    /// Returns errors from the underlying `Segment::read_into` calls.
+    pub fn read_into(&self, rel_offset: u64, buf: &mut [u8]) -> Result<()> {
+        ensure_in_bounds(rel_offset, buf.len() as u64, self.len)?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        // Index of the piece containing rel_offset (skipping empty pieces)
+        let mut idx = self.starts.partition_point(|&start| start <= rel_offset) - 1;
+        let mut pos = rel_offset;
+        let mut filled = 0usize;
+        while filled < buf.len() {
+            let piece = &self.pieces[idx];
+            let within = pos - self.starts[idx];
+            let n = (piece.len() - within).min((buf.len() - filled) as u64) as usize;
+            piece.read_into(within, &mut buf[filled..filled + n])?;
+            filled += n;
+            pos += n as u64;
+            idx += 1;
+        }
+        Ok(())
+    }
+
+    /// Total logical length of the chain in bytes.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Check if the chain is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The segments making up this chain, in logical order.
+    #[must_use]
+    pub fn segments(&self) -> &[Segment] {
+        &self.pieces
+    }
+}
+
+/// Iterator yielding [`Segment`] views that cover a mapping in fixed strides.
+///
+/// Created by [`MemoryMappedFile::segments`]. The final segment may be shorter than the stride.
+#[derive(Clone, Debug)]
+pub struct SegmentIter {
+    parent: Arc<MemoryMappedFile>,
+    size: u64,
+    offset: u64,
+    total: u64,
+}
+
+impl Iterator for SegmentIter {
+    type Item = Segment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (offset, len) = next_stride(&mut self.offset, self.size, self.total)?;
+        Some(Segment {
+            parent: self.parent.clone(),
+            offset,
+            len,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = remaining_strides(self.offset, self.size, self.total);
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for SegmentIter {}
+
+/// Iterator yielding [`SegmentMut`] views that cover a mapping in fixed strides.
+///
+/// Created by [`MemoryMappedFile::segments_mut`]. The final segment may be shorter than the stride.
+#[derive(Clone, Debug)]
+pub struct SegmentMutIter {
+    parent: Arc<MemoryMappedFile>,
+    size: u64,
+    offset: u64,
+    total: u64,
+}
+
+impl Iterator for SegmentMutIter {
+    type Item = SegmentMut;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (offset, len) = next_stride(&mut self.offset, self.size, self.total)?;
+        Some(SegmentMut {
+            parent: self.parent.clone(),
+            offset,
+            len,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = remaining_strides(self.offset, self.size, self.total);
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for SegmentMutIter {}
+
+// Advance `cursor` by one stride, returning the (offset, len) of the stride just passed.
+fn next_stride(cursor: &mut u64, size: u64, total: u64) -> Option<(u64, u64)> {
+    if *cursor >= total {
+        return None;
+    }
+    let offset = *cursor;
+    let len = (total - offset).min(size);
+    *cursor += len;
+    Some((offset, len))
+}
+
+fn remaining_strides(cursor: u64, size: u64, total: u64) -> usize {
+    total.saturating_sub(cursor).div_ceil(size) as usize
+}
+
+impl MemoryMappedFile {
+    /// Iterate over the mapping as consecutive [`Segment`] views of `size` bytes.
+    ///
+    /// Unlike `chunks()`, no data is copied; each segment shares the `Arc`'d parent.
+    /// The segments cover the length of the mapping at the time of the call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn segments(self: &Arc<Self>, size: u64) -> SegmentIter {
+        assert!(size > 0, "segment size must be non-zero");
+        SegmentIter {
+            parent: self.clone(),
+            size,
+            offset: 0,
+            total: self.len(),
+        }
+    }
+
+    /// Iterate over the mapping as consecutive [`SegmentMut`] views of `size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn segments_mut(self: &Arc<Self>, size: u64) -> SegmentMutIter {
+        assert!(size > 0, "segment size must be non-zero");
+        SegmentMutIter {
+            parent: self.clone(),
+            size,
+            offset: 0,
+            total: self.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use crate::errors::MmapIoError;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_segment_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_segment_mut_write_at() {
+        let path = tmp_path("write_at");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 64).expect("create"));
+        let seg = SegmentMut::new(mmap.clone(), 16, 16).expect("segment");
+
+        seg.write_at(4, b"abcd").expect("write_at");
+        let mut buf = [0u8; 4];
+        mmap.read_into(20, &mut buf).expect("read");
+        assert_eq!(&buf, b"abcd");
+
+        // Writes must stay inside the segment even if the file is larger
+        assert!(matches!(
+            seg.write_at(14, b"xyz"),
+            Err(MmapIoError::OutOfBounds { .. })
+        ));
+        assert!(seg.write_at(17, b"").is_err());
+        seg.write_at(12, b"wxyz").expect("write_at end");
+
+        drop(seg);
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_segment_read_into_and_read_at() {
+        let path = tmp_path("read_into");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 64).expect("create"));
+        mmap.update_region(8, b"0123456789").expect("write");
+
+        // RW parent: as_slice is unavailable but read_into works
+        let seg = Segment::new(mmap.clone(), 8, 10).expect("segment");
+        let mut buf = [0u8; 3];
+        seg.read_into(2, &mut buf).expect("read_into");
+        assert_eq!(&buf, b"234");
+        assert!(seg.read_into(8, &mut buf).is_err());
+
+        // read_at clamps to the segment end
+        let mut big = [0u8; 8];
+        assert_eq!(seg.read_at(6, &mut big).expect("read_at"), 4);
+        assert_eq!(&big[..4], b"6789");
+        assert_eq!(seg.read_at(10, &mut big).expect("read_at end"), 0);
+        assert!(seg.read_at(11, &mut big).is_err());
+        drop(seg);
+        drop(mmap);
+
+        // RO parent
+        let ro = Arc::new(MemoryMappedFile::open_ro(&path).expect("open ro"));
+        let seg = Segment::new(ro, 8, 10).expect("segment");
+        seg.read_into(0, &mut buf).expect("ro read_into");
+        assert_eq!(&buf, b"012");
+        drop(seg);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_segment_slice_and_split_at() {
+        let path = tmp_path("slice_split");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 64).expect("create"));
+        mmap.update_region(0, b"record-header-field")
+            .expect("write");
+
+        let block = Segment::new(mmap.clone(), 7, 12).expect("block");
+        let field = block.slice(7, 5).expect("field");
+        assert_eq!(field.offset(), 14);
+        let mut buf = [0u8; 5];
+        field.read_into(0, &mut buf).expect("read");
+        assert_eq!(&buf, b"field");
+        assert!(block.slice(8, 5).is_err());
+
+        let seg = SegmentMut::new(mmap.clone(), 32, 16).expect("segment");
+        let (left, right) = seg.split_at(10).expect("split");
+        assert_eq!((left.offset(), left.len()), (32, 10));
+        assert_eq!((right.offset(), right.len()), (42, 6));
+        right.write_at(0, b"tail").expect("write right");
+        assert!(left.write_at(8, b"xyz").is_err());
+        assert!(seg.split_at(17).is_err());
+        let (_, empty) = seg.split_at(16).expect("split at end");
+        assert!(empty.is_empty());
+
+        drop((block, field, seg, left, right, empty));
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_segment_mut_flush() {
+        let path = tmp_path("flush");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 8192).expect("create"));
+        let seg = SegmentMut::new(mmap.clone(), 4096, 128).expect("segment");
+        seg.write(b"durable").expect("write");
+        seg.flush().expect("flush");
+        drop(seg);
+        drop(mmap);
+
+        let ro = MemoryMappedFile::open_ro(&path).expect("open ro");
+        assert_eq!(ro.as_slice(4096, 7).expect("slice"), b"durable");
+        drop(ro);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_segment_mut_fill_and_zero() {
+        let path = tmp_path("fill");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 64).expect("create"));
+        let seg = SegmentMut::new(mmap.clone(), 8, 16).expect("segment");
+        seg.fill(0xAB).expect("fill");
+
+        let mut buf = [0u8; 32];
+        mmap.read_into(0, &mut buf).expect("read");
+        assert!(buf[..8].iter().all(|&b| b == 0));
+        assert!(buf[8..24].iter().all(|&b| b == 0xAB));
+        assert!(buf[24..].iter().all(|&b| b == 0));
+
+        seg.zero().expect("zero");
+        mmap.read_into(0, &mut buf).expect("read");
+        assert!(buf.iter().all(|&b| b == 0));
+
+        drop(seg);
+        drop(mmap);
+        let ro = Arc::new(MemoryMappedFile::open_ro(&path).expect("open ro"));
+        let seg = SegmentMut::new(ro, 0, 8).expect("segment");
+        assert!(matches!(seg.zero(), Err(MmapIoError::InvalidMode(_))));
+        drop(seg);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_segment_chain_read_across_pieces() {
+        let path = tmp_path("chain");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 64).expect("create"));
+        mmap.update_region(40, b"hello ").expect("write 1");
+        mmap.update_region(2, b"mapped ").expect("write 2");
+        mmap.update_region(20, b"world").expect("write 3");
+
+        let chain = SegmentChain::new(vec![
+            Segment::new(mmap.clone(), 40, 6).expect("piece 1"),
+            Segment::new(mmap.clone(), 0, 0).expect("empty piece"),
+            Segment::new(mmap.clone(), 2, 7).expect("piece 2"),
+            Segment::new(mmap.clone(), 20, 5).expect("piece 3"),
+        ]);
+        assert_eq!(chain.len(), 18);
+
+        let mut all = vec![0u8; 18];
+        chain.read_into(0, &mut all).expect("read all");
+        assert_eq!(&all, b"hello mapped world");
+
+        let mut mid = [0u8; 9];
+        chain.read_into(4, &mut mid).expect("read mid");
+        assert_eq!(&mid, b"o mapped ");
+
+        let mut tail = [0u8; 5];
+        chain.read_into(13, &mut tail).expect("read tail");
+        assert_eq!(&tail, b"world");
+        assert!(chain.read_into(14, &mut tail).is_err());
+
+        drop(chain);
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_segment_mut_copy_from() {
+        let path = tmp_path("copy_from");
+        let other_path = tmp_path("copy_from_other");
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&other_path);
+
+        let mmap = Arc::new(create_mmap(&path, 64).expect("create"));
+        mmap.update_region(0, b"abcdefgh").expect("write");
+
+        // Same parent, overlapping ranges
+        let src = Segment::new(mmap.clone(), 0, 8).expect("src");
+        let dst = SegmentMut::new(mmap.clone(), 4, 8).expect("dst");
+        dst.copy_from(&src).expect("copy within");
+        let mut buf = [0u8; 12];
+        mmap.read_into(0, &mut buf).expect("read");
+        assert_eq!(&buf, b"abcdabcdefgh");
+
+        // Different parents
+        let other = Arc::new(create_mmap(&other_path, 16).expect("create other"));
+        let dst = SegmentMut::new(other.clone(), 2, 12).expect("dst other");
+        dst.copy_from(&Segment::new(mmap.clone(), 0, 12).expect("src"))
+            .expect("copy across");
+        other.read_into(2, &mut buf).expect("read other");
+        assert_eq!(&buf, b"abcdabcdefgh");
+
+        // Source longer than destination
+        let small = SegmentMut::new(other.clone(), 0, 4).expect("small");
+        assert!(small.copy_from(&src).is_err());
+
+        drop((src, dst, small));
+        drop((mmap, other));
+        fs::remove_file(&path).expect("cleanup");
+        fs::remove_file(&other_path).expect("cleanup other");
+    }
+
+    #[test]
+    fn test_segments_iteration() {
+        let path = tmp_path("segments");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 100).expect("create"));
+
+        let iter = mmap.segments_mut(32);
+        assert_eq!(iter.len(), 4);
+        for (i, seg) in iter.enumerate() {
+            seg.write(&vec![i as u8; seg.len() as usize])
+                .expect("write");
+        }
+
+        let segs: Vec<_> = mmap.segments(32).collect();
+        assert_eq!(segs.len(), 4);
+        assert_eq!(segs[3].offset(), 96);
+        assert_eq!(segs[3].len(), 4);
+        let mut buf = [0u8; 4];
+        segs[3].read_into(0, &mut buf).expect("read");
+        assert_eq!(buf, [3u8; 4]);
+
+        drop(segs);
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
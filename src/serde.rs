@@ -0,0 +1,181 @@
+//! Length-prefixed serde records stored in mapped regions.
+//!
+//! Each record is framed as an 8-byte header followed by the encoded payload:
+//!
+//! | bytes | field                                   |
+//! |-------|-----------------------------------------|
+//! | 0     | format tag (see [`SerdeFormat`])        |
+//! | 1..4  | reserved (zero)                         |
+//! | 4..8  | payload length, little-endian `u32`     |
+//!
+//! The format is stored in the frame, so readers do not need to know how a record was written.
+
+use ::serde::de::DeserializeOwned;
+use ::serde::Serialize;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+
+/// Size of the frame header preceding every serialized payload.
+pub const FRAME_HEADER_SIZE: u64 = 8;
+
+/// Encoding used for a serialized record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerdeFormat {
+    /// Human-readable JSON via `serde_json`.
+    Json,
+    /// Compact binary encoding via `bincode` 1.x (default options).
+    Bincode,
+}
+
+impl SerdeFormat {
+    fn tag(self) -> u8 {
+        match self {
+            SerdeFormat::Json => 1,
+            SerdeFormat::Bincode => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(SerdeFormat::Json),
+            2 => Some(SerdeFormat::Bincode),
+            _ => None,
+        }
+    }
+}
+
+impl MemoryMappedFile {
+    /// Serialize `value` with `format` and store it as a framed record at `offset`.
+    ///
+    /// Returns the total number of bytes written (header plus payload), i.e. the offset
+    /// delta to the next free byte after the record.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Serialization` if encoding fails or the payload exceeds `u32::MAX`.
+    /// Returns `MmapIoError::OutOfBounds` if the framed record does not fit in the mapping.
+    /// Returns `MmapIoError::InvalidMode` if the mapping is read-only.
+    pub fn write_serialized<T: Serialize + ?Sized>(
+        &self,
+        offset: u64,
+        value: &T,
+        format: SerdeFormat,
+    ) -> Result<u64> {
+        let payload = match format {
+            SerdeFormat::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            SerdeFormat::Bincode => bincode::serialize(value).map_err(|e| e.to_string()),
+        }
+        .map_err(MmapIoError::Serialization)?;
+        let len = u32::try_from(payload.len()).map_err(|_| {
+            MmapIoError::Serialization(format!(
+                "payload of {} bytes exceeds the u32 frame limit",
+                payload.len()
+            ))
+        })?;
+
+        let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE as usize + payload.len());
+        frame.push(format.tag());
+        frame.extend_from_slice(&[0u8; 3]);
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(&payload);
+        self.update_region(offset, &frame)?;
+        Ok(frame.len() as u64)
+    }
+
+    /// Read and decode the framed record stored at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Serialization` if the header has an unknown format tag or the
+    /// payload fails to decode.
+    /// Returns `MmapIoError::OutOfBounds` if the header or payload extends past the mapping.
+    pub fn read_deserialized<T: DeserializeOwned>(&self, offset: u64) -> Result<T> {
+        let mut header = [0u8; FRAME_HEADER_SIZE as usize];
+        self.read_into(offset, &mut header)?;
+        let format = SerdeFormat::from_tag(header[0]).ok_or_else(|| {
+            MmapIoError::Serialization(format!(
+                "unknown serde format tag {} at offset {offset}",
+                header[0]
+            ))
+        })?;
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+        // Validate before allocating so a corrupt length cannot trigger a huge allocation
+        crate::utils::ensure_in_bounds(offset + FRAME_HEADER_SIZE, u64::from(len), self.len())?;
+        let mut payload = vec![0u8; len as usize];
+        self.read_into(offset + FRAME_HEADER_SIZE, &mut payload)?;
+        match format {
+            SerdeFormat::Json => serde_json::from_slice(&payload).map_err(|e| e.to_string()),
+            SerdeFormat::Bincode => bincode::deserialize(&payload).map_err(|e| e.to_string()),
+        }
+        .map_err(MmapIoError::Serialization)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use ::serde::Deserialize;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_serde_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Meta {
+        name: String,
+        version: u32,
+        shards: Vec<u64>,
+    }
+
+    #[test]
+    fn test_serialized_round_trip() {
+        let path = tmp_path("round_trip");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 4096).expect("create");
+        let meta = Meta {
+            name: "index".into(),
+            version: 3,
+            shards: vec![1, 2, 3],
+        };
+
+        let json_len = mmap
+            .write_serialized(0, &meta, SerdeFormat::Json)
+            .expect("write json");
+        let bin_len = mmap
+            .write_serialized(json_len, &meta, SerdeFormat::Bincode)
+            .expect("write bincode");
+        assert!(bin_len > FRAME_HEADER_SIZE);
+
+        assert_eq!(mmap.read_deserialized::<Meta>(0).expect("read json"), meta);
+        assert_eq!(
+            mmap.read_deserialized::<Meta>(json_len)
+                .expect("read bincode"),
+            meta
+        );
+
+        // Unwritten space has no valid frame
+        assert!(matches!(
+            mmap.read_deserialized::<Meta>(2048),
+            Err(MmapIoError::Serialization(_))
+        ));
+        // Records that do not fit are rejected
+        assert!(mmap
+            .write_serialized(4090, &meta, SerdeFormat::Json)
+            .is_err());
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,416 @@
+//! Size-classed slab allocator with free lists persisted in a mapped segment.
+//!
+//! Unlike [`MmapArena`](crate::arena::MmapArena), blocks can be freed and are reused by later
+//! allocations of the same size class, across restarts. Each class keeps an intrusive singly
+//! linked free list: a free block stores the offset of the next free block in its first
+//! eight bytes. Blocks never seen before are carved from the untouched tail of the segment.
+//!
+//! Segment layout (all integers little-endian, offsets relative to the segment start):
+//!
+//! | bytes          | field                                            |
+//! |----------------|--------------------------------------------------|
+//! | 0..8           | magic `MMIOSLAB`                                 |
+//! | 8..12          | number of size classes `n`                       |
+//! | 12..16         | reserved                                         |
+//! | 16..24         | end of carved blocks                             |
+//! | 24..24+16n     | per class: block size (8), free list head (8)    |
+//! | then (16-aligned) | blocks                                        |
+
+use std::mem::{align_of, size_of};
+
+use parking_lot::Mutex;
+
+use crate::errors::{MmapIoError, Result};
+use crate::segment::SegmentMut;
+use crate::typed::{Pod, SegmentTyped};
+use crate::utils::align_up;
+
+/// Size classes used by [`SlabAllocator::create_default`].
+pub const DEFAULT_SIZE_CLASSES: [u64; 9] = [16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+/// Alignment of every block.
+pub const SLAB_BLOCK_ALIGN: u64 = 16;
+
+const MAGIC: &[u8; 8] = b"MMIOSLAB";
+const FIXED_HEADER: u64 = 24;
+const CLASS_ENTRY: u64 = 16;
+const MAX_CLASSES: usize = 64;
+// Free list terminator; offset 0 is always inside the header, never a block
+const NIL: u64 = 0;
+
+/// Persistent allocator of fixed-size blocks in a segment of a mapped file.
+///
+/// A request is served from the smallest class that fits. Operations through one handle
+/// are serialized; allocating from several processes at once must be coordinated
+/// externally (e.g. with a file lock). Freeing a block twice corrupts its free list.
+/// A crash between allocation and the caller recording the block may leak it, but a
+/// block is never handed out twice.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, segment::SegmentMut, slab::SlabAllocator};
+///
+/// let mmap = Arc::new(MemoryMappedFile::create_rw("heap.bin", 1 << 20)?);
+/// let slab = SlabAllocator::create_default(SegmentMut::new(mmap, 0, 1 << 20)?)?;
+/// let block = slab.alloc(100)?; // served from the 128-byte class
+/// block.write_at(0, b"record")?;
+/// slab.free(&block)?;
+/// assert_eq!(slab.alloc(120)?.offset(), block.offset());
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct SlabAllocator {
+    segment: SegmentMut,
+    classes: Vec<u64>,
+    // Serializes read-modify-write of the persisted free lists and carve mark
+    lock: Mutex<()>,
+}
+
+impl SlabAllocator {
+    /// Initialize an empty allocator in `segment` with the given block sizes.
+    ///
+    /// Sizes must be strictly increasing, non-zero multiples of [`SLAB_BLOCK_ALIGN`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for invalid size classes or a non-ReadWrite
+    /// parent, and `MmapIoError::OutOfBounds` if the segment cannot hold the header.
+    pub fn create(segment: SegmentMut, size_classes: &[u64]) -> Result<Self> {
+        if size_classes.is_empty() || size_classes.len() > MAX_CLASSES {
+            return Err(MmapIoError::InvalidMode(
+                "slab needs between 1 and 64 size classes",
+            ));
+        }
+        let valid = size_classes
+            .iter()
+            .all(|&s| s > 0 && s % SLAB_BLOCK_ALIGN == 0)
+            && size_classes.windows(2).all(|w| w[0] < w[1]);
+        if !valid {
+            return Err(MmapIoError::InvalidMode(
+                "slab size classes must be increasing multiples of 16",
+            ));
+        }
+
+        let mut header = vec![0u8; header_len(size_classes.len()) as usize];
+        header[8..12].copy_from_slice(&(size_classes.len() as u32).to_le_bytes());
+        let blocks_start = blocks_start(segment.offset(), size_classes.len());
+        header[16..24].copy_from_slice(&blocks_start.to_le_bytes());
+        for (i, &size) in size_classes.iter().enumerate() {
+            let at = (FIXED_HEADER + i as u64 * CLASS_ENTRY) as usize;
+            header[at..at + 8].copy_from_slice(&size.to_le_bytes());
+            header[at + 8..at + 16].copy_from_slice(&NIL.to_le_bytes());
+        }
+        segment.write_at(0, &header)?;
+        // Publish the magic last so a torn initialization is not mistaken for a slab
+        segment.write_at(0, MAGIC)?;
+        Ok(Self {
+            segment,
+            classes: size_classes.to_vec(),
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Initialize an empty allocator with [`DEFAULT_SIZE_CLASSES`].
+    ///
+    /// # Errors
+    ///
+    /// As [`create`](Self::create).
+    pub fn create_default(segment: SegmentMut) -> Result<Self> {
+        Self::create(segment, &DEFAULT_SIZE_CLASSES)
+    }
+
+    /// Open an allocator previously created in `segment`, keeping its free lists.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the header is missing or invalid.
+    pub fn open(segment: SegmentMut) -> Result<Self> {
+        let mut slab = Self {
+            segment,
+            classes: Vec::new(),
+            lock: Mutex::new(()),
+        };
+        let mut magic = [0u8; 8];
+        slab.read(0, &mut magic)?;
+        if &magic != MAGIC {
+            return Err(MmapIoError::Corrupt("missing slab magic".into()));
+        }
+        let mut word = [0u8; 4];
+        slab.read(8, &mut word)?;
+        let count = u32::from_le_bytes(word) as usize;
+        if count == 0 || count > MAX_CLASSES {
+            return Err(MmapIoError::Corrupt(format!(
+                "slab header lists {count} size classes"
+            )));
+        }
+        for i in 0..count {
+            slab.classes
+                .push(slab.read_u64(FIXED_HEADER + i as u64 * CLASS_ENTRY)?);
+        }
+        if slab.classes.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(MmapIoError::Corrupt(
+                "slab size classes out of order".into(),
+            ));
+        }
+        slab.carve_mark()?;
+        Ok(slab)
+    }
+
+    /// Allocate a block of at least `len` bytes, returned as a `len`-byte segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if `len` exceeds the largest class and
+    /// `MmapIoError::OutOfBounds` if the segment has no room for a new block.
+    pub fn alloc(&self, len: u64) -> Result<SegmentMut> {
+        let class = self
+            .classes
+            .iter()
+            .position(|&size| size >= len.max(1))
+            .ok_or(MmapIoError::InvalidMode(
+                "allocation exceeds the largest slab size class",
+            ))?;
+        let _guard = self.lock.lock();
+        let head_at = head_offset(class);
+        let head = self.read_u64(head_at)?;
+        let block = if head != NIL {
+            self.check_block(head, class)?;
+            let next = self.read_u64(head)?;
+            self.write_u64(head_at, next)?;
+            head
+        } else {
+            let block = self.carve_mark()?;
+            let end = block
+                .checked_add(self.classes[class])
+                .filter(|&end| end <= self.segment.len())
+                .ok_or(MmapIoError::OutOfBounds {
+                    offset: block,
+                    len: self.classes[class],
+                    total: self.segment.len(),
+                })?;
+            self.write_u64(16, end)?;
+            block
+        };
+        self.segment.sub(block, len)
+    }
+
+    /// Allocate room for `count` `T`s.
+    ///
+    /// # Errors
+    ///
+    /// As [`alloc`](Self::alloc), plus `MmapIoError::Misaligned` if `T` needs more than
+    /// [`SLAB_BLOCK_ALIGN`] alignment.
+    pub fn alloc_typed<T: Pod>(&self, count: usize) -> Result<SegmentTyped<T>> {
+        if align_of::<T>() as u64 > SLAB_BLOCK_ALIGN {
+            return Err(MmapIoError::Misaligned {
+                required: align_of::<T>() as u64,
+                offset: SLAB_BLOCK_ALIGN,
+            });
+        }
+        let len =
+            (size_of::<T>() as u64)
+                .checked_mul(count as u64)
+                .ok_or(MmapIoError::InvalidMode(
+                    "allocation exceeds the largest slab size class",
+                ))?;
+        SegmentTyped::from_segment(self.alloc(len)?)
+    }
+
+    /// Return `block` (as handed out by this allocator) to its class's free list.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the block does not belong to this allocator.
+    pub fn free(&self, block: &SegmentMut) -> Result<()> {
+        let class = self
+            .classes
+            .iter()
+            .position(|&size| size >= block.len().max(1))
+            .ok_or(MmapIoError::InvalidMode(
+                "block does not belong to this slab",
+            ))?;
+        let rel =
+            block
+                .offset()
+                .checked_sub(self.segment.offset())
+                .ok_or(MmapIoError::InvalidMode(
+                    "block does not belong to this slab",
+                ))?;
+        let _guard = self.lock.lock();
+        self.check_block(rel, class)
+            .map_err(|_| MmapIoError::InvalidMode("block does not belong to this slab"))?;
+        let head_at = head_offset(class);
+        let head = self.read_u64(head_at)?;
+        // Link the block in before publishing it as the new head
+        self.write_u64(rel, head)?;
+        self.write_u64(head_at, rel)
+    }
+
+    /// Block sizes of the size classes, ascending.
+    #[must_use]
+    pub fn size_classes(&self) -> &[u64] {
+        &self.classes
+    }
+
+    /// Number of blocks on the free list of class `class`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` for an unknown class and `MmapIoError::Corrupt`
+    /// if the free list is malformed.
+    pub fn free_blocks(&self, class: usize) -> Result<usize> {
+        if class >= self.classes.len() {
+            return Err(MmapIoError::OutOfBounds {
+                offset: class as u64,
+                len: 1,
+                total: self.classes.len() as u64,
+            });
+        }
+        let _guard = self.lock.lock();
+        let limit = self.segment.len() / self.classes[class];
+        let mut count = 0;
+        let mut cur = self.read_u64(head_offset(class))?;
+        while cur != NIL {
+            self.check_block(cur, class)?;
+            count += 1;
+            if count as u64 > limit {
+                return Err(MmapIoError::Corrupt("slab free list has a cycle".into()));
+            }
+            cur = self.read_u64(cur)?;
+        }
+        Ok(count)
+    }
+
+    /// Bytes of the segment not yet carved into blocks.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the persisted mark is invalid.
+    pub fn uncarved(&self) -> Result<u64> {
+        Ok(self.segment.len() - self.carve_mark()?)
+    }
+
+    /// The segment the allocator lives in.
+    #[must_use]
+    pub fn segment(&self) -> &SegmentMut {
+        &self.segment
+    }
+
+    fn carve_mark(&self) -> Result<u64> {
+        let mark = self.read_u64(16)?;
+        let first = blocks_start(self.segment.offset(), self.classes.len());
+        if mark < first || mark > self.segment.len() {
+            return Err(MmapIoError::Corrupt(format!(
+                "slab carve mark {mark} lies outside the block area"
+            )));
+        }
+        Ok(mark)
+    }
+
+    // Validate that `rel` can be a block of `class`: inside the carved area and aligned.
+    fn check_block(&self, rel: u64, class: usize) -> Result<()> {
+        let first = blocks_start(self.segment.offset(), self.classes.len());
+        let mark = self.carve_mark()?;
+        let in_range = rel >= first
+            && rel
+                .checked_add(self.classes[class])
+                .is_some_and(|end| end <= mark);
+        if !in_range || (self.segment.offset() + rel) % SLAB_BLOCK_ALIGN != 0 {
+            return Err(MmapIoError::Corrupt(format!(
+                "slab block offset {rel} is invalid"
+            )));
+        }
+        Ok(())
+    }
+
+    fn read(&self, rel: u64, buf: &mut [u8]) -> Result<()> {
+        self.segment
+            .parent()
+            .read_into(self.segment.offset() + rel, buf)
+    }
+
+    fn read_u64(&self, rel: u64) -> Result<u64> {
+        let mut word = [0u8; 8];
+        self.read(rel, &mut word)?;
+        Ok(u64::from_le_bytes(word))
+    }
+
+    fn write_u64(&self, rel: u64, value: u64) -> Result<()> {
+        self.segment.write_at(rel, &value.to_le_bytes())
+    }
+}
+
+fn header_len(classes: usize) -> u64 {
+    FIXED_HEADER + classes as u64 * CLASS_ENTRY
+}
+
+fn head_offset(class: usize) -> u64 {
+    FIXED_HEADER + class as u64 * CLASS_ENTRY + 8
+}
+
+// First block offset (relative), aligned on absolute file offsets.
+fn blocks_start(segment_offset: u64, classes: usize) -> u64 {
+    align_up(segment_offset + header_len(classes), SLAB_BLOCK_ALIGN) - segment_offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!("mmap_io_slab_test_{}_{}", name, std::process::id()));
+        p
+    }
+
+    #[test]
+    fn test_slab_reuse_survives_reopen() {
+        let path = tmp_path("slab");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 8192).expect("create"));
+        let segment = SegmentMut::new(mmap.clone(), 8, 4096).expect("segment");
+        let slab = SlabAllocator::create(segment.clone(), &[16, 64, 256]).expect("slab");
+
+        let a = slab.alloc(10).expect("a");
+        let b = slab.alloc(10).expect("b");
+        let big = slab.alloc(200).expect("big");
+        assert_eq!(a.len(), 10);
+        assert_eq!(b.offset() - a.offset(), 16);
+        assert_eq!(big.offset() % SLAB_BLOCK_ALIGN, 0);
+        big.write_at(0, b"keep me").expect("write");
+        slab.free(&a).expect("free a");
+        slab.free(&b).expect("free b");
+        assert_eq!(slab.free_blocks(0).expect("count"), 2);
+        drop(slab);
+
+        let slab = SlabAllocator::open(segment.clone()).expect("reopen");
+        assert_eq!(slab.size_classes(), &[16, 64, 256]);
+        // LIFO reuse of freed blocks
+        assert_eq!(slab.alloc(16).expect("reuse").offset(), b.offset());
+        assert_eq!(slab.alloc(1).expect("reuse").offset(), a.offset());
+        assert_eq!(slab.free_blocks(0).expect("count"), 0);
+        let words = slab.alloc_typed::<u64>(8).expect("typed");
+        words.set(7, 99).expect("set");
+        assert_eq!(words.segment().len(), 64);
+
+        assert!(slab.alloc(257).is_err());
+        let outside = SegmentMut::new(mmap.clone(), 6000, 16).expect("outside");
+        assert!(slab.free(&outside).is_err());
+        while slab.alloc(256).is_ok() {}
+        assert!(slab.uncarved().expect("uncarved") < 256);
+        assert!(SlabAllocator::create(segment.clone(), &[16, 8]).is_err());
+        assert!(
+            SlabAllocator::open(SegmentMut::new(mmap.clone(), 5000, 512).expect("seg")).is_err()
+        );
+
+        drop((slab, a, b, big, words, outside, segment, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,322 @@
+//! Disjoint mutable parts of a mapping for parallel writers.
+//!
+//! [`MemoryMappedFile::split_into_mut`] and [`MemoryMappedFile::split_at_mut`] cut a
+//! `ReadWrite` mapping into non-overlapping [`MmapPart`]s. Each part is an owned, `Send`
+//! handle that writes straight into its own range without taking the mapping's write lock,
+//! so N download or encoder threads can fill N chunks with no contention.
+//!
+//! While any part is alive the mapping cannot be resized or split again, and its own
+//! reads, writes, slices, and atomic views return `MmapIoError::InvalidMode`. Parts do not
+//! run the flush policy, observers, or access recording; call [`MmapPart::flush`] (or flush
+//! the mapping) when a part is done.
+//!
+//! Splitting is `unsafe` because the mapping cannot police everything that reaches its
+//! bytes: atomic references taken before the split, segments and windows of other handles
+//! onto the same file, and other processes would all race with the parts.
+
+use std::ptr::NonNull;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::{Inner, MapVariant, MemoryMappedFile};
+use crate::utils::ensure_in_bounds;
+
+/// Exclusive writer for one range of a split mapping.
+pub struct MmapPart {
+    inner: Arc<Inner>,
+    ptr: NonNull<u8>,
+    offset: u64,
+    len: usize,
+}
+
+// SAFETY: the caller of `split_into_mut` promised the part is the only handle touching its
+// range, and the mapping cannot be remapped or unmapped while the part holds `inner` and
+// is counted in `split_parts`.
+unsafe impl Send for MmapPart {}
+// SAFETY: shared references only allow reads of the range.
+unsafe impl Sync for MmapPart {}
+
+impl MmapPart {
+    /// Offset of this part within the mapping.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Length of this part in bytes.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len as u64
+    }
+
+    /// Whether this part is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The part's bytes.
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: ptr..ptr+len is mapped for the part's lifetime and not written elsewhere.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// The part's bytes, mutably. The whole part counts as written for the next flush.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.mark_written(self.offset, self.len as u64);
+        // SAFETY: as above, and `&mut self` makes this the only live reference.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Copy `data` to `rel_offset` within the part.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the write does not fit in the part.
+    pub fn write_at(&mut self, rel_offset: u64, data: &[u8]) -> Result<()> {
+        ensure_in_bounds(rel_offset, data.len() as u64, self.len as u64)?;
+        self.mark_written(self.offset + rel_offset, data.len() as u64);
+        // SAFETY: bounds checked above; the range belongs to this part alone.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                self.ptr.as_ptr().add(rel_offset as usize),
+                data.len(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Flush this part's range to disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors from [`MemoryMappedFile::flush_range`].
+    pub fn flush(&self) -> Result<()> {
+        let mmap = MemoryMappedFile {
+            inner: Arc::clone(&self.inner),
+        };
+        mmap.flush_range(self.offset, self.len as u64)
+    }
+
+    fn mark_written(&self, offset: u64, bytes: u64) {
+        *self.inner.written_since_last_flush.write() += bytes;
+        self.inner.dirty.lock().mark(offset, bytes);
+    }
+}
+
+impl Drop for MmapPart {
+    fn drop(&mut self) {
+        self.inner.split_parts.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl std::fmt::Debug for MmapPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MmapPart")
+            .field("offset", &self.offset)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl MemoryMappedFile {
+    /// Split the mapping into `n` contiguous parts of near-equal length (earlier parts get
+    /// one extra byte when the length does not divide evenly).
+    ///
+    /// # Safety
+    ///
+    /// Until every returned part is dropped, nothing else may read or write the mapped
+    /// bytes: no atomic views or slices obtained before the split, no other mapping of the
+    /// same file in this or another process. The mapping's own accessors are rejected while
+    /// it is split, but references handed out earlier are not.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the mapping is not `ReadWrite`, is guarded or
+    /// already split, or `n` is zero or exceeds the length.
+    pub unsafe fn split_into_mut(&self, n: usize) -> Result<Vec<MmapPart>> {
+        self.split_with(|total| {
+            let n64 = n as u64;
+            if n == 0 || n64 > total {
+                return Err(MmapIoError::InvalidMode(
+                    "split_into_mut needs between 1 and len parts",
+                ));
+            }
+            let (base, extra) = (total / n64, total % n64);
+            let mut start = 0;
+            Ok((0..n64)
+                .map(|i| {
+                    let len = base + u64::from(i < extra);
+                    let range = (start, len);
+                    start += len;
+                    range
+                })
+                .collect())
+        })
+    }
+
+    /// Split the mapping at the given strictly increasing offsets; `k` offsets give `k + 1`
+    /// parts covering the whole mapping.
+    ///
+    /// # Safety
+    ///
+    /// Same contract as [`split_into_mut`](Self::split_into_mut).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if the mapping is not `ReadWrite`, is guarded or
+    /// already split, or the offsets are not strictly increasing within `(0, len)`.
+    pub unsafe fn split_at_mut(&self, offsets: &[u64]) -> Result<Vec<MmapPart>> {
+        self.split_with(|total| {
+            let mut ranges = Vec::with_capacity(offsets.len() + 1);
+            let mut start = 0;
+            for &at in offsets.iter().chain(std::iter::once(&total)) {
+                if at <= start || at > total {
+                    return Err(MmapIoError::InvalidMode(
+                        "split_at_mut offsets must be strictly increasing within the mapping",
+                    ));
+                }
+                ranges.push((start, at - start));
+                start = at;
+            }
+            Ok(ranges)
+        })
+    }
+
+    fn split_with(
+        &self,
+        plan: impl FnOnce(u64) -> Result<Vec<(u64, u64)>>,
+    ) -> Result<Vec<MmapPart>> {
+        let MapVariant::Rw(lock) = &self.inner.map else {
+            return Err(MmapIoError::InvalidMode("split requires ReadWrite mode"));
+        };
+        self.check_unguarded()?;
+        // The write lock serializes against resize, which checks the count under it
+        let mut guard = lock.write();
+        let total = self.current_len()?;
+        let ranges = plan(total)?;
+        if self
+            .inner
+            .split_parts
+            .compare_exchange(0, ranges.len(), Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(MmapIoError::InvalidMode(
+                "mapping is already split; drop the existing parts first",
+            ));
+        }
+        let base = guard.as_mut_ptr();
+        Ok(ranges
+            .into_iter()
+            .map(|(offset, len)| MmapPart {
+                inner: Arc::clone(&self.inner),
+                // SAFETY: offset < total, so the pointer stays inside the mapping.
+                ptr: unsafe { NonNull::new_unchecked(base.add(offset as usize)) },
+                offset,
+                len: len as usize,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::thread;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_split_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_split_into_parallel_writers() {
+        let path = tmp_path("parallel");
+        let _ = fs::remove_file(&path);
+        let mmap = MemoryMappedFile::create_rw(&path, 1003).expect("create");
+
+        // SAFETY: nothing else maps the file, and no views were taken before the split.
+        let parts = unsafe { mmap.split_into_mut(4) }.expect("split");
+        let lens: Vec<_> = parts.iter().map(MmapPart::len).collect();
+        assert_eq!(lens, vec![251, 251, 251, 250]);
+        assert!(unsafe { mmap.split_into_mut(2) }.is_err());
+        assert!(matches!(
+            mmap.resize(2048),
+            Err(MmapIoError::ResizeFailed(_))
+        ));
+
+        let workers: Vec<_> = parts
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut part)| {
+                thread::spawn(move || {
+                    part.as_mut_slice().fill(b'a' + i as u8);
+                    part.write_at(0, b"#").expect("write");
+                    assert!(part.write_at(part.len(), b"x").is_err());
+                    part.flush().expect("flush");
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().expect("worker");
+        }
+        let data = mmap.read_all().expect("read");
+        assert_eq!(&data[0..2], b"#a");
+        assert_eq!(&data[251..253], b"#b");
+        assert_eq!(data[1002], b'd');
+
+        // Parts dropped: resize and custom splits work again
+        mmap.resize(1100).expect("resize");
+        let parts = unsafe { mmap.split_at_mut(&[100, 600]) }.expect("split at");
+        let spans: Vec<_> = parts.iter().map(|p| (p.offset(), p.len())).collect();
+        assert_eq!(spans, vec![(0, 100), (100, 500), (600, 500)]);
+        drop(parts);
+        assert!(unsafe { mmap.split_at_mut(&[600, 100]) }.is_err());
+        assert!(unsafe { mmap.split_at_mut(&[1100]) }.is_err());
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+    #[test]
+    fn test_parent_access_rejected_while_split() {
+        let path = tmp_path("parent");
+        let trace = tmp_path("parent_trace");
+        let _ = fs::remove_file(&path);
+        let mmap = MemoryMappedFile::create_rw(&path, 4096).expect("create");
+        mmap.start_recording(&trace).expect("record");
+        mmap.update_region(0, b"x").expect("traced write");
+        mmap.stop_recording().expect("stop");
+
+        // SAFETY: nothing else maps the file, and no views were taken before the split.
+        let mut parts = unsafe { mmap.split_into_mut(2) }.expect("split");
+        let invalid = |res: Result<()>| matches!(res, Err(MmapIoError::InvalidMode(_)));
+        assert!(invalid(mmap.update_region(0, b"parent")));
+        assert!(invalid(mmap.read_into(100, &mut [0u8; 4])));
+        assert!(invalid(mmap.as_slice_mut(0, 8).map(drop)));
+        assert!(invalid(mmap.read_all().map(drop)));
+        let mut prev = [0u8; 2];
+        assert!(invalid(mmap.swap_region(3000, b"zz", &mut prev)));
+        assert!(invalid(crate::trace::replay(&mmap, &trace).map(drop)));
+
+        parts[0].write_at(0, b"part").expect("part write");
+        drop(parts);
+        mmap.update_region(4, b"!").expect("write after drop");
+        let mut buf = [0u8; 5];
+        mmap.read_into(0, &mut buf).expect("read after drop");
+        assert_eq!(&buf, b"part!");
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+        fs::remove_file(&trace).expect("cleanup trace");
+    }
+}
@@ -0,0 +1,313 @@
+//! Streaming between mapped regions and `std::io` readers and writers.
+//!
+//! These move data straight between the mapping and the other side of the stream without an
+//! intermediate `Vec`. ReadWrite mappings are processed in bounded chunks, with the map lock
+//! taken per chunk so a slow peer cannot starve other readers and writers. On Linux,
+//! `send_to_socket` goes further and lets the kernel send file pages with `sendfile(2)`.
+
+use std::io::{ErrorKind, Read, Write};
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::{MapVariant, MemoryMappedFile};
+use crate::observer::MmapOperation;
+use crate::utils::{ensure_in_bounds, slice_range};
+
+// Bytes moved per lock acquisition for ReadWrite mappings
+const STREAM_CHUNK_SIZE: u64 = 256 * 1024;
+#[cfg(target_os = "linux")]
+const SENDFILE_MAX: u64 = 0x7fff_f000;
+
+impl MemoryMappedFile {
+    /// Fill `[offset, offset + len)` from `reader`, reading directly into the mapping.
+    ///
+    /// Data is read in chunks of at most 256 KiB, each under the write lock. Every chunk
+    /// counts as one write for the flush policy, metrics, and observers. Stops early if the
+    /// reader reaches end-of-file and returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if not in `ReadWrite` mode,
+    /// `MmapIoError::OutOfBounds` if the range exceeds the mapping, and `MmapIoError::Io`
+    /// if the reader fails. Bytes copied before a reader error stay in the mapping.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mmap_io::MemoryMappedFile;
+    ///
+    /// let mmap = MemoryMappedFile::create_rw("download.part", 1 << 20)?;
+    /// let mut body = std::fs::File::open("chunk-0.bin")?;
+    /// let n = mmap.write_from_reader(0, &mut body, 1 << 20)?;
+    /// # let _ = n;
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    pub fn write_from_reader<R: Read>(&self, offset: u64, reader: R, len: u64) -> Result<u64> {
+        let res = self.write_from_reader_impl(offset, reader, len);
+        self.observed(MmapOperation::Write, res)
+    }
+
+    fn write_from_reader_impl<R: Read>(&self, offset: u64, mut reader: R, len: u64) -> Result<u64> {
+        let (MapVariant::Rw(lock) | MapVariant::Cow(lock)) = &self.inner.map else {
+            return Err(MmapIoError::InvalidMode(
+                "write_from_reader requires ReadWrite or CopyOnWrite mode.",
+            ));
+        };
+        ensure_in_bounds(offset, len, self.current_len()?)?;
+
+        let mut written = 0u64;
+        while written < len {
+            let chunk_offset = offset + written;
+            let chunk_len = (len - written).min(STREAM_CHUNK_SIZE);
+            let (filled, res) = {
+                let mut guard = lock.write();
+                self.check_unsplit()?;
+                let (start, end) = slice_range(chunk_offset, chunk_len, self.current_len()?)?;
+                fill_from(&mut reader, &mut guard[start..end])
+            };
+            if filled > 0 {
+                self.record_streamed_write(chunk_offset, filled)?;
+                written += filled as u64;
+            }
+            res?;
+            if (filled as u64) < chunk_len {
+                break;
+            }
+        }
+        Ok(written)
+    }
+
+    // Flush policy, metrics, and observer bookkeeping for bytes already in the map.
+    fn record_streamed_write(&self, offset: u64, len: usize) -> Result<()> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_write(&self.inner.path, len as u64);
+        // Only copy the chunk back out when someone is listening
+        self.observe(|o| {
+            let mut data = vec![0u8; len];
+            if self.read_into(offset, &mut data).is_ok() {
+                o.on_write(offset, &data);
+            }
+        });
+        self.apply_flush_policy(offset, len as u64)
+    }
+
+    /// Write `[offset, offset + len)` to `writer` straight from the mapping.
+    ///
+    /// ReadOnly mappings are written in one `write_all`; ReadWrite and CopyOnWrite mappings
+    /// are written in chunks of at most 256 KiB, each under the read lock.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the range exceeds the mapping and
+    /// `MmapIoError::Io` if the writer fails.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mmap_io::MemoryMappedFile;
+    ///
+    /// let mmap = MemoryMappedFile::open_ro("data.bin")?;
+    /// let mut out = std::io::stdout().lock();
+    /// mmap.read_to_writer(0, mmap.len(), &mut out)?;
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    pub fn read_to_writer<W: Write>(&self, offset: u64, len: u64, mut writer: W) -> Result<()> {
+        let total = self.current_len()?;
+        ensure_in_bounds(offset, len, total)?;
+        match &self.inner.map {
+            MapVariant::Ro(m) => {
+                let (start, end) = slice_range(offset, len, total)?;
+                writer.write_all(&m[start..end])?;
+            }
+            MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
+                let mut done = 0u64;
+                while done < len {
+                    let chunk_len = (len - done).min(STREAM_CHUNK_SIZE);
+                    let guard = lock.read();
+                    self.check_unsplit()?;
+                    let (start, end) = slice_range(offset + done, chunk_len, self.current_len()?)?;
+                    writer.write_all(&guard[start..end])?;
+                    done += chunk_len;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Send `[offset, offset + len)` to a socket (or any fd) with `sendfile(2)`.
+    ///
+    /// The kernel copies straight from the page cache of the backing file, so the data never
+    /// passes through userspace. Writes made through a ReadWrite mapping are visible because
+    /// the mapping shares those pages. Returns the number of bytes sent, which is less than
+    /// `len` only if a non-blocking `fd` would block after some progress was made.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the range exceeds the mapping and
+    /// `MmapIoError::Io` if `sendfile` fails (including `WouldBlock` before any progress).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::os::fd::AsRawFd;
+    /// use mmap_io::MemoryMappedFile;
+    ///
+    /// let mmap = MemoryMappedFile::open_ro("index.html")?;
+    /// let stream = std::net::TcpStream::connect("127.0.0.1:8080")?;
+    /// mmap.send_to_socket(stream.as_raw_fd(), 0, mmap.len())?;
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn send_to_socket(&self, fd: std::os::fd::RawFd, offset: u64, len: u64) -> Result<u64> {
+        use std::os::fd::AsRawFd;
+
+        ensure_in_bounds(offset, len, self.current_len()?)?;
+        let src = self.inner.file.as_raw_fd();
+        let mut sent = 0u64;
+        while sent < len {
+            let mut off = libc::off_t::try_from(self.file_offset() + offset + sent)
+                .map_err(|_| MmapIoError::InvalidMode("offset exceeds off_t"))?;
+            // The kernel caps a single transfer just below 2 GiB
+            let count = (len - sent).min(SENDFILE_MAX) as usize;
+            // SAFETY: `off` is a valid out-pointer for the duration of the call; both fds are
+            // only read by the kernel and an invalid `fd` is reported as EBADF.
+            let rc = unsafe { libc::sendfile(fd, src, &mut off, count) };
+            if rc < 0 {
+                let err = std::io::Error::last_os_error();
+                match err.kind() {
+                    ErrorKind::Interrupted => continue,
+                    ErrorKind::WouldBlock if sent > 0 => break,
+                    _ => return Err(err.into()),
+                }
+            }
+            if rc == 0 {
+                // The file was truncated underneath the mapping
+                break;
+            }
+            sent += rc as u64;
+        }
+        Ok(sent)
+    }
+}
+
+// Read into `buf` until it is full or the reader hits EOF. Returns the bytes filled along
+// with the first non-retryable error, so callers can account for partial progress.
+fn fill_from<R: Read>(reader: &mut R, buf: &mut [u8]) -> (usize, Result<()>) {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return (filled, Err(e.into())),
+        }
+    }
+    (filled, Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::create_mmap;
+    use std::fs;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_stream_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    // Hands out at most 1000 bytes per read to exercise partial reads
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(1000);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_write_from_reader() {
+        let path = tmp_path("write_from_reader");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 1 << 20).expect("create");
+        let data: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+        // Spans several chunks
+        let n = mmap
+            .write_from_reader(100, Trickle(&data), data.len() as u64)
+            .expect("write");
+        assert_eq!(n, data.len() as u64);
+        let mut back = vec![0u8; data.len()];
+        mmap.read_into(100, &mut back).expect("read");
+        assert_eq!(back, data);
+
+        // EOF before `len` stops early
+        let n = mmap
+            .write_from_reader(0, &b"abc"[..], 10)
+            .expect("short write");
+        assert_eq!(n, 3);
+        assert!(mmap.write_from_reader(1 << 20, &b"x"[..], 1).is_err());
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_read_to_writer() {
+        let path = tmp_path("read_to_writer");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 700_000).expect("create");
+        mmap.update_region(0, b"head").expect("write");
+        mmap.update_region(699_996, b"tail").expect("write");
+        let mut out = Vec::new();
+        mmap.read_to_writer(0, 700_000, &mut out)
+            .expect("rw export");
+        assert_eq!(out.len(), 700_000);
+        assert_eq!(&out[..4], b"head");
+        assert_eq!(&out[699_996..], b"tail");
+        assert!(mmap.read_to_writer(699_999, 2, &mut out).is_err());
+        drop(mmap);
+
+        let ro = crate::MemoryMappedFile::open_ro(&path).expect("open ro");
+        let mut out = Vec::new();
+        ro.read_to_writer(699_996, 4, &mut out).expect("ro export");
+        assert_eq!(out, b"tail");
+
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_send_to_socket() {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::net::UnixStream;
+
+        let path = tmp_path("sendfile");
+        let _ = fs::remove_file(&path);
+
+        let mmap = create_mmap(&path, 8192).expect("create");
+        mmap.update_region(4096, b"served without a copy")
+            .expect("write");
+        let (tx, mut rx) = UnixStream::pair().expect("socketpair");
+        let sent = mmap.send_to_socket(tx.as_raw_fd(), 4096, 21).expect("send");
+        assert_eq!(sent, 21);
+        drop(tx);
+        let mut got = String::new();
+        rx.read_to_string(&mut got).expect("recv");
+        assert_eq!(got, "served without a copy");
+        assert!(mmap.send_to_socket(rx.as_raw_fd(), 8000, 500).is_err());
+
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
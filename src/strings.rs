@@ -0,0 +1,311 @@
+//! Append-only string interner stored in a mapped segment.
+//!
+//! A [`StringTable`] assigns each distinct string a dense `u32` id. Strings are appended to the
+//! segment as length-prefixed records and never moved or rewritten, so ids stay valid across
+//! restarts and concurrent readers never observe a partially written entry.
+//!
+//! Segment layout (all integers little-endian):
+//!
+//! | bytes   | field                                        |
+//! |---------|----------------------------------------------|
+//! | 0..8    | magic `MMIOSTRT`                             |
+//! | 8..12   | number of published strings                  |
+//! | 12..16  | reserved                                     |
+//! | 16..24  | end of the record area, relative to segment  |
+//! | 24..    | records: byte length (4) followed by UTF-8   |
+
+use std::collections::HashMap;
+
+use parking_lot::{Mutex, RwLock};
+
+use crate::errors::{MmapIoError, Result};
+use crate::segment::SegmentMut;
+
+/// Size of the table header at the start of the segment.
+pub const STRING_TABLE_HEADER_SIZE: u64 = 24;
+
+const MAGIC: &[u8; 8] = b"MMIOSTRT";
+const LEN_PREFIX: u64 = 4;
+
+/// Persistent string interner living in a segment of a mapped file.
+///
+/// Lookups are served from an in-memory index rebuilt on [`open`](Self::open); the mapped
+/// data is the source of truth. Readers take a shared lock, interning takes an exclusive
+/// one, and a new string is published only after its bytes are fully written. Appends
+/// from several processes must be serialized externally (e.g. with a file lock).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, segment::SegmentMut, strings::StringTable};
+///
+/// let mmap = Arc::new(MemoryMappedFile::create_rw("strings.bin", 64 * 1024)?);
+/// let table = StringTable::create(SegmentMut::new(mmap, 0, 64 * 1024)?)?;
+/// let id = table.intern("alpha")?;
+/// assert_eq!(table.intern("alpha")?, id);
+/// assert_eq!(table.resolve(id)?, "alpha");
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct StringTable {
+    segment: SegmentMut,
+    index: RwLock<TableIndex>,
+    // Serializes appends so the header is updated by one writer at a time
+    append: Mutex<()>,
+}
+
+#[derive(Debug, Default)]
+struct TableIndex {
+    // Relative offset of each record's length prefix, indexed by id
+    records: Vec<u64>,
+    lookup: HashMap<Box<str>, u32>,
+    end: u64,
+}
+
+impl StringTable {
+    /// Initialize an empty table in `segment`, overwriting its header.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the segment cannot hold the header.
+    /// Returns `MmapIoError::InvalidMode` if the parent mapping is not ReadWrite.
+    pub fn create(segment: SegmentMut) -> Result<Self> {
+        let mut header = [0u8; STRING_TABLE_HEADER_SIZE as usize];
+        header[0..8].copy_from_slice(MAGIC);
+        header[16..24].copy_from_slice(&STRING_TABLE_HEADER_SIZE.to_le_bytes());
+        segment.write_at(0, &header)?;
+        Ok(Self {
+            segment,
+            index: RwLock::new(TableIndex {
+                end: STRING_TABLE_HEADER_SIZE,
+                ..TableIndex::default()
+            }),
+            append: Mutex::new(()),
+        })
+    }
+
+    /// Open a table previously created in `segment`, rebuilding the in-memory index.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if the magic is missing or a record is malformed.
+    pub fn open(segment: SegmentMut) -> Result<Self> {
+        let table = Self {
+            segment,
+            index: RwLock::new(TableIndex::default()),
+            append: Mutex::new(()),
+        };
+        let mut magic = [0u8; 8];
+        table.read(0, &mut magic)?;
+        if &magic != MAGIC {
+            return Err(MmapIoError::Corrupt("missing string table magic".into()));
+        }
+        table.index.write().end = STRING_TABLE_HEADER_SIZE;
+        table.refresh()?;
+        Ok(table)
+    }
+
+    /// Return the id of `s`, appending it to the table if it is not present yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the segment has no room for the new string.
+    pub fn intern(&self, s: &str) -> Result<u32> {
+        if let Some(&id) = self.index.read().lookup.get(s) {
+            return Ok(id);
+        }
+        let _guard = self.append.lock();
+        // Pick up strings appended through other handles so their records are not overwritten
+        self.refresh_locked()?;
+        if let Some(&id) = self.index.read().lookup.get(s) {
+            return Ok(id);
+        }
+
+        let (id, at) = {
+            let index = self.index.read();
+            (index.records.len(), index.end)
+        };
+        let id = u32::try_from(id)
+            .map_err(|_| MmapIoError::Corrupt("string table id space exhausted".into()))?;
+        let byte_len = u32::try_from(s.len()).map_err(|_| MmapIoError::OutOfBounds {
+            offset: at,
+            len: s.len() as u64,
+            total: self.segment.len(),
+        })?;
+        let new_end = at + LEN_PREFIX + u64::from(byte_len);
+        if new_end > self.segment.len() {
+            return Err(MmapIoError::OutOfBounds {
+                offset: at,
+                len: LEN_PREFIX + u64::from(byte_len),
+                total: self.segment.len(),
+            });
+        }
+
+        // Write the record, then publish it by bumping end and count
+        self.segment.write_at(at, &byte_len.to_le_bytes())?;
+        self.segment.write_at(at + LEN_PREFIX, s.as_bytes())?;
+        self.segment.write_at(16, &new_end.to_le_bytes())?;
+        self.segment.write_at(8, &(id + 1).to_le_bytes())?;
+
+        let mut index = self.index.write();
+        index.records.push(at);
+        index.lookup.insert(s.into(), id);
+        index.end = new_end;
+        Ok(id)
+    }
+
+    /// Return the string with the given id.
+    ///
+    /// The string is copied out of the mapping, because a ReadWrite parent may be remapped
+    /// by a resize while a borrowed `&str` is still alive.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `id` has not been interned (call
+    /// [`refresh`](Self::refresh) to pick up strings appended by other processes).
+    pub fn resolve(&self, id: u32) -> Result<String> {
+        let at = {
+            let index = self.index.read();
+            *index
+                .records
+                .get(id as usize)
+                .ok_or(MmapIoError::OutOfBounds {
+                    offset: u64::from(id),
+                    len: 1,
+                    total: index.records.len() as u64,
+                })?
+        };
+        let mut prefix = [0u8; LEN_PREFIX as usize];
+        self.read(at, &mut prefix)?;
+        let mut bytes = vec![0u8; u32::from_le_bytes(prefix) as usize];
+        self.read(at + LEN_PREFIX, &mut bytes)?;
+        String::from_utf8(bytes)
+            .map_err(|_| MmapIoError::Corrupt(format!("string {id} is not valid UTF-8")))
+    }
+
+    /// Look up the id of `s` without interning it.
+    #[must_use]
+    pub fn get(&self, s: &str) -> Option<u32> {
+        self.index.read().lookup.get(s).copied()
+    }
+
+    /// Index strings appended since the last open or refresh (e.g. by another process).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Corrupt` if a published record is malformed.
+    pub fn refresh(&self) -> Result<()> {
+        let _guard = self.append.lock();
+        self.refresh_locked()
+    }
+
+    // Caller must hold `append`.
+    fn refresh_locked(&self) -> Result<()> {
+        let mut word = [0u8; 4];
+        self.read(8, &mut word)?;
+        let published = u32::from_le_bytes(word) as usize;
+
+        let mut index = self.index.write();
+        while index.records.len() < published {
+            let at = index.end;
+            let mut prefix = [0u8; LEN_PREFIX as usize];
+            self.read(at, &mut prefix)?;
+            let byte_len = u64::from(u32::from_le_bytes(prefix));
+            if at + LEN_PREFIX + byte_len > self.segment.len() {
+                return Err(MmapIoError::Corrupt(format!(
+                    "string record at {at} runs past the segment"
+                )));
+            }
+            let mut bytes = vec![0u8; byte_len as usize];
+            self.read(at + LEN_PREFIX, &mut bytes)?;
+            let s = String::from_utf8(bytes).map_err(|_| {
+                MmapIoError::Corrupt(format!("string record at {at} is not valid UTF-8"))
+            })?;
+            let id = index.records.len() as u32;
+            index.records.push(at);
+            index.lookup.insert(s.into_boxed_str(), id);
+            index.end = at + LEN_PREFIX + byte_len;
+        }
+        Ok(())
+    }
+
+    /// Number of interned strings.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.index.read().records.len()
+    }
+
+    /// Whether the table holds no strings.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes still available for new records.
+    #[must_use]
+    pub fn remaining(&self) -> u64 {
+        self.segment.len().saturating_sub(self.index.read().end)
+    }
+
+    fn read(&self, rel: u64, buf: &mut [u8]) -> Result<()> {
+        self.segment
+            .parent()
+            .read_into(self.segment.offset() + rel, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_strings_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_string_table_intern_and_reopen() {
+        let path = tmp_path("intern");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 4096).expect("create"));
+        let table = StringTable::create(SegmentMut::new(mmap.clone(), 1024, 128).expect("segment"))
+            .expect("table");
+        let a = table.intern("alpha").expect("intern");
+        let b = table.intern("beta").expect("intern");
+        assert_eq!((a, b), (0, 1));
+        assert_eq!(table.intern("alpha").expect("intern again"), a);
+        assert_eq!(table.resolve(b).expect("resolve"), "beta");
+        assert_eq!(table.get("gamma"), None);
+        assert!(table.resolve(2).is_err());
+        assert!(table.intern(&"x".repeat(200)).is_err());
+
+        let reopened =
+            StringTable::open(SegmentMut::new(mmap.clone(), 1024, 128).expect("segment"))
+                .expect("reopen");
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.get("beta"), Some(1));
+        assert_eq!(reopened.intern("gamma").expect("intern"), 2);
+
+        // The first handle sees the other writer's string after a refresh
+        table.refresh().expect("refresh");
+        assert_eq!(table.resolve(2).expect("resolve"), "gamma");
+
+        assert!(
+            StringTable::open(SegmentMut::new(mmap.clone(), 0, 128).expect("segment")).is_err()
+        );
+
+        drop((table, reopened, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,181 @@
+//! Lock-free publication of replacement mappings for read-mostly workloads.
+//!
+//! [`SwappableMmap`] holds the current `Arc<MemoryMappedFile>`. Readers take a snapshot
+//! with [`SwappableMmap::load`], an atomic pointer load plus reference-count increment
+//! that never waits on a lock; a maintenance thread publishes a new mapping (after a
+//! resize, compaction, or file replacement) with [`SwappableMmap::store`]. Publishing
+//! waits only for readers that are mid-load, which takes a few instructions each.
+//!
+//! Internally readers announce themselves in one of two counters selected by an epoch.
+//! A writer swaps the pointer, then flips the epoch twice, each time waiting for the
+//! counter of the previous epoch to drain, before releasing its reference to the old
+//! mapping. Any reader that could have seen the old pointer is counted in one of the two
+//! drained counters, so the old `Arc` cannot be freed between its load and increment.
+
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::mmap::MemoryMappedFile;
+
+/// Atomically replaceable shared mapping.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, swap::SwappableMmap};
+///
+/// let slot = SwappableMmap::new(Arc::new(MemoryMappedFile::open_ro("index.v1")?));
+/// let snapshot = slot.load(); // readers: never block
+/// // Maintenance thread: publish the rebuilt index
+/// let previous = slot.swap(Arc::new(MemoryMappedFile::open_ro("index.v2")?));
+/// drop((snapshot, previous));
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+pub struct SwappableMmap {
+    // Raw pointer of an Arc<MemoryMappedFile> owned by this slot
+    current: AtomicPtr<MemoryMappedFile>,
+    epoch: AtomicUsize,
+    readers: [AtomicUsize; 2],
+    // Serializes writers so epoch flips of different swaps do not interleave
+    writer: Mutex<()>,
+    _owns: PhantomData<Arc<MemoryMappedFile>>,
+}
+
+impl std::fmt::Debug for SwappableMmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SwappableMmap")
+            .field("path", &self.load().path())
+            .finish()
+    }
+}
+
+impl SwappableMmap {
+    /// Create a slot holding `mmap`.
+    #[must_use]
+    pub fn new(mmap: Arc<MemoryMappedFile>) -> Self {
+        Self {
+            current: AtomicPtr::new(Arc::into_raw(mmap).cast_mut()),
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            writer: Mutex::new(()),
+            _owns: PhantomData,
+        }
+    }
+
+    /// Snapshot of the current mapping. Never blocks.
+    #[must_use]
+    pub fn load(&self) -> Arc<MemoryMappedFile> {
+        let slot = &self.readers[self.epoch.load(Ordering::SeqCst) & 1];
+        slot.fetch_add(1, Ordering::SeqCst);
+        let ptr = self.current.load(Ordering::SeqCst);
+        // SAFETY: ptr came from Arc::into_raw and writers keep it alive until every reader
+        // announced in `slot` before their swap has left this section.
+        let mmap = unsafe {
+            Arc::increment_strong_count(ptr);
+            Arc::from_raw(ptr)
+        };
+        slot.fetch_sub(1, Ordering::SeqCst);
+        mmap
+    }
+
+    /// Publish `mmap`, returning the previously current mapping.
+    ///
+    /// Readers that already hold the old mapping keep using it until they drop it.
+    pub fn swap(&self, mmap: Arc<MemoryMappedFile>) -> Arc<MemoryMappedFile> {
+        let _writer = self.writer.lock();
+        let new = Arc::into_raw(mmap).cast_mut();
+        let old = self.current.swap(new, Ordering::SeqCst);
+        for _ in 0..2 {
+            let previous = self.epoch.fetch_add(1, Ordering::SeqCst);
+            while self.readers[previous & 1].load(Ordering::SeqCst) != 0 {
+                std::hint::spin_loop();
+            }
+        }
+        // SAFETY: old came from Arc::into_raw and no reader can still be about to increment
+        // its count, so this slot's reference can be handed back.
+        unsafe { Arc::from_raw(old) }
+    }
+
+    /// Publish `mmap`, dropping the slot's reference to the previous mapping.
+    pub fn store(&self, mmap: Arc<MemoryMappedFile>) {
+        drop(self.swap(mmap));
+    }
+}
+
+impl Drop for SwappableMmap {
+    fn drop(&mut self) {
+        // SAFETY: the slot owns one reference to the current pointer and no reader can
+        // outlive `&self`.
+        unsafe { drop(Arc::from_raw(*self.current.get_mut())) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!("mmap_io_swap_test_{}_{}", name, std::process::id()));
+        p
+    }
+
+    #[test]
+    fn test_swappable_mmap_concurrent_readers() {
+        let paths: Vec<PathBuf> = (0..4).map(|i| tmp_path(&format!("v{i}"))).collect();
+        let mappings: Vec<Arc<MemoryMappedFile>> = paths
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let mmap = create_mmap(p, 64).expect("create");
+                mmap.update_region(0, &[i as u8; 64]).expect("fill");
+                Arc::new(mmap)
+            })
+            .collect();
+
+        let slot = Arc::new(SwappableMmap::new(mappings[0].clone()));
+        let done = Arc::new(AtomicBool::new(false));
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let slot = Arc::clone(&slot);
+                let done = Arc::clone(&done);
+                thread::spawn(move || {
+                    while !done.load(Ordering::Relaxed) {
+                        let mut buf = [0u8; 64];
+                        slot.load().read_into(0, &mut buf).expect("read");
+                        // Every snapshot is one complete version
+                        assert!(buf.iter().all(|&b| b == buf[0]) && buf[0] < 4);
+                    }
+                })
+            })
+            .collect();
+
+        for round in 1..2000 {
+            slot.store(mappings[round % 4].clone());
+        }
+        let previous = slot.swap(mappings[2].clone());
+        assert!(Arc::ptr_eq(&previous, &mappings[1999 % 4]));
+        done.store(true, Ordering::Relaxed);
+        for reader in readers {
+            reader.join().expect("reader");
+        }
+        assert!(Arc::ptr_eq(&slot.load(), &mappings[2]));
+
+        drop((slot, previous));
+        // Only our own references remain, so nothing leaked
+        assert!(mappings.iter().all(|m| Arc::strong_count(m) == 1));
+        drop(mappings);
+        for p in paths {
+            fs::remove_file(p).expect("cleanup");
+        }
+    }
+}
@@ -0,0 +1,976 @@
+//! Cross-process locks stored in the mapping itself.
+//!
+//! [`MappedMutex`], [`MappedRwLock`], and [`MappedCondvar`] live at caller-chosen offsets,
+//! so every process (or thread) that maps the same file with [`MmapMode::ReadWrite`] can
+//! coordinate through them without a separate lock file. Zeroed bytes are an unlocked lock
+//! and an idle condition variable, so a freshly created file needs no initialization.
+//!
+//! A mutex records the process id of the holder. Contended lockers sleep on a shared
+//! `futex` on Linux and `WaitOnAddress` on Windows (which only wakes waiters in the same
+//! process, so waiters elsewhere notice the release within one poll interval), and spin
+//! and yield on other platforms. While waiting they periodically check that the holder is
+//! still alive; if it died inside its critical section, the next locker takes over and is
+//! told so through [`MappedMutexGuard::owner_died`], much like `EOWNERDEAD` for POSIX
+//! robust mutexes.
+//!
+//! Owner-death detection has limits:
+//!
+//! - Process ids can be reused, so a holder whose id was recycled by a new process looks
+//!   alive until that process exits too.
+//! - Process ids are only meaningful inside a pid namespace. On Linux the mutex also
+//!   records the holder's pid namespace (the inode of `/proc/self/ns/pid`), and lockers in
+//!   a different namespace, such as another container sharing the file, never presume the
+//!   holder dead: if it really died, they wait until a process from its namespace
+//!   recovers the lock. Where `/proc` is unavailable, holders are never presumed dead.
+//! - Other platforms have no namespace check, so processes sharing a lock there must see
+//!   each other's process ids (e.g. not be in different FreeBSD jails).
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::{MemoryMappedFile, MmapMode};
+
+// Low half of a mutex: the holder's process id (0 when unlocked) and the flags below. The
+// high half holds the holder's pid namespace (see `pid_namespace`).
+const OWNER_MASK: u32 = 0x3fff_ffff;
+// The last holder died and the protected data has not been marked consistent since
+const OWNER_DIED: u32 = 1 << 30;
+// Someone may be sleeping on the word; set by waiters, checked on unlock
+const WAITERS: u32 = 1 << 31;
+// Set on a reader-writer lock's reader count by a writer waiting for readers to drain
+const WRITER_WAITING: u32 = 1 << 31;
+// Upper bound on a single sleep, and how often waiters check the holder is alive
+const POLL_SLICE: Duration = Duration::from_millis(10);
+
+/// A mutex living in a shared mapping, usable across processes.
+///
+/// It protects no data of its own: the caller decides which bytes of the mapping it guards
+/// and only touches them while holding a [`MappedMutexGuard`]. The lock is not reentrant;
+/// locking it again from the thread that holds it deadlocks.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, sync::MappedMutex};
+///
+/// let mmap = Arc::new(MemoryMappedFile::open_or_create("shared.bin", 4096)?);
+/// let mutex = MappedMutex::new(Arc::clone(&mmap), 0)?;
+/// let guard = mutex.lock_or_recover(|| {
+///     // The previous holder died mid-update: repair the records it guarded
+///     mmap.update_region(8, &[0u8; 8])
+/// })?;
+/// let count = mmap.read_u64_le(8)?;
+/// mmap.write_u64_le(8, count + 1)?;
+/// drop(guard);
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct MappedMutex {
+    mmap: Arc<MemoryMappedFile>,
+    offset: u64,
+}
+
+impl MappedMutex {
+    /// Bytes the mutex occupies in the mapping.
+    pub const SIZE: u64 = 8;
+
+    /// Use the 8 bytes at `offset` (8-byte aligned) of `mmap` as a mutex.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` unless `mmap` is a ReadWrite mapping, since other
+    /// processes would not see a private or read-only lock word.
+    /// Returns `MmapIoError::Misaligned` / `OutOfBounds` for a bad offset.
+    pub fn new(mmap: Arc<MemoryMappedFile>, offset: u64) -> Result<Self> {
+        check_shared(&mmap)?;
+        mmap.atomic_u64(offset)?;
+        Ok(Self { mmap, offset })
+    }
+
+    /// Block until the mutex is acquired.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Unsupported` if the process id does not fit the lock word,
+    /// `MmapIoError::OutOfBounds` if the mapping shrank below the mutex, and
+    /// `MmapIoError::Io` if sleeping fails.
+    pub fn lock(&self) -> Result<MappedMutexGuard<'_>> {
+        loop {
+            if let Some(guard) = self.acquire(None)? {
+                return Ok(guard);
+            }
+        }
+    }
+
+    /// Acquire the mutex only if it is free (or its holder died); never sleeps.
+    ///
+    /// # Errors
+    ///
+    /// As for [`lock`](Self::lock).
+    pub fn try_lock(&self) -> Result<Option<MappedMutexGuard<'_>>> {
+        self.acquire(Some(Instant::now()))
+    }
+
+    /// Block until the mutex is acquired or `timeout` elapses; `None` on timeout.
+    ///
+    /// # Errors
+    ///
+    /// As for [`lock`](Self::lock).
+    pub fn lock_timeout(&self, timeout: Duration) -> Result<Option<MappedMutexGuard<'_>>> {
+        self.acquire(Some(Instant::now() + timeout))
+    }
+
+    /// Lock, running `recover` first if the previous holder died while holding the mutex.
+    ///
+    /// If `recover` succeeds the guard is marked consistent, so later lockers are not told
+    /// about the death again. If it fails, the error is returned and the mutex is released
+    /// still flagged, so the next locker gets to recover instead.
+    ///
+    /// # Errors
+    ///
+    /// As for [`lock`](Self::lock), plus any error returned by `recover`.
+    pub fn lock_or_recover<F>(&self, recover: F) -> Result<MappedMutexGuard<'_>>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let mut guard = self.lock()?;
+        if guard.owner_died() {
+            recover()?;
+            guard.mark_consistent();
+        }
+        Ok(guard)
+    }
+
+    /// Offset of the lock word in the mapping.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    // Offset of the half that holds the owner and flags, which sleepers wait on.
+    fn word_offset(&self) -> u64 {
+        if cfg!(target_endian = "little") {
+            self.offset
+        } else {
+            self.offset + 4
+        }
+    }
+
+    fn acquire(&self, deadline: Option<Instant>) -> Result<Option<MappedMutexGuard<'_>>> {
+        let me = u64::from(owner_id()?) | u64::from(pid_namespace()) << 32;
+        // Owner and namespace are swapped in together, so no locker sees a stale pairing
+        let state = self.mmap.atomic_u64(self.offset)?;
+        // Lockers that slept keep WAITERS set, since others may still be asleep behind them
+        let mut waiters = 0;
+        let mut check_owner = true;
+        let mut last_check = Instant::now();
+        loop {
+            let cur = state.load(Ordering::Relaxed);
+            let word = cur as u32;
+            let owner = word & OWNER_MASK;
+            let died = if owner == 0 {
+                word & OWNER_DIED
+            } else if check_owner && same_pid_namespace((cur >> 32) as u32) && !process_alive(owner)
+            {
+                OWNER_DIED
+            } else {
+                check_owner = false;
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    return Ok(None);
+                }
+                if word & WAITERS == 0
+                    && state
+                        .compare_exchange(
+                            cur,
+                            cur | u64::from(WAITERS),
+                            Ordering::Relaxed,
+                            Ordering::Relaxed,
+                        )
+                        .is_err()
+                {
+                    continue;
+                }
+                park(&self.mmap, self.word_offset(), word | WAITERS, deadline)?;
+                waiters = WAITERS;
+                if last_check.elapsed() >= POLL_SLICE {
+                    check_owner = true;
+                    last_check = Instant::now();
+                }
+                continue;
+            };
+            if state
+                .compare_exchange(
+                    cur,
+                    me | u64::from(died | waiters),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                return Ok(Some(MappedMutexGuard {
+                    mutex: self,
+                    owner_died: died != 0,
+                    consistent: died == 0,
+                }));
+            }
+        }
+    }
+}
+
+/// Proof of holding a [`MappedMutex`]; unlocks on drop.
+#[derive(Debug)]
+#[must_use = "the mutex is unlocked as soon as the guard is dropped"]
+pub struct MappedMutexGuard<'a> {
+    mutex: &'a MappedMutex,
+    owner_died: bool,
+    consistent: bool,
+}
+
+impl MappedMutexGuard<'_> {
+    /// Whether a previous holder died without unlocking, leaving the protected data in an
+    /// unknown state.
+    ///
+    /// Until [`mark_consistent`](Self::mark_consistent) is called, every later locker is
+    /// told the same.
+    #[must_use]
+    pub fn owner_died(&self) -> bool {
+        self.owner_died
+    }
+
+    /// Declare the protected data repaired after an owner death, clearing the flag when
+    /// this guard unlocks.
+    pub fn mark_consistent(&mut self) {
+        self.consistent = true;
+    }
+}
+
+impl Drop for MappedMutexGuard<'_> {
+    fn drop(&mut self) {
+        let Ok(state) = self.mutex.mmap.atomic_u64(self.mutex.offset) else {
+            return;
+        };
+        let next = if self.consistent { 0 } else { OWNER_DIED };
+        if state.swap(u64::from(next), Ordering::Release) as u32 & WAITERS != 0 {
+            let _ = wake(&self.mutex.mmap, self.mutex.word_offset(), 1);
+        }
+    }
+}
+
+/// A reader-writer lock living in a shared mapping, usable across processes.
+///
+/// The lock is an embedded [`MappedMutex`] followed by a reader count. Writers hold the
+/// mutex for the whole write; readers hold it only while registering, so a waiting writer
+/// keeps new readers out. A writer that dies mid-write is detected as for the mutex and
+/// reported through `owner_died` on the next read or write guard. Readers are counted,
+/// not named: one that dies while holding a read lock leaves writers waiting forever.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, sync::MappedRwLock};
+///
+/// let mmap = Arc::new(MemoryMappedFile::open_or_create("table.bin", 4096)?);
+/// let lock = MappedRwLock::new(Arc::clone(&mmap), 0)?;
+/// {
+///     let _read = lock.read()?;
+///     let rows = mmap.read_u64_le(16)?;
+///     # let _ = rows;
+/// }
+/// let mut write = lock.write()?;
+/// if write.owner_died() {
+///     mmap.write_u64_le(16, 0)?;
+///     write.mark_consistent();
+/// }
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct MappedRwLock {
+    mutex: MappedMutex,
+    readers: u64,
+}
+
+impl MappedRwLock {
+    /// Bytes the lock occupies in the mapping.
+    pub const SIZE: u64 = 12;
+
+    /// Use the 12 bytes at `offset` (8-byte aligned) of `mmap` as a reader-writer lock.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` unless `mmap` is a ReadWrite mapping.
+    /// Returns `MmapIoError::Misaligned` / `OutOfBounds` for a bad offset.
+    pub fn new(mmap: Arc<MemoryMappedFile>, offset: u64) -> Result<Self> {
+        let readers = offset.saturating_add(MappedMutex::SIZE);
+        mmap.atomic_u32(readers)?;
+        Ok(Self {
+            mutex: MappedMutex::new(mmap, offset)?,
+            readers,
+        })
+    }
+
+    /// Block until shared access is acquired.
+    ///
+    /// # Errors
+    ///
+    /// As for [`MappedMutex::lock`].
+    pub fn read(&self) -> Result<MappedRwLockReadGuard<'_>> {
+        let guard = self.mutex.lock()?;
+        self.register_reader(&guard)
+    }
+
+    /// Acquire shared access only if no writer holds or waits for the lock.
+    ///
+    /// # Errors
+    ///
+    /// As for [`MappedMutex::lock`].
+    pub fn try_read(&self) -> Result<Option<MappedRwLockReadGuard<'_>>> {
+        match self.mutex.try_lock()? {
+            Some(guard) => self.register_reader(&guard).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Block until exclusive access is acquired and all readers have left.
+    ///
+    /// # Errors
+    ///
+    /// As for [`MappedMutex::lock`].
+    pub fn write(&self) -> Result<MappedRwLockWriteGuard<'_>> {
+        let guard = self.mutex.lock()?;
+        let readers = self.mutex.mmap.atomic_u32(self.readers)?;
+        loop {
+            let cur = readers.load(Ordering::Acquire);
+            if cur & !WRITER_WAITING == 0 {
+                readers.fetch_and(!WRITER_WAITING, Ordering::Relaxed);
+                return Ok(MappedRwLockWriteGuard { guard });
+            }
+            if cur & WRITER_WAITING == 0
+                && readers
+                    .compare_exchange(
+                        cur,
+                        cur | WRITER_WAITING,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    )
+                    .is_err()
+            {
+                continue;
+            }
+            park(&self.mutex.mmap, self.readers, cur | WRITER_WAITING, None)?;
+        }
+    }
+
+    /// Acquire exclusive access only if nobody holds the lock.
+    ///
+    /// # Errors
+    ///
+    /// As for [`MappedMutex::lock`].
+    pub fn try_write(&self) -> Result<Option<MappedRwLockWriteGuard<'_>>> {
+        let Some(guard) = self.mutex.try_lock()? else {
+            return Ok(None);
+        };
+        let readers = self.mutex.mmap.atomic_u32(self.readers)?;
+        if readers.load(Ordering::Acquire) & !WRITER_WAITING != 0 {
+            return Ok(None);
+        }
+        Ok(Some(MappedRwLockWriteGuard { guard }))
+    }
+
+    /// Offset of the lock in the mapping.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.mutex.offset
+    }
+
+    // Count a reader while holding the mutex; the caller releases it right after.
+    fn register_reader(&self, guard: &MappedMutexGuard<'_>) -> Result<MappedRwLockReadGuard<'_>> {
+        self.mutex
+            .mmap
+            .atomic_u32(self.readers)?
+            .fetch_add(1, Ordering::Acquire);
+        Ok(MappedRwLockReadGuard {
+            lock: self,
+            owner_died: guard.owner_died(),
+        })
+    }
+}
+
+/// Shared access to a [`MappedRwLock`]; released on drop.
+#[derive(Debug)]
+#[must_use = "the lock is released as soon as the guard is dropped"]
+pub struct MappedRwLockReadGuard<'a> {
+    lock: &'a MappedRwLock,
+    owner_died: bool,
+}
+
+impl MappedRwLockReadGuard<'_> {
+    /// Whether a writer died mid-write and no writer has marked the data consistent since.
+    #[must_use]
+    pub fn owner_died(&self) -> bool {
+        self.owner_died
+    }
+}
+
+impl Drop for MappedRwLockReadGuard<'_> {
+    fn drop(&mut self) {
+        let mmap = &self.lock.mutex.mmap;
+        let Ok(readers) = mmap.atomic_u32(self.lock.readers) else {
+            return;
+        };
+        if readers.fetch_sub(1, Ordering::Release) == WRITER_WAITING | 1 {
+            let _ = wake(mmap, self.lock.readers, 1);
+        }
+    }
+}
+
+/// Exclusive access to a [`MappedRwLock`]; released on drop.
+#[derive(Debug)]
+#[must_use = "the lock is released as soon as the guard is dropped"]
+pub struct MappedRwLockWriteGuard<'a> {
+    guard: MappedMutexGuard<'a>,
+}
+
+impl MappedRwLockWriteGuard<'_> {
+    /// Whether a previous writer died mid-write; see [`MappedMutexGuard::owner_died`].
+    #[must_use]
+    pub fn owner_died(&self) -> bool {
+        self.guard.owner_died()
+    }
+
+    /// Declare the protected data repaired; see [`MappedMutexGuard::mark_consistent`].
+    pub fn mark_consistent(&mut self) {
+        self.guard.mark_consistent();
+    }
+}
+
+/// A condition variable living in a shared mapping, used together with a [`MappedMutex`].
+///
+/// The variable is a `u32` sequence number bumped by every notification; waiters sleep
+/// until it changes. Wakeups can be spurious, so wait in a loop that re-checks the
+/// condition. If the mutex holder dies, the waiter relocking it gets a guard reporting
+/// [`owner_died`](MappedMutexGuard::owner_died).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, sync::{MappedCondvar, MappedMutex}};
+///
+/// let mmap = Arc::new(MemoryMappedFile::open_or_create("queue.bin", 4096)?);
+/// let mutex = MappedMutex::new(Arc::clone(&mmap), 0)?;
+/// let ready = MappedCondvar::new(Arc::clone(&mmap), 8)?;
+/// let mut guard = mutex.lock()?;
+/// while mmap.read_u32_le(12)? == 0 {
+///     guard = ready.wait(guard)?;
+/// }
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct MappedCondvar {
+    mmap: Arc<MemoryMappedFile>,
+    offset: u64,
+}
+
+impl MappedCondvar {
+    /// Bytes the condition variable occupies in the mapping.
+    pub const SIZE: u64 = 4;
+
+    /// Use the 4 bytes at `offset` (4-byte aligned) of `mmap` as a condition variable.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` unless `mmap` is a ReadWrite mapping.
+    /// Returns `MmapIoError::Misaligned` / `OutOfBounds` for a bad offset.
+    pub fn new(mmap: Arc<MemoryMappedFile>, offset: u64) -> Result<Self> {
+        check_shared(&mmap)?;
+        mmap.atomic_u32(offset)?;
+        Ok(Self { mmap, offset })
+    }
+
+    /// Unlock `guard`, sleep until notified, and lock the mutex again.
+    ///
+    /// # Errors
+    ///
+    /// As for [`MappedMutex::lock`]. The mutex is not held when an error is returned.
+    pub fn wait<'a>(&self, guard: MappedMutexGuard<'a>) -> Result<MappedMutexGuard<'a>> {
+        Ok(self.wait_until(guard, None)?.0)
+    }
+
+    /// Like [`wait`](Self::wait), but gives up after `timeout`; the flag is `true` if it
+    /// timed out.
+    ///
+    /// # Errors
+    ///
+    /// As for [`wait`](Self::wait).
+    pub fn wait_timeout<'a>(
+        &self,
+        guard: MappedMutexGuard<'a>,
+        timeout: Duration,
+    ) -> Result<(MappedMutexGuard<'a>, bool)> {
+        self.wait_until(guard, Some(Instant::now() + timeout))
+    }
+
+    /// Wake one waiter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the mapping shrank below the variable and
+    /// `MmapIoError::Io` if the wake fails.
+    pub fn notify_one(&self) -> Result<()> {
+        self.notify(1)
+    }
+
+    /// Wake every waiter.
+    ///
+    /// # Errors
+    ///
+    /// As for [`notify_one`](Self::notify_one).
+    pub fn notify_all(&self) -> Result<()> {
+        self.notify(u32::MAX)
+    }
+
+    fn notify(&self, n: u32) -> Result<()> {
+        self.mmap
+            .atomic_u32(self.offset)?
+            .fetch_add(1, Ordering::Release);
+        wake(&self.mmap, self.offset, n)
+    }
+
+    fn wait_until<'a>(
+        &self,
+        guard: MappedMutexGuard<'a>,
+        deadline: Option<Instant>,
+    ) -> Result<(MappedMutexGuard<'a>, bool)> {
+        let seq = self.mmap.atomic_u32(self.offset)?;
+        // Read before unlocking, so a notification sent after the unlock is not missed
+        let seen = seq.load(Ordering::Relaxed);
+        let mutex = guard.mutex;
+        drop(guard);
+        let mut timed_out = false;
+        while seq.load(Ordering::Acquire) == seen {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                timed_out = true;
+                break;
+            }
+            park(&self.mmap, self.offset, seen, deadline)?;
+        }
+        Ok((mutex.lock()?, timed_out))
+    }
+}
+
+// Lock words must be shared with other processes.
+fn check_shared(mmap: &MemoryMappedFile) -> Result<()> {
+    if mmap.mode() != MmapMode::ReadWrite {
+        return Err(MmapIoError::InvalidMode(
+            "mapped locks require a ReadWrite mapping",
+        ));
+    }
+    Ok(())
+}
+
+// Value stored in the lock word for this process.
+fn owner_id() -> Result<u32> {
+    let pid = std::process::id();
+    if pid == 0 || pid & !OWNER_MASK != 0 {
+        return Err(MmapIoError::Unsupported(
+            "process id does not fit a mapped lock word",
+        ));
+    }
+    Ok(pid)
+}
+
+// Inode of this process's pid namespace, recorded next to the holder's pid; 0 if unknown.
+#[cfg(target_os = "linux")]
+fn pid_namespace() -> u32 {
+    use std::os::unix::fs::MetadataExt;
+    use std::sync::OnceLock;
+
+    static NAMESPACE: OnceLock<u32> = OnceLock::new();
+    *NAMESPACE.get_or_init(|| {
+        std::fs::metadata("/proc/self/ns/pid")
+            .ok()
+            .and_then(|m| u32::try_from(m.ino()).ok())
+            .unwrap_or(0)
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pid_namespace() -> u32 {
+    0
+}
+
+// Whether a holder from namespace `ns` has a pid this process can check.
+fn same_pid_namespace(ns: u32) -> bool {
+    ns == pid_namespace() && (ns != 0 || cfg!(not(target_os = "linux")))
+}
+
+// Sleep while the word still reads `seen`, for at most one poll slice or until `deadline`.
+#[cfg(any(target_os = "linux", windows))]
+fn park(mmap: &MemoryMappedFile, offset: u64, seen: u32, deadline: Option<Instant>) -> Result<()> {
+    let slice = deadline.map_or(POLL_SLICE, |d| {
+        d.saturating_duration_since(Instant::now()).min(POLL_SLICE)
+    });
+    mmap.wait_u32(offset, seen, Some(slice))?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn park(
+    _mmap: &MemoryMappedFile,
+    _offset: u64,
+    _seen: u32,
+    _deadline: Option<Instant>,
+) -> Result<()> {
+    for _ in 0..64 {
+        std::hint::spin_loop();
+    }
+    std::thread::yield_now();
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", windows))]
+fn wake(mmap: &MemoryMappedFile, offset: u64, n: u32) -> Result<()> {
+    mmap.wake_u32(offset, n)
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn wake(_mmap: &MemoryMappedFile, _offset: u64, _n: u32) -> Result<()> {
+    Ok(())
+}
+
+// Whether process `pid` still exists; errs towards "alive" when unsure.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return true;
+    };
+    // SAFETY: signal 0 only checks that the process exists; nothing is delivered.
+    if unsafe { libc::kill(pid, 0) } != 0
+        && std::io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH)
+    {
+        return false;
+    }
+    // An exited child nobody has reaped yet still answers kill(); check it is not a zombie
+    #[cfg(target_os = "linux")]
+    if let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+        // The state follows the command name, which is parenthesized and may contain ')'
+        if let Some(state) = stat
+            .rsplit_once(')')
+            .and_then(|(_, rest)| rest.trim_start().chars().next())
+        {
+            return !matches!(state, 'Z' | 'X');
+        }
+    }
+    true
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use std::ffi::c_void;
+
+    const SYNCHRONIZE: u32 = 0x0010_0000;
+    const ERROR_INVALID_PARAMETER: i32 = 87;
+    const WAIT_TIMEOUT: u32 = 258;
+
+    extern "system" {
+        fn OpenProcess(access: u32, inherit: i32, pid: u32) -> *mut c_void;
+        fn WaitForSingleObject(handle: *mut c_void, millis: u32) -> u32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    // SAFETY: OpenProcess has no preconditions; a null handle reports failure.
+    let handle = unsafe { OpenProcess(SYNCHRONIZE, 0, pid) };
+    if handle.is_null() {
+        // Access denied means the process exists; an invalid parameter means it does not
+        return std::io::Error::last_os_error().raw_os_error() != Some(ERROR_INVALID_PARAMETER);
+    }
+    // SAFETY: handle is a live process handle, closed right after the zero-timeout wait.
+    unsafe {
+        let state = WaitForSingleObject(handle, 0);
+        CloseHandle(handle);
+        state == WAIT_TIMEOUT
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::thread;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!("mmap_io_sync_test_{}_{}", name, std::process::id()));
+        p
+    }
+
+    #[test]
+    fn test_mapped_mutex_excludes_across_mappings() {
+        let path = tmp_path("mutex");
+        let _ = fs::remove_file(&path);
+        let first = Arc::new(MemoryMappedFile::create_rw(&path, 64).expect("create"));
+        let second = Arc::new(MemoryMappedFile::open_rw(&path).expect("second mapping"));
+
+        // A plain read-modify-write counter, only correct if the lock excludes
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let mmap = Arc::clone(if i % 2 == 0 { &first } else { &second });
+                thread::spawn(move || {
+                    let mutex = MappedMutex::new(Arc::clone(&mmap), 0).expect("mutex");
+                    for _ in 0..200 {
+                        let guard = mutex.lock().expect("lock");
+                        assert!(!guard.owner_died());
+                        let count = mmap.read_u64_le(8).expect("read");
+                        thread::yield_now();
+                        mmap.write_u64_le(8, count + 1).expect("write");
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("join");
+        }
+        assert_eq!(first.read_u64_le(8).expect("read"), 800);
+
+        let mutex = MappedMutex::new(Arc::clone(&first), 0).expect("mutex");
+        let other = MappedMutex::new(Arc::clone(&second), 0).expect("mutex");
+        let guard = mutex.try_lock().expect("try").expect("free");
+        assert!(other.try_lock().expect("try").is_none());
+        let started = Instant::now();
+        assert!(other
+            .lock_timeout(Duration::from_millis(30))
+            .expect("timeout")
+            .is_none());
+        assert!(started.elapsed() >= Duration::from_millis(25));
+        drop(guard);
+        assert!(other.try_lock().expect("try").is_some());
+        assert_eq!(second.read_u32_le(0).expect("word"), 0);
+
+        assert!(matches!(
+            MappedMutex::new(Arc::clone(&first), 4),
+            Err(MmapIoError::Misaligned { .. })
+        ));
+        drop((mutex, other, first, second));
+        let ro = Arc::new(MemoryMappedFile::open_ro(&path).expect("open ro"));
+        assert!(matches!(
+            MappedMutex::new(ro, 0),
+            Err(MmapIoError::InvalidMode(_))
+        ));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_mapped_rwlock_readers_and_writers() {
+        let path = tmp_path("rwlock");
+        let _ = fs::remove_file(&path);
+        let first = Arc::new(MemoryMappedFile::create_rw(&path, 64).expect("create"));
+        let second = Arc::new(MemoryMappedFile::open_rw(&path).expect("second mapping"));
+
+        let lock = MappedRwLock::new(Arc::clone(&first), 0).expect("lock");
+        let other = MappedRwLock::new(Arc::clone(&second), 0).expect("lock");
+        let read = lock.read().expect("read");
+        assert!(!read.owner_died());
+        let shared = other.try_read().expect("try").expect("shared");
+        assert!(other.try_write().expect("try").is_none());
+        drop((read, shared));
+        let write = other.try_write().expect("try").expect("exclusive");
+        assert!(lock.try_read().expect("try").is_none());
+        assert!(lock.try_write().expect("try").is_none());
+        drop(write);
+
+        // Writers keep two fields equal; readers must never see them differ
+        let handles: Vec<_> = (0..6)
+            .map(|i| {
+                let mmap = Arc::clone(if i % 2 == 0 { &first } else { &second });
+                thread::spawn(move || {
+                    let lock = MappedRwLock::new(Arc::clone(&mmap), 0).expect("lock");
+                    for _ in 0..100 {
+                        if i < 2 {
+                            let _guard = lock.write().expect("write");
+                            let n = mmap.read_u64_le(16).expect("read");
+                            mmap.write_u64_le(16, n + 1).expect("write");
+                            thread::yield_now();
+                            mmap.write_u64_le(24, n + 1).expect("write");
+                        } else {
+                            let _guard = lock.read().expect("read");
+                            let a = mmap.read_u64_le(16).expect("read");
+                            thread::yield_now();
+                            assert_eq!(mmap.read_u64_le(24).expect("read"), a);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("join");
+        }
+        assert_eq!(first.read_u64_le(24).expect("read"), 200);
+        assert_eq!(first.read_u64_le(0).expect("mutex"), 0);
+        assert_eq!(first.read_u32_le(8).expect("readers"), 0);
+
+        assert!(MappedRwLock::new(Arc::clone(&first), 56).is_err());
+        drop((lock, other, first, second));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[test]
+    fn test_mapped_condvar_wakes_waiter_on_other_mapping() {
+        let path = tmp_path("condvar");
+        let _ = fs::remove_file(&path);
+        let first = Arc::new(MemoryMappedFile::create_rw(&path, 64).expect("create"));
+        let second = Arc::new(MemoryMappedFile::open_rw(&path).expect("second mapping"));
+
+        let mutex = MappedMutex::new(Arc::clone(&first), 0).expect("mutex");
+        let ready = MappedCondvar::new(Arc::clone(&first), 8).expect("condvar");
+        let guard = mutex.lock().expect("lock");
+        let started = Instant::now();
+        let (guard, timed_out) = ready
+            .wait_timeout(guard, Duration::from_millis(30))
+            .expect("wait");
+        assert!(timed_out);
+        assert!(started.elapsed() >= Duration::from_millis(25));
+        drop(guard);
+
+        let producer = {
+            let mmap = Arc::clone(&second);
+            thread::spawn(move || {
+                let mutex = MappedMutex::new(Arc::clone(&mmap), 0).expect("mutex");
+                let ready = MappedCondvar::new(Arc::clone(&mmap), 8).expect("condvar");
+                thread::sleep(Duration::from_millis(20));
+                let guard = mutex.lock().expect("lock");
+                mmap.write_u32_le(12, 7).expect("publish");
+                drop(guard);
+                ready.notify_all().expect("notify");
+            })
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut guard = mutex.lock().expect("lock");
+        while first.read_u32_le(12).expect("read") == 0 {
+            assert!(Instant::now() < deadline, "never notified");
+            guard = ready
+                .wait_timeout(guard, Duration::from_secs(1))
+                .expect("wait")
+                .0;
+        }
+        assert!(!guard.owner_died());
+        drop(guard);
+        producer.join().expect("join");
+
+        drop((mutex, ready, first, second));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mapped_mutex_recovers_from_dead_owner() {
+        let path = tmp_path("owner_died");
+        let _ = fs::remove_file(&path);
+        let mmap = Arc::new(MemoryMappedFile::create_rw(&path, 64).expect("create"));
+        let mutex = MappedMutex::new(Arc::clone(&mmap), 8).expect("mutex");
+
+        // Leave the lock held by a process that has exited and been reaped
+        let mut child = std::process::Command::new("true").spawn().expect("spawn");
+        let dead = u64::from(child.id()) | u64::from(pid_namespace()) << 32;
+        child.wait().expect("reap");
+        let state = mmap.atomic_u64(8).expect("state");
+
+        // A holder from another pid namespace is never presumed dead
+        state.store(dead ^ 1 << 32, Ordering::SeqCst);
+        assert!(mutex.try_lock().expect("try").is_none());
+
+        state.store(dead, Ordering::SeqCst);
+
+        let guard = mutex.lock().expect("take over");
+        assert!(guard.owner_died());
+        drop(guard);
+        // Not marked consistent, so the next locker is told as well
+        let mut recovered = 0;
+        assert!(matches!(
+            mutex.lock_or_recover(|| Err(MmapIoError::Corrupt("still broken".into()))),
+            Err(MmapIoError::Corrupt(_))
+        ));
+        let guard = mutex
+            .lock_or_recover(|| {
+                recovered += 1;
+                Ok(())
+            })
+            .expect("recover");
+        drop(guard);
+        assert_eq!(recovered, 1);
+        assert!(!mutex.lock().expect("lock").owner_died());
+        drop(mutex);
+
+        // A writer that died mid-write is reported to readers until a writer repairs
+        let lock = MappedRwLock::new(Arc::clone(&mmap), 8).expect("rwlock");
+        state.store(dead, Ordering::SeqCst);
+        assert!(lock.read().expect("read").owner_died());
+        let mut write = lock.write().expect("write");
+        assert!(write.owner_died());
+        write.mark_consistent();
+        drop(write);
+        assert!(!lock.read().expect("read").owner_died());
+
+        drop((lock, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    // Set for the child process of `test_mapped_mutex_survives_holder_exit`
+    const HOLDER_ENV: &str = "MMAP_IO_SYNC_TEST_HOLDER";
+
+    // In the child only: take the mutex, half-update the data it guards, and exit.
+    #[test]
+    fn test_mapped_mutex_holder_child() {
+        let Some(path) = std::env::var_os(HOLDER_ENV) else {
+            return;
+        };
+        let mmap = Arc::new(MemoryMappedFile::open_rw(path).expect("open"));
+        let mutex = MappedMutex::new(Arc::clone(&mmap), 8).expect("mutex");
+        let guard = mutex.lock().expect("lock");
+        mmap.update_region(16, b"half").expect("write");
+        std::mem::forget(guard);
+        std::process::exit(0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mapped_mutex_survives_holder_exit() {
+        let path = tmp_path("holder_exit");
+        let _ = fs::remove_file(&path);
+        let mmap = Arc::new(MemoryMappedFile::create_rw(&path, 64).expect("create"));
+        let mutex = MappedMutex::new(Arc::clone(&mmap), 8).expect("mutex");
+
+        // Another process dies inside its critical section
+        let status = std::process::Command::new(std::env::current_exe().expect("test binary"))
+            .args(["--exact", "sync::tests::test_mapped_mutex_holder_child"])
+            .env(HOLDER_ENV, &path)
+            .stdout(std::process::Stdio::null())
+            .status()
+            .expect("run child");
+        assert!(status.success());
+
+        // The next locker takes over and repairs what the dead holder left behind
+        let mut seen = [0u8; 4];
+        let guard = mutex
+            .lock_or_recover(|| {
+                mmap.read_into(16, &mut seen)?;
+                mmap.update_region(16, b"full")
+            })
+            .expect("recover");
+        drop(guard);
+        assert_eq!(&seen, b"half");
+        assert!(!mutex.lock().expect("lock").owner_died());
+
+        drop((mutex, mmap));
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
@@ -0,0 +1,319 @@
+//! Access-pattern recording and replay for faster cold starts.
+//!
+//! While recording is on ([`MemoryMappedFile::start_recording`]), every slice, read, and
+//! write through the mapping appends a `(timestamp, offset, len, kind)` record to a sidecar
+//! trace file. On the next startup [`replay`] faults the recorded pages in, in the order
+//! they were first used, so a mapped database is warm before the first query arrives.
+//!
+//! Accesses through raw pointers or long-lived `as_slice` borrows are recorded once, when
+//! the slice is taken. Recording costs a buffered write per access; leave it off outside
+//! of profiling or warm-up capture runs.
+//!
+//! Trace layout (all integers little-endian):
+//!
+//! | bytes  | field                                             |
+//! |--------|---------------------------------------------------|
+//! | 0..8   | magic `MMIOTRCE`                                  |
+//! | 8..12  | format version (currently 1)                      |
+//! | 12..16 | reserved                                          |
+//! | 16..   | records of 32 bytes: nanoseconds since start (8), offset (8), len (8), kind (1: 0 = read, 1 = write), reserved (7) |
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::{MapVariant, MemoryMappedFile};
+use crate::utils::page_size;
+
+const MAGIC: &[u8; 8] = b"MMIOTRCE";
+const FORMAT_VERSION: u32 = 1;
+const HEADER_LEN: usize = 16;
+const RECORD_LEN: usize = 32;
+
+/// Whether a recorded access read or wrote the mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    /// `as_slice`, `read_into`, and the APIs built on them.
+    Read,
+    /// `update_region`, `as_slice_mut`, and the APIs built on them.
+    Write,
+}
+
+/// One entry of an access trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessRecord {
+    /// Time since recording started.
+    pub timestamp: Duration,
+    /// Offset of the access.
+    pub offset: u64,
+    /// Length of the access in bytes.
+    pub len: u64,
+    /// Read or write.
+    pub kind: AccessKind,
+}
+
+/// Sidecar trace writer attached to a mapping while recording.
+pub(crate) struct AccessRecorder {
+    start: Instant,
+    out: Mutex<Sink>,
+}
+
+struct Sink {
+    writer: BufWriter<File>,
+    // First write error, reported by stop_recording
+    error: Option<io::Error>,
+}
+
+impl AccessRecorder {
+    fn create(path: &Path) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let mut header = [0u8; HEADER_LEN];
+        header[0..8].copy_from_slice(MAGIC);
+        header[8..12].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        writer.write_all(&header)?;
+        Ok(Self {
+            start: Instant::now(),
+            out: Mutex::new(Sink {
+                writer,
+                error: None,
+            }),
+        })
+    }
+
+    fn record(&self, offset: u64, len: u64, kind: AccessKind) {
+        let nanos = self.start.elapsed().as_nanos() as u64;
+        let mut raw = [0u8; RECORD_LEN];
+        raw[0..8].copy_from_slice(&nanos.to_le_bytes());
+        raw[8..16].copy_from_slice(&offset.to_le_bytes());
+        raw[16..24].copy_from_slice(&len.to_le_bytes());
+        raw[24] = u8::from(kind == AccessKind::Write);
+        let mut sink = self.out.lock();
+        if sink.error.is_none() {
+            if let Err(e) = sink.writer.write_all(&raw) {
+                sink.error = Some(e);
+            }
+        }
+    }
+
+    pub(crate) fn finish(&self) -> Result<()> {
+        let mut sink = self.out.lock();
+        if let Some(e) = sink.error.take() {
+            return Err(e.into());
+        }
+        sink.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl MemoryMappedFile {
+    /// Start appending every access to a new trace file at `trace_path`.
+    ///
+    /// Recording is shared by all clones of this mapping. An active recording is finished
+    /// first, as by [`stop_recording`](Self::stop_recording).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if the trace file cannot be created, or an error left by
+    /// the previous recording.
+    pub fn start_recording<P: AsRef<Path>>(&self, trace_path: P) -> Result<()> {
+        let recorder = Arc::new(AccessRecorder::create(trace_path.as_ref())?);
+        let previous = self.inner.recorder.write().replace(recorder);
+        match previous {
+            Some(previous) => previous.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop recording and flush the trace file. No-op if not recording.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if writing the trace failed at any point.
+    pub fn stop_recording(&self) -> Result<()> {
+        let recorder = self.inner.recorder.write().take();
+        match recorder {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether accesses are currently being recorded.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.inner.recorder.read().is_some()
+    }
+
+    // Note an access: stamps tracking recency and appends to the trace if recording.
+    pub(crate) fn accessed(&self, offset: u64, len: u64, kind: AccessKind) {
+        self.touch();
+        if let Some(recorder) = &*self.inner.recorder.read() {
+            recorder.record(offset, len, kind);
+        }
+    }
+}
+
+/// Read all records of the trace file at `path`.
+///
+/// # Errors
+///
+/// Returns `MmapIoError::Corrupt` if the header is missing or has an unsupported version.
+/// Returns `MmapIoError::Io` if the file cannot be read.
+pub fn read_trace<P: AsRef<Path>>(path: P) -> Result<Vec<AccessRecord>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = [0u8; HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .map_err(|_| MmapIoError::Corrupt("access trace too short for header".into()))?;
+    if &header[0..8] != MAGIC {
+        return Err(MmapIoError::Corrupt("missing access trace magic".into()));
+    }
+    let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+    if version != FORMAT_VERSION {
+        return Err(MmapIoError::Corrupt(format!(
+            "unsupported access trace version {version}"
+        )));
+    }
+    let mut records = Vec::new();
+    let mut raw = [0u8; RECORD_LEN];
+    loop {
+        match reader.read_exact(&mut raw) {
+            Ok(()) => {}
+            // A crash can leave a torn final record; everything before it is usable
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let field = |at: usize| {
+            let mut b = [0u8; 8];
+            b.copy_from_slice(&raw[at..at + 8]);
+            u64::from_le_bytes(b)
+        };
+        records.push(AccessRecord {
+            timestamp: Duration::from_nanos(field(0)),
+            offset: field(8),
+            len: field(16),
+            kind: if raw[24] == 0 {
+                AccessKind::Read
+            } else {
+                AccessKind::Write
+            },
+        });
+    }
+    Ok(records)
+}
+
+/// Fault in the pages of `mmap` named by the trace at `trace_path`, in first-use order.
+///
+/// Each page is touched once; records past the current end of the mapping are skipped.
+/// Replaying is not itself recorded. Returns the number of pages touched.
+///
+/// # Errors
+///
+/// Returns errors from [`read_trace`].
+/// Returns `MmapIoError::InvalidMode` while parts from `split_into_mut` are alive.
+pub fn replay<P: AsRef<Path>>(mmap: &MemoryMappedFile, trace_path: P) -> Result<u64> {
+    let records = read_trace(trace_path)?;
+    let page = page_size() as u64;
+    let mut seen = HashSet::new();
+    let mut order = Vec::new();
+    let total = mmap.current_len()?;
+    for record in records {
+        if record.len == 0 || record.offset >= total {
+            continue;
+        }
+        let end = record.offset.saturating_add(record.len).min(total);
+        for index in record.offset / page..end.div_ceil(page) {
+            if seen.insert(index) {
+                order.push(index * page);
+            }
+        }
+    }
+    let prefault = |bytes: &[u8]| {
+        for &offset in &order {
+            if let Some(b) = bytes.get(offset as usize) {
+                // SAFETY: reading an in-bounds byte through a valid reference.
+                unsafe { std::ptr::read_volatile(b) };
+            }
+        }
+    };
+    match &mmap.inner.map {
+        MapVariant::Ro(m) => prefault(m),
+        MapVariant::Rw(lock) | MapVariant::Cow(lock) => {
+            let guard = lock.read();
+            mmap.check_unsplit()?;
+            prefault(&guard);
+        }
+    }
+    Ok(order.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_trace_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_record_and_replay_trace() {
+        let path = tmp_path("data");
+        let trace = tmp_path("data_trace");
+        let _ = fs::remove_file(&path);
+        let page = page_size() as u64;
+
+        let mmap = MemoryMappedFile::create_rw(&path, 8 * page).expect("create");
+        assert!(!mmap.is_recording());
+        mmap.start_recording(&trace).expect("start");
+        mmap.update_region(5 * page, b"hot").expect("write");
+        let mut buf = [0u8; 10];
+        mmap.read_into(page - 5, &mut buf).expect("read");
+        mmap.read_into(5 * page, &mut buf[..3]).expect("read again");
+        let (mut head, mut tail) = ([0u8; 2], [0u8; 4]);
+        let mut bufs = [
+            std::io::IoSliceMut::new(&mut head),
+            std::io::IoSliceMut::new(&mut tail),
+        ];
+        mmap.read_vectored_into(6 * page, &mut bufs)
+            .expect("vectored read");
+        mmap.stop_recording().expect("stop");
+        // Not recorded
+        mmap.read_into(7 * page, &mut buf).expect("unrecorded read");
+
+        let records = read_trace(&trace).expect("trace");
+        let summary: Vec<_> = records.iter().map(|r| (r.offset, r.len, r.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (5 * page, 3, AccessKind::Write),
+                (page - 5, 10, AccessKind::Read),
+                (5 * page, 3, AccessKind::Read),
+                (6 * page, 6, AccessKind::Read),
+            ]
+        );
+        assert!(records.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        drop(mmap);
+
+        // Pages 5, 0, 1 (the read straddles the boundary), and 6, each once
+        let ro = MemoryMappedFile::open_ro(&path).expect("reopen");
+        assert_eq!(replay(&ro, &trace).expect("replay"), 4);
+        assert!(matches!(read_trace(&path), Err(MmapIoError::Corrupt(_))));
+        drop(ro);
+
+        fs::remove_file(&path).expect("cleanup");
+        fs::remove_file(&trace).expect("cleanup trace");
+    }
+}
@@ -0,0 +1,467 @@
+//! Opt-in process-wide accounting of the bytes mapped by this crate.
+//!
+//! Once enabled with [`set_enabled`], every mapping created afterwards is registered until
+//! it is dropped, so services can watch their address-space footprint (see
+//! [`mapped_bytes`]) and alert before hitting `vm.max_map_count` or commit limits. Mappings
+//! can carry a label (see [`MemoryMappedFileBuilder::label`]) to attribute usage in
+//! [`snapshot`]. With a soft budget set ([`set_soft_budget`]), a [`BudgetHandler`] is told
+//! which least recently used mappings to drop or advise away once usage exceeds it. A
+//! [`WindowedMmap`](crate::window::WindowedMmap) counts only its current
+//! window. Mappings created while tracking was disabled are never counted.
+//!
+//! [`MemoryMappedFileBuilder::label`]: crate::mmap::MemoryMappedFileBuilder::label
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use parking_lot::{Mutex, RwLock};
+
+use crate::errors::{MmapIoError, Result};
+use crate::flush::FlushPolicy;
+use crate::flusher::IntervalFlusher;
+use crate::mmap::{Inner, MapVariant, MemoryMappedFile};
+
+// No budget configured
+const NO_BUDGET: u64 = u64::MAX;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TOTAL_BYTES: AtomicU64 = AtomicU64::new(0);
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static ENTRIES: Mutex<BTreeMap<u64, Entry>> = Mutex::new(BTreeMap::new());
+static SOFT_BUDGET: AtomicU64 = AtomicU64::new(NO_BUDGET);
+static BUDGET_HANDLER: RwLock<Option<Arc<dyn BudgetHandler>>> = RwLock::new(None);
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// A tracked mapping as reported by [`snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingInfo {
+    /// Registry id; matches [`MemoryMappedFile::tracking_id`] of the mapping.
+    pub id: u64,
+    /// Label given via the builder or `MemoryMappedFile::set_label`.
+    pub label: Option<String>,
+    /// Path of the mapped file.
+    pub path: PathBuf,
+    /// Bytes currently mapped.
+    pub len: u64,
+    /// When the mapping was created or last accessed through slices, reads, or writes.
+    pub last_used: Instant,
+}
+
+/// Report passed to a [`BudgetHandler`] when tracked mappings exceed the soft budget.
+#[derive(Debug, Clone)]
+pub struct BudgetReport {
+    /// Total bytes mapped when the budget was found exceeded.
+    pub mapped_bytes: u64,
+    /// The configured soft budget.
+    pub budget: u64,
+    /// Least recently used mappings first, just enough to get back under the budget if all
+    /// of them were dropped (or advised `DontNeed`).
+    pub candidates: Vec<MappingInfo>,
+}
+
+/// Reacts to the soft budget being exceeded, e.g. by dropping cached mappings.
+///
+/// Called on the thread that created or grew the mapping that pushed usage over the
+/// budget, with no registry locks held, so it may drop mappings directly.
+pub trait BudgetHandler: Send + Sync {
+    /// Called after a registration or growth leaves usage above the budget.
+    fn on_budget_exceeded(&self, report: &BudgetReport);
+}
+
+struct Entry {
+    label: Option<String>,
+    path: PathBuf,
+    len: u64,
+    last_used: Arc<AtomicU64>,
+}
+
+impl Entry {
+    fn info(&self, id: u64) -> MappingInfo {
+        let nanos = self.last_used.load(Ordering::Relaxed);
+        MappingInfo {
+            id,
+            label: self.label.clone(),
+            path: self.path.clone(),
+            len: self.len,
+            last_used: epoch() + Duration::from_nanos(nanos),
+        }
+    }
+}
+
+fn epoch() -> Instant {
+    *EPOCH.get_or_init(Instant::now)
+}
+
+// Nanoseconds since the registry epoch, for cheap atomic last-use stamps.
+fn now_nanos() -> u64 {
+    epoch().elapsed().as_nanos() as u64
+}
+
+/// Turn tracking of newly created mappings on or off.
+///
+/// Turning it off does not forget mappings that are already tracked; they are removed when
+/// dropped as usual.
+pub fn set_enabled(enable: bool) {
+    ENABLED.store(enable, Ordering::Relaxed);
+}
+
+/// Whether newly created mappings are tracked.
+#[must_use]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Total bytes currently mapped by tracked mappings.
+#[must_use]
+pub fn mapped_bytes() -> u64 {
+    TOTAL_BYTES.load(Ordering::Relaxed)
+}
+
+/// Number of tracked mappings currently alive.
+#[must_use]
+pub fn mapped_count() -> usize {
+    COUNT.load(Ordering::Relaxed)
+}
+
+/// Every tracked mapping currently alive, in creation order.
+#[must_use]
+pub fn snapshot() -> Vec<MappingInfo> {
+    ENTRIES
+        .lock()
+        .iter()
+        .map(|(&id, entry)| entry.info(id))
+        .collect()
+}
+
+/// Total bytes of tracked mappings carrying `label`.
+#[must_use]
+pub fn mapped_bytes_for(label: &str) -> u64 {
+    ENTRIES
+        .lock()
+        .values()
+        .filter(|e| e.label.as_deref() == Some(label))
+        .map(|e| e.len)
+        .sum()
+}
+
+/// Set (or clear with `None`) the soft budget for tracked mapped bytes.
+///
+/// Exceeding the budget never fails a mapping; it only notifies the handler installed with
+/// [`set_budget_handler`].
+pub fn set_soft_budget(budget: Option<u64>) {
+    SOFT_BUDGET.store(budget.unwrap_or(NO_BUDGET), Ordering::Relaxed);
+}
+
+/// The configured soft budget, if any.
+#[must_use]
+pub fn soft_budget() -> Option<u64> {
+    Some(SOFT_BUDGET.load(Ordering::Relaxed)).filter(|&b| b != NO_BUDGET)
+}
+
+/// Install (or remove with `None`) the handler notified when the soft budget is exceeded.
+pub fn set_budget_handler(handler: Option<Arc<dyn BudgetHandler>>) {
+    *BUDGET_HANDLER.write() = handler;
+}
+
+/// Build a report of least recently used mappings if usage is above the soft budget.
+#[must_use]
+pub fn over_budget() -> Option<BudgetReport> {
+    let budget = soft_budget()?;
+    let entries = ENTRIES.lock();
+    let mapped_bytes = TOTAL_BYTES.load(Ordering::Relaxed);
+    if mapped_bytes <= budget {
+        return None;
+    }
+    let mut all: Vec<MappingInfo> = entries.iter().map(|(&id, e)| e.info(id)).collect();
+    drop(entries);
+    all.sort_by_key(|info| info.last_used);
+    let mut excess = mapped_bytes - budget;
+    let mut candidates = Vec::new();
+    for info in all {
+        if excess == 0 {
+            break;
+        }
+        excess = excess.saturating_sub(info.len);
+        candidates.push(info);
+    }
+    Some(BudgetReport {
+        mapped_bytes,
+        budget,
+        candidates,
+    })
+}
+
+// Notify the budget handler if usage went over the budget.
+fn check_budget() {
+    if soft_budget().is_none() {
+        return;
+    }
+    let handler = BUDGET_HANDLER.read().clone();
+    if let Some(handler) = handler {
+        if let Some(report) = over_budget() {
+            handler.on_budget_exceeded(&report);
+        }
+    }
+}
+
+/// Membership of one mapping in the registry; dropping it unregisters the mapping.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    id: u64,
+    last_used: Arc<AtomicU64>,
+}
+
+impl Registration {
+    /// Register a mapping of `len` bytes if tracking is enabled.
+    pub(crate) fn new(path: &Path, len: u64) -> Option<Self> {
+        if !is_enabled() {
+            return None;
+        }
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let last_used = Arc::new(AtomicU64::new(now_nanos()));
+        ENTRIES.lock().insert(
+            id,
+            Entry {
+                label: None,
+                path: path.to_path_buf(),
+                len,
+                last_used: last_used.clone(),
+            },
+        );
+        TOTAL_BYTES.fetch_add(len, Ordering::Relaxed);
+        COUNT.fetch_add(1, Ordering::Relaxed);
+        check_budget();
+        Some(Self { id, last_used })
+    }
+
+    /// Record that the mapping now spans `len` bytes.
+    pub(crate) fn set_len(&self, len: u64) {
+        let grew = {
+            let mut entries = ENTRIES.lock();
+            let Some(entry) = entries.get_mut(&self.id) else {
+                return;
+            };
+            TOTAL_BYTES.fetch_add(len, Ordering::Relaxed);
+            TOTAL_BYTES.fetch_sub(entry.len, Ordering::Relaxed);
+            let grew = len > entry.len;
+            entry.len = len;
+            grew
+        };
+        self.touch();
+        if grew {
+            check_budget();
+        }
+    }
+
+    /// Mark the mapping as used now.
+    pub(crate) fn touch(&self) {
+        self.last_used.store(now_nanos(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_label(&self, label: Option<String>) {
+        if let Some(entry) = ENTRIES.lock().get_mut(&self.id) {
+            entry.label = label;
+        }
+    }
+
+    pub(crate) fn label(&self) -> Option<String> {
+        ENTRIES.lock().get(&self.id).and_then(|e| e.label.clone())
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Some(entry) = ENTRIES.lock().remove(&self.id) {
+            TOTAL_BYTES.fetch_sub(entry.len, Ordering::Relaxed);
+            COUNT.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl MemoryMappedFile {
+    /// Label this mapping in the tracking registry. No-op if the mapping is not tracked.
+    pub fn set_label(&self, label: impl Into<String>) {
+        if let Some(tracking) = &self.inner.tracking {
+            tracking.set_label(Some(label.into()));
+        }
+    }
+
+    /// Label of this mapping in the tracking registry, if tracked and labelled.
+    #[must_use]
+    pub fn label(&self) -> Option<String> {
+        self.inner.tracking.as_ref().and_then(Registration::label)
+    }
+
+    /// Id of this mapping in the tracking registry, if tracked.
+    ///
+    /// Matches [`MappingInfo::id`], so budget handlers can find the mapping to drop.
+    #[must_use]
+    pub fn tracking_id(&self) -> Option<u64> {
+        self.inner.tracking.as_ref().map(|t| t.id)
+    }
+
+    // Wrap a freshly built mapping, registering it if tracking is enabled and starting the
+    // timer thread of `FlushPolicy::EveryMillis`. Fails if that thread cannot be spawned.
+    pub(crate) fn from_inner(mut inner: Inner) -> Result<Self> {
+        if inner.tracking.is_none() {
+            inner.tracking = Registration::new(&inner.path, *inner.cached_len.read());
+        }
+        let interval = match inner.flush_policy {
+            FlushPolicy::EveryMillis(ms) if ms > 0 && matches!(inner.map, MapVariant::Rw(_)) => {
+                Some(Duration::from_millis(ms))
+            }
+            _ => None,
+        };
+        let mut spawn_error = None;
+        let inner = Arc::new_cyclic(|weak| {
+            if let Some(interval) = interval {
+                match IntervalFlusher::start(weak.clone(), interval) {
+                    Ok(flusher) => inner.interval_flusher = Some(flusher),
+                    Err(e) => spawn_error = Some(e),
+                }
+            }
+            inner
+        });
+        match spawn_error {
+            Some(e) => Err(e.into()),
+            None => Ok(Self { inner }),
+        }
+    }
+
+    // Take the mapping apart if this is its only handle. The EveryMillis timer thread is
+    // stopped first, so its temporary handle cannot make the check fail, and restarted if
+    // other handles remain.
+    pub(crate) fn try_into_inner(self, what: &'static str) -> Result<Inner> {
+        if let Some(flusher) = &self.inner.interval_flusher {
+            flusher.stop();
+        }
+        Arc::try_unwrap(self.inner).map_err(|inner| {
+            if let Some(flusher) = &inner.interval_flusher {
+                if let Err(e) = flusher.resume(Arc::downgrade(&inner)) {
+                    log::warn!("could not restart interval flusher: {e}");
+                }
+            }
+            MmapIoError::InvalidMode(what)
+        })
+    }
+
+    // Stamp the last-use time consulted by budget reports.
+    pub(crate) fn touch(&self) {
+        if let Some(tracking) = &self.inner.tracking {
+            tracking.touch();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_tracking_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_tracking_register_resize_drop() {
+        let path = tmp_path("track");
+        let _ = fs::remove_file(&path);
+        // Other tests may map files concurrently, so only look at our own entries
+        let ours = || {
+            snapshot()
+                .into_iter()
+                .filter(|e| e.path == path)
+                .collect::<Vec<_>>()
+        };
+
+        set_enabled(true);
+        let mmap = MemoryMappedFile::builder(&path)
+            .size(4096)
+            .label("tracking-test")
+            .create()
+            .expect("create");
+        assert_eq!(mmap.label().as_deref(), Some("tracking-test"));
+        assert_eq!(ours().len(), 1);
+        assert!(mapped_bytes() >= 4096);
+
+        mmap.resize(3 * 4096).expect("grow");
+        assert_eq!(ours()[0].len, 3 * 4096);
+        assert_eq!(mapped_bytes_for("tracking-test"), 3 * 4096);
+
+        let ro = MemoryMappedFile::open_ro(&path).expect("open ro");
+        ro.set_label("tracking-test-ro");
+        assert_eq!(ours().len(), 2);
+        assert_eq!(mapped_bytes_for("tracking-test-ro"), 3 * 4096);
+
+        drop(mmap);
+        drop(ro);
+        assert!(ours().is_empty());
+        assert_eq!(mapped_bytes_for("tracking-test"), 0);
+        fs::remove_file(&path).expect("cleanup");
+    }
+
+    struct Recorder(Mutex<Vec<BudgetReport>>);
+
+    impl BudgetHandler for Recorder {
+        fn on_budget_exceeded(&self, report: &BudgetReport) {
+            self.0.lock().push(report.clone());
+        }
+    }
+
+    #[test]
+    fn test_soft_budget_reports_lru_candidates() {
+        let path_a = tmp_path("budget_a");
+        let path_b = tmp_path("budget_b");
+        set_enabled(true);
+
+        let a = MemoryMappedFile::create_rw(&path_a, 4096).expect("a");
+        let b = MemoryMappedFile::create_rw(&path_b, 4096).expect("b");
+        std::thread::sleep(Duration::from_millis(2));
+        a.update_region(0, b"recent").expect("touch a");
+
+        let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+        set_budget_handler(Some(recorder.clone()));
+        // A one-byte budget makes every tracked mapping, including ours, a candidate
+        set_soft_budget(Some(1));
+        assert_eq!(soft_budget(), Some(1));
+        b.resize(8192).expect("grow b");
+        let report = over_budget().expect("over budget");
+        set_soft_budget(None);
+        set_budget_handler(None);
+
+        let reports = recorder.0.lock();
+        assert!(!reports.is_empty());
+        let delivered = &reports[0];
+        assert!(delivered.mapped_bytes > delivered.budget);
+        let freed: u64 = delivered.candidates.iter().map(|c| c.len).sum();
+        assert!(freed >= delivered.mapped_bytes - delivered.budget);
+        assert!(report
+            .candidates
+            .windows(2)
+            .all(|w| w[0].last_used <= w[1].last_used));
+        let pos = |m: &MemoryMappedFile| {
+            let id = m.tracking_id().expect("tracked");
+            report
+                .candidates
+                .iter()
+                .position(|c| c.id == id)
+                .expect("candidate")
+        };
+        // b was grown after a was written, so a is now the less recently used one
+        assert!(pos(&a) < pos(&b));
+
+        drop((a, b));
+        fs::remove_file(&path_a).expect("cleanup a");
+        fs::remove_file(&path_b).expect("cleanup b");
+    }
+}
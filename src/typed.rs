@@ -0,0 +1,262 @@
+//! Typed array views over segments of plain-old-data records.
+
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of, MaybeUninit};
+use std::sync::Arc;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MemoryMappedFile;
+use crate::segment::SegmentMut;
+
+/// Marker for plain-old-data types that can be copied to and from mapped bytes.
+///
+/// # Safety
+///
+/// Implementors must be `Copy`, contain no padding bytes, no pointers or references,
+/// and every bit pattern must be a valid value (e.g. no `bool`, `char`, or enums).
+/// `#[repr(C)]` structs made only of `Pod` fields without padding qualify.
+pub unsafe trait Pod: Copy + Send + Sync + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),* $(,)?) => {
+        $(unsafe impl Pod for $t {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Fixed-length array of `T` records stored in a segment.
+///
+/// Alignment and bounds are validated once at construction; element access copies
+/// values in and out, so it works for RO, RW, and COW parents alike.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use mmap_io::{MemoryMappedFile, typed::SegmentTyped};
+///
+/// let mmap = Arc::new(MemoryMappedFile::create_rw("records.bin", 4096)?);
+/// let counters = SegmentTyped::<u64>::new(mmap, 0, 16)?;
+/// counters.set(3, 42)?;
+/// assert_eq!(counters.get(3)?, 42);
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct SegmentTyped<T: Pod> {
+    segment: SegmentMut,
+    count: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Clone for SegmentTyped<T> {
+    fn clone(&self) -> Self {
+        Self {
+            segment: self.segment.clone(),
+            count: self.count,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Pod> SegmentTyped<T> {
+    /// Create a typed view of `count` elements of `T` starting at `offset` in `parent`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` if `offset` is not aligned for `T`.
+    /// Returns `MmapIoError::OutOfBounds` if the elements exceed the file bounds.
+    pub fn new(parent: Arc<MemoryMappedFile>, offset: u64, count: usize) -> Result<Self> {
+        check_alignment::<T>(offset)?;
+        let size = size_of::<T>() as u64;
+        let len = size
+            .checked_mul(count as u64)
+            .ok_or(MmapIoError::OutOfBounds {
+                offset,
+                len: u64::MAX,
+                total: parent.len(),
+            })?;
+        Ok(Self {
+            segment: SegmentMut::new(parent, offset, len)?,
+            count,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Reinterpret an existing segment as an array of `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Misaligned` if the segment offset is not aligned for `T`,
+    /// or if its length is not a multiple of `size_of::<T>()`.
+    pub fn from_segment(segment: SegmentMut) -> Result<Self> {
+        check_alignment::<T>(segment.offset())?;
+        let size = size_of::<T>() as u64;
+        if size == 0 || segment.len() % size != 0 {
+            return Err(MmapIoError::Misaligned {
+                required: size,
+                offset: segment.len(),
+            });
+        }
+        Ok(Self {
+            count: (segment.len() / size) as usize,
+            segment,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Read element `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `index >= len()`.
+    pub fn get(&self, index: usize) -> Result<T> {
+        let offset = self.element_offset(index)?;
+        read_pod(self.segment.parent(), offset)
+    }
+
+    /// Overwrite element `index` with `value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if `index >= len()`.
+    /// Returns `MmapIoError::InvalidMode` if the parent is read-only.
+    pub fn set(&self, index: usize, value: T) -> Result<()> {
+        let offset = self.element_offset(index)?;
+        write_pod(self.segment.parent(), offset, &value)
+    }
+
+    /// Iterate over all elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = Result<T>> + '_ {
+        (0..self.count).map(move |i| self.get(i))
+    }
+
+    /// Number of elements.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether the view holds no elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The underlying byte segment.
+    #[must_use]
+    pub fn segment(&self) -> &SegmentMut {
+        &self.segment
+    }
+
+    fn element_offset(&self, index: usize) -> Result<u64> {
+        if index >= self.count {
+            return Err(MmapIoError::OutOfBounds {
+                offset: index as u64,
+                len: 1,
+                total: self.count as u64,
+            });
+        }
+        Ok(self.segment.offset() + (index * size_of::<T>()) as u64)
+    }
+}
+
+// Copy a `T` out of the mapping at `offset`.
+pub(crate) fn read_pod<T: Pod>(mmap: &MemoryMappedFile, offset: u64) -> Result<T> {
+    let mut value = MaybeUninit::<T>::zeroed();
+    // SAFETY: T: Pod, so the zeroed value is valid and may be viewed as size_of::<T>() bytes.
+    let bytes =
+        unsafe { std::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    mmap.read_into(offset, bytes)?;
+    // SAFETY: every bit pattern is a valid T (Pod contract) and all bytes are initialized.
+    Ok(unsafe { value.assume_init() })
+}
+
+// Copy `value` into the mapping at `offset`.
+pub(crate) fn write_pod<T: Pod>(mmap: &MemoryMappedFile, offset: u64, value: &T) -> Result<()> {
+    // SAFETY: T: Pod has no padding, so all of its bytes are initialized.
+    let bytes =
+        unsafe { std::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+    mmap.update_region(offset, bytes)
+}
+
+pub(crate) fn check_alignment<T>(offset: u64) -> Result<()> {
+    let align = align_of::<T>() as u64;
+    if offset % align != 0 {
+        return Err(MmapIoError::Misaligned {
+            required: align,
+            offset,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_mmap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_typed_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(C)]
+    struct Record {
+        id: u32,
+        flags: u32,
+        value: f64,
+    }
+
+    unsafe impl Pod for Record {}
+
+    #[test]
+    fn test_segment_typed_get_set_iter() {
+        let path = tmp_path("records");
+        let _ = fs::remove_file(&path);
+
+        let mmap = Arc::new(create_mmap(&path, 256).expect("create"));
+        let records = SegmentTyped::<Record>::new(mmap.clone(), 16, 4).expect("typed");
+        assert_eq!(records.len(), 4);
+        assert_eq!(records.segment().len(), 64);
+
+        for i in 0..4 {
+            let r = Record {
+                id: i as u32,
+                flags: 0xF0,
+                value: i as f64 * 1.5,
+            };
+            records.set(i, r).expect("set");
+        }
+        assert_eq!(records.get(2).expect("get").value, 3.0);
+        let ids: Vec<u32> = records.iter().map(|r| r.expect("iter").id).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+        assert!(records.get(4).is_err());
+
+        assert!(matches!(
+            SegmentTyped::<u64>::new(mmap.clone(), 4, 2),
+            Err(MmapIoError::Misaligned { required: 8, .. })
+        ));
+        assert!(SegmentTyped::<u64>::new(mmap.clone(), 248, 2).is_err());
+
+        let seg = SegmentMut::new(mmap.clone(), 16, 64).expect("segment");
+        let words = SegmentTyped::<u32>::from_segment(seg).expect("from segment");
+        assert_eq!(words.len(), 16);
+        assert_eq!(words.get(0).expect("get"), 0);
+        assert_eq!(words.get(1).expect("get"), 0xF0);
+
+        drop((records, words));
+        drop(mmap);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}
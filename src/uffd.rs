@@ -0,0 +1,495 @@
+//! Lazily populated mappings backed by `userfaultfd` (Linux).
+//!
+//! A [`LazyMmap`] reserves anonymous memory whose pages are supplied on first access by a
+//! [`PageSource`], e.g. by fetching a block from a remote object store or decompressing
+//! it. Touching an unpopulated page blocks the accessing thread while a handler thread
+//! asks the source for that page's contents and installs them with `UFFDIO_COPY`; later
+//! accesses are plain memory reads. Pages the program writes itself are kept private.
+//!
+//! Creating a userfaultfd needs either kernel 5.11+ (user-mode-only faults) or
+//! `vm.unprivileged_userfaultfd = 1` / `CAP_SYS_PTRACE`; otherwise [`LazyMmap::new`]
+//! fails with a permission error.
+//!
+//! A source that fails (or panics) for a page cannot leave the faulting thread waiting
+//! forever, so the page is zero-filled instead. The same happens if the fetched page
+//! cannot be installed. Such pages are counted in [`LazyMmap::fill_failures`] and logged;
+//! check it before trusting the contents.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::errors::{MmapIoError, Result};
+use crate::utils::{align_up, page_size};
+
+// <linux/userfaultfd.h>, asm-generic ioctl encoding
+const UFFD_API: u64 = 0xAA;
+const UFFD_USER_MODE_ONLY: libc::c_int = 1;
+const UFFD_EVENT_PAGEFAULT: u8 = 0x12;
+const UFFDIO_REGISTER_MODE_MISSING: u64 = 1;
+const UFFDIO_API: libc::c_ulong = ioc(3, 0x3F, 24);
+const UFFDIO_REGISTER: libc::c_ulong = ioc(3, 0x00, 32);
+const UFFDIO_UNREGISTER: libc::c_ulong = ioc(2, 0x01, 16);
+const UFFDIO_WAKE: libc::c_ulong = ioc(2, 0x02, 16);
+const UFFDIO_COPY: libc::c_ulong = ioc(3, 0x03, 40);
+const UFFDIO_ZEROPAGE: libc::c_ulong = ioc(3, 0x04, 32);
+const UFFD_MSG_SIZE: usize = 32;
+// How often the handler thread checks for shutdown while idle
+const POLL_TIMEOUT_MS: libc::c_int = 50;
+
+const fn ioc(dir: libc::c_ulong, nr: libc::c_ulong, size: libc::c_ulong) -> libc::c_ulong {
+    (dir << 30) | (size << 16) | (0xAA << 8) | nr
+}
+
+#[repr(C)]
+struct UffdioApi {
+    api: u64,
+    features: u64,
+    ioctls: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UffdioRange {
+    start: u64,
+    len: u64,
+}
+
+#[repr(C)]
+struct UffdioRegister {
+    range: UffdioRange,
+    mode: u64,
+    ioctls: u64,
+}
+
+#[repr(C)]
+struct UffdioCopy {
+    dst: u64,
+    src: u64,
+    len: u64,
+    mode: u64,
+    copy: i64,
+}
+
+#[repr(C)]
+struct UffdioZeropage {
+    range: UffdioRange,
+    mode: u64,
+    zeropage: i64,
+}
+
+/// Supplies the contents of a [`LazyMmap`] page on first access.
+///
+/// Implemented for closures `Fn(u64, &mut [u8]) -> io::Result<()>`.
+pub trait PageSource: Send + Sync + 'static {
+    /// Fill `buf` with the bytes at `offset`. `buf` is one page, shorter for the final page
+    /// of a mapping whose length is not page aligned, and arrives zeroed.
+    ///
+    /// Called on the handler thread while the faulting thread waits.
+    fn fill(&self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+}
+
+impl<F> PageSource for F
+where
+    F: Fn(u64, &mut [u8]) -> io::Result<()> + Send + Sync + 'static,
+{
+    fn fill(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self(offset, buf)
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    filled: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Anonymous mapping whose pages are fetched from a [`PageSource`] when first touched.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mmap_io::uffd::LazyMmap;
+///
+/// // Front a large remote object; only the pages actually read are fetched
+/// let lazy = LazyMmap::new(1 << 30, |offset: u64, buf: &mut [u8]| {
+///     // e.g. an HTTP range request for bytes offset..offset + buf.len()
+///     buf.fill((offset >> 12) as u8);
+///     Ok(())
+/// })?;
+/// let header = &lazy.as_slice()[..64];
+/// # let _ = header;
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+pub struct LazyMmap {
+    ptr: *mut u8,
+    len: usize,
+    map_len: usize,
+    uffd: Arc<OwnedFd>,
+    stop: Arc<AtomicBool>,
+    stats: Arc<Stats>,
+    handler: Option<JoinHandle<()>>,
+}
+
+// SAFETY: the mapping is plain memory owned by this value; shared access only hands out
+// `&[u8]`, and mutation requires `&mut self`.
+unsafe impl Send for LazyMmap {}
+// SAFETY: see above.
+unsafe impl Sync for LazyMmap {}
+
+impl std::fmt::Debug for LazyMmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyMmap")
+            .field("len", &self.len)
+            .field("pages_filled", &self.pages_filled())
+            .field("fill_failures", &self.fill_failures())
+            .finish()
+    }
+}
+
+impl LazyMmap {
+    /// Reserve `len` bytes whose pages are filled by `source` on first access.
+    ///
+    /// Where the kernel supports it, the userfaultfd only serves faults from user space
+    /// (`UFFD_USER_MODE_ONLY`). The kernel touching an unpopulated page on the program's
+    /// behalf, e.g. `write(2)` of a buffer from [`as_slice`](Self::as_slice), then fails
+    /// with `EFAULT` instead of fetching it; read such pages from user space first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::ResizeFailed` if `len` is zero.
+    /// Returns `MmapIoError::Unsupported` if the kernel has no userfaultfd.
+    /// Returns `MmapIoError::Io` if the userfaultfd cannot be created or registered, or the
+    /// handler thread cannot be spawned.
+    pub fn new<S: PageSource>(len: u64, source: S) -> Result<Self> {
+        if len == 0 {
+            return Err(MmapIoError::ResizeFailed(
+                "LazyMmap length must be greater than zero".into(),
+            ));
+        }
+        let page = page_size();
+        let len = usize::try_from(len)
+            .map_err(|_| MmapIoError::ResizeFailed("LazyMmap length exceeds usize".into()))?;
+        let map_len = align_up(len as u64, page as u64) as usize;
+        let uffd = Arc::new(open_uffd()?);
+
+        // SAFETY: fresh anonymous mapping, released in Drop.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error().into());
+        }
+        let mut register = UffdioRegister {
+            range: UffdioRange {
+                start: ptr as u64,
+                len: map_len as u64,
+            },
+            mode: UFFDIO_REGISTER_MODE_MISSING,
+            ioctls: 0,
+        };
+        // SAFETY: valid uffd and a pointer to a properly laid out uffdio_register.
+        if unsafe { libc::ioctl(uffd.as_raw_fd(), UFFDIO_REGISTER, &mut register) } != 0 {
+            let err = io::Error::last_os_error();
+            // SAFETY: unmapping the region mapped above.
+            unsafe { libc::munmap(ptr, map_len) };
+            return Err(err.into());
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stats = Arc::new(Stats::default());
+        let handler = Handler {
+            uffd: Arc::clone(&uffd),
+            base: ptr as u64,
+            len: len as u64,
+            page,
+            source,
+            stop: Arc::clone(&stop),
+            stats: Arc::clone(&stats),
+        };
+        let spawned = std::thread::Builder::new()
+            .name("mmap-io-uffd".into())
+            .spawn(move || handler.run());
+        let handler = match spawned {
+            Ok(h) => h,
+            Err(e) => {
+                // SAFETY: nothing can have faulted on the region yet.
+                unsafe { libc::munmap(ptr, map_len) };
+                return Err(e.into());
+            }
+        };
+        Ok(Self {
+            ptr: ptr.cast(),
+            len,
+            map_len,
+            uffd,
+            stop,
+            stats,
+            handler: Some(handler),
+        })
+    }
+
+    /// Length in bytes.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len as u64
+    }
+
+    /// Whether the mapping is empty (never true; construction requires a non-zero length).
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The whole mapping; pages are fetched as they are touched.
+    ///
+    /// Pages not yet touched from user space may not be fetched for system calls; see
+    /// [`new`](Self::new).
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: ptr..ptr+len is mapped for the lifetime of self.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    /// Mutable access; a page is fetched before its first write, and writes stay private.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: ptr..ptr+len is mapped for the lifetime of self and &mut self is unique.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+
+    /// Pages populated from the source so far.
+    #[must_use]
+    pub fn pages_filled(&self) -> u64 {
+        self.stats.filled.load(Ordering::Relaxed)
+    }
+
+    /// Pages zero-filled because the source failed or panicked, or the page could not be
+    /// installed.
+    #[must_use]
+    pub fn fill_failures(&self) -> u64 {
+        self.stats.failed.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for LazyMmap {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handler) = self.handler.take() {
+            let _ = handler.join();
+        }
+        let mut range = UffdioRange {
+            start: self.ptr as u64,
+            len: self.map_len as u64,
+        };
+        // SAFETY: the range was registered in `new`; unmapping also unregisters, so a
+        // failure here is harmless.
+        unsafe {
+            libc::ioctl(self.uffd.as_raw_fd(), UFFDIO_UNREGISTER, &mut range);
+            libc::munmap(self.ptr.cast(), self.map_len);
+        }
+    }
+}
+
+fn open_uffd() -> Result<OwnedFd> {
+    let flags = libc::O_CLOEXEC | libc::O_NONBLOCK;
+    // Prefer user-mode-only faults, which need no privileges on 5.11+
+    // SAFETY: plain syscall; the result is checked.
+    let mut fd =
+        unsafe { libc::syscall(libc::SYS_userfaultfd, flags | UFFD_USER_MODE_ONLY) } as i32;
+    if fd < 0 && io::Error::last_os_error().raw_os_error() == Some(libc::EINVAL) {
+        // SAFETY: as above, for kernels without UFFD_USER_MODE_ONLY.
+        fd = unsafe { libc::syscall(libc::SYS_userfaultfd, flags) } as i32;
+    }
+    if fd < 0 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::ENOSYS) {
+            return Err(MmapIoError::Unsupported(
+                "kernel was built without userfaultfd",
+            ));
+        }
+        return Err(err.into());
+    }
+    // SAFETY: fd was just returned by the kernel and is owned by nobody else.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    let mut api = UffdioApi {
+        api: UFFD_API,
+        features: 0,
+        ioctls: 0,
+    };
+    // SAFETY: valid fd and a pointer to a properly laid out uffdio_api.
+    if unsafe { libc::ioctl(fd.as_raw_fd(), UFFDIO_API, &mut api) } != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(fd)
+}
+
+struct Handler<S> {
+    uffd: Arc<OwnedFd>,
+    base: u64,
+    len: u64,
+    page: usize,
+    source: S,
+    stop: Arc<AtomicBool>,
+    stats: Arc<Stats>,
+}
+
+impl<S: PageSource> Handler<S> {
+    fn run(self) {
+        let fd = self.uffd.as_raw_fd();
+        let mut buf = vec![0u8; self.page];
+        while !self.stop.load(Ordering::Relaxed) {
+            let mut pfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: one valid pollfd.
+            if unsafe { libc::poll(&mut pfd, 1, POLL_TIMEOUT_MS) } <= 0 {
+                continue;
+            }
+            let mut msg = [0u8; UFFD_MSG_SIZE];
+            // SAFETY: reading into a buffer of exactly one uffd_msg.
+            let n = unsafe { libc::read(fd, msg.as_mut_ptr().cast(), UFFD_MSG_SIZE) };
+            if n != UFFD_MSG_SIZE as isize || msg[0] != UFFD_EVENT_PAGEFAULT {
+                continue;
+            }
+            let mut addr = [0u8; 8];
+            addr.copy_from_slice(&msg[16..24]);
+            let page_addr = u64::from_ne_bytes(addr) & !(self.page as u64 - 1);
+            self.serve(page_addr, &mut buf);
+        }
+    }
+
+    fn serve(&self, page_addr: u64, buf: &mut [u8]) {
+        let offset = page_addr - self.base;
+        let valid = (self.len - offset).min(self.page as u64) as usize;
+        buf.fill(0);
+        let filled = catch_unwind(AssertUnwindSafe(|| {
+            self.source.fill(offset, &mut buf[..valid])
+        }));
+        // Counted before installing, which wakes the faulting thread
+        let ok = match filled {
+            Ok(Ok(())) => {
+                self.stats.filled.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            failed => {
+                self.stats.failed.fetch_add(1, Ordering::Relaxed);
+                match failed {
+                    Ok(Err(e)) => log::error!("page source failed at offset {offset}: {e}"),
+                    _ => log::error!("page source panicked at offset {offset}"),
+                }
+                buf.fill(0);
+                false
+            }
+        };
+        if let Err(e) = self.install(page_addr, buf) {
+            log::error!("installing the page at offset {offset} failed: {e}");
+            if ok {
+                self.stats.filled.fetch_sub(1, Ordering::Relaxed);
+                self.stats.failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // Copy `buf` into the page at `page_addr` and wake its waiters. If the copy fails, the
+    // page is zero-filled (or at least the waiters woken) so no thread stays blocked.
+    fn install(&self, page_addr: u64, buf: &[u8]) -> io::Result<()> {
+        let fd = self.uffd.as_raw_fd();
+        let mut range = UffdioRange {
+            start: page_addr,
+            len: self.page as u64,
+        };
+        let err = loop {
+            let mut copy = UffdioCopy {
+                dst: page_addr,
+                src: buf.as_ptr() as u64,
+                len: self.page as u64,
+                mode: 0,
+                copy: 0,
+            };
+            // SAFETY: dst is a registered, page-aligned address and src a page-sized buffer.
+            if unsafe { libc::ioctl(fd, UFFDIO_COPY, &mut copy) } == 0 {
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                // The address space was changing under the copy; try again
+                Some(libc::EAGAIN) if !self.stop.load(Ordering::Relaxed) => {}
+                Some(libc::EEXIST) => {
+                    // Another fault already populated the page; just release the waiters
+                    // SAFETY: valid uffd and range within the registered region.
+                    unsafe { libc::ioctl(fd, UFFDIO_WAKE, &mut range) };
+                    return Ok(());
+                }
+                _ => break err,
+            }
+        };
+        let mut zero = UffdioZeropage {
+            range,
+            mode: 0,
+            zeropage: 0,
+        };
+        // SAFETY: valid uffd and range within the registered region.
+        if unsafe { libc::ioctl(fd, UFFDIO_ZEROPAGE, &mut zero) } != 0 {
+            // SAFETY: as above; the faulting thread retries and faults again.
+            unsafe { libc::ioctl(fd, UFFDIO_WAKE, &mut range) };
+        }
+        Err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lazy_mmap_fills_on_first_access() {
+        let page = page_size() as u64;
+        let len = 3 * page + 100;
+        let calls = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&calls);
+        let lazy = match LazyMmap::new(len, move |offset: u64, buf: &mut [u8]| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            if offset == 2 * page {
+                return Err(io::Error::other("unreachable"));
+            }
+            buf.fill((offset / page) as u8 + 1);
+            Ok(())
+        }) {
+            Ok(lazy) => lazy,
+            // Sandboxes commonly forbid userfaultfd
+            Err(MmapIoError::Io(_)) => return,
+            Err(e) => panic!("unexpected error: {e}"),
+        };
+        assert_eq!(lazy.len(), len);
+        assert_eq!(lazy.pages_filled(), 0);
+
+        let data = lazy.as_slice();
+        assert_eq!(data[0], 1);
+        assert_eq!(data[page as usize + 5], 2);
+        assert_eq!(data[(3 * page + 99) as usize], 4);
+        // Re-reading a populated page does not ask the source again
+        assert_eq!(data[10], 1);
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        assert_eq!(lazy.pages_filled(), 3);
+
+        // A failed fetch zero-fills instead of hanging
+        assert_eq!(data[(2 * page) as usize], 0);
+        assert_eq!(lazy.fill_failures(), 1);
+
+        let mut lazy = lazy;
+        lazy.as_mut_slice()[0] = 9;
+        assert_eq!(lazy.as_slice()[0], 9);
+    }
+}
@@ -1,5 +1,8 @@
 //! Utility helpers for alignment, page size, and safe range calculations.
 
+use std::borrow::Cow;
+use std::path::Path;
+
 use crate::errors::{MmapIoError, Result};
 
 /// Get the system page size in bytes.
@@ -17,35 +20,56 @@ pub fn page_size() -> usize {
     }
 }
 
+/// Get the granularity at which a mapping's file offset must be aligned, in bytes.
+///
+/// On Windows this is the system allocation granularity (typically 64 KiB), which is
+/// coarser than the page size. On other platforms it equals [`page_size`].
+#[must_use]
+pub fn allocation_granularity() -> usize {
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "windows")] {
+            windows_system_info().dwAllocationGranularity as usize
+        } else {
+            unix_page_size()
+        }
+    }
+}
+
 #[cfg(target_os = "windows")]
-fn windows_page_size() -> usize {
+#[allow(non_camel_case_types, non_snake_case)]
+#[repr(C)]
+struct SYSTEM_INFO {
+    wProcessorArchitecture: u16,
+    wReserved: u16,
+    dwPageSize: u32,
+    lpMinimumApplicationAddress: *mut core::ffi::c_void,
+    lpMaximumApplicationAddress: *mut core::ffi::c_void,
+    dwActiveProcessorMask: usize,
+    dwNumberOfProcessors: u32,
+    dwProcessorType: u32,
+    dwAllocationGranularity: u32,
+    wProcessorLevel: u16,
+    wProcessorRevision: u16,
+}
+
+#[cfg(target_os = "windows")]
+fn windows_system_info() -> SYSTEM_INFO {
     use std::mem::MaybeUninit;
-    #[allow(non_snake_case)]
-    #[repr(C)]
-    struct SYSTEM_INFO {
-        wProcessorArchitecture: u16,
-        wReserved: u16,
-        dwPageSize: u32,
-        lpMinimumApplicationAddress: *mut core::ffi::c_void,
-        lpMaximumApplicationAddress: *mut core::ffi::c_void,
-        dwActiveProcessorMask: usize,
-        dwNumberOfProcessors: u32,
-        dwProcessorType: u32,
-        dwAllocationGranularity: u32,
-        wProcessorLevel: u16,
-        wProcessorRevision: u16,
-    }
     extern "system" {
         fn GetSystemInfo(lpSystemInfo: *mut SYSTEM_INFO);
     }
     let mut sysinfo = MaybeUninit::<SYSTEM_INFO>::uninit();
     unsafe {
         GetSystemInfo(sysinfo.as_mut_ptr());
-        let s = sysinfo.assume_init();
-        s.dwPageSize as usize
+        sysinfo.assume_init()
     }
 }
 
+#[cfg(target_os = "windows")]
+fn windows_page_size() -> usize {
+    windows_system_info().dwPageSize as usize
+}
+
 #[cfg(not(target_os = "windows"))]
 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 fn unix_page_size() -> usize {
@@ -59,6 +83,9 @@ fn unix_page_size() -> usize {
 }
 
 /// Align a value up to the nearest multiple of `alignment`.
+///
+/// Saturates at `u64::MAX` when that multiple does not fit in a `u64`, so the result
+/// always fails a later bounds check instead of wrapping to a small offset.
 #[must_use]
 pub fn align_up(value: u64, alignment: u64) -> u64 {
     if alignment == 0 {
@@ -67,12 +94,41 @@ pub fn align_up(value: u64, alignment: u64) -> u64 {
     // Fast path for power-of-2 alignments (common case for page sizes)
     if alignment.is_power_of_two() {
         let mask = alignment - 1;
-        (value + mask) & !mask
+        value.checked_add(mask).map_or(u64::MAX, |v| v & !mask)
     } else {
-        value.div_ceil(alignment) * alignment
+        value.div_ceil(alignment).saturating_mul(alignment)
     }
 }
 
+/// Align a value down to the nearest multiple of `alignment`.
+#[must_use]
+pub fn align_down(value: u64, alignment: u64) -> u64 {
+    if alignment == 0 {
+        return value;
+    }
+    value - value % alignment
+}
+
+// Round `[addr, addr + len)` out to start on a page boundary, as `msync` and `madvise`
+// require. A mapping of a file window at an unaligned offset starts mid-page; the bytes
+// before it on that page are still mapped.
+#[cfg(unix)]
+pub(crate) fn page_span(addr: *const u8, len: usize) -> (*mut u8, usize) {
+    let lead = addr as usize % page_size();
+    (addr.wrapping_sub(lead) as *mut u8, len + lead)
+}
+
+/// Split a requested file offset into the granularity-aligned offset that can actually be
+/// mapped and the delta from that mapping's start to the requested offset.
+///
+/// Mapping `[aligned, offset + len)` and exposing bytes from `delta` onward lets callers use
+/// arbitrary logical offsets without hitting the platform's alignment requirement.
+#[must_use]
+pub fn granularity_split(offset: u64) -> (u64, u64) {
+    let aligned = align_down(offset, allocation_granularity() as u64);
+    (aligned, offset - aligned)
+}
+
 /// Ensure the requested [offset, offset+len) range is within [0, total).
 /// Returns `Ok(())` if valid; otherwise an `OutOfBounds` error.
 ///
@@ -80,14 +136,10 @@ pub fn align_up(value: u64, alignment: u64) -> u64 {
 ///
 /// Returns `MmapIoError::OutOfBounds` if the range exceeds bounds.
 pub fn ensure_in_bounds(offset: u64, len: u64, total: u64) -> Result<()> {
-    if offset > total {
-        return Err(MmapIoError::OutOfBounds { offset, len, total });
+    match offset.checked_add(len) {
+        Some(end) if end <= total => Ok(()),
+        _ => Err(MmapIoError::OutOfBounds { offset, len, total }),
     }
-    let end = offset.saturating_add(len);
-    if end > total {
-        return Err(MmapIoError::OutOfBounds { offset, len, total });
-    }
-    Ok(())
 }
 
 /// Compute a safe byte slice range for a given total length, returning start..end as usize tuple.
@@ -95,12 +147,164 @@ pub fn ensure_in_bounds(offset: u64, len: u64, total: u64) -> Result<()> {
 /// # Errors
 ///
 /// Returns `MmapIoError::OutOfBounds` if the requested range exceeds the total length.
-#[allow(clippy::cast_possible_truncation)]
 pub fn slice_range(offset: u64, len: u64, total: u64) -> Result<(usize, usize)> {
     ensure_in_bounds(offset, len, total)?;
-    // Safe to cast because we've already validated bounds against total
-    // which itself must fit in memory (and thus usize)
-    let start = offset as usize;
-    let end = (offset + len) as usize;
-    Ok((start, end))
+    // `total` may not fit in usize on 32-bit targets, so convert rather than cast
+    let to_usize =
+        |v: u64| usize::try_from(v).map_err(|_| MmapIoError::OutOfBounds { offset, len, total });
+    Ok((to_usize(offset)?, to_usize(offset + len)?))
+}
+
+/// Path to hand to the OS when opening `path` for mapping.
+///
+/// On Windows the path is made absolute and converted to verbatim form (`\\?\C:\...` or
+/// `\\?\UNC\server\share\...`), so paths longer than `MAX_PATH` and components with
+/// reserved names such as `con` or `aux.txt` open as ordinary files. `.` and `..` are
+/// resolved lexically first, since verbatim paths reach the filesystem unmodified.
+/// Device paths, drive-relative paths (`C:file`), and non-Unicode paths are left alone.
+/// Elsewhere the path is returned unchanged.
+#[must_use]
+pub fn os_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        let cwd = std::env::current_dir().ok();
+        let verbatim = path
+            .to_str()
+            .and_then(|p| verbatim_path(p, cwd.as_deref().and_then(Path::to_str)));
+        if let Some(verbatim) = verbatim {
+            return Cow::Owned(std::path::PathBuf::from(verbatim));
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+// Verbatim form of a Windows path, resolving relative paths against `cwd`. None means the
+// path should be used as is.
+#[cfg(any(windows, test))]
+fn verbatim_path(path: &str, cwd: Option<&str>) -> Option<String> {
+    let path = path.replace('/', "\\");
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    }
+    let (prefix, rest) = match split_root(&path) {
+        Some(root) => root,
+        // Drive-relative paths depend on per-drive state only the OS knows
+        None if path.as_bytes().get(1) == Some(&b':') => return None,
+        None => {
+            let (prefix, cwd_rest) = split_root(&cwd?.replace('/', "\\"))?;
+            if path.starts_with('\\') {
+                (prefix, path.clone())
+            } else {
+                (prefix, format!("{cwd_rest}\\{path}"))
+            }
+        }
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for component in rest.split('\\') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            c => parts.push(c),
+        }
+    }
+    Some(format!("{prefix}\\{}", parts.join("\\")))
+}
+
+// Split an absolute Windows path into its verbatim root prefix and the remainder.
+#[cfg(any(windows, test))]
+fn split_root(path: &str) -> Option<(String, String)> {
+    let is_drive = |p: &str| {
+        let b = p.as_bytes();
+        b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':'
+    };
+    let unc = |p: &str| {
+        let mut parts = p.splitn(3, '\\');
+        let server = parts.next().filter(|s| !s.is_empty())?;
+        let share = parts.next().filter(|s| !s.is_empty())?;
+        Some((
+            format!(r"\\?\UNC\{server}\{share}"),
+            parts.next().unwrap_or("").to_string(),
+        ))
+    };
+    if let Some(verbatim) = path.strip_prefix(r"\\?\") {
+        if let Some(share) = verbatim.strip_prefix(r"UNC\") {
+            return unc(share);
+        }
+        if is_drive(verbatim) {
+            return Some((
+                format!(r"\\?\{}", &verbatim[..2]),
+                verbatim[2..].to_string(),
+            ));
+        }
+        return None;
+    }
+    if let Some(share) = path.strip_prefix(r"\\") {
+        return unc(share);
+    }
+    if is_drive(path) && path[2..].starts_with('\\') {
+        return Some((format!(r"\\?\{}", &path[..2]), path[2..].to_string()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alignment_helpers() {
+        assert_eq!(align_up(1, 4096), 4096);
+        assert_eq!(align_up(u64::MAX - 1, 4096), u64::MAX);
+        assert_eq!(align_up(u64::MAX - 1, 3), u64::MAX);
+        assert_eq!(align_down(4097, 4096), 4096);
+        assert_eq!(align_down(100, 0), 100);
+        assert_eq!(align_down(10, 3), 9);
+
+        let gran = allocation_granularity() as u64;
+        assert!(gran >= page_size() as u64);
+        assert_eq!(granularity_split(gran * 3 + 17), (gran * 3, 17));
+        assert_eq!(granularity_split(0), (0, 0));
+    }
+
+    #[test]
+    fn test_range_checks_near_u64_max() {
+        let oob = |r: Result<()>| matches!(r, Err(MmapIoError::OutOfBounds { .. }));
+        assert!(oob(ensure_in_bounds(u64::MAX - 1, 10, u64::MAX)));
+        assert!(oob(ensure_in_bounds(1, u64::MAX, u64::MAX)));
+        assert!(ensure_in_bounds(u64::MAX - 10, 10, u64::MAX).is_ok());
+        assert!(ensure_in_bounds(u64::MAX, 0, u64::MAX).is_ok());
+        assert!(oob(slice_range(u64::MAX, 1, u64::MAX).map(|_| ())));
+        assert_eq!(slice_range(4, 6, 10).expect("range"), (4, 10));
+    }
+
+    #[test]
+    fn test_verbatim_windows_paths() {
+        let v = |p: &str, cwd: Option<&str>| verbatim_path(p, cwd);
+        assert_eq!(
+            v(r"C:\data\..\db\file.bin", None).as_deref(),
+            Some(r"\\?\C:\db\file.bin")
+        );
+        assert_eq!(v("C:/a/./b/", None).as_deref(), Some(r"\\?\C:\a\b"));
+        assert_eq!(
+            v(r"\\server\share\dir\f.bin", None).as_deref(),
+            Some(r"\\?\UNC\server\share\dir\f.bin")
+        );
+        // Relative and rooted paths resolve against the current directory
+        assert_eq!(
+            v(r"sub\con.txt", Some(r"C:\work")).as_deref(),
+            Some(r"\\?\C:\work\sub\con.txt")
+        );
+        assert_eq!(
+            v(r"..\x", Some(r"\\?\UNC\srv\sh\a")).as_deref(),
+            Some(r"\\?\UNC\srv\sh\x")
+        );
+        assert_eq!(v(r"\x", Some(r"\\?\D:\w")).as_deref(), Some(r"\\?\D:\x"));
+        // Left alone
+        assert_eq!(v(r"\\?\C:\a\..\b", None), None);
+        assert_eq!(v(r"\\.\pipe\p", None), None);
+        assert_eq!(v("C:rel", Some(r"C:\work")), None);
+        assert_eq!(v("rel", None), None);
+    }
 }
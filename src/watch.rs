@@ -2,12 +2,17 @@
 
 use crate::errors::Result;
 use crate::mmap::MemoryMappedFile;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
 // Watch polling interval in milliseconds
 const WATCH_POLL_INTERVAL_MS: u64 = 100;
 
+// Granularity of content hashes; changed events report ranges rounded to this size
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
 /// Type of change detected in a watched file.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChangeKind {
@@ -30,17 +35,71 @@ pub struct ChangeEvent {
     pub kind: ChangeKind,
 }
 
+/// Options for [`MemoryMappedFile::watch_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchOptions {
+    poll_interval: Duration,
+    verify_content: bool,
+    follow_recreation: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(WATCH_POLL_INTERVAL_MS),
+            verify_content: false,
+            follow_recreation: false,
+        }
+    }
+}
+
+impl WatchOptions {
+    /// Default options: 100 ms polling, timestamps only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How often the file is checked for changes.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Hash the file contents and report `Modified` only when they actually changed.
+    ///
+    /// Timestamp-only updates (e.g. `touch`) are reported as `Metadata` instead. Modified
+    /// events then carry the changed range, rounded out to 64 KiB chunks. The file is
+    /// re-read whenever its timestamp or length changes, so this costs one full read per
+    /// detected update.
+    pub fn verify_content(mut self, enable: bool) -> Self {
+        self.verify_content = enable;
+        self
+    }
+
+    /// Keep watching after the file is removed instead of stopping at the `Removed` event.
+    ///
+    /// A file that later appears at the path is reported as changed, so writers may delete
+    /// and recreate it rather than rename over it.
+    pub fn follow_recreation(mut self, enable: bool) -> Self {
+        self.follow_recreation = enable;
+        self
+    }
+}
+
 /// Handle for controlling a file watch operation.
 pub struct WatchHandle {
     // Thread handle is kept to ensure the watch thread is properly joined on drop
     thread: thread::JoinHandle<()>,
+    // Tells the polling thread to exit at its next wakeup
+    stop: Arc<AtomicBool>,
 }
 
 impl Drop for WatchHandle {
     fn drop(&mut self) {
-        // The thread will naturally exit when it detects the file is removed
-        // or when the handle is dropped. We don't join here to avoid blocking.
-        // The thread will clean up on its own.
+        // The thread exits at its next poll after seeing the stop flag, or when it detects
+        // the file is removed (unless following recreation). We don't join here to avoid
+        // blocking for a poll interval.
+        self.stop.store(true, Ordering::Relaxed);
     }
 }
 
@@ -87,52 +146,180 @@ impl MemoryMappedFile {
     /// ```
     #[cfg(feature = "watch")]
     pub fn watch<F>(&self, callback: F) -> Result<WatchHandle>
+    where
+        F: Fn(ChangeEvent) + Send + 'static,
+    {
+        self.watch_with(WatchOptions::default(), callback)
+    }
+
+    /// Watch for changes to the mapped file with explicit [`WatchOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mmap_io::{MemoryMappedFile, watch::{ChangeKind, WatchOptions}};
+    ///
+    /// let mmap = MemoryMappedFile::open_ro("config.bin")?;
+    /// let options = WatchOptions::new().verify_content(true);
+    /// let _handle = mmap.watch_with(options, |event| {
+    ///     if event.kind == ChangeKind::Modified {
+    ///         println!("content changed at {:?}+{:?}", event.offset, event.len);
+    ///     }
+    /// })?;
+    /// # Ok::<(), mmap_io::MmapIoError>(())
+    /// ```
+    #[cfg(feature = "watch")]
+    pub fn watch_with<F>(&self, options: WatchOptions, callback: F) -> Result<WatchHandle>
     where
         F: Fn(ChangeEvent) + Send + 'static,
     {
         let path = self.path().to_path_buf();
+        let mut last_hashes = if options.verify_content {
+            Some(hash_chunks(&path)?)
+        } else {
+            None
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = Arc::clone(&stop);
 
         // For this implementation, we'll use a simple polling approach
         // In a production implementation, you'd use platform-specific APIs
         let thread = thread::spawn(move || {
-            let mut last_modified = std::fs::metadata(&path)
-                .ok()
-                .and_then(|m| m.modified().ok());
+            let initial = std::fs::metadata(&path).ok();
+            let mut last_modified = initial.as_ref().and_then(|m| m.modified().ok());
+            let mut last_id = initial.as_ref().and_then(file_id);
+            let mut last_len = initial.map(|m| m.len());
+            let mut removed = false;
 
             loop {
-                thread::sleep(Duration::from_millis(WATCH_POLL_INTERVAL_MS));
+                thread::sleep(options.poll_interval);
+                if stop_flag.load(Ordering::Relaxed) {
+                    break;
+                }
 
                 // Check if file still exists
                 let metadata = match std::fs::metadata(&path) {
                     Ok(m) => m,
                     Err(_) => {
-                        callback(ChangeEvent {
-                            offset: None,
-                            len: None,
-                            kind: ChangeKind::Removed,
-                        });
-                        break;
+                        if !removed {
+                            callback(ChangeEvent {
+                                offset: None,
+                                len: None,
+                                kind: ChangeKind::Removed,
+                            });
+                        }
+                        if !options.follow_recreation {
+                            break;
+                        }
+                        removed = true;
+                        continue;
                     }
                 };
 
-                // Check modification time
-                if let Ok(modified) = metadata.modified() {
-                    if Some(modified) != last_modified {
-                        callback(ChangeEvent {
-                            offset: None,
-                            len: None,
-                            kind: ChangeKind::Modified,
-                        });
-                        last_modified = Some(modified);
-                    }
+                // A file renamed over the path may keep the old timestamp and length, so a
+                // different file identity (or one reappearing) counts as a change too
+                let id = file_id(&metadata);
+                let replaced = std::mem::take(&mut removed) || id != last_id;
+                // Check modification time (and length when verifying content)
+                let modified = metadata.modified().ok();
+                let len_changed = last_hashes.is_some() && Some(metadata.len()) != last_len;
+                if !replaced && (modified.is_none() || (modified == last_modified && !len_changed))
+                {
+                    continue;
                 }
+                last_id = id;
+                last_modified = modified;
+                last_len = Some(metadata.len());
+                let Some(previous) = last_hashes.as_mut() else {
+                    callback(ChangeEvent {
+                        offset: None,
+                        len: None,
+                        kind: ChangeKind::Modified,
+                    });
+                    continue;
+                };
+                let Ok(current) = hash_chunks(&path) else {
+                    // Raced with a removal; the next poll reports it
+                    continue;
+                };
+                let event = match changed_span(previous, &current, metadata.len()) {
+                    Some((offset, len)) => ChangeEvent {
+                        offset: Some(offset),
+                        len: Some(len),
+                        kind: ChangeKind::Modified,
+                    },
+                    None => ChangeEvent {
+                        offset: None,
+                        len: None,
+                        kind: ChangeKind::Metadata,
+                    },
+                };
+                *previous = current;
+                callback(event);
             }
         });
 
-        Ok(WatchHandle { thread })
+        Ok(WatchHandle { thread, stop })
+    }
+}
+
+// Device and inode of the file, where the platform exposes them.
+#[cfg(all(feature = "watch", unix))]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(all(feature = "watch", not(unix)))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+// Hash the file in HASH_CHUNK_SIZE pieces so changes can be localized.
+#[cfg(feature = "watch")]
+fn hash_chunks(path: &std::path::Path) -> std::io::Result<Vec<u64>> {
+    use std::hash::Hasher;
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = vec![0u8; HASH_CHUNK_SIZE];
+    let mut hashes = Vec::new();
+    loop {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match file.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if filled == 0 {
+            return Ok(hashes);
+        }
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        hasher.write(&buf[..filled]);
+        hashes.push(hasher.finish());
+        if filled < buf.len() {
+            return Ok(hashes);
+        }
     }
 }
 
+// Byte range covering every chunk whose hash differs, or None if the content is unchanged.
+#[cfg(feature = "watch")]
+fn changed_span(old: &[u64], new: &[u64], new_len: u64) -> Option<(u64, u64)> {
+    let chunks = old.len().max(new.len());
+    let differs = |i: &usize| old.get(*i) != new.get(*i);
+    let first = (0..chunks).find(differs)?;
+    let last = (0..chunks).rev().find(differs)?;
+    let chunk = HASH_CHUNK_SIZE as u64;
+    let offset = first as u64 * chunk;
+    let end = ((last as u64 + 1) * chunk).min(new_len.max(offset));
+    Some((offset, end - offset))
+}
+
 // Platform-specific implementations would go here
 // For now, we use polling for all platforms
 
@@ -144,6 +331,8 @@ where
     F: Fn(ChangeEvent) + Send + 'static,
 {
     let path = path.to_path_buf();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = Arc::clone(&stop);
 
     let thread = thread::spawn(move || {
         let mut last_modified = std::fs::metadata(&path)
@@ -153,6 +342,9 @@ where
 
         loop {
             thread::sleep(Duration::from_millis(WATCH_POLL_INTERVAL_MS));
+            if stop_flag.load(Ordering::Relaxed) {
+                break;
+            }
 
             // Check if file still exists
             let metadata = match std::fs::metadata(&path) {
@@ -190,7 +382,7 @@ where
         }
     });
 
-    Ok(WatchHandle { thread })
+    Ok(WatchHandle { thread, stop })
 }
 
 #[cfg(test)]
@@ -415,4 +607,55 @@ mod tests {
 
         fs::remove_file(&path).expect("cleanup");
     }
+
+    #[test]
+    #[cfg(all(feature = "watch", unix))]
+    fn test_watch_verify_content_filters_touches() {
+        use std::sync::Mutex;
+
+        let path = tmp_path("watch_verify");
+        let _ = fs::remove_file(&path);
+        let mmap = create_mmap(&path, 200 * 1024).expect("create");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let options = WatchOptions::new()
+            .poll_interval(Duration::from_millis(20))
+            .verify_content(true);
+        let _handle = mmap
+            .watch_with(options, move |event| {
+                sink.lock().expect("lock").push(event);
+            })
+            .expect("watch");
+
+        // A touch moves the timestamp without changing any bytes
+        thread::sleep(Duration::from_millis(30));
+        let cpath = std::ffi::CString::new(path.to_str().expect("utf8")).expect("cstring");
+        // SAFETY: utime with null sets times to current time
+        unsafe { libc::utime(cpath.as_ptr(), std::ptr::null()) };
+        thread::sleep(Duration::from_millis(200));
+        {
+            let seen = events.lock().expect("lock");
+            assert!(
+                seen.iter().all(|e| e.kind != ChangeKind::Modified),
+                "{seen:?}"
+            );
+        }
+
+        // A real write in the second 64 KiB chunk is reported with its range
+        mmap.update_region(70_000, b"changed").expect("write");
+        mmap.flush().expect("flush");
+        unsafe { libc::utime(cpath.as_ptr(), std::ptr::null()) };
+        thread::sleep(Duration::from_millis(300));
+        let seen = events.lock().expect("lock");
+        let modified = seen
+            .iter()
+            .find(|e| e.kind == ChangeKind::Modified)
+            .expect("modified event");
+        assert_eq!(modified.offset, Some(HASH_CHUNK_SIZE as u64));
+        assert_eq!(modified.len, Some(HASH_CHUNK_SIZE as u64));
+        drop(seen);
+
+        fs::remove_file(&path).expect("cleanup");
+    }
 }
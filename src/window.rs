@@ -0,0 +1,358 @@
+//! Sliding-window access to files larger than the address space budget.
+//!
+//! [`WindowedMmap`] keeps only one window of the file mapped at a time and remaps whenever an
+//! access falls outside it, so a multi-terabyte file costs a single bounded mapping rather
+//! than address space (and VMAs) proportional to its size. Callers use flat `u64` offsets
+//! and never see window boundaries; accesses that straddle one are split transparently.
+//!
+//! This is also how 32-bit targets work with files over their ~2 GB mapping limit, where
+//! [`MemoryMappedFile`](crate::MemoryMappedFile) refuses to map the whole file:
+//! [`WindowedMmap::open_ro`] / [`WindowedMmap::open_rw`] mirror the regular constructors,
+//! and `read_into`, `update_region`, `flush`, and `len` behave the same.
+
+use std::fs::{File, OpenOptions};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use memmap2::{Mmap, MmapMut, MmapOptions};
+use parking_lot::Mutex;
+
+use crate::errors::{MmapIoError, Result};
+use crate::mmap::MmapMode;
+use crate::tracking::Registration;
+use crate::utils::{align_down, align_up, allocation_granularity, ensure_in_bounds};
+
+/// Default window size: 256 MiB on 64-bit targets.
+#[cfg(target_pointer_width = "64")]
+pub const DEFAULT_WINDOW_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Default window size: 64 MiB on 32-bit targets, leaving room in the address space for
+/// the rest of the process.
+#[cfg(not(target_pointer_width = "64"))]
+pub const DEFAULT_WINDOW_SIZE: u64 = 64 * 1024 * 1024;
+
+enum WindowMap {
+    Ro(Mmap),
+    Rw(MmapMut),
+}
+
+struct Window {
+    start: u64,
+    map: WindowMap,
+}
+
+impl Window {
+    fn end(&self) -> u64 {
+        let len = match &self.map {
+            WindowMap::Ro(m) => m.len(),
+            WindowMap::Rw(m) => m.len(),
+        };
+        self.start + len as u64
+    }
+}
+
+/// File accessor that maps one window of a large file at a time.
+///
+/// Windows start at allocation-granularity boundaries and are `window_size` bytes long
+/// (shorter at the end of the file). Dirty pages of a ReadWrite window stay in the page
+/// cache when it is unmapped; [`flush`](Self::flush) syncs the whole file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mmap_io::{MmapMode, window::WindowedMmap};
+///
+/// let log = WindowedMmap::open("huge.log", MmapMode::ReadOnly, 64 << 20)?;
+/// let mut buf = [0u8; 4096];
+/// log.read_into(log.len() - 4096, &mut buf)?;
+/// # Ok::<(), mmap_io::MmapIoError>(())
+/// ```
+pub struct WindowedMmap {
+    path: PathBuf,
+    file: File,
+    mode: MmapMode,
+    len: u64,
+    window_size: u64,
+    window: Mutex<Option<Window>>,
+    // Counts the current window towards the global mapped-bytes total
+    tracking: Option<Registration>,
+}
+
+impl std::fmt::Debug for WindowedMmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WindowedMmap")
+            .field("path", &self.path)
+            .field("mode", &self.mode)
+            .field("len", &self.len)
+            .field("window_size", &self.window_size)
+            .field("window", &self.current_window())
+            .finish()
+    }
+}
+
+impl WindowedMmap {
+    /// Open an existing file for windowed access in `mode` (`ReadOnly` or `ReadWrite`).
+    ///
+    /// `window_size` is rounded up to the allocation granularity and capped at the largest
+    /// mapping the target supports. No window is mapped until the first access.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` for other modes or a zero `window_size`, and
+    /// `MmapIoError::Io` if the file cannot be opened.
+    pub fn open<P: AsRef<Path>>(path: P, mode: MmapMode, window_size: u64) -> Result<Self> {
+        let writable = match mode {
+            MmapMode::ReadOnly => false,
+            MmapMode::ReadWrite => true,
+            _ => {
+                return Err(MmapIoError::InvalidMode(
+                    "WindowedMmap supports ReadOnly and ReadWrite modes",
+                ))
+            }
+        };
+        if window_size == 0 {
+            return Err(MmapIoError::InvalidMode("window size must be non-zero"));
+        }
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(writable)
+            .open(crate::utils::os_path(&path))?;
+        let len = file.metadata()?.len();
+        let tracking = Registration::new(&path, 0);
+        Ok(Self {
+            path,
+            file,
+            mode,
+            len,
+            window_size: align_up(
+                window_size.min(crate::mmap::max_mmap_size()),
+                allocation_granularity() as u64,
+            ),
+            window: Mutex::new(None),
+            tracking,
+        })
+    }
+
+    /// Open an existing file read-only with [`DEFAULT_WINDOW_SIZE`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if the file cannot be opened.
+    pub fn open_ro<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open(path, MmapMode::ReadOnly, DEFAULT_WINDOW_SIZE)
+    }
+
+    /// Open an existing file read-write with [`DEFAULT_WINDOW_SIZE`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::Io` if the file cannot be opened.
+    pub fn open_rw<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open(path, MmapMode::ReadWrite, DEFAULT_WINDOW_SIZE)
+    }
+
+    /// Total file length.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the file is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Effective window size after rounding.
+    #[must_use]
+    pub fn window_size(&self) -> u64 {
+        self.window_size
+    }
+
+    /// Access mode.
+    #[must_use]
+    pub fn mode(&self) -> MmapMode {
+        self.mode
+    }
+
+    /// Path of the underlying file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// File range currently mapped, if any.
+    #[must_use]
+    pub fn current_window(&self) -> Option<Range<u64>> {
+        self.window.lock().as_ref().map(|w| w.start..w.end())
+    }
+
+    /// Read `buf.len()` bytes starting at `offset`, remapping as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::OutOfBounds` if the range exceeds the file and
+    /// `MmapIoError::Io` if a window cannot be mapped.
+    pub fn read_into(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        ensure_in_bounds(offset, buf.len() as u64, self.len)?;
+        let mut window = self.window.lock();
+        let mut done = 0usize;
+        while done < buf.len() {
+            let pos = offset + done as u64;
+            let w = self.window_for(&mut window, pos)?;
+            let start = (pos - w.start) as usize;
+            let n = (buf.len() - done).min((w.end() - pos) as usize);
+            let src = match &w.map {
+                WindowMap::Ro(m) => &m[start..start + n],
+                WindowMap::Rw(m) => &m[start..start + n],
+            };
+            buf[done..done + n].copy_from_slice(src);
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// Write `data` starting at `offset`, remapping as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::InvalidMode` if not in `ReadWrite` mode,
+    /// `MmapIoError::OutOfBounds` if the range exceeds the file, and `MmapIoError::Io` if a
+    /// window cannot be mapped.
+    pub fn update_region(&self, offset: u64, data: &[u8]) -> Result<()> {
+        if self.mode != MmapMode::ReadWrite {
+            return Err(MmapIoError::InvalidMode(
+                "Update region requires ReadWrite mode.",
+            ));
+        }
+        ensure_in_bounds(offset, data.len() as u64, self.len)?;
+        let mut window = self.window.lock();
+        let mut done = 0usize;
+        while done < data.len() {
+            let pos = offset + done as u64;
+            let w = self.window_for(&mut window, pos)?;
+            let start = (pos - w.start) as usize;
+            let n = (data.len() - done).min((w.end() - pos) as usize);
+            match &mut w.map {
+                WindowMap::Rw(m) => m[start..start + n].copy_from_slice(&data[done..done + n]),
+                WindowMap::Ro(_) => {
+                    return Err(MmapIoError::InvalidMode(
+                        "Cannot write to read-only mapping",
+                    ))
+                }
+            }
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// Flush the current window and sync the file, covering windows already unmapped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MmapIoError::FlushFailed` if flushing or syncing fails.
+    pub fn flush(&self) -> Result<()> {
+        if self.mode != MmapMode::ReadWrite {
+            return Ok(());
+        }
+        if let Some(Window {
+            map: WindowMap::Rw(m),
+            ..
+        }) = self.window.lock().as_ref()
+        {
+            m.flush()
+                .map_err(|e| MmapIoError::FlushFailed(format!("window flush failed: {e}")))?;
+        }
+        self.file
+            .sync_data()
+            .map_err(|e| MmapIoError::FlushFailed(format!("file sync failed: {e}")))
+    }
+
+    // Return the window containing `pos`, mapping a new one if the current one does not.
+    fn window_for<'a>(&self, slot: &'a mut Option<Window>, pos: u64) -> Result<&'a mut Window> {
+        let hit = matches!(slot, Some(w) if w.start <= pos && pos < w.end());
+        if !hit {
+            // Unmap first so at most one window is ever mapped
+            *slot = None;
+            let start = align_down(pos, allocation_granularity() as u64);
+            let len = self.window_size.min(self.len - start) as usize;
+            let mut opts = MmapOptions::new();
+            opts.offset(start).len(len);
+            // SAFETY: the file stays open for the lifetime of `self` and the window is only
+            // reachable through the mutex guarding `slot`.
+            let map = unsafe {
+                match self.mode {
+                    MmapMode::ReadWrite => WindowMap::Rw(opts.map_mut(&self.file)?),
+                    _ => WindowMap::Ro(opts.map(&self.file)?),
+                }
+            };
+            *slot = Some(Window { start, map });
+            if let Some(tracking) = &self.tracking {
+                tracking.set_len(len as u64);
+            }
+        }
+        slot.as_mut()
+            .ok_or(MmapIoError::InvalidMode("window unavailable"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        p.push(format!(
+            "mmap_io_window_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        p
+    }
+
+    #[test]
+    fn test_windowed_read_write_across_windows() {
+        let path = tmp_path("rw");
+        let _ = fs::remove_file(&path);
+        let gran = allocation_granularity() as u64;
+        let len = gran * 10 + 123;
+        fs::write(&path, vec![0u8; len as usize]).expect("seed file");
+
+        let wm = WindowedMmap::open(&path, MmapMode::ReadWrite, 1).expect("open");
+        assert_eq!(wm.window_size(), gran);
+        assert_eq!(wm.current_window(), None);
+
+        // Straddles three windows
+        let data: Vec<u8> = (0..(gran * 2 + 10)).map(|i| (i % 253) as u8).collect();
+        wm.update_region(gran - 5, &data).expect("write");
+        wm.update_region(len - 3, b"end").expect("write tail");
+        assert_eq!(wm.current_window(), Some(gran * 10..len));
+
+        let mut back = vec![0u8; data.len()];
+        wm.read_into(gran - 5, &mut back).expect("read");
+        assert_eq!(back, data);
+        assert!(wm.read_into(len - 2, &mut [0u8; 3]).is_err());
+        wm.flush().expect("flush");
+        drop(wm);
+
+        let ro = WindowedMmap::open(&path, MmapMode::ReadOnly, gran * 4).expect("open ro");
+        let mut tail = [0u8; 3];
+        ro.read_into(len - 3, &mut tail).expect("read tail");
+        assert_eq!(&tail, b"end");
+        assert!(ro.update_region(0, b"x").is_err());
+        assert!(WindowedMmap::open(&path, MmapMode::CopyOnWrite, gran).is_err());
+
+        let default = WindowedMmap::open_rw(&path).expect("open default");
+        assert_eq!(default.window_size(), DEFAULT_WINDOW_SIZE);
+        default.update_region(0, b"same api").expect("write");
+        let mut head = [0u8; 8];
+        default.read_into(0, &mut head).expect("read");
+        assert_eq!(&head, b"same api");
+        drop(default);
+
+        drop(ro);
+        fs::remove_file(&path).expect("cleanup");
+    }
+}